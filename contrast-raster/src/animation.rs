@@ -0,0 +1,58 @@
+//! Same functions as contrast/src/shaders/animation.glsl.
+
+use contrast::Easing;
+
+/// Returns the eased progress at the time 't' of an animation.
+pub(crate) fn progress(start : f32, duration : f32, easing : u32, t : f32) -> f32 {
+    if duration <= 0.0 {
+        return 1.0;
+    }
+    Easing::from_integer(easing).apply((t - start) / duration)
+}
+
+/// 'anim' is the triple (start, duration, easing) of the animation of a property.
+pub(crate) fn progress_of(anim : [f32; 3], t : f32) -> f32 {
+    progress(anim[0], anim[1], anim[2] as u32, t)
+}
+
+/// Returns the progress of the animation of the property 'i' of a line or a polygon,
+/// whose easings are packed on 4 bits each.
+pub(crate) fn progress_packed(starts : [f32; 4], durations : [f32; 4], easings : u32, i : usize, t : f32) -> f32 {
+    progress(starts[i], durations[i], (easings >> (4 * i)) & 15, t)
+}
+
+pub(crate) fn mix(a : f32, b : f32, p : f32) -> f32 {
+    a + (b - a) * p
+}
+
+pub(crate) fn mix2(a : [f32; 2], b : [f32; 2], p : f32) -> [f32; 2] {
+    [mix(a[0], b[0], p), mix(a[1], b[1], p)]
+}
+
+pub(crate) fn mix3(a : [f32; 3], b : [f32; 3], p : f32) -> [f32; 3] {
+    [mix(a[0], b[0], p), mix(a[1], b[1], p), mix(a[2], b[2], p)]
+}
+
+pub(crate) fn mix4(a : [f32; 4], b : [f32; 4], p : f32) -> [f32; 4] {
+    [mix(a[0], b[0], p), mix(a[1], b[1], p), mix(a[2], b[2], p), mix(a[3], b[3], p)]
+}
+
+/// Returns the color packed by Color::to_rgba8, red in the lowest byte.
+pub(crate) fn unpack_color(c : u32) -> [f32; 4] {
+    [(c & 255) as f32 / 255.0, ((c >> 8) & 255) as f32 / 255.0, ((c >> 16) & 255) as f32 / 255.0, (c >> 24) as f32 / 255.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_packed()
+    {
+        let easings = (Easing::Step as u32) | ((Easing::CubicIn as u32) << 4);
+        assert_eq!(super::progress_packed([0.0, 1.0, 0.0, 0.0], [2.0, 2.0, 0.0, 0.0], easings, 0, 1.0), 0.0);
+        assert_eq!(super::progress_packed([0.0, 1.0, 0.0, 0.0], [2.0, 2.0, 0.0, 0.0], easings, 1, 2.0), 0.125);
+        assert_eq!(super::progress_packed([0.0, 1.0, 0.0, 0.0], [2.0, 2.0, 0.0, 0.0], easings, 2, 2.0), 1.0);
+        assert_eq!(progress_of([1.0, 0.0, 0.0], 0.0), 1.0);
+    }
+}
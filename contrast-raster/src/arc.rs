@@ -0,0 +1,173 @@
+//! Same drawing as contrast/src/shaders/arc : each arc is a square around its center,
+//! in which the coverage of each pixel comes from the signed distance to the arc.
+
+use std::f32::consts::PI;
+use contrast::marks::arcmark::VertexArc;
+use crate::animation::{progress_of, mix, mix3, mix4};
+use crate::framebuffer::{Framebuffer, M44, blend, barycentric, interpolate};
+use crate::line::dot;
+use crate::point::{length, sub};
+
+/// Values of an arc which are the same for all its pixels, computed by arc.geom.
+struct Arc
+{
+    radii: [f32; 2],
+    aperture: f32,
+    corner_radius: f32
+}
+
+impl Arc
+{
+    /// Distance from 'p' to the arc of radius 'r' going from the axis to the side of the arc
+    /// eroded by the corner radius 'c', or infinity if there is no such arc.
+    fn distance_arc(&self, p: [f32; 2], r: f32, c: f32) -> f32
+    {
+        if r <= 0.0 || c > r
+        {
+            return f32::INFINITY;
+        }
+        let end = self.aperture - (c / r).asin();
+        if end < 0.0
+        {
+            return f32::INFINITY;
+        }
+        let angle = p[0].atan2(p[1]).clamp(0.0, end);
+        length(sub(p, [r * angle.sin(), r * angle.cos()]))
+    }
+
+    /// Signed distance in pixels to the arc, from its right half.
+    fn distance_shape(&self, p: [f32; 2]) -> f32
+    {
+        let p = [p[0].abs(), p[1]];
+        let len = length(p);
+        if self.aperture >= PI
+        {
+            return (self.radii[0] - len).max(len - self.radii[1]);
+        }
+        let c = self.corner_radius;
+        let inner = self.radii[0] + c;
+        let outer = self.radii[1] - c;
+        let (sin, cos) = self.aperture.sin_cos();
+        let (side, normal) = ([sin, cos], [cos, -sin]);
+
+        let inside_side = dot(p, normal) <= -c || (self.aperture > PI / 2.0 && dot(p, side) < 0.0);
+        let inside = len >= inner && len <= outer && inside_side;
+
+        let mut d = self.distance_arc(p, inner, c).min(self.distance_arc(p, outer, c));
+        let mut first = (inner * inner - c * c).max(0.0).sqrt();
+        if self.aperture <= PI / 2.0
+        {
+            first = first.max(c * cos / sin);
+        }
+        let last = (outer * outer - c * c).max(0.0).sqrt();
+        if last >= first
+        {
+            let t = dot(p, side).clamp(first, last);
+            d = d.min(length(sub(p, [t * side[0] - c * normal[0], t * side[1] - c * normal[1]])));
+        }
+        (if inside { -d } else { d }) - c
+    }
+}
+
+impl Framebuffer
+{
+    /// Draw the arc marks, as returned by Contrast::get_arcmarks_properties, at the
+    /// time 't' of the animations, blended over the marks.
+    pub fn draw_arcs(&mut self, vertices: &[VertexArc], projection: &M44, t: f32)
+    {
+        for v in vertices
+        {
+            // arc.vert
+            let center = mix3(v.0, v.1, progress_of(v.2, t));
+            let color = mix4(v.3, v.4, progress_of(v.5, t));
+            let inner_radius = mix(v.6[0], v.6[1], progress_of(v.7, t));
+            let outer_radius = mix(v.6[2], v.6[3], progress_of(v.8, t));
+            let start_angle = mix(v.9[0], v.9[1], progress_of(v.10, t));
+            let end_angle = mix(v.9[2], v.9[3], progress_of(v.11, t));
+            let corner_radius = mix(v.12[0], v.12[1], progress_of(v.13, t));
+            let pad_angle = mix(v.12[2], v.12[3], progress_of(v.14, t));
+
+            // arc.geom
+            let radii = [inner_radius.min(outer_radius), inner_radius.max(outer_radius)];
+            let (start, end) = (start_angle.min(end_angle), start_angle.max(end_angle));
+            let middle = (start + end) / 2.0;
+            let aperture = if end - start >= 2.0 * PI { PI } else { (end - start - pad_angle.max(0.0)) / 2.0 };
+            if aperture <= 0.0 || radii[1] <= 0.0
+            {
+                continue;
+            }
+            let mut corner_radius = corner_radius.max(0.0).min((radii[1] - radii[0]) / 2.0);
+            if aperture < PI / 2.0
+            {
+                corner_radius = corner_radius.min(radii[1] * aperture.sin() / (1.0 + aperture.sin()));
+            }
+            let arc = Arc{radii, aperture, corner_radius};
+
+            let (x_axis, y_axis) = ([middle.cos(), middle.sin()], [middle.sin(), -middle.cos()]);
+            let half_side = radii[1] + 1.0;
+            let mut locals = [[0.0; 2]; 4];
+            let mut corners = [[0.0; 2]; 4];
+            for (i, corner) in [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]].iter().enumerate()
+            {
+                locals[i] = [corner[0] * half_side, corner[1] * half_side];
+                let delta = [locals[i][0] * x_axis[0] + locals[i][1] * y_axis[0], locals[i][0] * x_axis[1] + locals[i][1] * y_axis[1]];
+                corners[i] = self.project(projection, [center[0] + delta[0], center[1] + delta[1], center[2]]);
+            }
+
+            // arc.frag
+            for (a, b, c) in &[(0, 1, 3), (0, 2, 3)]
+            {
+                let triangle = [corners[*a], corners[*b], corners[*c]];
+                let local = [locals[*a], locals[*b], locals[*c]];
+                self.fill_triangle(triangle, |pixel, p, weights|
+                {
+                    let g_local = interpolate(local, weights);
+                    let d = arc.distance_shape(g_local);
+                    let dx = arc.distance_shape(interpolate(local, barycentric(triangle, [p[0] + 1.0, p[1]]))) - d;
+                    let dy = arc.distance_shape(interpolate(local, barycentric(triangle, [p[0], p[1] + 1.0]))) - d;
+                    let coverage = (0.5 - d / length([dx, dy]).max(1e-4)).clamp(0.0, 1.0);
+                    if coverage > 0.0
+                    {
+                        blend(pixel, [color[0], color[1], color[2], color[3] * coverage]);
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contrast::camera::Camera;
+    use contrast::markscontainer::Contrast;
+
+    #[test]
+    fn draw_arcs()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        // A half donut on the right, and a quarter of pie on the top left with rounded corners.
+        c.add_arc_mark().set_position((50.0, 50.0)).set_inner_radius(20.0).set_outer_radius(40.0).set_end_angle(PI)
+            .set_color((1.0, 0.0, 0.0, 1.0));
+        c.add_arc_mark().set_position((50.0, 50.0)).set_outer_radius(40.0).set_start_angle(-PI / 2.0).set_end_angle(0.0)
+            .set_corner_radius(10.0).set_color((0.0, 0.0, 1.0, 1.0));
+        let vertices = c.get_arcmarks_properties();
+
+        let projection = Camera::init(100, 100).data();
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_arcs(&vertices, &projection, 0.0);
+
+        assert_eq!(fb.get_pixel(80, 50), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(50, 80), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(60, 50), Some([0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(fb.get_pixel(25, 50), Some([0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(fb.get_pixel(30, 30), Some([0.0, 0.0, 1.0, 1.0]));
+        // The rounded corner at the left of the pie leaves its tip empty.
+        assert_eq!(fb.get_pixel(11, 48), Some([0.0, 0.0, 0.0, 0.0]));
+
+        // The edges are anti-aliased.
+        let pixel = fb.get_pixel(89, 50).unwrap();
+        assert!(pixel[3] > 0.0 && pixel[3] < 1.0);
+    }
+}
@@ -0,0 +1,184 @@
+use contrast::image::Image;
+use contrast::properties::Color;
+
+/// Projection matrix given to the shaders, stored column by column
+/// like the one returned by Camera::data.
+pub type M44 = [[f32; 4]; 4];
+
+/// RGBA framebuffer in which the marks are rasterized.
+/// The components are stored in floats, like in the framebuffer of the
+/// GPU renderer, and are only clamped when converted into an Image.
+/// The pixels are stored row by row from the top left corner.
+pub struct Framebuffer
+{
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) pixels: Vec<[f32; 4]>
+}
+
+impl Framebuffer
+{
+    /// Returns a transparent framebuffer of 'width' x 'height' pixels.
+    pub fn new(width: u32, height: u32) -> Self
+    {
+        Framebuffer{width, height, pixels: vec![[0.0; 4]; (width * height) as usize]}
+    }
+
+    /// Fill every pixel with 'color'.
+    pub fn clear<C: Into<Color>>(&mut self, color: C)
+    {
+        let color = *color.into().to_array();
+        for pixel in &mut self.pixels { *pixel = color; }
+    }
+
+    pub fn get_width(&self) -> u32 { self.width }
+
+    pub fn get_height(&self) -> u32 { self.height }
+
+    /// Returns the pixel at the column 'x' and the row 'y', or None if it is outside of the framebuffer.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<[f32; 4]>
+    {
+        if x < self.width && y < self.height { Some(self.pixels[(y * self.width + x) as usize]) } else { None }
+    }
+
+    /// Converts the framebuffer into an image, clamping each component between 0 and 1.
+    pub fn to_image(&self) -> Image
+    {
+        let mut image = Image::new(self.width, self.height);
+        for (i, pixel) in self.pixels.iter().enumerate()
+        {
+            let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+            image.set_pixel(x, y, Color{r: pixel[0], g: pixel[1], b: pixel[2], a: pixel[3]});
+        }
+        image
+    }
+
+    /// Transforms 'position' with 'projection' like the shaders do, then returns its
+    /// coordinates in pixels from the top left corner of the framebuffer.
+    pub(crate) fn project(&self, projection: &M44, position: [f32; 3]) -> [f32; 2]
+    {
+        let v = [position[0], position[1], position[2], 1.0];
+        let clip = |row: usize| (0..4).map(|col| projection[col][row] * v[col]).sum::<f32>();
+        let w = clip(3);
+        let (x, y) = (clip(0) / w, clip(1) / w);
+        [(x + 1.0) / 2.0 * self.width as f32, (1.0 - y) / 2.0 * self.height as f32]
+    }
+
+    /// Calls 'shade' for each pixel whose center is inside the triangle 'v', with the pixel,
+    /// its coordinates and its barycentric coordinates in the triangle.
+    /// A pixel on an edge shared by two triangles is given to only one of them, like OpenGL
+    /// does, so that a blended pixel is not drawn twice.
+    pub(crate) fn fill_triangle<F>(&mut self, v: [[f32; 2]; 3], mut shade: F)
+        where F: FnMut(&mut [f32; 4], [f32; 2], [f32; 3])
+    {
+        if v.iter().any(|p| !p[0].is_finite() || !p[1].is_finite()) { return; }
+
+        // Make the triangle counter-clockwise on the screen, then give back the
+        // barycentric coordinates in the order of the vertices.
+        let flip = edge(v[0], v[1], v[2]) < 0.0;
+        let v = if flip { [v[0], v[2], v[1]] } else { v };
+        let area = edge(v[0], v[1], v[2]);
+        if area == 0.0 { return; }
+
+        let min_x = v.iter().map(|p| p[0]).fold(f32::INFINITY, f32::min).floor().max(0.0) as u32;
+        let min_y = v.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min).floor().max(0.0) as u32;
+        let max_x = (v.iter().map(|p| p[0]).fold(f32::NEG_INFINITY, f32::max).ceil().max(0.0) as u32).min(self.width);
+        let max_y = (v.iter().map(|p| p[1]).fold(f32::NEG_INFINITY, f32::max).ceil().max(0.0) as u32).min(self.height);
+        let edges = [(v[1], v[2]), (v[2], v[0]), (v[0], v[1])];
+
+        for y in min_y..max_y
+        {
+            for x in min_x..max_x
+            {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let mut weights = [0.0; 3];
+                let mut inside = true;
+                for (i, (a, b)) in edges.iter().enumerate()
+                {
+                    let e = edge(*a, *b, p);
+                    inside &= e > 0.0 || (e == 0.0 && owns_edge(*a, *b));
+                    weights[i] = e / area;
+                }
+                if flip { weights.swap(1, 2); }
+                if inside
+                {
+                    shade(&mut self.pixels[(y * self.width + x) as usize], p, weights);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the barycentric coordinates of 'p' in the triangle 'v', even outside of it.
+/// Allows to compute the derivatives of the interpolated values, like dFdx and dFdy do.
+pub(crate) fn barycentric(v: [[f32; 2]; 3], p: [f32; 2]) -> [f32; 3]
+{
+    let area = edge(v[0], v[1], v[2]);
+    [edge(v[1], v[2], p) / area, edge(v[2], v[0], p) / area, edge(v[0], v[1], p) / area]
+}
+
+/// Interpolates the values 'values' of the vertices of a triangle.
+pub(crate) fn interpolate(values: [[f32; 2]; 3], weights: [f32; 3]) -> [f32; 2]
+{
+    let mut result = [0.0; 2];
+    for (value, weight) in values.iter().zip(weights.iter())
+    {
+        result[0] += value[0] * weight;
+        result[1] += value[1] * weight;
+    }
+    result
+}
+
+/// Blends 'color' over 'pixel' with the factors (SrcAlpha, 1 - SrcAlpha) used for the texts.
+pub(crate) fn blend(pixel: &mut [f32; 4], color: [f32; 4])
+{
+    for i in 0..4
+    {
+        pixel[i] = color[i] * color[3] + pixel[i] * (1.0 - color[3]);
+    }
+}
+
+/// Twice the signed area of the triangle (a, b, p).
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32
+{
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Tells which of two triangles sharing the edge (a, b) draws the pixels exactly on it.
+/// Exactly one of (a, b) and (b, a) is owned.
+fn owns_edge(a: [f32; 2], b: [f32; 2]) -> bool
+{
+    b[1] > a[1] || (b[1] == a[1] && b[0] < a[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project()
+    {
+        let fb = Framebuffer::new(200, 100);
+        let projection = contrast::camera::Camera::init(200, 100).data();
+        let p = fb.project(&projection, [50.0, 25.0, 0.0]);
+        assert!((p[0] - 50.0).abs() < 1e-4 && (p[1] - 25.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fill_triangle()
+    {
+        // Two triangles sharing a diagonal cover each pixel of the square exactly once.
+        let mut fb = Framebuffer::new(4, 4);
+        fb.fill_triangle([[0.0, 0.0], [4.0, 0.0], [4.0, 4.0]], |pixel, _, _| pixel[0] += 1.0);
+        fb.fill_triangle([[0.0, 0.0], [0.0, 4.0], [4.0, 4.0]], |pixel, _, _| pixel[0] += 1.0);
+        assert!(fb.pixels.iter().all(|pixel| pixel[0] == 1.0));
+
+        // The barycentric coordinates are given in the order of the vertices.
+        let mut fb = Framebuffer::new(4, 4);
+        fb.fill_triangle([[0.0, 0.0], [8.0, 0.0], [0.0, 8.0]], |pixel, p, weights| {
+            pixel[0] = weights[0];
+            assert_eq!(barycentric([[0.0, 0.0], [8.0, 0.0], [0.0, 8.0]], p), weights);
+        });
+        assert_eq!(fb.get_pixel(0, 0).unwrap()[0], 0.875);
+    }
+}
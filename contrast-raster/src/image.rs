@@ -0,0 +1,160 @@
+//! Same drawing as contrast/src/shaders/image : each image is a rotated rectangle
+//! textured with its part of the atlas of the images, multiplied by its color and
+//! blended over the marks.
+
+use contrast::marks::imagemark::{VertexImage, ImageTexels, IMAGE_ATLAS_SIZE};
+use crate::animation::{progress_of, mix, mix2, mix3, mix4};
+use crate::framebuffer::{Framebuffer, M44, blend, interpolate};
+
+/// Texture atlas of the images, with 4 floats per pixel like the atlas of the GPU renderer.
+/// Its texels are only allocated when the first image is uploaded.
+pub struct ImageAtlas
+{
+    texels: Vec<[f32; 4]>
+}
+
+impl ImageAtlas
+{
+    /// Returns an empty atlas.
+    pub fn new() -> Self
+    {
+        ImageAtlas{texels: Vec::new()}
+    }
+
+    /// Copy the pixels of an image at their place in the atlas.
+    pub fn upload(&mut self, image: &ImageTexels)
+    {
+        let size = IMAGE_ATLAS_SIZE as usize;
+        if self.texels.is_empty()
+        {
+            self.texels = vec![[0.0; 4]; size * size];
+        }
+        let (x, y, w) = (image.rect.x as usize, image.rect.y as usize, image.rect.width as usize);
+        for (row, line) in image.texels.chunks(4 * w.max(1)).take(image.rect.height as usize).enumerate()
+        {
+            for (column, texel) in line.chunks(4).enumerate()
+            {
+                self.texels[(y + row) * size + x + column].copy_from_slice(texel);
+            }
+        }
+    }
+
+    /// Returns the color of the atlas at the texture coordinates 'uv', with the
+    /// bilinear filtering and the clamping to the edges of the default sampler.
+    fn sample(&self, uv: [f32; 2]) -> [f32; 4]
+    {
+        if self.texels.is_empty()
+        {
+            return [0.0; 4];
+        }
+        let size = IMAGE_ATLAS_SIZE as i64;
+        let texel = |x: i64, y: i64| self.texels[(y.max(0).min(size - 1) * size + x.max(0).min(size - 1)) as usize];
+        let (x, y) = (uv[0] * size as f32 - 0.5, uv[1] * size as f32 - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = mix4(texel(x0, y0), texel(x0 + 1, y0), fx);
+        let bottom = mix4(texel(x0, y0 + 1), texel(x0 + 1, y0 + 1), fx);
+        mix4(top, bottom, fy)
+    }
+}
+
+impl Default for ImageAtlas
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl Framebuffer
+{
+    /// Draw the image marks, as returned by Contrast::get_imagemarks_properties, at the
+    /// time 't' of the animations. The atlas must contain every image given by contrast so far.
+    pub fn draw_images(&mut self, vertices: &[VertexImage], images: &ImageAtlas, projection: &M44, t: f32)
+    {
+        for v in vertices
+        {
+            // image.vert
+            let center = mix3(v.0, v.1, progress_of(v.2, t));
+            let size = mix2(v.3, v.4, progress_of(v.5, t));
+            let color = mix4(v.6, v.7, progress_of(v.8, t));
+            let rotation = mix(v.9[0], v.9[1], progress_of(v.10, t));
+
+            // image.geom
+            let (cos_rotation, sin_rotation) = (rotation.cos(), rotation.sin());
+            let corners = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+            let mut tex = [[0.0; 2]; 4];
+            let mut positions = [[0.0; 2]; 4];
+            for (i, corner) in corners.iter().enumerate()
+            {
+                let delta = [corner[0] * size[0] / 2.0, corner[1] * size[1] / 2.0];
+                tex[i] = [mix(v.11[0], v.11[2], corner[0] * 0.5 + 0.5), mix(v.11[1], v.11[3], corner[1] * 0.5 + 0.5)];
+                let x = delta[0] * cos_rotation - delta[1] * sin_rotation;
+                let y = sin_rotation * delta[0] + cos_rotation * delta[1];
+                positions[i] = self.project(projection, [center[0] + x, center[1] + y, center[2]]);
+            }
+
+            // image.frag
+            for (a, b, c) in &[(0, 1, 3), (0, 2, 3)]
+            {
+                let triangle = [positions[*a], positions[*b], positions[*c]];
+                let coordinates = [tex[*a], tex[*b], tex[*c]];
+                self.fill_triangle(triangle, |pixel, _, weights|
+                {
+                    let sampled = images.sample(interpolate(coordinates, weights));
+                    blend(pixel, [color[0] * sampled[0], color[1] * sampled[1], color[2] * sampled[2], color[3] * sampled[3]]);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contrast::camera::Camera;
+    use contrast::markscontainer::Contrast;
+    use contrast::image::Image;
+    use contrast::properties::Color;
+
+    #[test]
+    fn draw_images()
+    {
+        let mut c = Contrast::new();
+        // Each quarter of the image has its color.
+        let mut image = Image::new(4, 4);
+        for (x, y) in (0..4).flat_map(|x| (0..4).map(move |y| (x, y)))
+        {
+            let color = [Color::red(), Color::green(), Color::blue(), Color::white()][(x / 2 + 2 * (y / 2)) as usize];
+            image.set_pixel(x, y, color);
+        }
+        c.register_image("logo", image).unwrap();
+        let mut images = ImageAtlas::new();
+        for image in c.get_new_images()
+        {
+            images.upload(&image);
+        }
+
+        c.init();
+        c.add_image_mark().set_image("logo").set_position((50.0, 50.0)).set_size((80.0, 80.0));
+        c.add_image_mark().set_image("logo").set_position((50.0, 50.0)).set_size((10.0, 10.0))
+            .set_rotation(std::f32::consts::PI).set_color((1.0, 1.0, 1.0, 0.5));
+        let vertices = c.get_imagemarks_properties();
+
+        let projection = Camera::init(100, 100).data();
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_images(&vertices, &images, &projection, 0.0);
+
+        // The corners of the image are not blended with the other quarters.
+        assert_eq!(fb.get_pixel(12, 12), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(87, 12), Some([0.0, 1.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(12, 87), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(fb.get_pixel(87, 87), Some([1.0, 1.0, 1.0, 1.0]));
+        assert_eq!(fb.get_pixel(5, 5), Some([0.0, 0.0, 0.0, 0.0]));
+
+        // The small image is upside down and half transparent over the large one.
+        let pixel = fb.get_pixel(46, 46).unwrap();
+        assert!(pixel[0] > 0.5 && pixel[1] > 0.5 && pixel[2] > 0.5);
+    }
+}
@@ -0,0 +1,254 @@
+//! Renderer drawing the marks of Contrast without OpenGL.
+//! The vertices given to the shaders of the GPU renderer are rasterized on the
+//! CPU with the same computations, so this renderer can be used on servers without
+//! any GPU, or to compare the output of the GPU renderer in tests, as the tests
+//! of contrast-renderer do wherever an OpenGL context can be created.
+
+mod animation;
+mod framebuffer;
+mod point;
+mod line;
+mod polygon;
+mod text;
+mod image;
+mod arc;
+
+use std::collections::{HashMap, LinkedList};
+use contrast::camera::Camera;
+use contrast::markscontainer::Contrast;
+use contrast::marks::mark::MarkTy;
+use contrast::marks::pointmark::VertexPoint;
+use contrast::marks::linemark::VertexSubLine;
+use contrast::marks::polygonmark::VertexPolygon;
+use contrast::marks::imagemark::VertexImage;
+use contrast::marks::arcmark::VertexArc;
+use contrast::marks::textmark::{VertexText, TextMarkCmd};
+use contrast::properties::Color;
+use contrast::image::Image;
+use contrast::error::Error;
+
+pub use crate::framebuffer::{Framebuffer, M44};
+pub use crate::text::Atlas;
+pub use crate::point::ShapeAtlas;
+pub use crate::image::ImageAtlas;
+
+/// Renders the marks of a Contrast into images, like LumiRenderer::render_to_image does.
+/// The vertices and the font atlases are kept between two renderings, so the renderer
+/// must always be used with the same Contrast.
+/// Each rendering takes the modified marks, the custom shapes and the images of the Contrast
+/// not taken yet, as LumiRenderer does, so a Contrast cannot be shared with another renderer :
+/// to compare two renderers, give each one its own Contrast holding the same marks.
+pub struct RasterRenderer
+{
+    background_color: Color,
+    points: Vec<VertexPoint>,
+    lines: Vec<VertexSubLine>,
+    markers: Vec<VertexPoint>,
+    polygons: Vec<VertexPolygon>,
+    images: Vec<VertexImage>,
+    arcs: Vec<VertexArc>,
+    texts: Vec<VertexText>,
+    commands: LinkedList<TextMarkCmd>,
+    atlases: HashMap<String, Atlas>,
+    shapes: ShapeAtlas,
+    image_atlas: ImageAtlas
+}
+
+impl RasterRenderer
+{
+    pub fn new() -> Self
+    {
+        RasterRenderer
+        {
+            background_color: Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 },
+            points: Vec::new(),
+            lines: Vec::new(),
+            markers: Vec::new(),
+            polygons: Vec::new(),
+            images: Vec::new(),
+            arcs: Vec::new(),
+            texts: Vec::new(),
+            commands: LinkedList::new(),
+            atlases: HashMap::new(),
+            shapes: ShapeAtlas::new(),
+            image_atlas: ImageAtlas::new()
+        }
+    }
+
+    /// Change the background color.
+    pub fn set_background_color<C: Into<Color>>(&mut self, color: C)
+    {
+        self.background_color = color.into();
+    }
+
+    /// Render the marks of 'contrast' into an image of 'width' x 'height' pixels, at the
+    /// current time of its clock. The marks are zoomed by the scale of 'contrast', as
+    /// LumiRenderer::set_camera_scale zooms them in the window.
+    /// Unlike LumiRenderer::render_to_image, the clock does not tick and the animations are
+    /// not updated : call Contrast::tick and Contrast::update_animations before, to get the
+    /// same frames than the GPU renderer and the finished animations.
+    /// Returns an error if the text marks cannot be converted into vertices.
+    pub fn render(&mut self, contrast: &mut Contrast, width: u32, height: u32) -> Result<Image, Error>
+    {
+        self.update(contrast)?;
+
+        let mut camera = Camera::init(width, height);
+        camera.set_scale(contrast.get_scale());
+        let projection = camera.data();
+        let time = contrast.get_time();
+        let mut framebuffer = Framebuffer::new(width, height);
+        framebuffer.clear(self.background_color);
+        framebuffer.draw_points(&self.points, &self.shapes, &projection, time);
+        framebuffer.draw_lines(&self.lines, &projection, time);
+        framebuffer.draw_points(&self.markers, &self.shapes, &projection, time);
+        framebuffer.draw_polygons(&self.polygons, &projection, time);
+        framebuffer.draw_arcs(&self.arcs, &projection, time);
+        framebuffer.draw_images(&self.images, &self.image_atlas, &projection, time);
+        framebuffer.draw_texts(&self.texts, &self.commands, &self.atlases, &projection, time);
+        Ok(framebuffer.to_image())
+    }
+
+    /// Rebuild the vertices of the types of marks modified since the last rendering,
+    /// and upload the custom shapes and the images registered since then.
+    fn update(&mut self, contrast: &mut Contrast) -> Result<(), Error>
+    {
+        for shape in contrast.get_new_shapes()
+        {
+            self.shapes.upload(&shape);
+        }
+        for image in contrast.get_new_images()
+        {
+            self.image_atlas.upload(&image);
+        }
+        for (ty, _) in contrast.fetch_update()
+        {
+            match ty
+            {
+                MarkTy::Point => self.points = contrast.get_pointmarks_properties(),
+                MarkTy::Line =>
+                {
+                    self.lines = contrast.get_linemarks_properties();
+                    self.markers = contrast.get_linemarks_markers();
+                }
+                MarkTy::Polygon => self.polygons = contrast.get_polygonmarks_properties(),
+                MarkTy::Image => self.images = contrast.get_imagemarks_properties(),
+                MarkTy::Arc => self.arcs = contrast.get_arcmarks_properties(),
+                MarkTy::Text =>
+                {
+                    let (vertices, commands, glyphs) = contrast.get_textmarks_properties()?;
+                    self.texts = vertices;
+                    self.commands = commands;
+                    for glyph in glyphs
+                    {
+                        self.atlases.entry(glyph.name.clone()).or_default().upload(&glyph);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for RasterRenderer
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contrast::clock::ManualClock;
+    use contrast::properties::Size;
+    use contrast::marks::linemark::Marker;
+    use contrast::MarkMacro;
+
+    #[test]
+    fn render()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.set_clock(ManualClock::fixed_step(1.0));
+        let mark = c.add_point_mark()
+            .set_position((50.0, 50.0, 0.0))
+            .set_size((20.0, 20.0))
+            .set_color((1.0, 0.0, 0.0, 1.0))
+            .get_id();
+
+        let mut renderer = RasterRenderer::new();
+        renderer.set_background_color((1.0, 1.0, 1.0, 1.0));
+        let image = renderer.render(&mut c, 100, 100).unwrap();
+        assert_eq!(image.get_pixel(50, 50), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(5, 5), Some([255, 255, 255, 255]));
+
+        // Only the modified marks are rebuilt, the others are kept.
+        c.get_mark_mut(&mark).unwrap().as_point_mark_mut().unwrap().set_size(Size { width : 4.0, height : 4.0 });
+        let image = renderer.render(&mut c, 100, 100).unwrap();
+        assert_eq!(image.get_pixel(50, 50), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(56, 50), Some([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn render_scale()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_polygon_mark()
+            .add_point((10.0, 10.0, 0.0)).add_point((20.0, 10.0, 0.0)).add_point((20.0, 20.0, 0.0)).add_point((10.0, 20.0, 0.0))
+            .set_stroke_width(0.0)
+            .set_fill()
+            .set_color((1.0, 0.0, 0.0, 1.0));
+        c.set_scale(2.0);
+
+        // The square is zoomed from the top left corner, from 20 to 40 pixels.
+        let mut renderer = RasterRenderer::new();
+        renderer.set_background_color((1.0, 1.0, 1.0, 1.0));
+        let image = renderer.render(&mut c, 100, 100).unwrap();
+        assert_eq!(image.get_pixel(30, 30), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(15, 15), Some([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(45, 45), Some([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn one_renderer_per_contrast()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_point_mark()
+            .set_position((50.0, 50.0, 0.0))
+            .set_size((20.0, 20.0))
+            .set_color((1.0, 0.0, 0.0, 1.0));
+
+        let mut first = RasterRenderer::new();
+        assert_eq!(first.render(&mut c, 100, 100).unwrap().get_pixel(50, 50), Some([255, 0, 0, 255]));
+        // The first renderer took the new mark, so another renderer does not see it.
+        let mut second = RasterRenderer::new();
+        assert_eq!(second.render(&mut c, 100, 100).unwrap().get_pixel(50, 50), Some([0, 0, 0, 0]));
+        assert_eq!(first.render(&mut c, 100, 100).unwrap().get_pixel(50, 50), Some([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn render_markers()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark()
+            .add_point((10.0, 50.0, 0.0))
+            .add_point((80.0, 50.0, 0.0))
+            .set_thickness(2.0)
+            .set_color((1.0, 0.0, 0.0, 1.0))
+            .set_end_marker(Marker::Arrow);
+
+        let mut renderer = RasterRenderer::new();
+        renderer.set_background_color((1.0, 1.0, 1.0, 1.0));
+        let image = renderer.render(&mut c, 100, 100).unwrap();
+        // The arrowhead is 8 pixels long, its tip on the last point.
+        assert_eq!(image.get_pixel(74, 46), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(74, 53), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(79, 49), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(78, 46), Some([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(81, 49), Some([255, 255, 255, 255]));
+    }
+}
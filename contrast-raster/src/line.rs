@@ -0,0 +1,301 @@
+//! Same drawing as contrast/src/shaders/line : each sub-line is a quad
+//! whose ends are cut along the miters with the previous and the next sub-lines,
+//! or extended by caps and followed by a round or beveled join.
+
+use contrast::marks::linemark::{VertexSubLine, MAX_DASHES};
+use crate::animation::{progress_packed, mix, mix3, mix4, unpack_color};
+use crate::framebuffer::{Framebuffer, M44, interpolate};
+
+pub(crate) fn normalize(v: [f32; 2]) -> [f32; 2]
+{
+    let length = (v[0] * v[0] + v[1] * v[1]).sqrt();
+    [v[0] / length, v[1] / length]
+}
+
+pub(crate) fn dot(a: [f32; 2], b: [f32; 2]) -> f32
+{
+    a[0] * b[0] + a[1] * b[1]
+}
+
+pub(crate) fn xy(v: [f32; 3]) -> [f32; 2]
+{
+    [v[0], v[1]]
+}
+
+/// Returns 'v' moved of 'offset' times 'length' on the screen plane.
+pub(crate) fn offset(v: [f32; 3], offset: [f32; 2], length: f32) -> [f32; 3]
+{
+    [v[0] + offset[0] * length, v[1] + offset[1] * length, v[2]]
+}
+
+/// Returns the distance from 's' to the interval [a, b], which is far away when it is empty.
+fn gap_to(s: f32, a: f32, b: f32) -> f32
+{
+    if a > b { return 1e20; }
+    (a - s).max(s - b).max(0.0)
+}
+
+/// Returns the distance along the line from 's' to the nearest dash, like gap in line.frag.
+/// Each dash of the pattern is followed by its gap, the pattern being padded with zeros.
+fn gap(s: f32, length: f32, dashes: [f32; MAX_DASHES], dash_offset: f32) -> f32
+{
+    let pattern: f32 = dashes.iter().sum();
+    if pattern <= 0.0 { return gap_to(s, 0.0, length); }
+    let period = ((s + dash_offset) / pattern).floor() * pattern - dash_offset;
+    let mut result = 1e20_f32;
+    for k in -1..=1
+    {
+        let mut start = period + k as f32 * pattern;
+        for dash in dashes.chunks(2)
+        {
+            result = result.min(gap_to(s, start.max(0.0), (start + dash[0]).min(length)));
+            start += dash[0] + dash[1];
+        }
+    }
+    result
+}
+
+/// Returns the coordinates of 'position' along and across the line, like emit in line.geom.
+fn line_coordinates(position: [f32; 3], origin: [f32; 3], along: f32, t: [f32; 2]) -> [f32; 2]
+{
+    let offset = [position[0] - origin[0], position[1] - origin[1]];
+    [along + dot(offset, t), dot(offset, [-t[1], t[0]])]
+}
+
+/// The properties of a sub-line needed to discard its pixels, like the flat inputs of line.frag.
+struct Stroke
+{
+    color: [f32; 4],
+    target_color: [f32; 4],
+    /// Distances along the line of the origin and of the target, between which the colors are interpolated.
+    span: [f32; 2],
+    width: f32,
+    length: f32,
+    dashes: [f32; MAX_DASHES],
+    dash_offset: f32,
+    cap: u32
+}
+
+const BUTT: u32 = 0;
+const MITER: u32 = 0;
+const ROUND: u32 = 1;
+
+impl Framebuffer
+{
+    /// Fills the triangle strip made of 'vertices' whose coordinates on the line are 'lines',
+    /// and discards the pixels outside of the dashes and of their caps like line.frag.
+    /// 'join' is the distance along the line of the center of a round join, if any.
+    fn fill_line_strip(&mut self, vertices: &[[f32; 2]], lines: &[[f32; 2]], stroke: &Stroke, join: Option<f32>)
+    {
+        let (w, cap) = (stroke.width, stroke.cap);
+        for i in 0..vertices.len() - 2
+        {
+            let values = [lines[i], lines[i + 1], lines[i + 2]];
+            self.fill_triangle([vertices[i], vertices[i + 1], vertices[i + 2]], |pixel, _, weights| {
+                let mut line = interpolate(values, weights);
+                if let Some(join) = join
+                {
+                    if ((line[0] - join).powi(2) + line[1] * line[1]).sqrt() > w { return; }
+                    line = [join, 0.0];
+                }
+                // The dashes are extended by their caps : a half square or a half disc.
+                let g = gap(line[0], stroke.length, stroke.dashes, stroke.dash_offset);
+                if (cap == BUTT && g > 0.0) || g > w || (cap == ROUND && g * g + line[1] * line[1] > w * w) { return; }
+                let (start, end) = (stroke.span[0], stroke.span[1]);
+                let along = if end > start { ((line[0] - start) / (end - start)).clamp(0.0, 1.0) } else { 0.0 };
+                *pixel = mix4(stroke.color, stroke.target_color, along);
+            });
+        }
+    }
+
+    /// Draw the sub-lines of the line marks, as returned by Contrast::get_linemarks_properties,
+    /// at the time 't' of the animations.
+    pub fn draw_lines(&mut self, vertices: &[VertexSubLine], projection: &M44, t: f32)
+    {
+        for v in vertices
+        {
+            // line.vert : properties are in this order : color, points and thickness.
+            let easing = v.14[0];
+            let p = progress_packed(v.12, v.13, easing, 1, t);
+            let position = mix3(v.1, v.2, p);
+            let target = mix3(v.3, v.4, p);
+            let previous = mix3(v.5, v.6, p);
+            let next = mix3(v.7, v.8, p);
+            let pc = progress_packed(v.12, v.13, easing, 0, t);
+            let color = mix4(unpack_color(v.0[0]), unpack_color(v.0[1]), pc);
+            let target_color = mix4(unpack_color(v.0[2]), unpack_color(v.0[3]), pc);
+            let thick = mix(v.9[0], v.9[1], progress_packed(v.12, v.13, easing, 2, t));
+            let (start, length) = (mix(v.10[0], v.10[1], p), mix(v.10[2], v.10[3], p));
+            let (miter_limit, join) = (v.9[2], v.14[1] >> 2);
+
+            // line.geom
+            let w = thick / 2.0;
+            let end = start + ((target[0] - position[0]).powi(2) + (target[1] - position[1]).powi(2)).sqrt();
+            let stroke = Stroke { color, target_color, span: [start, end], width: w, length, dashes: v.11, dash_offset: v.9[3], cap: v.14[1] & 3 };
+            let t0 = normalize([position[0] - previous[0], position[1] - previous[1]]);
+            let t1 = normalize([next[0] - target[0], next[1] - target[1]]);
+            let t2 = normalize([target[0] - position[0], target[1] - position[1]]);
+
+            let n0 = [-t0[1], t0[0]];
+            let n1 = [-t2[1], t2[0]];
+            let n2 = [-t1[1], t1[0]];
+            let has_miter = |a: [f32; 2], b: [f32; 2]| join == MITER && 1.0 / dot(normalize([a[0] + b[0], a[1] + b[1]]), a) <= miter_limit;
+
+            let first = xy(previous) == xy(position);
+            let last = xy(target) == xy(next);
+            let miter_start = !first && has_miter(n0, n1);
+            let miter_end = !last && has_miter(n1, n2);
+            // The ends of the line are extended by the caps, which are then cut by the pixels.
+            let cap_length = if stroke.cap == BUTT { 0.0 } else { w };
+
+            let (a, b) = if miter_start
+            {
+                let miter = normalize([n0[0] + n1[0], n0[1] + n1[1]]);
+                let dy = w / dot(miter, n0);
+                (offset(position, miter, dy), offset(position, miter, -dy))
+            }
+            else
+            {
+                let base = if first { offset(position, t2, -cap_length) } else { position };
+                (offset(base, n1, w), offset(base, n1, -w))
+            };
+            let (c, d) = if miter_end
+            {
+                let miter2 = normalize([n1[0] + n2[0], n1[1] + n2[1]]);
+                let dy2 = w / dot(miter2, n2);
+                (offset(target, miter2, dy2), offset(target, miter2, -dy2))
+            }
+            else
+            {
+                let base = if last { offset(target, t2, cap_length) } else { target };
+                (offset(base, n1, w), offset(base, n1, -w))
+            };
+
+            let quad = [a, b, c, d];
+            let screen: Vec<[f32; 2]> = quad.iter().map(|q| self.project(projection, *q)).collect();
+            let lines: Vec<[f32; 2]> = quad.iter().map(|q| line_coordinates(*q, position, start, t2)).collect();
+            self.fill_line_strip(&screen, &lines, &stroke, None);
+
+            if last || miter_end { continue; }
+            let (joint, join_center) = if join == ROUND
+            {
+                // A square around the joint, cut into a disc by the pixels.
+                let corner = |u: f32, v: f32| offset(target, [n1[0] * u + t2[0] * v, n1[1] * u + t2[1] * v], w);
+                (vec![corner(1.0, -1.0), corner(-1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)], Some(end))
+            }
+            else
+            {
+                // A bevel fills the corner outside the turn, and takes the dash of the joint.
+                let side = if t2[0] * t1[1] - t2[1] * t1[0] > 0.0 { -1.0 } else { 1.0 };
+                (vec![target, offset(target, n1, w * side), offset(target, n2, w * side)], None)
+            };
+            let direction = if join_center.is_some() { t2 } else { [0.0, 0.0] };
+            let screen: Vec<[f32; 2]> = joint.iter().map(|q| self.project(projection, *q)).collect();
+            let lines: Vec<[f32; 2]> = joint.iter().map(|q| line_coordinates(*q, target, end, direction)).collect();
+            self.fill_line_strip(&screen, &lines, &stroke, join_center);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contrast::camera::Camera;
+
+    /// A sub-line 10 pixels thick starting at the distance 'start' of a line of length 'length'.
+    fn subline(origin: [f32; 3], target: [f32; 3], previous: [f32; 3], next: [f32; 3], start: f32, length: f32) -> VertexSubLine
+    {
+        let color = contrast::properties::Color::green().to_rgba8();
+        ([color; 4], origin, origin, target, target, previous, previous, next, next,
+         [10.0, 10.0, 4.0, 0.0], [start, start, length, length], [0.0; 4], [0.0; 4], [0.0; 4], [0, 0])
+    }
+
+    #[test]
+    fn draw_lines()
+    {
+        let projection = Camera::init(100, 100).data();
+        let mut fb = Framebuffer::new(100, 100);
+        let (a, b, c) = ([10.0, 50.0, 0.0], [50.0, 50.0, 0.0], [50.0, 90.0, 0.0]);
+        fb.draw_lines(&[subline(a, b, a, c, 0.0, 80.0), subline(b, c, a, c, 40.0, 80.0)], &projection, 0.0);
+
+        let green = Some([0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(fb.get_pixel(10, 50), green);
+        assert_eq!(fb.get_pixel(30, 54), green);
+        assert_eq!(fb.get_pixel(30, 56), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(50, 89), green);
+        // The corner is cut along the miter.
+        assert_eq!(fb.get_pixel(54, 46), green);
+        assert_eq!(fb.get_pixel(44, 56), Some([0.0; 4]));
+    }
+
+    #[test]
+    fn draw_lines_caps_and_joins()
+    {
+        let projection = Camera::init(100, 100).data();
+        let (a, b, c) = ([10.0, 50.0, 0.0], [50.0, 50.0, 0.0], [50.0, 90.0, 0.0]);
+        let green = Some([0.0, 1.0, 0.0, 1.0]);
+        // Round caps and joins : cap = 1, join = 1.
+        let mut sublines = [subline(a, b, a, c, 0.0, 80.0), subline(b, c, a, c, 40.0, 80.0)];
+        for s in sublines.iter_mut() { s.14[1] = 1 | 1 << 2; }
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_lines(&sublines, &projection, 0.0);
+        assert_eq!(fb.get_pixel(7, 50), green);
+        assert_eq!(fb.get_pixel(5, 46), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(53, 46), green);
+        assert_eq!(fb.get_pixel(54, 46), Some([0.0; 4]));
+        // Bevel joins cut the corner.
+        for s in sublines.iter_mut() { s.14[1] = 2 << 2; }
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_lines(&sublines, &projection, 0.0);
+        assert_eq!(fb.get_pixel(7, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(51, 47), green);
+        assert_eq!(fb.get_pixel(54, 46), Some([0.0; 4]));
+    }
+
+    #[test]
+    fn draw_lines_colors()
+    {
+        // The colors are interpolated from the origin to the target of the sub-line.
+        let projection = Camera::init(100, 100).data();
+        let (a, b) = ([10.0, 50.0, 0.0], [90.0, 50.0, 0.0]);
+        let mut line = subline(a, b, a, b, 0.0, 80.0);
+        line.0 = [0xff0000ff, 0xff0000ff, 0xffff0000, 0xffff0000];
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_lines(&[line], &projection, 0.0);
+        assert!(fb.get_pixel(10, 50).unwrap()[0] > 0.98);
+        let middle = fb.get_pixel(50, 50).unwrap();
+        assert!((middle[0] - 0.5).abs() < 0.02 && (middle[2] - 0.5).abs() < 0.02);
+        assert!(fb.get_pixel(89, 50).unwrap()[2] > 0.98);
+    }
+
+    #[test]
+    fn draw_lines_dashes()
+    {
+        let projection = Camera::init(100, 100).data();
+        let (a, b) = ([10.0, 50.0, 0.0], [90.0, 50.0, 0.0]);
+        let mut line = subline(a, b, a, b, 0.0, 80.0);
+        line.11 = [10.0, 10.0, 0.0, 0.0];
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_lines(&[line], &projection, 0.0);
+        let green = Some([0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(fb.get_pixel(15, 50), green);
+        assert_eq!(fb.get_pixel(25, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(35, 50), green);
+        // An offset shifts the pattern backwards along the line.
+        line.9[3] = 5.0;
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_lines(&[line], &projection, 0.0);
+        assert_eq!(fb.get_pixel(12, 50), green);
+        assert_eq!(fb.get_pixel(17, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(27, 50), green);
+        // Every dash of the pattern is drawn, not only the first one.
+        line.9[3] = 0.0;
+        line.11 = [10.0, 5.0, 20.0, 5.0];
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_lines(&[line], &projection, 0.0);
+        assert_eq!(fb.get_pixel(22, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(35, 50), green);
+        assert_eq!(fb.get_pixel(47, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(55, 50), green);
+    }
+}
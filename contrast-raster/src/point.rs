@@ -0,0 +1,460 @@
+//! Same drawing as contrast/src/shaders/point : each point is a rectangle
+//! in which the pixels outside of the signed distance of its shape are discarded,
+//! and the pixels near its outline take the color of the stroke.
+
+use std::f32::consts::PI;
+use contrast::marks::pointmark::VertexPoint;
+use contrast::marks::shapeatlas::{ShapeField, SHAPE_SIZE, SHAPE_ATLAS_SIZE, FIRST_CUSTOM_SHAPE};
+use crate::animation::{progress_of, progress_packed, mix, mix2, mix3, mix4, unpack_color};
+use crate::framebuffer::{Framebuffer, M44, barycentric, interpolate};
+
+const SQRT_2: f32 = std::f32::consts::SQRT_2;
+const TWO_PI: f32 = 2.0 * PI;
+
+pub(crate) fn length(v: [f32; 2]) -> f32 { (v[0] * v[0] + v[1] * v[1]).sqrt() }
+
+pub(crate) fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] { [a[0] - b[0], a[1] - b[1]] }
+
+fn scale(v: [f32; 2], s: f32) -> [f32; 2] { [v[0] * s, v[1] * s] }
+
+/// Divides 'f' at 'uv' by the length of its gradient on the screen, computed from
+/// its values at the next pixels like dFdx and dFdy do.
+/// 'dx' and 'dy' are the variations of 'uv' from one pixel to the next one.
+fn normalized<F: Fn([f32; 2]) -> f32>(f: F, uv: [f32; 2], dx: [f32; 2], dy: [f32; 2]) -> f32
+{
+    let d = f(uv);
+    let g = length([f([uv[0] + dx[0], uv[1] + dx[1]]) - d, f([uv[0] + dy[0], uv[1] + dy[1]]) - d]);
+    d / g
+}
+
+/// Three discs of the club and the clover.
+fn clover(uv: [f32; 2]) -> f32
+{
+    let size = 1.0;
+    let t1 = -PI / 2.0;
+    let t2 = t1 + 2.0 * PI / 3.0;
+    let t3 = t2 + 2.0 * PI / 3.0;
+    let r = |t: f32| length(sub(uv, scale([0.225 * t.cos(), 0.225 * t.sin()], size))) - size / 4.25;
+    r(t1).min(r(t2)).min(r(t3))
+}
+
+/// Root of the club and the spade (2 circles and 2 half-planes), from 'top' to 0.5.
+fn root(uv: [f32; 2], top: f32) -> f32
+{
+    let size = 1.0;
+    let r5 = length(sub(uv, scale([0.65, 0.125], size))) - size / 1.6;
+    let r6 = length(sub(uv, scale([-0.65, 0.125], size))) - size / 1.6;
+    let r7 = uv[1] - 0.5 * size;
+    let r8 = top * size - uv[1];
+    (-r5.min(r6)).max(r7.max(r8))
+}
+
+/// Texture atlas of the signed distance fields of the custom shapes, like the one of the GPU renderer.
+/// Its texels are only allocated when the first shape is uploaded.
+pub struct ShapeAtlas
+{
+    texels: Vec<f32>
+}
+
+impl ShapeAtlas
+{
+    /// Returns an empty atlas.
+    pub fn new() -> Self
+    {
+        ShapeAtlas{texels: Vec::new()}
+    }
+
+    /// Copy the field of a custom shape at its place in the atlas.
+    pub fn upload(&mut self, shape: &ShapeField)
+    {
+        let size = SHAPE_ATLAS_SIZE as usize;
+        if self.texels.is_empty()
+        {
+            self.texels = vec![0.0; size * size];
+        }
+        let [x, y] = shape.origin();
+        for (row, line) in shape.field.chunks(SHAPE_SIZE as usize).enumerate()
+        {
+            let start = (y as usize + row) * size + x as usize;
+            self.texels[start..start + line.len()].copy_from_slice(line);
+        }
+    }
+
+    /// Returns the value of the atlas at the texel coordinates 'texel', with the bilinear
+    /// filtering of the default sampler.
+    fn sample(&self, texel: [f32; 2]) -> f32
+    {
+        if self.texels.is_empty()
+        {
+            return 0.0;
+        }
+        let size = SHAPE_ATLAS_SIZE as i64;
+        let value = |x: i64, y: i64| self.texels[(y.max(0).min(size - 1) * size + x.max(0).min(size - 1)) as usize];
+        let (x, y) = (texel[0] - 0.5, texel[1] - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = value(x0, y0) * (1.0 - fx) + value(x0 + 1, y0) * fx;
+        let bottom = value(x0, y0 + 1) * (1.0 - fx) + value(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+impl Default for ShapeAtlas
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+/// Returns the signed distance of 'shape' at 'uv', like distance_shape in point.frag.
+/// The custom shapes are read in 'shapes', and the corners of a rectangle of size 'size'
+/// are rounded with 'corner_radius' pixels.
+pub(crate) fn distance_shape(shape: u32, uv: [f32; 2], dx: [f32; 2], dy: [f32; 2], shapes: &ShapeAtlas, size: [f32; 2], corner_radius: f32) -> f32
+{
+    let (x, y) = (uv[0], uv[1]);
+    match shape
+    {
+        0 => length(uv),
+        // Rectangle
+        1 if corner_radius <= 0.0 => x.abs().max(y.abs()) - 1.0,
+        // Rounded rectangle, whose distance is computed in pixels then brought back
+        // to the scale of the unrounded one along its smallest side.
+        1 => {
+            let half_size = [size[0] / 2.0, size[1] / 2.0];
+            let radius = corner_radius.min(half_size[0].min(half_size[1]));
+            let q = [(x * half_size[0]).abs() - half_size[0] + radius, (y * half_size[1]).abs() - half_size[1] + radius];
+            (length([q[0].max(0.0), q[1].max(0.0)]) + q[0].max(q[1]).min(0.0) - radius) / half_size[0].min(half_size[1])
+        }
+        // Triangle
+        2 => {
+            let a = x.atan2(y) + PI;
+            let r = TWO_PI / 3.0;
+            ((0.5 + a / r).floor() * r - a).cos() * length(uv) - 0.5
+        }
+        // Disc and Ellipse
+        3 => length(uv) - 0.6,
+        // Point
+        4 => length(uv) - 0.1,
+        // Squircle
+        5 => normalized(|uv| uv[0].abs().powf(4.0) + uv[1].abs().powf(4.0) - 1.0, uv, dx, dy),
+        // Diamond
+        6 => normalized(|uv| uv[0].abs() + uv[1].abs() - 1.0, uv, dx, dy),
+        // Donut
+        7 => normalized(|uv| {
+            let r1 = uv[0] * uv[0] + uv[1] * uv[1] - 0.9;
+            let r2 = r1 + 0.7;
+            r1.max(-r2)
+        }, uv, dx, dy),
+        // Pin
+        8 => {
+            let size = 1.0;
+            let c1 = scale([0.0, -0.15], size);
+            let r1 = length(sub(uv, c1)) - size / 2.675;
+            let r2 = length(sub(uv, scale([1.49, -0.80], size))) - 2.0 * size;
+            let r3 = length(sub(uv, scale([-1.49, -0.80], size))) - 2.0 * size;
+            let r4 = length(sub(uv, c1)) - size / 5.0;
+            r1.min(r2.max(r3).max(-y)).max(-r4)
+        }
+        // Club
+        9 => clover(uv).min(root(uv, 0.2)),
+        // Heart
+        10 => {
+            let size = 1.0;
+            let (u, v) = (SQRT_2 / 2.0 * (x - y), SQRT_2 / 2.0 * (x + y));
+            let r1 = u.abs().max(v.abs()) - size / 3.5;
+            let r2 = length(sub(uv, scale([1.0, -1.0], SQRT_2 / 2.0 * size / 3.5))) - size / 3.5;
+            let r3 = length(sub(uv, scale([-1.0, -1.0], SQRT_2 / 2.0 * size / 3.5))) - size / 3.5;
+            r1.min(r2).min(r3)
+        }
+        // Spade
+        11 => {
+            let size = 1.0;
+            // Reversed heart (diamond + 2 circles)
+            let s = size * 0.85 / 3.5;
+            let u = SQRT_2 / 2.0 * (x + y) + 0.4 * s;
+            let v = SQRT_2 / 2.0 * (x - y) - 0.4 * s;
+            let r1 = u.abs().max(v.abs()) - s;
+            let r2 = length(sub(uv, scale([1.0, 0.2], SQRT_2 / 2.0 * s))) - s;
+            let r3 = length(sub(uv, scale([-1.0, 0.2], SQRT_2 / 2.0 * s))) - s;
+            r1.min(r2).min(r3).min(root(uv, 0.1))
+        }
+        // Chevron
+        12 => {
+            let size = 1.0;
+            let (u, v) = (1.0 / SQRT_2 * (x - y), 1.0 / SQRT_2 * (x + y));
+            let r1 = u.abs().max(v.abs()) - size / 3.0;
+            let r2 = (u - size / 3.0).abs().max((v - size / 3.0).abs()) - size / 3.0;
+            r1.max(-r2)
+        }
+        // Clover
+        13 => clover(uv),
+        // Ring
+        14 => {
+            let size = 1.0;
+            let r1 = length(uv) - size / 2.0;
+            let r2 = length(uv) - size / 4.0;
+            r1.max(-r2)
+        }
+        // Tag
+        15 => {
+            let size = 1.0;
+            let r1 = (x.abs() - size / 2.0).max(y.abs() - size / 6.0);
+            let r2 = (x - size / 1.5).abs() + y.abs() - size;
+            r1.max(0.75 * r2)
+        }
+        // Cross
+        16 => {
+            let size = 1.0;
+            let (u, v) = (SQRT_2 / 2.0 * (x - y), SQRT_2 / 2.0 * (x + y));
+            let r1 = (u - size / 3.0).abs().max((u + size / 3.0).abs());
+            let r2 = (v - size / 3.0).abs().max((v + size / 3.0).abs());
+            let r3 = u.abs().max(v.abs());
+            r1.min(r2).max(r3) - size / 2.0
+        }
+        // Asterisk
+        17 => {
+            let size = 1.0;
+            let (u, v) = (SQRT_2 / 2.0 * (x - y), SQRT_2 / 2.0 * (x + y));
+            let r1 = (u.abs() - size / 2.0).max(v.abs() - size / 10.0);
+            let r2 = (v.abs() - size / 2.0).max(u.abs() - size / 10.0);
+            let r3 = (x.abs() - size / 2.0).max(y.abs() - size / 10.0);
+            let r4 = (y.abs() - size / 2.0).max(x.abs() - size / 10.0);
+            r1.min(r2).min(r3.min(r4))
+        }
+        // Infinity
+        18 => {
+            let size = 1.0;
+            let c1 = scale([0.2125, 0.0], size);
+            let c2 = scale([-0.2125, 0.0], size);
+            let r1 = length(sub(uv, c1)) - size / 3.5;
+            let r2 = length(sub(uv, c1)) - size / 7.5;
+            let r3 = length(sub(uv, c2)) - size / 3.5;
+            let r4 = length(sub(uv, c2)) - size / 7.5;
+            r1.max(-r2).min(r3.max(-r4))
+        }
+        // Arrow
+        19 => {
+            let size = 1.0;
+            let r1 = x.abs() + y.abs() - size / 2.0;
+            let r2 = (x + size / 2.0).abs().max(y.abs()) - size / 2.0;
+            let r3 = ((x - size / 6.0).abs() - size / 4.0).max(y.abs() - size / 4.0);
+            r3.min((0.75 * r1).max(r2))
+        }
+        // Custom, read in its field, without reading the next ones
+        s if s >= FIRST_CUSTOM_SHAPE => {
+            let index = s - FIRST_CUSTOM_SHAPE;
+            let per_row = SHAPE_ATLAS_SIZE / SHAPE_SIZE;
+            let size = SHAPE_SIZE as f32;
+            let origin = [(index % per_row) as f32 * size, (index / per_row) as f32 * size];
+            let texel = |c: f32| ((c * 0.5 + 0.5) * size).max(0.5).min(size - 0.5);
+            shapes.sample([origin[0] + texel(x), origin[1] + texel(y)])
+        }
+        _ => length(uv)
+    }
+}
+
+impl Framebuffer
+{
+    /// Draw the point marks, as returned by Contrast::get_pointmarks_properties,
+    /// at the time 't' of the animations. The atlas must contain every custom shape given by contrast so far.
+    pub fn draw_points(&mut self, vertices: &[VertexPoint], shapes: &ShapeAtlas, projection: &M44, t: f32)
+    {
+        for v in vertices
+        {
+            // point.vert
+            let center = mix3(v.0, v.1, progress_of(v.2, t));
+            let size = mix2(v.3, v.4, progress_of(v.5, t));
+            let color = mix4(v.6, v.7, progress_of(v.8, t));
+            let easing = v.13[0];
+            let rotation = mix(v.9[0], v.9[1], progress_packed(v.10, v.11, easing, 0, t));
+            let stroke_width = mix(v.9[2], v.9[3], progress_packed(v.10, v.11, easing, 1, t));
+            let (shape, target_shape, shape_progress) = (v.12[0], v.12[1], progress_packed(v.10, v.11, easing, 2, t));
+            let stroke_color = mix4(unpack_color(v.12[2]), unpack_color(v.12[3]), progress_packed(v.10, v.11, easing, 3, t));
+            let corner_radius = mix(v.14[0], v.14[1], progress_of(v.15, t));
+
+            // point.geom
+            let (mid_width, mid_height) = (size[0] / 2.0, size[1] / 2.0);
+            let (cos_rotation, sin_rotation) = (rotation.cos(), rotation.sin());
+            let deltas = [[mid_width, -mid_height], [mid_width, mid_height], [-mid_width, mid_height], [-mid_width, -mid_height]];
+            let mut uvs = [[0.0; 2]; 4];
+            let mut corners = [[0.0; 2]; 4];
+            for (i, delta) in deltas.iter().enumerate()
+            {
+                // point in object space (not rotated)
+                uvs[i] = [delta[0] / mid_width, delta[1] / mid_height];
+                // point in screen space (rotated)
+                let x = delta[0] * cos_rotation - delta[1] * sin_rotation;
+                let y = sin_rotation * delta[0] + cos_rotation * delta[1];
+                corners[i] = self.project(projection, [center[0] + x, center[1] + y, center[2]]);
+            }
+
+            // point.frag
+            for (a, b, c) in &[(0, 1, 2), (0, 3, 2)]
+            {
+                let triangle = [corners[*a], corners[*b], corners[*c]];
+                let uv = [uvs[*a], uvs[*b], uvs[*c]];
+                self.fill_triangle(triangle, |pixel, p, weights|
+                {
+                    let g_uv = interpolate(uv, weights);
+                    let dx = sub(interpolate(uv, barycentric(triangle, [p[0] + 1.0, p[1]])), g_uv);
+                    let dy = sub(interpolate(uv, barycentric(triangle, [p[0], p[1] + 1.0])), g_uv);
+                    let distance = |shape: u32, uv: [f32; 2]| distance_shape(shape, uv, dx, dy, shapes, size, corner_radius);
+                    let d = |uv: [f32; 2]| mix(distance(shape, uv), distance(target_shape, uv), shape_progress);
+
+                    let dist = d(g_uv);
+                    let gradient = [d([g_uv[0] + dx[0], g_uv[1] + dx[1]]) - dist, d([g_uv[0] + dy[0], g_uv[1] + dy[1]]) - dist];
+                    let width = gradient[0].abs() + gradient[1].abs();
+                    if dist > width
+                    {
+                        return;
+                    }
+                    // Distance in pixels to the outline, inside the shape.
+                    let g = length(gradient);
+                    let inside = if g > 0.0 { -dist / g } else { stroke_width };
+                    if stroke_width > 0.0 && inside < stroke_width
+                    {
+                        *pixel = stroke_color;
+                    }
+                    else if v.13[1] == 1
+                    {
+                        *pixel = color;
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contrast::camera::Camera;
+    use contrast::properties::Color;
+
+    fn point(shape: u32, rotation: f32) -> VertexPoint
+    {
+        let anim = [0.0, 0.0, 0.0];
+        let stroke = Color::blue().to_rgba8();
+        ([50.0, 50.0, 0.0], [50.0, 50.0, 0.0], anim, [80.0, 80.0], [80.0, 80.0], anim,
+         [1.0, 0.0, 0.0, 1.0], [1.0, 0.0, 0.0, 1.0], anim, [rotation, rotation, 0.0, 0.0], [0.0; 4], [0.0; 4],
+         [shape, shape, stroke, stroke], [0, 1], [0.0, 0.0], anim)
+    }
+
+    #[test]
+    fn distance_shape()
+    {
+        let (dx, dy) = ([0.01, 0.0], [0.0, 0.01]);
+        assert!(super::distance_shape(1, [0.9, 0.9], dx, dy, &ShapeAtlas::new(), [80.0, 80.0], 0.0) < 0.0);
+        assert!((super::distance_shape(1, [1.0, 0.5], dx, dy, &ShapeAtlas::new(), [80.0, 80.0], 0.0)).abs() < 1e-6);
+        assert!(super::distance_shape(3, [0.0, 0.0], dx, dy, &ShapeAtlas::new(), [80.0, 80.0], 0.0) < 0.0);
+        assert!(super::distance_shape(3, [0.7, 0.0], dx, dy, &ShapeAtlas::new(), [80.0, 80.0], 0.0) > 0.0);
+        assert!(super::distance_shape(14, [0.0, 0.0], dx, dy, &ShapeAtlas::new(), [80.0, 80.0], 0.0) > 0.0);
+        assert!(super::distance_shape(14, [0.4, 0.0], dx, dy, &ShapeAtlas::new(), [80.0, 80.0], 0.0) < 0.0);
+    }
+
+    #[test]
+    fn draw_points()
+    {
+        let projection = Camera::init(100, 100).data();
+
+        // The rectangle fills its whole size.
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[point(1, 0.0)], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(10, 10), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(89, 89), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(9, 50), Some([0.0, 0.0, 0.0, 0.0]));
+
+        // The disc has a radius of 0.6 times the half of its size.
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[point(3, 0.0)], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 50), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(50, 72), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(50, 77), Some([0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(fb.get_pixel(15, 15), Some([0.0, 0.0, 0.0, 0.0]));
+
+        // A rotated rectangle.
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[point(1, PI / 4.0)], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 8), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(12, 12), Some([0.0, 0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn draw_points_stroke()
+    {
+        let projection = Camera::init(100, 100).data();
+        let (red, blue, empty) = (Some([1.0, 0.0, 0.0, 1.0]), Some([0.0, 0.0, 1.0, 1.0]), Some([0.0, 0.0, 0.0, 0.0]));
+
+        // The stroke of 4 pixels is inside the rectangle, going from 10 to 90.
+        let mut rectangle = point(1, 0.0);
+        rectangle.9 = [0.0, 0.0, 4.0, 4.0];
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[rectangle], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(11, 50), blue);
+        assert_eq!(fb.get_pixel(50, 88), blue);
+        assert_eq!(fb.get_pixel(16, 50), red);
+        assert_eq!(fb.get_pixel(50, 50), red);
+
+        // Without fill, only the ring of the disc is drawn.
+        let mut disc = point(3, 0.0);
+        disc.9 = [0.0, 0.0, 3.0, 3.0];
+        disc.13[1] = 0;
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[disc], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 50), empty);
+        assert_eq!(fb.get_pixel(50, 88), empty);
+        assert_eq!(fb.get_pixel(50, 73), blue);
+        assert_eq!(fb.get_pixel(50, 60), empty);
+    }
+
+    #[test]
+    fn draw_points_rounded()
+    {
+        let projection = Camera::init(100, 100).data();
+        let (red, empty) = (Some([1.0, 0.0, 0.0, 1.0]), Some([0.0, 0.0, 0.0, 0.0]));
+
+        // The corners of 20 pixels are cut, but not the sides of the rectangle going from 10 to 90.
+        let mut rectangle = point(1, 0.0);
+        rectangle.14 = [20.0, 20.0];
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[rectangle], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(12, 12), empty);
+        assert_eq!(fb.get_pixel(88, 87), empty);
+        assert_eq!(fb.get_pixel(17, 17), red);
+        assert_eq!(fb.get_pixel(10, 50), red);
+        assert_eq!(fb.get_pixel(50, 89), red);
+
+        // The radius is reduced to the half of the smallest side, for a pill.
+        rectangle.4 = [80.0, 20.0];
+        rectangle.3 = [80.0, 20.0];
+        rectangle.14 = [50.0, 50.0];
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[rectangle], &ShapeAtlas::new(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 41), red);
+        assert_eq!(fb.get_pixel(11, 50), red);
+        assert_eq!(fb.get_pixel(11, 41), empty);
+    }
+
+    #[test]
+    fn draw_points_custom()
+    {
+        let projection = Camera::init(100, 100).data();
+        let mut c = contrast::markscontainer::Contrast::new();
+        let shape = c.register_shape_path("M0 0h10v10h-10zM2 2v6h6v-6z").unwrap();
+        let mut shapes = ShapeAtlas::new();
+        for field in c.get_new_shapes()
+        {
+            shapes.upload(&field);
+        }
+
+        // The square frame fills the point, without its hole.
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_points(&[point(shape.to_integer(), 0.0)], &shapes, &projection, 0.0);
+        assert_eq!(fb.get_pixel(12, 12), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(20, 50), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(50, 50), Some([0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(fb.get_pixel(5, 5), Some([0.0, 0.0, 0.0, 0.0]));
+    }
+}
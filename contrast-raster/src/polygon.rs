@@ -0,0 +1,244 @@
+//! Same drawing as contrast/src/shaders/polygon : a filled polygon is made
+//! of triangles, and each side of an empty one is a quad going towards the
+//! centroid of its ring, or away from it for a hole, as wide as the stroke.
+
+use contrast::marks::polygonmark::VertexPolygon;
+use crate::animation::{progress_packed, mix, mix3, mix4, unpack_color};
+use crate::framebuffer::{Framebuffer, M44, interpolate};
+use crate::line::{normalize, dot, offset, xy};
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32
+{
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+fn distance_xy(a: [f32; 3], b: [f32; 3]) -> f32
+{
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+const LINEAR: u32 = 1;
+const RADIAL: u32 = 2;
+
+/// Returns how far 'p' is from the color of the polygon to the color of the gradient, like polygon.frag.
+fn gradient_progress(kind: u32, gradient: [f32; 4], p: [f32; 2]) -> f32
+{
+    let progress = match kind
+    {
+        LINEAR =>
+        {
+            let d = [gradient[2] - gradient[0], gradient[3] - gradient[1]];
+            if dot(d, d) > 0.0 { dot([p[0] - gradient[0], p[1] - gradient[1]], d) / dot(d, d) } else { 1.0 }
+        },
+        RADIAL => if gradient[2] > 0.0 { ((p[0] - gradient[0]).powi(2) + (p[1] - gradient[1]).powi(2)).sqrt() / gradient[2] } else { 1.0 },
+        _ => 0.0
+    };
+    progress.clamp(0.0, 1.0)
+}
+
+impl Framebuffer
+{
+    /// Draw the polygon marks, as returned by Contrast::get_polygonmarks_properties,
+    /// at the time 't' of the animations.
+    pub fn draw_polygons(&mut self, vertices: &[VertexPolygon], projection: &M44, t: f32)
+    {
+        for v in vertices
+        {
+            // polygon.vert : properties are in this order : color, points, rotation and stroke width.
+            let easing = v.15[0];
+            let p = progress_packed(v.13, v.14, easing, 1, t);
+            let position = mix3(v.2, v.3, p);
+            let target = mix3(v.4, v.5, p);
+            let previous = mix3(v.6, v.7, p);
+            let next = mix3(v.8, v.9, p);
+            let pc = progress_packed(v.13, v.14, easing, 0, t);
+            let color = mix4(unpack_color(v.0[0]), unpack_color(v.0[1]), pc);
+            let gradient_color = mix4(unpack_color(v.0[2]), unpack_color(v.0[3]), pc);
+            let (gradient, kind) = (v.1, v.15[1] >> 2);
+            let thick = mix(v.12[2], v.12[3], progress_packed(v.13, v.14, easing, 3, t));
+            let centroid = mix3(v.10, v.11, p);
+            let fill = (v.15[1] & 3) as i32;
+            // The gradient is computed from the coordinates of the pixel before the projection.
+            let mut paint = |vertices: [[f32; 3]; 3]|
+            {
+                let positions = [xy(vertices[0]), xy(vertices[1]), xy(vertices[2])];
+                let screen = [self.project(projection, vertices[0]), self.project(projection, vertices[1]), self.project(projection, vertices[2])];
+                self.fill_triangle(screen, |pixel, _, weights| {
+                    *pixel = mix4(color, gradient_color, gradient_progress(kind, gradient, interpolate(positions, weights)));
+                });
+            };
+
+            // polygon.geom
+            if fill == 1
+            {
+                paint([position, target, centroid]);
+                continue;
+            }
+
+            let w = thick;
+            let t0 = normalize([position[0] - previous[0], position[1] - previous[1]]);
+            let t1 = normalize([next[0] - target[0], next[1] - target[1]]);
+            let t2 = normalize([target[0] - position[0], target[1] - position[1]]);
+
+            let n0 = [-t0[1], t0[0]];
+            let n1 = [-t2[1], t2[0]];
+            let n2 = [-t1[1], t1[0]];
+
+            let miter = normalize([n0[0] + n1[0], n0[1] + n1[1]]);
+            let miter2 = normalize([n1[0] + n2[0], n1[1] + n2[1]]);
+            let mut dot1 = dot(miter, n0);
+            let mut dot2 = dot(miter2, n2);
+            if dot1 == 0.0 { dot1 = 1.0; }
+            if dot2 == 0.0 { dot2 = 1.0; }
+            let mut dy = w / dot1;
+            let mut dy2 = w / dot2;
+
+            let d1 = distance_xy(position, centroid);
+            let d2 = distance_xy(target, centroid);
+            if dy < 0.0 { dy = -dy; }
+            if dy2 < 0.0 { dy2 = -dy2; }
+            // The stroke of a hole goes away from its centroid, so it can be as wide as wanted.
+            let hole = fill == 2;
+            if d1 <= dy && !hole { dy = d1; }
+            if d2 <= dy2 && !hole { dy2 = d2; }
+
+            // The quad goes from the side towards the centroid, on the side of the miter facing it,
+            // or on the other side for a hole.
+            let towards = distance(position, centroid) > distance(offset(position, miter, 0.001), centroid);
+            let strip = if towards != hole
+            {
+                [offset(position, miter, dy), offset(target, miter2, dy2), position, target]
+            }
+            else
+            {
+                [position, target, offset(position, miter, -dy), offset(target, miter2, -dy2)]
+            };
+
+            // polygon.frag
+            paint([strip[0], strip[1], strip[2]]);
+            paint([strip[1], strip[2], strip[3]]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contrast::camera::Camera;
+    use contrast::markscontainer::Contrast;
+    use contrast::MarkMacro;
+    use contrast::triangulation::FillRule;
+    use contrast::marks::polygonmark::Gradient;
+    use contrast::properties::Color;
+
+    fn square(fill: bool) -> Vec<VertexPolygon>
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let polygon = c.add_polygon_mark()
+            .add_point((20.0, 20.0, 0.0))
+            .add_point((80.0, 20.0, 0.0))
+            .add_point((80.0, 80.0, 0.0))
+            .add_point((20.0, 80.0, 0.0))
+            .set_stroke_width(5.0)
+            .set_color((0.0, 0.0, 1.0, 1.0));
+        if fill { polygon.set_fill(); }
+        c.get_polygonmarks_properties()
+    }
+
+    #[test]
+    fn draw_polygons()
+    {
+        let projection = Camera::init(100, 100).data();
+        let blue = Some([0.0, 0.0, 1.0, 1.0]);
+
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&square(true), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 50), blue);
+        assert_eq!(fb.get_pixel(21, 78), blue);
+        assert_eq!(fb.get_pixel(19, 50), Some([0.0; 4]));
+
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&square(false), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(22, 50), blue);
+        assert_eq!(fb.get_pixel(50, 77), blue);
+        assert_eq!(fb.get_pixel(50, 18), Some([0.0; 4]));
+    }
+
+    #[test]
+    fn draw_polygon_gradients()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let id = c.add_polygon_mark()
+            .set_points(vec![(0.0, 0.0, 0.0).into(), (100.0, 0.0, 0.0).into(), (100.0, 100.0, 0.0).into(), (0.0, 100.0, 0.0).into()])
+            .set_color((1.0, 0.0, 0.0, 1.0))
+            .set_gradient(Gradient::Linear((20.0, 0.0).into(), (80.0, 0.0).into(), Color::blue()))
+            .set_fill()
+            .get_id();
+        let projection = Camera::init(100, 100).data();
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&c.get_polygonmarks_properties(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(10, 50), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(fb.get_pixel(90, 20), Some([0.0, 0.0, 1.0, 1.0]));
+        let middle = fb.get_pixel(49, 70).unwrap();
+        assert!((middle[0] - 0.5).abs() < 0.02 && (middle[2] - 0.5).abs() < 0.02);
+
+        c.get_mark_mut(&id).unwrap().as_polygon_mark_mut_unchecked().set_gradient(Gradient::Radial((50.0, 50.0).into(), 40.0, Color::blue()));
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&c.get_polygonmarks_properties(), &projection, 0.0);
+        assert!(fb.get_pixel(50, 50).unwrap()[0] > 0.95);
+        assert_eq!(fb.get_pixel(95, 95), Some([0.0, 0.0, 1.0, 1.0]));
+        assert!((fb.get_pixel(69, 49).unwrap()[2] - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn draw_concave_polygon()
+    {
+        // A U shape, whose centroid is in the notch.
+        let mut c = Contrast::new();
+        c.init();
+        c.add_polygon_mark()
+            .set_points(vec![(10.0, 10.0, 0.0).into(), (30.0, 10.0, 0.0).into(), (30.0, 70.0, 0.0).into(), (70.0, 70.0, 0.0).into(),
+                             (70.0, 10.0, 0.0).into(), (90.0, 10.0, 0.0).into(), (90.0, 90.0, 0.0).into(), (10.0, 90.0, 0.0).into()])
+            .set_color((0.0, 0.0, 1.0, 1.0))
+            .set_fill();
+        let projection = Camera::init(100, 100).data();
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&c.get_polygonmarks_properties(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 40), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(20, 40), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(fb.get_pixel(50, 80), Some([0.0, 0.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn draw_polygon_with_hole()
+    {
+        let blue = Some([0.0, 0.0, 1.0, 1.0]);
+        let mut c = Contrast::new();
+        c.init();
+        let id = c.add_polygon_mark()
+            .set_points(vec![(10.0, 10.0, 0.0).into(), (90.0, 10.0, 0.0).into(), (90.0, 90.0, 0.0).into(), (10.0, 90.0, 0.0).into()])
+            .add_ring(vec![(30.0, 30.0, 0.0).into(), (70.0, 30.0, 0.0).into(), (70.0, 70.0, 0.0).into(), (30.0, 70.0, 0.0).into()])
+            .set_stroke_width(5.0)
+            .set_color((0.0, 0.0, 1.0, 1.0))
+            .set_fill_rule(FillRule::EvenOdd)
+            .set_fill()
+            .get_id();
+        let projection = Camera::init(100, 100).data();
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&c.get_polygonmarks_properties(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(20, 50), blue);
+
+        // The stroke of the hole is outside the hole, in the filled part.
+        c.get_mark_mut(&id).unwrap().as_polygon_mark_mut_unchecked().set_empty();
+        let mut fb = Framebuffer::new(100, 100);
+        fb.draw_polygons(&c.get_polygonmarks_properties(), &projection, 0.0);
+        assert_eq!(fb.get_pixel(50, 50), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(50, 28), blue);
+        assert_eq!(fb.get_pixel(50, 32), Some([0.0; 4]));
+        assert_eq!(fb.get_pixel(12, 50), blue);
+    }
+}
@@ -0,0 +1,114 @@
+//! Same drawing as contrast/src/shaders/text : the glyphs are textured triangles
+//! whose alpha is read in the texture atlas of their font, and blended over the marks.
+
+use std::collections::LinkedList;
+use std::collections::HashMap;
+use contrast::marks::textmark::{VertexText, TextMarkCmd, Glyph, ATLAS_SIZE};
+use crate::animation::{progress_of, mix3};
+use crate::framebuffer::{Framebuffer, M44, blend, interpolate};
+
+/// Texture atlas of a font, with one float per pixel like the atlas of the GPU renderer.
+pub struct Atlas
+{
+    pub(crate) texels: Vec<f32>
+}
+
+impl Atlas
+{
+    /// Returns an empty atlas.
+    pub fn new() -> Self
+    {
+        Atlas{texels: vec![0.0; (ATLAS_SIZE * ATLAS_SIZE) as usize]}
+    }
+
+    /// Copy the bitmap of a glyph at its place in the atlas.
+    pub fn upload(&mut self, glyph: &Glyph)
+    {
+        let (x, y) = (glyph.rect.x as usize, glyph.rect.y as usize);
+        let (w, h) = (glyph.rect.width as usize, glyph.rect.height as usize);
+        for (row, line) in glyph.bitmap.chunks(w.max(1)).take(h).enumerate()
+        {
+            let start = (y + row) * ATLAS_SIZE as usize + x;
+            self.texels[start..start + line.len()].copy_from_slice(line);
+        }
+    }
+
+    /// Returns the value of the atlas at the texture coordinates 'uv', with the
+    /// bilinear filtering and the clamping to the edges of the default sampler.
+    pub(crate) fn sample(&self, uv: [f32; 2]) -> f32
+    {
+        let size = ATLAS_SIZE as i64;
+        let texel = |x: i64, y: i64| self.texels[(y.max(0).min(size - 1) * size + x.max(0).min(size - 1)) as usize];
+        let (x, y) = (uv[0] * size as f32 - 0.5, uv[1] * size as f32 - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+        let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+impl Default for Atlas
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl Framebuffer
+{
+    /// Draw the text marks, as returned by Contrast::get_textmarks_properties, at the
+    /// time 't' of the animations. The atlases must contain every glyph given by contrast
+    /// so far, with the name of their font.
+    pub fn draw_texts(&mut self, vertices: &[VertexText], commands: &LinkedList<TextMarkCmd>, atlases: &HashMap<String, Atlas>,
+                      projection: &M44, t: f32)
+    {
+        for cmd in commands
+        {
+            let atlas = match atlases.get(&cmd.name) { Some(atlas) => atlas, None => continue };
+            let color = *cmd.color_at(t).to_array();
+            let end = (cmd.start + cmd.end).min(vertices.len());
+
+            for triangle in vertices[cmd.start.min(end)..end].chunks(3).filter(|triangle| triangle.len() == 3)
+            {
+                // text.vert
+                let mut positions = [[0.0; 2]; 3];
+                let mut tex = [[0.0; 2]; 3];
+                for (i, v) in triangle.iter().enumerate()
+                {
+                    let origin = mix3(v.2, v.3, progress_of(v.4, t));
+                    positions[i] = self.project(projection, [v.0[0] + origin[0], v.0[1] + origin[1], v.0[2] + origin[2]]);
+                    tex[i] = v.1;
+                }
+
+                // text.frag
+                self.fill_triangle(positions, |pixel, _, weights|
+                {
+                    let sampled = atlas.sample(interpolate(tex, weights));
+                    blend(pixel, [color[0], color[1], color[2], color[3] * sampled]);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample()
+    {
+        let mut atlas = Atlas::new();
+        atlas.texels[0] = 1.0;
+        atlas.texels[1] = 0.5;
+
+        let size = ATLAS_SIZE as f32;
+        assert_eq!(atlas.sample([0.5 / size, 0.5 / size]), 1.0);
+        assert_eq!(atlas.sample([1.0 / size, 0.5 / size]), 0.75);
+        assert_eq!(atlas.sample([0.0, 0.0]), 1.0);
+        assert_eq!(atlas.sample([1.0, 1.0]), 0.0);
+    }
+}
@@ -22,9 +22,7 @@ fn main()
             .set_color((1.0, 0.0, 0.0, 1.0))
             .set_shape(Shape::Triangle);
 
-    // Indicate to contrast that a new mark was added and that it needs to refresh.
-    contrast.mark_dirty_all();
 
     // Run the renderer, which makes it display the marks and listen to devices events.
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -41,6 +41,5 @@ fn main()
         }
     }
 
-    contrast.mark_dirty_all();
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -37,6 +37,5 @@ fn main()
         .set_thickness(10.0)
         .set_color((1.0, 1.0, 0.0, 1.0));
 
-    contrast.mark_dirty_all();
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -81,7 +81,5 @@ fn main()
         .set_stroke_width(10.0)
         .set_color((1.0, 0.7, 0.3, 1.0));
 
-
-    contrast.mark_dirty_all();
-    renderer.run();
+    renderer.run().unwrap();
 }
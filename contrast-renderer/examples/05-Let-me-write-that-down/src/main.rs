@@ -15,8 +15,8 @@ fn main()
     let contrast = renderer.get_contrast_mut();
 
     // Register some fonts so we can use them later.
-    contrast.register_font("fatty", "../../crimson-b.ttf", 120);
-    contrast.register_font("helvetica", "../../uhvr8a.pfb", 120);
+    contrast.register_font("fatty", "../../crimson-b.ttf", 120).unwrap();
+    contrast.register_font("helvetica", "../../uhvr8a.pfb", 120).unwrap();
 
     // Add some text marks.
     contrast.add_text_mark()
@@ -43,6 +43,5 @@ fn main()
         .set_text("Green")
         .set_color((0.0, 1.0, 0.0, 1.0));
 
-    contrast.mark_dirty_all();
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -24,14 +24,12 @@ fn move_one_text(contrast : &mut Contrast, markid : &MarkId) {
     // Retrieve our mark thanks to the mark's id. We can now modify it as we wish.
     // Note that we must cast our mark to a text mark to have access to specific
     // method, like 'set_text'.
-    let mark = contrast.get_mark_mut(&markid).unwrap().as_text_mark_mut_unchecked();
+    let mut mark = contrast.get_mark_mut(&markid).unwrap();
+    let mark = mark.as_text_mark_mut_unchecked();
 
     // Change randomly the position and the text of our mark.
     mark.set_position((rng.gen_range::<f32>(0.0, WINDOW_WIDTH as f32 - 200.0), rng.gen_range::<f32>(50.0, WINDOW_HEIGHT as f32), 0.0));
     mark.set_text(RANDOM_TEXT[rng.gen_range::<usize>(0, 3)]);
-
-    // Indicate to contrast that the mark represented by 'markid' has been modified and that it needs to refresh.
-    contrast.mark_dirty(*markid);
 }
 
 fn move_both_texts(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -39,12 +37,11 @@ fn move_both_texts(contrast : &mut Contrast, markids : &Vec<MarkId>) {
 
     // Change randomly the position and the text of our marks. 
     for m in markids {
-        let mark = contrast.get_mark_mut(&m).unwrap().as_text_mark_mut_unchecked();
+        let mut mark = contrast.get_mark_mut(&m).unwrap();
+        let mark = mark.as_text_mark_mut_unchecked();
         mark.set_position((rng.gen_range::<f32>(0.0, WINDOW_WIDTH as f32 - 200.0), rng.gen_range::<f32>(50.0, WINDOW_HEIGHT as f32), 0.0));
         mark.set_text(RANDOM_TEXT[rng.gen_range::<usize>(0, 3)]);
     }
-
-    contrast.mark_dirty_all();
 }
 
 fn main()
@@ -52,7 +49,7 @@ fn main()
     let mut renderer = LumiRenderer::init(WINDOW_WIDTH, WINDOW_HEIGHT, "That keyboard is not useless");
     let contrast = renderer.get_contrast_mut();
 
-    contrast.register_font("fatty", "../../crimson-b.ttf", 50);
+    contrast.register_font("fatty", "../../crimson-b.ttf", 50).unwrap();
 
     // We retrieve our mark's id. This allows us to later modify our mark later.
     let m1 = contrast.add_text_mark()
@@ -69,8 +66,6 @@ fn main()
         .set_color((0.0, 0.0, 1.0, 1.0))
         .get_id();
 
-    contrast.mark_dirty_all();
-
     // Create a vector containing both marks.
     let marks = vec!(m1, m2);
 
@@ -80,5 +75,5 @@ fn main()
     // Add an action that will modify both marks on press of the F key.
     renderer.add_mark_list_action_on_press(Key::F, move_both_texts, &marks);
 
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -31,7 +31,6 @@ fn move_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked()
             .set_position((rng.gen_range::<f32>(0.0, WINDOW_WIDTH as f32), rng.gen_range::<f32>(0.0, WINDOW_HEIGHT as f32), 0.0));
     }
-    contrast.mark_dirty_all();
 }
 
 fn resize_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -42,7 +41,6 @@ fn resize_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked().set_size((rng.gen_range::<f32>(100.0, 200.0), 
             rng.gen_range::<f32>(100.0, 200.0)));
     }
-    contrast.mark_dirty_all();
 }
 
 fn color_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -53,7 +51,6 @@ fn color_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().set_color((rng.gen_range::<f32>(0.0, 1.0), rng.gen_range::<f32>(0.0, 1.0), 
             rng.gen_range::<f32>(0.0, 1.0), 1.0));
     }
-    contrast.mark_dirty_all();
 }
 
 fn rotate_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -65,7 +62,6 @@ fn rotate_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked()
             .set_rotation(rng.gen_range::<f32>(Rotation::from_degrees(45.0), Rotation::from_degrees(360.0)));
     }
-    contrast.mark_dirty_all();
 }
 
 fn reshape_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -75,7 +71,6 @@ fn reshape_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
     for m in markids {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked().set_shape(Shape::rand(&mut rng));
     }
-    contrast.mark_dirty_all();
 }
 
 fn main()
@@ -83,7 +78,7 @@ fn main()
     let mut renderer = LumiRenderer::init(WINDOW_WIDTH, WINDOW_HEIGHT, "It's moving!");
     let contrast = renderer.get_contrast_mut();
 
-    contrast.register_font("fatty", "../../crimson-b.ttf", 40);
+    contrast.register_font("fatty", "../../crimson-b.ttf", 40).unwrap();
 
     // Add some text marks to show the commands
     contrast.add_text_mark()
@@ -151,8 +146,6 @@ fn main()
         .get_id();
 
 
-    contrast.mark_dirty_all();
-
     let marks = vec!(point1, point2, point3);
 
     // Bind keys to different functions, each of these modifying an attribute of our point marks.
@@ -163,5 +156,5 @@ fn main()
     renderer.add_mark_list_action_on_press(Key::F, rotate_marks, &marks);
     // Notice we wrote Q,W,E,R,F but you will actually need to press A,Z,E,R,F on an azerty keyboard.
 
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
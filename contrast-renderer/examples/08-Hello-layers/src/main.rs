@@ -63,7 +63,7 @@ fn main()
         .set_color((1.0, 0.0, 0.0, 1.0))
         .get_id();
 
-    let mut _mark_triangle = contrast.add_point_mark().set_position(pos)
+    let _mark_triangle = contrast.add_point_mark().set_position(pos)
         .set_size(size)
         .set_color((0.0, 0.0, 1.0, 1.0))
         .set_shape(Shape::Triangle)
@@ -81,7 +81,7 @@ fn main()
         .set_shape(Shape::Point)
         .get_id();
 
-    let mut _mark_spade = contrast.add_point_mark().set_position((pos.x + 500.0, pos.y+250.0, pos.z))
+    let _mark_spade = contrast.add_point_mark().set_position((pos.x + 500.0, pos.y+250.0, pos.z))
         .set_size(size)
         .set_color((0.0, 0.0, 1.0, 1.0))
         .set_shape(Shape::Spade)
@@ -105,7 +105,7 @@ fn main()
         .set_shape(Shape::Tag)
         .get_id();
 
-    let mut _mark_cross = contrast.add_point_mark().set_position((pos.x + 250.0, pos.y -250.0, pos.z))
+    let _mark_cross = contrast.add_point_mark().set_position((pos.x + 250.0, pos.y -250.0, pos.z))
         .set_size(size)
         .set_color((1.0, 0.0, 0.0, 1.0))
         .set_shape(Shape::Cross)
@@ -117,7 +117,7 @@ fn main()
         .set_shape(Shape::Asterisk)
         .get_id();
 
-    let mut rect_1 = contrast.add_point_mark().set_position((pos.x + 230.0, pos.y, 0.0))
+    let rect_1 = contrast.add_point_mark().set_position((pos.x + 230.0, pos.y, 0.0))
         .set_size((100.0, 100.0))
         .set_color((0.0, 0.0, 1.0, 1.0))
         .set_shape(Shape::Rectangle)
@@ -129,7 +129,7 @@ fn main()
         .set_shape(Shape::Rectangle)
         .get_id();
 
-    let mut rect_3 = contrast.add_point_mark().set_position((pos.x + 270.0, pos.y + 20.0, 0.0))
+    let rect_3 = contrast.add_point_mark().set_position((pos.x + 270.0, pos.y + 20.0, 0.0))
         .set_size((100.0, 100.0))
         .set_color((1.0, 0.0, 0.0, 1.0))
         .set_shape(Shape::Rectangle)
@@ -139,27 +139,25 @@ fn main()
     contrast.add_layers(2);
 
     // Retrieve the layer 0 and add some marks to it
-    let layer_0 = contrast.get_layer_mut(0).unwrap();
-    layer_0.add_mark(&mut rect_1);
-    layer_0.add_mark(&mut _mark_triangle);
-    layer_0.add_mark(&mut _mark_spade);
+    let mut layer_0 = contrast.get_layer_mut(0).unwrap();
+    layer_0.add_mark(&rect_1).unwrap();
+    layer_0.add_mark(&_mark_triangle).unwrap();
+    layer_0.add_mark(&_mark_spade).unwrap();
 
     // Apply a rotation to each mark of the layer 0
     layer_0.apply_to_marks(rotate_marks);
 
     // Retrieve the layer 2 and add some marks to it
-    let layer_2 = contrast.get_layer_mut(2).unwrap();
-    layer_2.add_mark(&mut rect_3);
-    layer_2.add_mark(&mut _mark_cross);
+    let mut layer_2 = contrast.get_layer_mut(2).unwrap();
+    layer_2.add_mark(&rect_3).unwrap();
+    layer_2.add_mark(&_mark_cross).unwrap();
 
     // Apply an enlargement to each mark of the layer 2
     layer_2.apply_to_marks(enlarge_marks);
 
     // Retrieve the layer 1 and make each of its marks pink
-    let layer_1 = contrast.get_layer_mut(1).unwrap();
+    let mut layer_1 = contrast.get_layer_mut(1).unwrap();
     layer_1.apply_to_marks(color_marks);
 
-    contrast.mark_dirty_all();
-
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -30,7 +30,6 @@ fn move_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked()
             .set_position((rng.gen_range::<f32>(0.0, WINDOW_WIDTH as f32), rng.gen_range::<f32>(0.0, WINDOW_HEIGHT as f32), 0.0));
     }
-    contrast.mark_dirty_all();
 }
 
 fn resize_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -41,7 +40,6 @@ fn resize_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked()
             .set_size((rng.gen_range::<f32>(6.0, 8.0), rng.gen_range::<f32>(6.0, 8.0)));
     }
-    contrast.mark_dirty_all();
 }
 
 fn color_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -52,7 +50,6 @@ fn color_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked()
             .set_color((rng.gen_range::<f32>(0.0, 1.0), rng.gen_range::<f32>(0.0, 1.0), rng.gen_range::<f32>(0.0, 1.0), 1.0));
     }
-    contrast.mark_dirty_all();
 }
 
 fn rotate_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -63,7 +60,6 @@ fn rotate_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked()
             .set_rotation(rng.gen_range::<f32>(Rotation::from_degrees(45.0), Rotation::from_degrees(360.0)));
     }
-    contrast.mark_dirty_all();
 }
 
 fn reshape_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
@@ -73,7 +69,6 @@ fn reshape_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
     for m in markids {
         contrast.get_mark_mut(&m).unwrap().as_point_mark_mut_unchecked().set_shape(Shape::rand(&mut rng));
     }
-    contrast.mark_dirty_all();
 }
 
 
@@ -83,7 +78,7 @@ fn main()
     let contrast = renderer.get_contrast_mut();
     let mut rng = rand::thread_rng();
 
-    contrast.register_font("fatty", "../../crimson-b.ttf", 40);
+    contrast.register_font("fatty", "../../crimson-b.ttf", 40).unwrap();
 
     let mut marks = Vec::new();
 
@@ -96,7 +91,6 @@ fn main()
             .get_id());
     }
 
-    contrast.mark_dirty_all();
 
     renderer.add_mark_list_action_on_press(Key::Q, reshape_marks, &marks);
     renderer.add_mark_list_action_on_press(Key::W, move_marks, &marks);
@@ -104,5 +98,5 @@ fn main()
     renderer.add_mark_list_action_on_press(Key::R, resize_marks, &marks);
     renderer.add_mark_list_action_on_press(Key::F, rotate_marks, &marks);
 
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
@@ -32,7 +32,6 @@ fn color_marks(contrast : &mut Contrast, markids : &Vec<MarkId>) {
     for m in markids {
         contrast.get_mark_mut(&m).unwrap().set_color((rng.gen_range::<f32>(0.0, 1.0), rng.gen_range::<f32>(0.0, 1.0), rng.gen_range::<f32>(0.0, 1.0), 1.0));
     }
-    contrast.mark_dirty_all();
 }
 
 // Parse a file containing our data and return its lines in a vector.
@@ -78,7 +77,7 @@ fn main()
         .set_color(Color::black());
 
     // Add text to name the axis
-    contrast.register_font("fatty", "../../crimson-b.ttf", 40);
+    contrast.register_font("fatty", "../../crimson-b.ttf", 40).unwrap();
 
     contrast.add_text_mark()
         .set_position((750.0, 750.0, 1.0))
@@ -92,10 +91,9 @@ fn main()
         .set_text("Y")
         .set_color(Color::black());
 
-    contrast.mark_dirty_all();
 
     // Randomly color the points when pressing <space>
     renderer.add_mark_list_action_on_press(Key::Space, color_marks, &marks);
 
-    renderer.run();
+    renderer.run().unwrap();
 }
\ No newline at end of file
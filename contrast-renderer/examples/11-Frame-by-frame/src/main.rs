@@ -0,0 +1,41 @@
+//! This program does not display anything : it renders an animation
+//! frame by frame into PNG images, for instance to make a video or
+//! a figure in a batch job.
+//!
+//! The clock of contrast is replaced by a clock moving forward of
+//! exactly 1/30 second at each frame, so the images are the same
+//! whatever the speed of the computer is.
+
+use contrast_renderer::LumiRenderer;
+use contrast::clock::ManualClock;
+use contrast::marks::pointmark::Shape;
+
+const WIDTH : u32 = 400;
+const HEIGHT : u32 = 400;
+const FRAMES : u32 = 30;
+
+fn main()
+{
+    // A window is still needed to create the OpenGL context, but nothing is drawn in it.
+    let mut renderer = LumiRenderer::init(WIDTH, HEIGHT, "Frame by frame");
+    let contrast = renderer.get_contrast_mut();
+    contrast.set_clock(ManualClock::fixed_step(1.0 / FRAMES as f32));
+
+    let point = contrast.add_point_mark()
+        .set_position((100.0, 200.0, 0.0))
+        .set_size((100.0, 100.0))
+        .set_color((1.0, 0.0, 0.0, 1.0))
+        .set_shape(Shape::Circle)
+        .get_id();
+
+    // The first frame displays the mark at its initial position.
+    renderer.render_to_png(WIDTH, HEIGHT, "frame-00.png").unwrap();
+
+    // Then it moves to the right during one second.
+    renderer.get_contrast_mut().get_mark_mut(&point).unwrap().as_point_mark_mut_unchecked()
+        .set_position((300.0, 200.0, 0.0));
+
+    for i in 1..=FRAMES {
+        renderer.render_to_png(WIDTH, HEIGHT, format!("frame-{:02}.png", i)).unwrap();
+    }
+}
@@ -6,51 +6,77 @@ use luminance_glfw::event::{Action, WindowEvent};
 use luminance::tess::{Mode, Tess, TessSlice};
 use luminance::context::GraphicsContext;
 use luminance::render_state::RenderState;
-use luminance::framebuffer::Framebuffer;
+use luminance::framebuffer::{ColorSlot, DepthSlot, Framebuffer};
 use luminance::shader::program::Program;
 use luminance::pipeline::BoundTexture;
 use luminance::texture::{Dim2, Flat, Sampler, Texture};
 use luminance::blending::{Equation, Factor};
 use luminance::vertex::Vertex;
 use luminance::linear::M44;
-use luminance::pixel::R32F;
+use luminance::pixel::{R32F, RGBA32F};
 
-use contrast::elapsed_time_float;
 use contrast::camera::Camera;
 use contrast::markscontainer::Contrast;
+use contrast::markscontainer::Update;
 use contrast::marks::pointmark::VertexPoint;
 use contrast::marks::linemark::VertexSubLine;
 use contrast::marks::textmark::VertexText;
 use contrast::marks::polygonmark::VertexPolygon;
+use contrast::marks::imagemark::{VertexImage, IMAGE_ATLAS_SIZE};
+use contrast::marks::arcmark::VertexArc;
 use contrast::marks::textmark::TextMarkCmd;
 use contrast::marks::textmark::Glyph;
+use contrast::marks::textmark::ATLAS_SIZE;
+use contrast::marks::shapeatlas::{SHAPE_SIZE, SHAPE_ATLAS_SIZE};
 use contrast::marks::mark::MarkTy;
 use contrast::properties::MarkId;
 use contrast::properties::Color;
+use contrast::Error;
+use contrast::animation::AnimationId;
+use contrast::image::Image;
+use contrast_raster::RasterRenderer;
 
 use std::collections::LinkedList;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::iter;
+use std::panic;
+use std::path::Path;
 
 pub use luminance_glfw::event::Key;
 
+/// Easing functions shared by the vertex shaders of every mark.
+macro_rules! animated_shader {
+    ($path:expr) => (concat!(include_str!("../../contrast/src/shaders/animation.glsl"), include_str!($path)))
+}
+
 /// Shaders Point.
-const VSPOINT: &'static str = include_str!("../../contrast/src/shaders/point/point.vert");
+const VSPOINT: &'static str = animated_shader!("../../contrast/src/shaders/point/point.vert");
 const FSPOINT: &'static str = include_str!("../../contrast/src/shaders/point/point.frag");
 const GSPOINT: &'static str = include_str!("../../contrast/src/shaders/point/point.geom");
 
 /// Shaders Line.
-const VSLINE: &'static str = include_str!("../../contrast/src/shaders/line/line.vert");
+const VSLINE: &'static str = animated_shader!("../../contrast/src/shaders/line/line.vert");
 const FSLINE: &'static str = include_str!("../../contrast/src/shaders/line/line.frag");
 const GSLINE: &'static str = include_str!("../../contrast/src/shaders/line/line.geom");
 
 /// Shaders Polygon.
-const VSPOLYGON: &'static str = include_str!("../../contrast/src/shaders/polygon/polygon.vert");
+const VSPOLYGON: &'static str = animated_shader!("../../contrast/src/shaders/polygon/polygon.vert");
 const FSPOLYGON: &'static str = include_str!("../../contrast/src/shaders/polygon/polygon.frag");
 const GSPOLYGON: &'static str = include_str!("../../contrast/src/shaders/polygon/polygon.geom");
 
+/// Shaders Image.
+const VSIMAGE: &'static str = animated_shader!("../../contrast/src/shaders/image/image.vert");
+const FSIMAGE: &'static str = include_str!("../../contrast/src/shaders/image/image.frag");
+const GSIMAGE: &'static str = include_str!("../../contrast/src/shaders/image/image.geom");
+
+/// Shaders Arc.
+const VSARC: &'static str = animated_shader!("../../contrast/src/shaders/arc/arc.vert");
+const FSARC: &'static str = include_str!("../../contrast/src/shaders/arc/arc.frag");
+const GSARC: &'static str = include_str!("../../contrast/src/shaders/arc/arc.geom");
+
 /// Shaders Text.
-const VSTEXT: &'static str = include_str!("../../contrast/src/shaders/text/text.vert");
+const VSTEXT: &'static str = animated_shader!("../../contrast/src/shaders/text/text.vert");
 const FSTEXT: &'static str = include_str!("../../contrast/src/shaders/text/text.frag");
 
 /// Glsl uniform for LineMark.
@@ -58,6 +84,8 @@ uniform_interface!
 {
     pub struct ShaderInterface
     {
+        #[as("t")]
+        time: f32,
         projection: M44
     }
 }
@@ -67,6 +95,19 @@ uniform_interface!
 {
     pub struct ShaderPointInterface
     {
+        shapes: &'static BoundTexture<'static, Flat, Dim2, R32F>,
+        #[as("t")]
+        time: f32,
+        projection: M44
+    }
+}
+
+/// Glsl uniform for ImageMark.
+uniform_interface!
+{
+    pub struct ShaderImageInterface
+    {
+        images: &'static BoundTexture<'static, Flat, Dim2, RGBA32F>,
         #[as("t")]
         time: f32,
         projection: M44
@@ -79,17 +120,32 @@ uniform_interface!
     pub struct ShaderTextInterface
     {
         atlas: &'static BoundTexture<'static, Flat, Dim2, R32F>,
+        #[as("t")]
+        time: f32,
         projection: M44,
         color: [f32; 4]
     }
 }
 
 /// Empty vertex used to fill a Tess.
-const DUMMY_POINT: &'static VertexPoint = &([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], 0.0, [0.0, 0.0], [0.0, 0.0], 0.0,
-                                            [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], 0.0, 0.0, 0.0, 0.0, 0u32, 0u32, 0.0);
-const DUMMY_LINE: &'static VertexSubLine = &([0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], 0.0);
-const DUMMY_TEXT: &'static VertexText = &([0.0, 0.0, 0.0], [0.0, 0.0]);
-const DUMMY_POLYGON: &'static VertexPolygon = &([0.0, 0.0, 0.0, 0.0], 0.0, [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], 0.0, [0.0,0.0,0.0], 0);
+const DUMMY_POINT: &'static VertexPoint = &([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0], [0.0, 0.0], [0.0, 0.0, 0.0],
+                                            [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                                            [0.0, 0.0, 0.0, 0.0], [0u32, 0u32, 0u32, 0u32], [0u32, 0u32], [0.0, 0.0], [0.0, 0.0, 0.0]);
+const DUMMY_LINE: &'static VertexSubLine = &([0u32, 0u32, 0u32, 0u32], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0],
+                                             [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0],
+                                             [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                                             [0.0, 0.0, 0.0, 0.0], [0u32, 0u32]);
+const DUMMY_IMAGE: &'static VertexImage = &([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0], [0.0, 0.0], [0.0, 0.0, 0.0],
+                                            [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0], [0.0, 0.0, 0.0],
+                                            [0.0, 0.0, 0.0, 0.0]);
+const DUMMY_ARC: &'static VertexArc = &([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                                        [0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                                        [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
+const DUMMY_TEXT: &'static VertexText = &([0.0, 0.0, 0.0], [0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
+const DUMMY_POLYGON: &'static VertexPolygon = &([0u32, 0u32, 0u32, 0u32], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0],
+                                                [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0],
+                                                [0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                                                [0u32, 0u32]);
 
 /// Manage a Tess to update & resize.
 /// Its size is initialized wider to update its unused vertices gradually on demand.
@@ -133,6 +189,17 @@ impl<V> TessPool<V> where V: Vertex, V: std::marker::Copy
         let mut buffer = self.tess.as_slice_mut::<GlfwSurface>().unwrap();
         buffer[..self.size].copy_from_slice(vertices.as_slice());
     }
+
+    /// Update only some ranges of the Tess, each one starting at its index.
+    /// The number of vertices really used does not change.
+    pub fn update_ranges(&mut self, changes: Vec<(usize, Vec<V>)>)
+    {
+        let mut buffer = self.tess.as_slice_mut::<GlfwSurface>().unwrap();
+        for (start, vertices) in changes
+        {
+            buffer[start..start+vertices.len()].copy_from_slice(vertices.as_slice());
+        }
+    }
 }
 
 /// Pair of TessPool & Shader Program used to render a mark type.
@@ -158,12 +225,20 @@ pub type RPoint = RenderPass<VertexPoint,ShaderPointInterface>;
 pub type RLine = RenderPass<VertexSubLine,ShaderInterface>;
 /// Polygon Renderer
 pub type RPolygon = RenderPass<VertexPolygon,ShaderInterface>;
+/// Image Renderer
+pub type RImage = RenderPass<VertexImage,ShaderImageInterface>;
+/// Arc Renderer
+pub type RArc = RenderPass<VertexArc,ShaderInterface>;
 /// Text Renderer
 pub type RText = RenderPass<VertexText,ShaderTextInterface>;
 /// Back Buffer
 pub type Frame = Framebuffer<Flat,Dim2,(),()>;
 /// 2D Texture RED-only
 pub type Atlas = Texture<Flat,Dim2,R32F>;
+/// 2D Texture RGBA
+pub type ImageAtlas = Texture<Flat,Dim2,RGBA32F>;
+/// Framebuffer drawn by render_to_image
+pub type Offscreen = Framebuffer<Flat,Dim2,RGBA32F,()>;
 
 enum Callback<'a> {
     NoArgument(fn(&mut Contrast)),
@@ -177,15 +252,25 @@ pub struct LumiRenderer<'a>
     contrast: Contrast,
     surface: GlfwSurface,
     background_color : Color,
-    frame: Frame,
     point: RPoint,
+    /// Markers of the lines, drawn with the program of the points.
+    markers: TessPool<VertexPoint>,
     line: RLine,
     polygon: RPolygon,
+    image: RImage,
+    arc: RArc,
     text: RText,
     cam: Camera,
     callbacks : HashMap<Key, Callback<'a>>,
+    animation_callbacks : Vec<fn(&mut Contrast, AnimationId)>,
     font_atlas: HashMap<String,Atlas>,
-    font_cmmds: LinkedList<TextMarkCmd>
+    font_cmmds: LinkedList<TextMarkCmd>,
+    /// Signed distance fields of the custom shapes of the points.
+    shapes: Atlas,
+    /// Pixels of the images of the image marks.
+    images: ImageAtlas,
+    /// Framebuffer of the last call to render_to_image and its size, reused for the same size.
+    offscreen: Option<([u32; 2], Offscreen)>
 }
 
 impl<'a> LumiRenderer<'a>
@@ -193,12 +278,38 @@ impl<'a> LumiRenderer<'a>
     /// Create & init a new LumiRenderer.
     pub fn init(w: u32, h: u32, title: &str) -> LumiRenderer
     {
-        let mut surface = GlfwSurface::new(WindowDim::Windowed(w, h), title, WindowOpt::default()).expect("GLFW ERROR");
-        let frame = Framebuffer::back_buffer(surface.size());
+        LumiRenderer::try_init(w, h, title).expect("GLFW ERROR")
+    }
+
+    /// Same as init, but returns an error instead of panicking when the window
+    /// cannot be created, for example on a server without display.
+    pub fn try_init(w: u32, h: u32, title: &str) -> Result<LumiRenderer, Error>
+    {
+        LumiRenderer::create(w, h, title, false)
+    }
+
+    /// Same as try_init, but the window is hidden, for a renderer which only draws
+    /// into images with render_to_image. See also HeadlessRenderer.
+    pub fn try_init_hidden(w: u32, h: u32, title: &str) -> Result<LumiRenderer, Error>
+    {
+        LumiRenderer::create(w, h, title, true)
+    }
+
+    fn create(w: u32, h: u32, title: &str, hidden: bool) -> Result<LumiRenderer, Error>
+    {
+        // GLFW panics on its own errors, as when no display can be opened.
+        let mut surface = panic::catch_unwind(|| GlfwSurface::new(WindowDim::Windowed(w, h), title, WindowOpt::default()))
+            .map_err(|_| Error::Window(String::from("GLFW error")))?
+            .map_err(|err| Error::Window(format!("{:?}", err)))?;
+        if hidden
+        {
+            surface.window.hide();
+        }
 
         let shd = Program::<VertexPoint, (), ShaderPointInterface>::from_strings(None, VSPOINT, GSPOINT, FSPOINT).expect("program creation");
         let tss = TessPool::new(&mut surface, Mode::Point, DUMMY_POINT.clone());
         let point = RPoint{pool: tss, program: shd.0};
+        let markers = TessPool::new(&mut surface, Mode::Point, DUMMY_POINT.clone());
 
         let shd = Program::<VertexSubLine, (), ShaderInterface>::from_strings(None, VSLINE, GSLINE, FSLINE).expect("program creation");
         let tss = TessPool::new(&mut surface, Mode::Point, DUMMY_LINE.clone());
@@ -208,20 +319,34 @@ impl<'a> LumiRenderer<'a>
         let tss = TessPool::new(&mut surface, Mode::Point, DUMMY_POLYGON.clone());
         let polygon = RPolygon{pool: tss, program: shd.0};
 
+        let shd = Program::<VertexImage, (), ShaderImageInterface>::from_strings(None, VSIMAGE, GSIMAGE, FSIMAGE).expect("program creation");
+        let tss = TessPool::new(&mut surface, Mode::Point, DUMMY_IMAGE.clone());
+        let image = RImage{pool: tss, program: shd.0};
+
+        let shd = Program::<VertexArc, (), ShaderInterface>::from_strings(None, VSARC, GSARC, FSARC).expect("program creation");
+        let tss = TessPool::new(&mut surface, Mode::Point, DUMMY_ARC.clone());
+        let arc = RArc{pool: tss, program: shd.0};
+
         let shd = Program::<VertexText, (), ShaderTextInterface>::from_strings(None, VSTEXT, None, FSTEXT).expect("program creation");
         let tss = TessPool::new(&mut surface, Mode::Triangle, DUMMY_TEXT.clone());
         let text = RText{pool: tss, program: shd.0};
 
-        let contrast = Contrast::new();
+        let mut contrast = Contrast::new();
+        contrast.init();
 
         let background_color = Color { r : 0.0, g : 0.0, b : 0.0, a : 0.0 };
 
         let cam = Camera::init(w, h);
         let callbacks = HashMap::new();
+        let animation_callbacks = Vec::new();
         let font_atlas = HashMap::new();
         let font_cmmds = LinkedList::new();
+        let shapes = Texture::new(&mut surface, [SHAPE_ATLAS_SIZE, SHAPE_ATLAS_SIZE], 0, &Sampler::default()).expect("luminance texture creation");
+        let images = Texture::new(&mut surface, [IMAGE_ATLAS_SIZE, IMAGE_ATLAS_SIZE], 0, &Sampler::default()).expect("luminance texture creation");
+        let offscreen = None;
 
-        LumiRenderer{contrast, surface, background_color, frame, point, line, polygon, text, cam, callbacks, font_atlas, font_cmmds}
+        Ok(LumiRenderer{contrast, surface, background_color, point, markers, line, polygon, image, arc, text, cam, callbacks, animation_callbacks, font_atlas,
+                        font_cmmds, shapes, images, offscreen})
     }
 
     /// Create or upload the textures atlas for each glyph.
@@ -234,7 +359,7 @@ impl<'a> LumiRenderer<'a>
             if !self.font_atlas.contains_key(&glyph.name)
             {
                 // Create
-                let tex = Texture::new(&mut self.surface, [1024, 1024], 0, &Sampler::default()).expect("luminance texture creation");
+                let tex = Texture::new(&mut self.surface, [ATLAS_SIZE, ATLAS_SIZE], 0, &Sampler::default()).expect("luminance texture creation");
                 self.font_atlas.insert(glyph.name.clone(), tex);
             }
 
@@ -260,15 +385,33 @@ impl<'a> LumiRenderer<'a>
         self.update_font_atlas(bundle.2);
     }
 
+    /// Returns the vertices of the modified marks if only them need to be rebuilt,
+    /// or None if every vertex of their type must be rebuilt.
+    fn sub_update<V>(&mut self, update: &Update, f: fn(&mut Contrast, &HashSet<MarkId>) -> Option<Vec<(usize, Vec<V>)>>) -> Option<Vec<(usize, Vec<V>)>>
+    {
+        match update
+        {
+            Update::Marks(markids) => f(&mut self.contrast, markids),
+            Update::All => None
+        }
+    }
+
     /// Change the background color.
     pub fn set_background_color<C : Into <Color>>(&mut self, color : C) {
         self.background_color = color.into();
     }
 
+    /// Zoom the camera of the window : one unit of the marks takes 'scale' pixels.
+    /// The curves of the line marks are rebuilt to stay smooth at this scale.
+    pub fn set_camera_scale(&mut self, scale: f32)
+    {
+        self.cam.set_scale(scale);
+        self.contrast.set_scale(scale);
+    }
+
     /// Borrow Contrast mutable.
     pub fn get_contrast_mut(&mut self) -> &mut Contrast
     {
-        self.contrast.init();
         &mut self.contrast
     }
 
@@ -287,11 +430,21 @@ impl<'a> LumiRenderer<'a>
         self.callbacks.insert(key, Callback::ArgumentMarkList(f, markids));
     }
 
+    /// Add listener on the end of the animations, to chain them for instance.
+    pub fn add_action_on_animation_end(&mut self, f: fn(&mut Contrast, AnimationId)) {
+        self.animation_callbacks.push(f);
+    }
+
     /// Main loop.
-    pub fn run(&mut self)
+    /// Returns an error if the marks cannot be converted into vertices,
+    /// for instance when the glyphs of a text mark cannot be loaded.
+    pub fn run(&mut self) -> Result<(), Error>
     {
         'app: loop
         {
+            // Move the clock of contrast to the time of this frame.
+            self.contrast.tick();
+
             // Execute events.
             for event in self.surface.poll_events()
             {
@@ -304,7 +457,6 @@ impl<'a> LumiRenderer<'a>
 
                     WindowEvent::FramebufferSize(width, height) =>
                     {
-                        self.frame = Framebuffer::back_buffer([width as u32, height as u32]);
                         self.cam.resize(width, height);
                     }
 
@@ -325,82 +477,466 @@ impl<'a> LumiRenderer<'a>
                 }
             }
 
-            // Try to update.
-            for ty in self.contrast.fetch_update()
+            self.update()?;
+
+            let back_buffer = Frame::back_buffer(self.surface.size());
+            let projection = self.cam.data();
+            self.draw(&back_buffer, projection);
+
+            self.surface.swap_buffers();
+        }
+
+        Ok(())
+    }
+
+    /// Render the current scene once into an image of 'width' x 'height' pixels,
+    /// without drawing in the window. The marks are placed in pixels from the top
+    /// left corner of the image and zoomed by the scale of the camera, as they are in the window.
+    /// This is an offscreen rendering, not a headless one : it uses the OpenGL context of the
+    /// window created by init, so it needs a display. Use HeadlessRenderer to render without display.
+    /// The framebuffer is kept for the next calls with the same size.
+    /// As in one turn of the main loop, the clock of contrast moves to the next frame
+    /// and the animations are updated, so with a ManualClock with a fixed step each
+    /// call gives the next frame of a video.
+    /// Returns an error if the framebuffer cannot be created or if the marks cannot
+    /// be converted into vertices.
+    pub fn render_to_image(&mut self, width: u32, height: u32) -> Result<Image, Error>
+    {
+        self.contrast.tick();
+        self.update()?;
+
+        let framebuffer = match self.offscreen.take()
+        {
+            Some((size, framebuffer)) if size == [width, height] => framebuffer,
+            _ => Offscreen::new(&mut self.surface, [width, height], 0, &Sampler::default())
+                .map_err(|err| Error::Framebuffer(format!("{:?}", err)))?
+        };
+        let mut cam = Camera::init(width, height);
+        cam.set_scale(self.cam.get_scale());
+        self.draw(&framebuffer, cam.data());
+
+        // OpenGL gives the rows from the bottom of the image.
+        let texels = framebuffer.color_slot().get_raw_texels();
+        self.offscreen = Some(([width, height], framebuffer));
+        let mut image = Image::new(width, height);
+        for (i, texel) in texels.chunks(4).enumerate()
+        {
+            let (x, y) = (i as u32 % width, height - 1 - i as u32 / width);
+            image.set_pixel(x, y, Color { r: texel[0], g: texel[1], b: texel[2], a: texel[3] });
+        }
+        Ok(image)
+    }
+
+    /// Same behavior than render_to_image, then save the image in PNG at 'path'.
+    pub fn render_to_png<P: AsRef<Path>>(&mut self, width: u32, height: u32, path: P) -> Result<(), Error>
+    {
+        self.render_to_image(width, height)?.save_png(path)
+    }
+
+    /// Play the animations, notify the finished ones and
+    /// upload the vertices of the modified marks.
+    fn update(&mut self) -> Result<(), Error>
+    {
+        // Play the animations and notify the finished ones.
+        for animation in self.contrast.update_animations()
+        {
+            for f in &self.animation_callbacks
             {
-                match ty
-                {
-                    MarkTy::Point => self.point.pool.update(&mut self.surface, self.contrast.get_pointmarks_properties()),
-                    MarkTy::Line => self.line.pool.update(&mut self.surface, self.contrast.get_linemarks_properties()),
-                    MarkTy::Polygon => self.polygon.pool.update(&mut self.surface, self.contrast.get_polygonmarks_properties()),
-                    MarkTy::Text => { let b = self.contrast.get_textmarks_properties(); self.build_text_marks(b); }
-                }
+                f(&mut self.contrast, animation);
             }
+        }
 
-            // Rust oblige...
-            let p = &self.point;
-            let l = &self.line;
-            let t = &self.text;
-            let poly = &self.polygon;
-
-            let mat = self.cam.data();
-            let ctx = &mut self.surface;
-            let back_buffer = &self.frame;
+        // Upload the custom shapes registered since the last update.
+        for shape in self.contrast.get_new_shapes()
+        {
+            self.shapes.upload_part(false, shape.origin(), [SHAPE_SIZE, SHAPE_SIZE], shape.field.as_slice());
+        }
 
-            let commands = &self.font_cmmds;
-            let textures = &self.font_atlas;
-            let blending = Some((Equation::Additive, Factor::SrcAlpha, Factor::SrcAlphaComplement));
+        // Upload the images registered since the last update.
+        for image in self.contrast.get_new_images()
+        {
+            let (x, y, w, h) = (image.rect.x as u32, image.rect.y as u32, image.rect.width as u32, image.rect.height as u32);
+            self.images.upload_part_raw(false, [x, y], [w, h], image.texels.as_slice());
+        }
 
-            // Main Pipeline.
-            ctx.pipeline_builder().pipeline(back_buffer, *self.background_color.to_array(), |pipeline, shd_gate|
+        // Try to update, only rebuilding the modified marks when possible.
+        // Text marks are always rebuilt since their glyphs may change.
+        for (ty, update) in self.contrast.fetch_update()
+        {
+            match ty
             {
-                // Render points.
-                shd_gate.shade(p.shader(), |rdr_gate, iface|
+                MarkTy::Point =>
                 {
-                    iface.time.update(elapsed_time_float());
-                    iface.projection.update(mat);
-                    rdr_gate.render(RenderState::default(), |tess_gate|
+                    match self.sub_update(&update, Contrast::get_pointmarks_sub_properties)
                     {
-                        tess_gate.render(ctx, p.vertices());
-                    });
-                });
-                // Render lines.
-                shd_gate.shade(l.shader(), |rdr_gate, iface|
+                        Some(changes) => self.point.pool.update_ranges(changes),
+                        None => self.point.pool.update(&mut self.surface, self.contrast.get_pointmarks_properties())
+                    }
+                }
+                MarkTy::Line =>
                 {
-                    iface.projection.update(mat);
-                    rdr_gate.render(RenderState::default(), |tess_gate|
+                    match self.sub_update(&update, Contrast::get_linemarks_sub_properties)
                     {
-                        tess_gate.render(ctx, l.vertices());
-                    });
-                });
-                // Render lines.
-                shd_gate.shade(poly.shader(), |rdr_gate, iface|
+                        Some(changes) => self.line.pool.update_ranges(changes),
+                        None => self.line.pool.update(&mut self.surface, self.contrast.get_linemarks_properties())
+                    }
+                    self.markers.update(&mut self.surface, self.contrast.get_linemarks_markers());
+                }
+                MarkTy::Polygon =>
                 {
-                    iface.projection.update(mat);
-                    rdr_gate.render(RenderState::default(), |tess_gate|
+                    match self.sub_update(&update, Contrast::get_polygonmarks_sub_properties)
                     {
-                        tess_gate.render(ctx, poly.vertices());
-                    });
-                });
-                // Render texts per batch with the associated texture & color.
-                for cmd in commands
+                        Some(changes) => self.polygon.pool.update_ranges(changes),
+                        None => self.polygon.pool.update(&mut self.surface, self.contrast.get_polygonmarks_properties())
+                    }
+                }
+                MarkTy::Image =>
                 {
-                    let tex = textures.get(&cmd.name).unwrap();
-                    let bound_tex = pipeline.bind_texture(tex);
-                    shd_gate.shade(t.shader(), |rdr_gate, iface|
+                    match self.sub_update(&update, Contrast::get_imagemarks_sub_properties)
                     {
-                        iface.projection.update(mat);
-                        iface.atlas.update(&bound_tex);
-                        iface.color.update(cmd.color.to_array().clone());
-                        rdr_gate.render(RenderState::default().set_blending(blending), |tess_gate|
-                        {
-                            tess_gate.render(ctx, t.vertices_range(cmd.start, cmd.end));
-                        });
-                    });
+                        Some(changes) => self.image.pool.update_ranges(changes),
+                        None => self.image.pool.update(&mut self.surface, self.contrast.get_imagemarks_properties())
+                    }
+                }
+                MarkTy::Arc =>
+                {
+                    match self.sub_update(&update, Contrast::get_arcmarks_sub_properties)
+                    {
+                        Some(changes) => self.arc.pool.update_ranges(changes),
+                        None => self.arc.pool.update(&mut self.surface, self.contrast.get_arcmarks_properties())
+                    }
                 }
+                MarkTy::Text => { let b = self.contrast.get_textmarks_properties()?; self.build_text_marks(b); }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draw every mark into 'framebuffer' with the projection matrix 'projection'.
+    fn draw<CS, DS>(&mut self, framebuffer: &Framebuffer<Flat, Dim2, CS, DS>, projection: M44)
+        where CS: ColorSlot<Flat, Dim2>, DS: DepthSlot<Flat, Dim2>
+    {
+        // Rust oblige...
+        let p = &self.point;
+        let m = &self.markers;
+        let l = &self.line;
+        let t = &self.text;
+        let poly = &self.polygon;
+        let img = &self.image;
+        let arc = &self.arc;
+
+        let mat = projection;
+        let ctx = &mut self.surface;
+
+        let commands = &self.font_cmmds;
+        let textures = &self.font_atlas;
+        let shapes = &self.shapes;
+        let images = &self.images;
+        let blending = Some((Equation::Additive, Factor::SrcAlpha, Factor::SrcAlphaComplement));
+        let time = self.contrast.get_time();
+
+        // Main Pipeline.
+        ctx.pipeline_builder().pipeline(framebuffer, *self.background_color.to_array(), |pipeline, shd_gate|
+        {
+            let bound_shapes = pipeline.bind_texture(shapes);
+            // Render points.
+            shd_gate.shade(p.shader(), |rdr_gate, iface|
+            {
+                iface.shapes.update(&bound_shapes);
+                iface.time.update(time);
+                iface.projection.update(mat);
+                rdr_gate.render(RenderState::default(), |tess_gate|
+                {
+                    tess_gate.render(ctx, p.vertices());
+                });
+            });
+            // Render lines.
+            shd_gate.shade(l.shader(), |rdr_gate, iface|
+            {
+                iface.time.update(time);
+                iface.projection.update(mat);
+                rdr_gate.render(RenderState::default(), |tess_gate|
+                {
+                    tess_gate.render(ctx, l.vertices());
+                });
+            });
+            // Render the markers of the lines over them.
+            shd_gate.shade(p.shader(), |rdr_gate, iface|
+            {
+                iface.shapes.update(&bound_shapes);
+                iface.time.update(time);
+                iface.projection.update(mat);
+                rdr_gate.render(RenderState::default(), |tess_gate|
+                {
+                    tess_gate.render(ctx, m.data());
+                });
+            });
+            // Render lines.
+            shd_gate.shade(poly.shader(), |rdr_gate, iface|
+            {
+                iface.time.update(time);
+                iface.projection.update(mat);
+                rdr_gate.render(RenderState::default(), |tess_gate|
+                {
+                    tess_gate.render(ctx, poly.vertices());
+                });
+            });
+            // Render arcs, blended for their anti-aliased edges.
+            shd_gate.shade(arc.shader(), |rdr_gate, iface|
+            {
+                iface.time.update(time);
+                iface.projection.update(mat);
+                rdr_gate.render(RenderState::default().set_blending(blending), |tess_gate|
+                {
+                    tess_gate.render(ctx, arc.vertices());
+                });
             });
+            // Render the images over the other marks, blended with them.
+            let bound_images = pipeline.bind_texture(images);
+            shd_gate.shade(img.shader(), |rdr_gate, iface|
+            {
+                iface.images.update(&bound_images);
+                iface.time.update(time);
+                iface.projection.update(mat);
+                rdr_gate.render(RenderState::default().set_blending(blending), |tess_gate|
+                {
+                    tess_gate.render(ctx, img.vertices());
+                });
+            });
+            // Render texts per batch with the associated texture & color.
+            for cmd in commands
+            {
+                let tex = textures.get(&cmd.name).unwrap();
+                let bound_tex = pipeline.bind_texture(tex);
+                shd_gate.shade(t.shader(), |rdr_gate, iface|
+                {
+                    iface.time.update(time);
+                    iface.projection.update(mat);
+                    iface.atlas.update(&bound_tex);
+                    iface.color.update(*cmd.color_at(time).to_array());
+                    rdr_gate.render(RenderState::default().set_blending(blending), |tess_gate|
+                    {
+                        tess_gate.render(ctx, t.vertices_range(cmd.start, cmd.end));
+                    });
+                });
+            }
+        });
+    }
+}
 
-            self.surface.swap_buffers();
+/// Renderer of images which needs no display. It draws with OpenGL in a hidden window
+/// when one can be created, and with the CPU rasterizer of contrast-raster otherwise, as on
+/// a server without display. Both give the same pixels, apart from the anti-aliased edges.
+pub enum HeadlessRenderer<'a>
+{
+    Gpu(LumiRenderer<'a>),
+    Cpu(Contrast, RasterRenderer)
+}
+
+impl<'a> HeadlessRenderer<'a>
+{
+    /// Create & init a new HeadlessRenderer, drawing images of about 'w' x 'h' pixels on the GPU
+    /// if a hidden window can be created, and on the CPU otherwise.
+    pub fn init(w: u32, h: u32) -> HeadlessRenderer<'a>
+    {
+        match LumiRenderer::try_init_hidden(w, h, "contrast")
+        {
+            Ok(renderer) => HeadlessRenderer::Gpu(renderer),
+            Err(_) => HeadlessRenderer::cpu()
+        }
+    }
+
+    /// Create & init a new HeadlessRenderer which always draws on the CPU.
+    pub fn cpu() -> HeadlessRenderer<'a>
+    {
+        let mut contrast = Contrast::new();
+        contrast.init();
+        HeadlessRenderer::Cpu(contrast, RasterRenderer::new())
+    }
+
+    /// Returns true if the images are drawn by OpenGL.
+    pub fn is_gpu(&self) -> bool
+    {
+        match self
+        {
+            HeadlessRenderer::Gpu(_) => true,
+            HeadlessRenderer::Cpu(..) => false
+        }
+    }
+
+    /// Change the background color.
+    pub fn set_background_color<C : Into <Color>>(&mut self, color : C)
+    {
+        match self
+        {
+            HeadlessRenderer::Gpu(renderer) => renderer.set_background_color(color),
+            HeadlessRenderer::Cpu(_, raster) => raster.set_background_color(color)
+        }
+    }
+
+    /// Zoom the marks from the top left corner of the images, see LumiRenderer::set_camera_scale.
+    pub fn set_camera_scale(&mut self, scale: f32)
+    {
+        match self
+        {
+            HeadlessRenderer::Gpu(renderer) => renderer.set_camera_scale(scale),
+            HeadlessRenderer::Cpu(contrast, _) => contrast.set_scale(scale)
+        }
+    }
+
+    /// Borrow Contrast mutable.
+    pub fn get_contrast_mut(&mut self) -> &mut Contrast
+    {
+        match self
+        {
+            HeadlessRenderer::Gpu(renderer) => renderer.get_contrast_mut(),
+            HeadlessRenderer::Cpu(contrast, _) => contrast
+        }
+    }
+
+    /// Render the current scene once into an image of 'width' x 'height' pixels.
+    /// As LumiRenderer::render_to_image, the clock of contrast moves to the next frame
+    /// and the animations are updated before the rendering.
+    /// Returns an error if the framebuffer cannot be created or if the marks cannot
+    /// be converted into vertices.
+    pub fn render_to_image(&mut self, width: u32, height: u32) -> Result<Image, Error>
+    {
+        match self
+        {
+            HeadlessRenderer::Gpu(renderer) => renderer.render_to_image(width, height),
+            HeadlessRenderer::Cpu(contrast, raster) =>
+            {
+                contrast.tick();
+                contrast.update_animations();
+                raster.render(contrast, width, height)
+            }
+        }
+    }
+
+    /// Same behavior than render_to_image, then save the image in PNG at 'path'.
+    pub fn render_to_png<P: AsRef<Path>>(&mut self, width: u32, height: u32, path: P) -> Result<(), Error>
+    {
+        self.render_to_image(width, height)?.save_png(path)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use contrast::MarkMacro;
+    use contrast::clock::ManualClock;
+    use contrast::marks::pointmark::Shape;
+    use contrast::marks::polygonmark::PolygonMark;
+    use std::sync::Mutex;
+
+    /// Held by the tests which create a window, since GLFW cannot be initialized by two threads at once.
+    static DISPLAY: Mutex<()> = Mutex::new(());
+
+    /// Returns the image of a red square from (4, 4) to (12, 12) on a white background.
+    fn square() -> Image
+    {
+        let mut image = Image::new(16, 16);
+        for y in 0..16
+        {
+            for x in 0..16
+            {
+                let inside = (4..12).contains(&x) && (4..12).contains(&y);
+                image.set_pixel(x, y, if inside { Color::red() } else { Color::white() });
+            }
+        }
+        image
+    }
+
+    /// Adds a red square without stroke from ('from', 'from') to ('to', 'to').
+    fn add_square(contrast: &mut Contrast, from: f32, to: f32) -> &mut PolygonMark
+    {
+        contrast.add_polygon_mark()
+            .add_point((from, from, 0.0)).add_point((to, from, 0.0)).add_point((to, to, 0.0)).add_point((from, to, 0.0))
+            .set_stroke_width(0.0).set_fill().set_color((1.0, 0.0, 0.0, 1.0))
+    }
+
+    /// Adds a mark of each type drawn without texture, at a fixed time.
+    fn scene(contrast: &mut Contrast)
+    {
+        contrast.set_clock(ManualClock::new());
+        contrast.add_point_mark().set_position((20.0, 20.0, 0.0)).set_size((24.0, 24.0)).set_color((1.0, 0.0, 0.0, 1.0));
+        contrast.add_point_mark().set_position((60.0, 20.0, 0.0)).set_size((20.0, 12.0)).set_shape(Shape::Rectangle)
+            .set_rotation(0.5).set_color((0.0, 0.5, 1.0, 1.0));
+        contrast.add_line_mark().add_point((8.0, 48.0, 0.0)).add_point((40.0, 56.0, 0.0)).add_point((72.0, 48.0, 0.0))
+            .set_thickness(4.0).set_color((0.0, 0.0, 0.0, 1.0));
+        add_square(contrast, 8.0, 24.0).set_color((0.0, 1.0, 0.0, 1.0));
+        contrast.add_arc_mark().set_position((56.0, 64.0, 0.0)).set_inner_radius(6.0).set_outer_radius(14.0)
+            .set_end_angle(4.0).set_color((1.0, 0.5, 0.0, 1.0));
+    }
+
+    // The tests drawing with OpenGL return early where no window can be created.
+    #[test]
+    fn render_to_image()
+    {
+        let _display = DISPLAY.lock().unwrap_or_else(|err| err.into_inner());
+        let mut renderer = match LumiRenderer::try_init_hidden(16, 16, "render_to_image") {
+            Ok(renderer) => renderer,
+            Err(err) => { eprintln!("render_to_image skipped, {}", err); return; }
+        };
+        renderer.set_background_color(Color::white());
+        let mark = add_square(renderer.get_contrast_mut(), 4.0, 12.0).get_id();
+
+        // A small tolerance since two implementations of OpenGL do not give the same pixels.
+        let image = renderer.render_to_image(16, 16).unwrap();
+        assert!(image.max_difference(&square()).unwrap() <= 2);
+        // The framebuffer of the first call gives the same image.
+        assert_eq!(renderer.render_to_image(16, 16).unwrap().max_difference(&image), Some(0));
+
+        // The marks are zoomed by the camera, as in the window.
+        renderer.get_contrast_mut().remove_mark(&mark).unwrap();
+        add_square(renderer.get_contrast_mut(), 2.0, 6.0);
+        renderer.set_camera_scale(2.0);
+        assert!(renderer.render_to_image(16, 16).unwrap().max_difference(&square()).unwrap() <= 2);
+        assert_eq!(renderer.render_to_image(32, 8).unwrap().get_width(), 32);
+    }
+
+    #[test]
+    fn same_as_raster()
+    {
+        let _display = DISPLAY.lock().unwrap_or_else(|err| err.into_inner());
+        let mut renderer = match LumiRenderer::try_init_hidden(80, 80, "same_as_raster") {
+            Ok(renderer) => renderer,
+            Err(err) => { eprintln!("same_as_raster skipped, {}", err); return; }
+        };
+        renderer.set_background_color(Color::white());
+        scene(renderer.get_contrast_mut());
+        let gpu = renderer.render_to_image(80, 80).unwrap();
+
+        // Each renderer fetches the updates of its Contrast, so they get the same scene twice.
+        let mut contrast = Contrast::new();
+        contrast.init();
+        scene(&mut contrast);
+        let mut raster = RasterRenderer::new();
+        raster.set_background_color(Color::white());
+        let cpu = raster.render(&mut contrast, 80, 80).unwrap();
+
+        // The anti-aliased edges are computed in 32 bits floats on both sides,
+        // only the rounding and the sampling of the rasterizers may differ.
+        assert!(gpu.max_difference(&cpu).unwrap() <= 8);
+    }
+
+    #[test]
+    fn headless_render_to_image()
+    {
+        let _display = DISPLAY.lock().unwrap_or_else(|err| err.into_inner());
+        // The CPU renderer is tested even where a window can be created.
+        for mut renderer in vec![HeadlessRenderer::init(16, 16), HeadlessRenderer::cpu()]
+        {
+            renderer.set_background_color(Color::white());
+            let mark = add_square(renderer.get_contrast_mut(), 4.0, 12.0).get_id();
+            assert!(renderer.render_to_image(16, 16).unwrap().max_difference(&square()).unwrap() <= 2);
+
+            renderer.get_contrast_mut().remove_mark(&mark).unwrap();
+            add_square(renderer.get_contrast_mut(), 2.0, 6.0);
+            renderer.set_camera_scale(2.0);
+            assert!(renderer.render_to_image(16, 16).unwrap().max_difference(&square()).unwrap() <= 2);
         }
     }
 }
@@ -19,7 +19,8 @@ pub fn mark_macro_derive(input: TokenStream) -> TokenStream {
 }
 
 /// Here are the implementations of the methods of the MarkMacro trait.
-/// Their behavior is the same for each mark type
+/// Their behavior is the same for each mark type. The color of the marks
+/// is animated, so they must have the fields 'is_displayed' and 'time' as well.
 fn impl_mark_macro(ast: &syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
 
@@ -30,19 +31,19 @@ fn impl_mark_macro(ast: &syn::DeriveInput) -> TokenStream {
             }
 
             fn get_color(&self) -> properties::color::Color {
-                self.color
+                self.color.target_value
             }
 
             fn get_layer_index(&self) -> usize {
-                self.markid.layer_index
+                self.layer_index
             }
 
             fn is_valid(&self) -> bool {
-                self.markid.valid
+                self.valid
             }
 
             fn set_color<C : Into <properties::color::Color>>(&mut self, color : C) -> &mut #name {
-                self.color = color.into();
+                self.color.set_value(color.into(), self.is_displayed, self.time);
                 self
             }
         }
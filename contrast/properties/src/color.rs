@@ -21,6 +21,18 @@ impl Color {
         Color { r : r / 255.0, g : g / 255.0, b : b / 255.0, a : a / 255.0 }
     }
 
+    /// Packs the color into 8 bits per component, red in the lowest byte, each component
+    /// being clamped between 0 and 1. The shaders unpack it with unpack_color.
+    pub fn to_rgba8(&self) -> u32 {
+        let byte = |c : f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+        byte(self.r) | (byte(self.g) << 8) | (byte(self.b) << 16) | (byte(self.a) << 24)
+    }
+
+    /// Returns the color packed by to_rgba8.
+    pub fn from_rgba8(c : u32) -> Color {
+        Color::from_rgba((c & 255) as f32, ((c >> 8) & 255) as f32, ((c >> 16) & 255) as f32, (c >> 24) as f32)
+    }
+
     pub fn black() -> Color {
         Color { r : 0.0, g : 0.0, b : 0.0, a : 1.0 }
     }
@@ -112,5 +124,13 @@ mod tests {
         assert_approx_eq!(c3.b, 0.786455);
         assert_approx_eq!(c3.a, 0.000965);
     }
-    
-}
\ No newline at end of file
+
+    #[test]
+    fn to_rgba8()
+    {
+        let c = Color { r : 1.0, g : 0.5, b : -1.0, a : 2.0 };
+        assert_eq!(c.to_rgba8(), 255 | (128 << 8) | (255 << 24));
+        assert_eq!(Color::from_rgba8(c.to_rgba8()), Color::from_rgba(255.0, 128.0, 0.0, 255.0));
+        assert_eq!(Color::from_rgba8(Color::blue().to_rgba8()), Color::blue());
+    }
+}
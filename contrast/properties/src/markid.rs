@@ -1,33 +1,53 @@
 /// Structure representing the identifier of a mark.
-/// The first index mark_index indicates where to find the mark
-/// in its layer.
-/// The second index layer_index indicates where to find the
-/// mark's layer in contrast.
-/// It also contains a boolean which indicates whether or not this mark
-/// is valid, meaning it will be displayed.
-#[derive(Copy, Clone, Debug)]
+/// It is an opaque handle made of a slot and a generation.
+/// The slot indicates where to find the location of the mark
+/// in the indirection table of contrast.
+/// The generation is incremented each time the slot is freed, so
+/// an identifier kept after its mark has been removed will never
+/// designate the mark that reuses the same slot.
+/// The default identifier does not designate any mark.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct MarkId {
-    pub mark_index : usize,
-    pub layer_index : usize,
-    pub valid : bool
+    slot : usize,
+    generation : u32
 }
 
 impl MarkId {
-    /// Simply returns a new instance of MarkId, initializing
-    /// all attributes to their default values.
-    pub fn new() -> Self {
+    /// Returns a new instance of MarkId. This is called by contrast
+    /// when a mark is created, the user should only retrieve the
+    /// identifiers of his marks with get_id().
+    pub fn new(slot : usize, generation : u32) -> Self {
         MarkId {
-            mark_index : 0,
-            layer_index : 0,
-            valid : true
+            slot,
+            generation
         }
     }
+
+    /// Returns the slot of the identifier in the indirection table.
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    /// Returns the generation of the identifier.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
 }
 
-impl PartialEq for MarkId {
-    fn eq(&self, other: &MarkId) -> bool {
-        self.mark_index == other.mark_index 
-            && self.layer_index == other.layer_index
-            && self.valid == other.valid
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eq()
+    {
+        let m1 = MarkId::new(3, 1);
+        let m2 = MarkId::new(3, 2);
+
+        assert_eq!(m1, MarkId::new(3, 1));
+        assert_ne!(m1, m2);
+        assert_eq!(m2.slot(), 3);
+        assert_eq!(m2.generation(), 2);
+        assert_eq!(MarkId::default(), MarkId::new(0, 0));
     }
 }
@@ -0,0 +1,503 @@
+use properties::position::Position;
+use properties::color::Color;
+use properties::size::Size;
+use properties::markid::MarkId;
+use crate::easing::Easing;
+use crate::marks::mark::Mark;
+use crate::marks::pointmark::{Shape, PointProperty};
+use crate::marks::linemark::LineProperty;
+use crate::marks::polygonmark::PolygonProperty;
+use crate::marks::textmark::TextProperty;
+use crate::marks::imagemark::ImageProperty;
+use crate::marks::arcmark::ArcProperty;
+use crate::MarkMacro;
+
+/// Identifier of an animation played by contrast, returned by Contrast::play.
+/// It is given back when the animation is finished.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationId(usize);
+
+/// Value taken by a property of a mark at a keyframe.
+/// A value that does not apply to the type of the mark is ignored,
+/// for instance a Size on a line mark.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Value {
+    /// Color of any mark.
+    Color(Color),
+    /// Position of a point, a text, an image or an arc mark.
+    Position(Position),
+    /// Size of a point or an image mark.
+    Size(Size),
+    /// Rotation of a point, a polygon or an image mark.
+    Rotation(f32),
+    /// Shape of a point mark.
+    Shape(Shape),
+    /// Thickness of a line mark.
+    Thickness(f32),
+    /// Stroke width of a point or a polygon mark.
+    StrokeWidth(f32),
+    /// Points of a line or a polygon mark.
+    Points(Vec<Position>),
+    /// Corner radius of a point or an arc mark.
+    CornerRadius(f32),
+    /// Inner radius of an arc mark.
+    InnerRadius(f32),
+    /// Outer radius of an arc mark.
+    OuterRadius(f32),
+    /// Start angle of an arc mark.
+    StartAngle(f32),
+    /// End angle of an arc mark.
+    EndAngle(f32),
+    /// Pad angle of an arc mark.
+    PadAngle(f32)
+}
+
+impl Value {
+    /// Starts a transition of the property of 'mark' to this value, lasting 'duration' seconds.
+    /// The duration and the easing only apply to this transition, the settings of the property are kept.
+    pub(crate) fn apply(&self, mark : &mut Mark, duration : f32, easing : Easing) {
+        match (mark, self) {
+            (Mark::Point(p), Value::Color(color)) => {
+                p.set_color(*color).set_property_transition(PointProperty::Color, duration, easing);
+            },
+            (Mark::Point(p), Value::Position(position)) => {
+                p.set_position(*position).set_property_transition(PointProperty::Position, duration, easing);
+            },
+            (Mark::Point(p), Value::Size(size)) => {
+                p.set_size(*size).set_property_transition(PointProperty::Size, duration, easing);
+            },
+            (Mark::Point(p), Value::Rotation(rotation)) => {
+                p.set_rotation(*rotation).set_property_transition(PointProperty::Rotation, duration, easing);
+            },
+            (Mark::Point(p), Value::Shape(shape)) => {
+                p.set_shape(*shape).set_property_transition(PointProperty::Shape, duration, easing);
+            },
+            (Mark::Point(p), Value::StrokeWidth(stroke_width)) => {
+                p.set_stroke_width(*stroke_width).set_property_transition(PointProperty::StrokeWidth, duration, easing);
+            },
+            (Mark::Line(l), Value::Color(color)) => {
+                l.set_color(*color).set_property_transition(LineProperty::Color, duration, easing);
+            },
+            (Mark::Line(l), Value::Thickness(thickness)) => {
+                l.set_thickness(*thickness).set_property_transition(LineProperty::Thickness, duration, easing);
+            },
+            (Mark::Line(l), Value::Points(points)) => {
+                l.set_points(points.clone()).set_property_transition(LineProperty::Points, duration, easing);
+            },
+            (Mark::Polygon(poly), Value::Color(color)) => {
+                poly.set_color(*color).set_property_transition(PolygonProperty::Color, duration, easing);
+            },
+            (Mark::Polygon(poly), Value::Rotation(rotation)) => {
+                poly.set_rotation(*rotation).set_property_transition(PolygonProperty::Rotation, duration, easing);
+            },
+            (Mark::Polygon(poly), Value::StrokeWidth(stroke_width)) => {
+                poly.set_stroke_width(*stroke_width).set_property_transition(PolygonProperty::StrokeWidth, duration, easing);
+            },
+            (Mark::Polygon(poly), Value::Points(points)) => {
+                poly.set_points(points.clone()).set_property_transition(PolygonProperty::Points, duration, easing);
+            },
+            (Mark::Text(t), Value::Color(color)) => {
+                t.set_color(*color).set_property_transition(TextProperty::Color, duration, easing);
+            },
+            (Mark::Text(t), Value::Position(position)) => {
+                t.set_position(*position).set_property_transition(TextProperty::Position, duration, easing);
+            },
+            (Mark::Image(i), Value::Color(color)) => {
+                i.set_color(*color).set_property_transition(ImageProperty::Color, duration, easing);
+            },
+            (Mark::Image(i), Value::Position(position)) => {
+                i.set_position(*position).set_property_transition(ImageProperty::Position, duration, easing);
+            },
+            (Mark::Image(i), Value::Size(size)) => {
+                i.set_size(*size).set_property_transition(ImageProperty::Size, duration, easing);
+            },
+            (Mark::Image(i), Value::Rotation(rotation)) => {
+                i.set_rotation(*rotation).set_property_transition(ImageProperty::Rotation, duration, easing);
+            },
+            (Mark::Point(p), Value::CornerRadius(corner_radius)) => {
+                p.set_corner_radius(*corner_radius).set_property_transition(PointProperty::CornerRadius, duration, easing);
+            },
+            (Mark::Arc(a), Value::Color(color)) => {
+                a.set_color(*color).set_property_transition(ArcProperty::Color, duration, easing);
+            },
+            (Mark::Arc(a), Value::Position(position)) => {
+                a.set_position(*position).set_property_transition(ArcProperty::Position, duration, easing);
+            },
+            (Mark::Arc(a), Value::CornerRadius(corner_radius)) => {
+                a.set_corner_radius(*corner_radius).set_property_transition(ArcProperty::CornerRadius, duration, easing);
+            },
+            (Mark::Arc(a), Value::InnerRadius(inner_radius)) => {
+                a.set_inner_radius(*inner_radius).set_property_transition(ArcProperty::InnerRadius, duration, easing);
+            },
+            (Mark::Arc(a), Value::OuterRadius(outer_radius)) => {
+                a.set_outer_radius(*outer_radius).set_property_transition(ArcProperty::OuterRadius, duration, easing);
+            },
+            (Mark::Arc(a), Value::StartAngle(start_angle)) => {
+                a.set_start_angle(*start_angle).set_property_transition(ArcProperty::StartAngle, duration, easing);
+            },
+            (Mark::Arc(a), Value::EndAngle(end_angle)) => {
+                a.set_end_angle(*end_angle).set_property_transition(ArcProperty::EndAngle, duration, easing);
+            },
+            (Mark::Arc(a), Value::PadAngle(pad_angle)) => {
+                a.set_pad_angle(*pad_angle).set_property_transition(ArcProperty::PadAngle, duration, easing);
+            },
+            _ => ()
+        }
+    }
+}
+
+/// A keyframe gives the value of a property at a time, in seconds since the start of the animation.
+/// 'easing' is the curve followed by the transition arriving at this keyframe.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Keyframe {
+    pub time : f32,
+    pub value : Value,
+    pub easing : Easing
+}
+
+/// A track animates one property of a mark through a list of keyframes sorted by time.
+/// The value of the first keyframe is set when it is reached, then the property
+/// goes from one keyframe to the next one.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Track {
+    keyframes : Vec<Keyframe>
+}
+
+impl Track {
+    /// Returns a new empty track.
+    pub fn new() -> Self {
+        Track { keyframes : Vec::new() }
+    }
+
+    /// Add a keyframe to the track. A negative time is treated as zero.
+    pub fn keyframe(mut self, time : f32, value : Value, easing : Easing) -> Self {
+        let time = time.max(0.0);
+        let index = self.keyframes.iter().take_while(|k| k.time <= time).count();
+        self.keyframes.insert(index, Keyframe { time, value, easing });
+        self
+    }
+
+    /// Returns the keyframes of the track, sorted by time.
+    pub fn get_keyframes(&self) -> &Vec<Keyframe> {
+        &self.keyframes
+    }
+
+    /// Returns the time of the last keyframe.
+    pub fn get_duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// Returns the index of the keyframe played at the step 'step', and the time
+    /// it is reached. When 'reversed' is true, the keyframes are played backwards
+    /// from 'duration'.
+    fn step(&self, step : usize, reversed : bool, duration : f32) -> (usize, f32) {
+        if reversed {
+            let index = self.keyframes.len() - 1 - step;
+            (index, duration - self.keyframes[index].time)
+        }
+        else {
+            (step, self.keyframes[step].time)
+        }
+    }
+
+    /// Returns the last step reached at the time 'time', if any.
+    fn step_at(&self, time : f32, reversed : bool, duration : f32) -> Option<usize> {
+        let reached = (0..self.keyframes.len()).take_while(|s| self.step(*s, reversed, duration).1 <= time).count();
+        if reached == 0 { None } else { Some(reached - 1) }
+    }
+}
+
+/// Number of times an animation is played.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Repeat {
+    Count(u32),
+    Forever
+}
+
+/// An animation is a set of tracks played together on one or several marks.
+/// It may start after a delay, start a bit later on each mark (stagger), be
+/// repeated, and be played backwards every other time (yoyo).
+#[derive(PartialEq, Clone, Debug)]
+pub struct Animation {
+    tracks : Vec<Track>,
+    delay : f32,
+    stagger : f32,
+    repeat : Repeat,
+    yoyo : bool
+}
+
+impl Animation {
+    /// Returns a new animation without track, played once.
+    pub fn new() -> Self {
+        Animation {
+            tracks : Vec::new(),
+            delay : 0.0,
+            stagger : 0.0,
+            repeat : Repeat::Count(1),
+            yoyo : false
+        }
+    }
+
+    /// Add a track to the animation. Empty tracks are ignored.
+    pub fn track(mut self, track : Track) -> Self {
+        if !track.keyframes.is_empty() {
+            self.tracks.push(track);
+        }
+        self
+    }
+
+    /// Set the time in seconds to wait before starting the animation.
+    pub fn delay(mut self, delay : f32) -> Self {
+        self.delay = delay.max(0.0);
+        self
+    }
+
+    /// Set the time in seconds between the start of the animation on a mark
+    /// and its start on the next mark.
+    pub fn stagger(mut self, stagger : f32) -> Self {
+        self.stagger = stagger.max(0.0);
+        self
+    }
+
+    /// Set the number of times the animation is played.
+    pub fn repeat(mut self, repeat : Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// If 'yoyo' is true, the animation is played backwards every other time.
+    pub fn yoyo(mut self, yoyo : bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    /// Returns the duration in seconds of one play of the animation.
+    pub fn get_duration(&self) -> f32 {
+        self.tracks.iter().map(|track| track.get_duration()).fold(0.0, f32::max)
+    }
+
+    /// Returns the number of plays, or None if the animation is repeated forever.
+    fn plays(&self) -> Option<u32> {
+        match self.repeat {
+            Repeat::Count(count) => Some(count.max(1)),
+            Repeat::Forever => None
+        }
+    }
+}
+
+/// A transition to start on a property of a mark.
+#[derive(PartialEq, Clone, Debug)]
+pub(crate) struct Action {
+    pub(crate) markid : MarkId,
+    pub(crate) value : Value,
+    pub(crate) duration : f32,
+    pub(crate) easing : Easing
+}
+
+/// An animation being played on some marks.
+/// 'cursors' holds, for each mark and each track, the last play and step reached.
+struct Playing {
+    id : AnimationId,
+    animation : Animation,
+    markids : Vec<MarkId>,
+    start : f32,
+    cursors : Vec<Vec<Option<(u32, usize)>>>
+}
+
+/// Schedules the animations played by contrast.
+pub(crate) struct Timeline {
+    next_id : usize,
+    playing : Vec<Playing>
+}
+
+impl Timeline {
+    pub(crate) fn new() -> Self {
+        Timeline { next_id : 0, playing : Vec::new() }
+    }
+
+    /// Plays 'animation' on 'markids' from the time 'start'.
+    pub(crate) fn play(&mut self, animation : Animation, markids : &[MarkId], start : f32) -> AnimationId {
+        let id = AnimationId(self.next_id);
+        self.next_id += 1;
+        let cursors = vec![vec![None; animation.tracks.len()]; markids.len()];
+        self.playing.push(Playing { id, animation, markids : markids.to_vec(), start, cursors });
+        id
+    }
+
+    /// Stops an animation. The marks keep their current transitions.
+    /// Returns false if the animation is not played.
+    pub(crate) fn stop(&mut self, id : AnimationId) -> bool {
+        let count = self.playing.len();
+        self.playing.retain(|playing| playing.id != id);
+        count != self.playing.len()
+    }
+
+    /// Returns true if the animation is played.
+    pub(crate) fn is_playing(&self, id : AnimationId) -> bool {
+        self.playing.iter().any(|playing| playing.id == id)
+    }
+
+    /// Returns the transitions to start at the time 't' and the animations finished at this time.
+    pub(crate) fn update(&mut self, t : f32) -> (Vec<Action>, Vec<AnimationId>) {
+        let mut actions = Vec::new();
+        let mut finished = Vec::new();
+
+        for playing in &mut self.playing {
+            let animation = &playing.animation;
+            let duration = animation.get_duration();
+            let mut done = true;
+
+            for (i, markid) in playing.markids.iter().enumerate() {
+                let local = t - playing.start - animation.delay - animation.stagger * i as f32;
+                if local < 0.0 {
+                    done = false;
+                    continue;
+                }
+
+                // Find the current play and the time elapsed in it.
+                let (play, time) = match animation.plays() {
+                    Some(plays) if duration <= 0.0 || local >= duration * plays as f32 => (plays - 1, duration),
+                    _ if duration <= 0.0 => {
+                        done = false;
+                        (0, duration)
+                    },
+                    _ => {
+                        done = false;
+                        let play = (local / duration).floor();
+                        (play as u32, local - play * duration)
+                    }
+                };
+                let reversed = animation.yoyo && play % 2 == 1;
+
+                for (j, track) in animation.tracks.iter().enumerate() {
+                    let step = match track.step_at(time, reversed, duration) {
+                        Some(step) => step,
+                        None => continue
+                    };
+                    let cursor = &mut playing.cursors[i][j];
+                    if *cursor == Some((play, step)) {
+                        continue;
+                    }
+
+                    // Set the value of the keyframe reached, unless the property is already going to it.
+                    let (index, reached) = track.step(step, reversed, duration);
+                    if step == 0 || *cursor != Some((play, step - 1)) {
+                        actions.push(Action { markid : *markid, value : track.keyframes[index].value.clone(), duration : 0.0, easing : Easing::Linear });
+                    }
+                    // Then start the transition to the next keyframe.
+                    if step + 1 < track.keyframes.len() {
+                        let (next, time) = track.step(step + 1, reversed, duration);
+                        let easing = track.keyframes[if reversed { index } else { next }].easing;
+                        actions.push(Action { markid : *markid, value : track.keyframes[next].value.clone(), duration : time - reached, easing });
+                    }
+                    *cursor = Some((play, step));
+                }
+            }
+
+            if done {
+                finished.push(playing.id);
+            }
+        }
+
+        self.playing.retain(|playing| !finished.contains(&playing.id));
+        (actions, finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_track() -> Track {
+        Track::new()
+            .keyframe(1.0, Value::Color(Color::blue()), Easing::Linear)
+            .keyframe(0.0, Value::Color(Color::red()), Easing::Linear)
+            .keyframe(3.0, Value::Color(Color::green()), Easing::Bounce)
+    }
+
+    #[test]
+    fn keyframe()
+    {
+        let track = color_track();
+
+        assert_eq!(track.get_keyframes()[0].value, Value::Color(Color::red()));
+        assert_eq!(track.get_keyframes()[1].value, Value::Color(Color::blue()));
+        assert_eq!(track.get_duration(), 3.0);
+        assert_eq!(track.step_at(0.5, false, 3.0), Some(0));
+        assert_eq!(track.step_at(2.5, true, 3.0), Some(1));
+        assert_eq!(track.step_at(-1.0, false, 3.0), None);
+    }
+
+    #[test]
+    fn stagger()
+    {
+        let mut timeline = Timeline::new();
+        let m1 = MarkId::new(1, 1);
+        let m2 = MarkId::new(2, 1);
+        let id = timeline.play(Animation::new().track(color_track()).delay(1.0).stagger(0.5), &[m1, m2], 10.0);
+
+        let (actions, finished) = timeline.update(10.5);
+        assert!(actions.is_empty());
+        assert!(finished.is_empty());
+
+        // The first mark starts, its color is set then goes to the next keyframe.
+        let (actions, _) = timeline.update(11.0);
+        assert_eq!(actions, vec!(
+            Action { markid : m1, value : Value::Color(Color::red()), duration : 0.0, easing : Easing::Linear },
+            Action { markid : m1, value : Value::Color(Color::blue()), duration : 1.0, easing : Easing::Linear }
+        ));
+
+        // Nothing new for the first mark, the second one starts.
+        let (actions, _) = timeline.update(11.6);
+        assert_eq!(actions.len(), 2);
+        assert!(actions.iter().all(|action| action.markid == m2));
+
+        // The first mark reached the second keyframe, the transition to the third one starts.
+        let (actions, _) = timeline.update(12.2);
+        assert_eq!(actions, vec!(
+            Action { markid : m1, value : Value::Color(Color::green()), duration : 2.0, easing : Easing::Bounce }
+        ));
+
+        assert!(timeline.is_playing(id));
+        let (_, finished) = timeline.update(14.6);
+        assert_eq!(finished, vec!(id));
+        assert!(!timeline.is_playing(id));
+    }
+
+    #[test]
+    fn yoyo()
+    {
+        let mut timeline = Timeline::new();
+        let m1 = MarkId::new(1, 1);
+        let id = timeline.play(Animation::new().track(color_track()).repeat(Repeat::Count(2)).yoyo(true), &[m1], 0.0);
+
+        timeline.update(0.0);
+        timeline.update(1.0);
+
+        // Played backwards : from the green color to the blue one in 2 seconds.
+        let (actions, _) = timeline.update(3.0);
+        assert_eq!(actions, vec!(
+            Action { markid : m1, value : Value::Color(Color::green()), duration : 0.0, easing : Easing::Linear },
+            Action { markid : m1, value : Value::Color(Color::blue()), duration : 2.0, easing : Easing::Bounce }
+        ));
+
+        // Frames may be late, the last keyframe is set anyway.
+        let (actions, finished) = timeline.update(100.0);
+        assert_eq!(actions, vec!(
+            Action { markid : m1, value : Value::Color(Color::red()), duration : 0.0, easing : Easing::Linear }
+        ));
+        assert_eq!(finished, vec!(id));
+    }
+
+    #[test]
+    fn forever()
+    {
+        let mut timeline = Timeline::new();
+        let id = timeline.play(Animation::new().track(color_track()).repeat(Repeat::Forever), &[MarkId::new(1, 1)], 0.0);
+
+        let (_, finished) = timeline.update(1000.0);
+        assert!(finished.is_empty());
+        assert!(timeline.stop(id));
+        assert!(!timeline.stop(id));
+    }
+}
@@ -4,7 +4,10 @@ use nalgebra::Matrix4;
 #[derive(Copy, Clone)]
 pub struct Camera
 {
-    mat: Matrix4<f32>
+    mat: Matrix4<f32>,
+    width: f32,
+    height: f32,
+    scale: f32
 }
 
 impl Camera
@@ -12,7 +15,8 @@ impl Camera
     /// Create a new Camera
     pub fn init(width: u32, height: u32) -> Self
     {
-        Camera{mat: Matrix4::new_orthographic(0.0, width as f32, height as f32, 0.0, -1000.0, 1000.0)}
+        let (width, height) = (width as f32, height as f32);
+        Camera{mat: projection(width, height, 1.0), width, height, scale: 1.0}
     }
 
     /// Return the projection matrix as a float array
@@ -24,6 +28,27 @@ impl Camera
     /// Resize the viewport
     pub fn resize(&mut self, width: i32, height: i32)
     {
-        self.mat = Matrix4::new_orthographic(0.0, width as f32, height as f32, 0.0, -1000.0, 1000.0);
+        self.width = width as f32;
+        self.height = height as f32;
+        self.mat = projection(self.width, self.height, self.scale);
     }
-}
\ No newline at end of file
+
+    /// Zoom from the top left corner : one unit of the marks takes 'scale' pixels.
+    /// Give the same scale to Contrast::set_scale so the curves stay smooth.
+    pub fn set_scale(&mut self, scale: f32)
+    {
+        self.scale = scale;
+        self.mat = projection(self.width, self.height, self.scale);
+    }
+
+    pub fn get_scale(&self) -> f32
+    {
+        self.scale
+    }
+}
+
+/// Returns the orthographic projection of a viewport of 'width' x 'height' pixels zoomed by 'scale'.
+fn projection(width: f32, height: f32, scale: f32) -> Matrix4<f32>
+{
+    Matrix4::new_orthographic(0.0, width / scale, height / scale, 0.0, -1000.0, 1000.0)
+}
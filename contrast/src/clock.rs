@@ -0,0 +1,249 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// This trait is implemented by every clock that contrast can use.
+/// The clock gives the time at which the animations start, and the time
+/// given to the shaders to compute the progress of the animations.
+/// Each Contrast owns its clock, see Contrast::set_clock.
+pub trait Clock {
+    /// Returns the current time in seconds.
+    fn now(&self) -> f32;
+
+    /// Called by the renderer once per frame, before the frame is drawn.
+    /// Does nothing by default.
+    fn tick(&mut self) {}
+}
+
+/// Clock following the real time, from its creation.
+/// This is the clock used by default.
+#[derive(Clone, Debug)]
+pub struct RealTimeClock {
+    start : Instant
+}
+
+impl RealTimeClock {
+    pub fn new() -> Self {
+        RealTimeClock { start : Instant::now() }
+    }
+}
+
+impl Default for RealTimeClock {
+    fn default() -> Self {
+        RealTimeClock::new()
+    }
+}
+
+impl Clock for RealTimeClock {
+    fn now(&self) -> f32 {
+        let elapsed = self.start.elapsed();
+        (elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9) as f32
+    }
+}
+
+/// Clock whose time only changes when it is told to.
+/// With a step, the time moves forward of the step at each frame, so every
+/// frame shows the animations at an exact time whatever the frame rate is.
+/// Without a step, the time is only changed by set_time and advance, which is
+/// useful to test the animations at given times.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ManualClock {
+    time : f32,
+    step : f32
+}
+
+impl ManualClock {
+    /// Returns a clock at the time 0 that does not move by itself.
+    pub fn new() -> Self {
+        ManualClock { time : 0.0, step : 0.0 }
+    }
+
+    /// Returns a clock at the time 0 that moves forward of 'step' seconds at each frame.
+    /// Example : ManualClock::fixed_step(1.0 / 60.0) for a video at 60 frames per second.
+    pub fn fixed_step(step : f32) -> Self {
+        ManualClock { time : 0.0, step }
+    }
+
+    pub fn set_time(&mut self, time : f32) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    /// Move the time forward of 'duration' seconds.
+    pub fn advance(&mut self, duration : f32) -> &mut Self {
+        self.time += duration;
+        self
+    }
+
+    pub fn set_step(&mut self, step : f32) -> &mut Self {
+        self.step = step;
+        self
+    }
+
+    pub fn get_step(&self) -> f32 {
+        self.step
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> f32 {
+        self.time
+    }
+
+    fn tick(&mut self) {
+        self.time += self.step;
+    }
+}
+
+/// Clock going 'scale' times faster than another clock. It can be paused,
+/// resumed and moved to any time, for instance to replay the animations.
+/// Changing the scale does not make the time jump : the new scale only
+/// applies from now.
+#[derive(Clone, Debug)]
+pub struct ScaledClock<C : Clock> {
+    clock : C,
+    scale : f32,
+    paused : bool,
+    // Time of 'clock' and time of this clock when the scale last changed.
+    origin : f32,
+    offset : f32
+}
+
+impl<C : Clock> ScaledClock<C> {
+    pub fn new(clock : C, scale : f32) -> Self {
+        let origin = clock.now();
+        ScaledClock { clock, scale, paused : false, origin, offset : 0.0 }
+    }
+
+    /// Set the speed of the clock. A scale of 0.5 slows the animations down by two,
+    /// and a negative scale plays them backwards.
+    pub fn set_scale(&mut self, scale : f32) -> &mut Self {
+        self.set_time(self.now());
+        self.scale = scale;
+        self
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Stop the time until resume is called.
+    pub fn pause(&mut self) -> &mut Self {
+        self.set_time(self.now());
+        self.paused = true;
+        self
+    }
+
+    /// Restart the time from where it was paused.
+    pub fn resume(&mut self) -> &mut Self {
+        self.origin = self.clock.now();
+        self.paused = false;
+        self
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Move the clock to 'time'. Setting a time already passed replays
+    /// the animations started since then.
+    pub fn set_time(&mut self, time : f32) -> &mut Self {
+        self.origin = self.clock.now();
+        self.offset = time;
+        self
+    }
+
+    /// Returns the clock that this clock follows.
+    pub fn get_clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Returns a mutable reference of the clock that this clock follows.
+    pub fn get_clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+}
+
+impl<C : Clock> Clock for ScaledClock<C> {
+    fn now(&self) -> f32 {
+        if self.paused {
+            self.offset
+        }
+        else {
+            self.offset + (self.clock.now() - self.origin) * self.scale
+        }
+    }
+
+    fn tick(&mut self) {
+        self.clock.tick();
+    }
+}
+
+/// A clock shared with Rc can be given to contrast while the user keeps
+/// a handle on it, to pause it or to move its time for instance.
+impl<C : Clock> Clock for Rc<RefCell<C>> {
+    fn now(&self) -> f32 {
+        self.borrow().now()
+    }
+
+    fn tick(&mut self) {
+        self.borrow_mut().tick();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock()
+    {
+        let mut clock = ManualClock::fixed_step(0.5);
+        assert_eq!(clock.now(), 0.0);
+
+        clock.tick();
+        clock.tick();
+        assert_eq!(clock.now(), 1.0);
+
+        clock.advance(2.0);
+        assert_eq!(clock.now(), 3.0);
+        clock.set_time(0.25);
+        assert_eq!(clock.now(), 0.25);
+    }
+
+    #[test]
+    fn scaled_clock()
+    {
+        let mut clock = ScaledClock::new(ManualClock::new(), 2.0);
+        clock.get_clock_mut().advance(1.0);
+        assert_eq!(clock.now(), 2.0);
+
+        clock.set_scale(0.5);
+        assert_eq!(clock.now(), 2.0);
+        clock.get_clock_mut().advance(1.0);
+        assert_eq!(clock.now(), 2.5);
+
+        clock.pause();
+        clock.get_clock_mut().advance(1.0);
+        assert_eq!(clock.now(), 2.5);
+        assert!(clock.is_paused());
+
+        clock.resume();
+        clock.get_clock_mut().advance(2.0);
+        assert_eq!(clock.now(), 3.5);
+
+        clock.set_time(0.0);
+        assert_eq!(clock.now(), 0.0);
+    }
+
+    #[test]
+    fn shared_clock()
+    {
+        let clock = Rc::new(RefCell::new(ManualClock::fixed_step(1.0)));
+        let mut shared = clock.clone();
+
+        shared.tick();
+        clock.borrow_mut().advance(1.0);
+        assert_eq!(shared.now(), 2.0);
+    }
+}
@@ -0,0 +1,252 @@
+use properties::position::Position;
+
+/// This enum tells how the line of a LineMark goes through its points.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Curve {
+    /// Straight sub-lines from one point to the next one.
+    Linear,
+    /// Quadratic Bézier curves : every second point is a control point, starting from the second one.
+    QuadraticBezier,
+    /// Cubic Bézier curves : the points go by three, two control points then a point of the curve.
+    CubicBezier,
+    /// A Catmull-Rom spline going through every point.
+    CatmullRom,
+    /// A cubic curve going through every point without overshooting them along the y axis,
+    /// for the data of a line chart whose points are sorted along the x axis.
+    Monotone
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve::Linear
+    }
+}
+
+/// Largest distance in pixels between a curve and the sub-lines drawing it.
+pub(crate) const TOLERANCE : f32 = 0.25;
+
+/// Maximum number of sub-lines of one piece of curve.
+const MAX_SEGMENTS : usize = 1000;
+
+/// A cubic Bézier curve, from its first point to its last one.
+type Cubic = [Position; 4];
+
+fn lerp(a : Position, b : Position, t : f32) -> Position {
+    Position { x : a.x + (b.x - a.x) * t, y : a.y + (b.y - a.y) * t, z : a.z + (b.z - a.z) * t }
+}
+
+/// Returns the straight line from 'a' to 'b' as a cubic curve.
+fn line(a : Position, b : Position) -> Cubic {
+    [a, lerp(a, b, 1.0 / 3.0), lerp(a, b, 2.0 / 3.0), b]
+}
+
+/// Returns the pieces of 'curve' going through 'points', each one starting where the previous one ends.
+/// The points left over by the Bézier curves are joined by straight lines.
+fn pieces(points : &[Position], curve : Curve) -> Vec<Cubic> {
+    let n = points.len();
+    match curve {
+        Curve::Linear => points.windows(2).map(|w| line(w[0], w[1])).collect(),
+        Curve::QuadraticBezier => {
+            let mut pieces = Vec::new();
+            let mut i = 0;
+            while i + 2 < n {
+                let (a, c, b) = (points[i], points[i + 1], points[i + 2]);
+                pieces.push([a, lerp(a, c, 2.0 / 3.0), lerp(b, c, 2.0 / 3.0), b]);
+                i += 2;
+            }
+            pieces.extend(points[i..].windows(2).map(|w| line(w[0], w[1])));
+            pieces
+        },
+        Curve::CubicBezier => {
+            let mut pieces = Vec::new();
+            let mut i = 0;
+            while i + 3 < n {
+                pieces.push([points[i], points[i + 1], points[i + 2], points[i + 3]]);
+                i += 3;
+            }
+            pieces.extend(points[i..].windows(2).map(|w| line(w[0], w[1])));
+            pieces
+        },
+        Curve::CatmullRom => {
+            // The tangent at each point is parallel to the line joining its neighbours.
+            (0..n.max(1) - 1).map(|i| {
+                let (p0, p1, p2, p3) = (points[i.max(1) - 1], points[i], points[i + 1], points[(i + 2).min(n - 1)]);
+                let c1 = Position { x : p1.x + (p2.x - p0.x) / 6.0, y : p1.y + (p2.y - p0.y) / 6.0, z : p1.z + (p2.z - p0.z) / 6.0 };
+                let c2 = Position { x : p2.x - (p3.x - p1.x) / 6.0, y : p2.y - (p3.y - p1.y) / 6.0, z : p2.z - (p3.z - p1.z) / 6.0 };
+                [p1, c1, c2, p2]
+            }).collect()
+        },
+        Curve::Monotone => {
+            let slopes = monotone_slopes(points);
+            points.windows(2).zip(slopes.windows(2)).map(|(p, m)| {
+                let dx = (p[1].x - p[0].x) / 3.0;
+                let (c1, c2) = (lerp(p[0], p[1], 1.0 / 3.0), lerp(p[0], p[1], 2.0 / 3.0));
+                [p[0], Position { y : p[0].y + m[0] * dx, ..c1 }, Position { y : p[1].y - m[1] * dx, ..c2 }, p[1]]
+            }).collect()
+        }
+    }
+}
+
+/// Returns the slope of the curve at each point, with the method of Fritsch and Carlson :
+/// the slopes are limited so that the curve is monotone between two points.
+fn monotone_slopes(points : &[Position]) -> Vec<f32> {
+    let secants : Vec<f32> = points.windows(2).map(|w| {
+        let dx = w[1].x - w[0].x;
+        if dx != 0.0 { (w[1].y - w[0].y) / dx } else { 0.0 }
+    }).collect();
+    let n = points.len();
+    let mut slopes = vec![0.0; n];
+    for i in 0..n {
+        slopes[i] = if i == 0 { secants[0] }
+                    else if i == n - 1 { secants[n - 2] }
+                    else if secants[i - 1] * secants[i] <= 0.0 { 0.0 }
+                    else { (secants[i - 1] + secants[i]) / 2.0 };
+    }
+    for (i, secant) in secants.iter().enumerate() {
+        if *secant == 0.0 {
+            slopes[i] = 0.0;
+            slopes[i + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (slopes[i] / secant, slopes[i + 1] / secant);
+        let norm = a * a + b * b;
+        if norm > 9.0 {
+            let t = 3.0 / norm.sqrt();
+            slopes[i] = t * a * secant;
+            slopes[i + 1] = t * b * secant;
+        }
+    }
+    slopes
+}
+
+/// Returns the number of sub-lines needed so that the flattened 'cubic' is never
+/// farther than 'tolerance' from the curve, with the formula of Wang.
+fn segments(cubic : &Cubic, tolerance : f32) -> usize {
+    let second = |a : Position, b : Position, c : Position| ((a.x - 2.0 * b.x + c.x).powi(2) + (a.y - 2.0 * b.y + c.y).powi(2)).sqrt();
+    let m = second(cubic[0], cubic[1], cubic[2]).max(second(cubic[1], cubic[2], cubic[3]));
+    if m == 0.0 || tolerance <= 0.0 {
+        return 1;
+    }
+    ((0.75 * m / tolerance).sqrt().ceil() as usize).clamp(1, MAX_SEGMENTS)
+}
+
+fn evaluate(cubic : &Cubic, t : f32) -> Position {
+    let (a, b, c) = (lerp(cubic[0], cubic[1], t), lerp(cubic[1], cubic[2], t), lerp(cubic[2], cubic[3], t));
+    lerp(lerp(a, b, t), lerp(b, c, t), t)
+}
+
+/// Returns the points of the polyline drawing 'curve' through 'points', never farther than
+/// 'tolerance' from the curve, and the index in this polyline of each point of 'points'
+/// the curve goes through, that is every point but the control points of the Bézier curves.
+pub fn flatten(points : &[Position], curve : Curve, tolerance : f32) -> (Vec<Position>, Vec<usize>) {
+    if points.len() < 2 || curve == Curve::Linear {
+        return (points.to_vec(), (0..points.len()).collect());
+    }
+    let mut flattened = vec![points[0]];
+    let mut knots = vec![0];
+    for cubic in pieces(points, curve) {
+        let n = segments(&cubic, tolerance);
+        flattened.extend((1..n).map(|i| evaluate(&cubic, i as f32 / n as f32)));
+        flattened.push(cubic[3]);
+        knots.push(flattened.len() - 1);
+    }
+    (flattened, knots)
+}
+
+/// Returns the index in the 'count' points of a line of each point 'curve' goes through,
+/// in the order of the indices returned by flatten.
+pub(crate) fn knot_points(count : usize, curve : Curve) -> Vec<usize> {
+    let step = match curve {
+        Curve::QuadraticBezier => 2,
+        Curve::CubicBezier => 3,
+        _ => 1
+    };
+    if count < 2 {
+        return (0..count).collect();
+    }
+    let mut knots = vec![0];
+    let mut i = 0;
+    while i + step < count {
+        i += step;
+        knots.push(i);
+    }
+    knots.extend(i + 1..count);
+    knots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x : f32, y : f32) -> Position {
+        Position { x, y, z : 0.0 }
+    }
+
+    #[test]
+    fn flatten_linear()
+    {
+        let points = vec![p(0.0, 0.0), p(10.0, 0.0), p(10.0, 10.0)];
+        assert_eq!(flatten(&points, Curve::Linear, 0.25), (points.clone(), vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn flatten_quadratic()
+    {
+        let points = vec![p(0.0, 0.0), p(50.0, 100.0), p(100.0, 0.0)];
+        let (flattened, knots) = flatten(&points, Curve::QuadraticBezier, 0.25);
+        assert_eq!(knots, vec![0, flattened.len() - 1]);
+        assert_eq!(flattened[0], points[0]);
+        assert_eq!(flattened[flattened.len() - 1], points[2]);
+        // The top of the parabola is half way to its control point.
+        let top = flattened.iter().map(|point| point.y).fold(0.0, f32::max);
+        assert!((top - 50.0).abs() < 0.25);
+
+        // A finer tolerance, as when the camera zooms in, gives more points.
+        let (finer, _) = flatten(&points, Curve::QuadraticBezier, 0.025);
+        assert!(finer.len() > 2 * flattened.len());
+    }
+
+    #[test]
+    fn flatten_cubic()
+    {
+        // The last point, left over by the cubic curve, is joined by a straight line.
+        let points = vec![p(0.0, 0.0), p(0.0, 10.0), p(10.0, 10.0), p(10.0, 0.0), p(20.0, 0.0)];
+        let (flattened, knots) = flatten(&points, Curve::CubicBezier, 0.25);
+        assert_eq!(knots.len(), 3);
+        assert_eq!(flattened[knots[1]], points[3]);
+        assert_eq!(knots[2], knots[1] + 1);
+        assert_eq!(knot_points(points.len(), Curve::CubicBezier), vec![0, 3, 4]);
+        let middle = evaluate(&[points[0], points[1], points[2], points[3]], 0.5);
+        assert_eq!(middle, p(5.0, 7.5));
+    }
+
+    #[test]
+    fn flatten_catmull_rom()
+    {
+        let points = vec![p(0.0, 0.0), p(10.0, 10.0), p(20.0, 10.0), p(30.0, 0.0)];
+        let (flattened, knots) = flatten(&points, Curve::CatmullRom, 0.1);
+        assert_eq!(knots.len(), 4);
+        for (knot, point) in knots.iter().zip(&points) {
+            assert_eq!(flattened[*knot], *point);
+        }
+        // Unlike a monotone curve, the spline goes above the points in its middle.
+        assert!(flattened.iter().any(|point| point.y > 10.0));
+    }
+
+    #[test]
+    fn flatten_monotone()
+    {
+        let points = vec![p(0.0, 0.0), p(10.0, 10.0), p(20.0, 0.0), p(30.0, 0.0), p(40.0, 20.0)];
+        let (flattened, knots) = flatten(&points, Curve::Monotone, 0.1);
+        assert_eq!(knots.len(), 5);
+        // The curve never goes above or below the points around it.
+        for w in knots.windows(2) {
+            let (a, b) = (flattened[w[0]].y, flattened[w[1]].y);
+            for point in &flattened[w[0]..=w[1]] {
+                assert!(point.y >= a.min(b) - 1e-4 && point.y <= a.max(b) + 1e-4);
+            }
+        }
+        // The flat part stays flat.
+        assert!(flattened[knots[2]..=knots[3]].iter().all(|point| point.y == 0.0));
+    }
+}
@@ -0,0 +1,116 @@
+use std::f32::consts::PI;
+
+/// This enum describes every easing curve that an animation can follow.
+/// An easing curve maps the linear progress of an animation (between 0 and 1)
+/// to the progress really applied to the animated property.
+/// The same curves are implemented in the shaders, so the integer value
+/// of each variant must not change.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Easing {
+    Linear = 0,
+    CubicIn = 1,
+    CubicOut = 2,
+    CubicInOut = 3,
+    Elastic = 4,
+    Bounce = 5,
+    Step = 6
+}
+
+impl Easing {
+    pub fn from_integer(i : u32) -> Self {
+        match i {
+            0 => Easing::Linear,
+            1 => Easing::CubicIn,
+            2 => Easing::CubicOut,
+            3 => Easing::CubicInOut,
+            4 => Easing::Elastic,
+            5 => Easing::Bounce,
+            6 => Easing::Step,
+            _ => Easing::Linear
+        }
+    }
+
+    /// Returns the eased progress of an animation whose linear progress is 'x'.
+    /// 'x' is clamped between 0 and 1. The result is 0 at the start and 1 at the end
+    /// of the animation, but it may go beyond those bounds in between (elastic).
+    pub fn apply(&self, x : f32) -> f32 {
+        let x = x.max(0.0).min(1.0);
+        match self {
+            Easing::Linear => x,
+            Easing::CubicIn => x * x * x,
+            Easing::CubicOut => 1.0 - (1.0 - x).powi(3),
+            Easing::CubicInOut => {
+                if x < 0.5 {
+                    4.0 * x * x * x
+                }
+                else {
+                    1.0 - (-2.0 * x + 2.0).powi(3) / 2.0
+                }
+            },
+            Easing::Elastic => {
+                if x == 0.0 || x == 1.0 {
+                    x
+                }
+                else {
+                    2f32.powf(-10.0 * x) * ((x * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            },
+            Easing::Bounce => bounce(x),
+            Easing::Step => if x < 1.0 { 0.0 } else { 1.0 }
+        }
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+fn bounce(x : f32) -> f32 {
+    let n = 7.5625;
+    let d = 2.75;
+
+    if x < 1.0 / d {
+        n * x * x
+    }
+    else if x < 2.0 / d {
+        let x = x - 1.5 / d;
+        n * x * x + 0.75
+    }
+    else if x < 2.5 / d {
+        let x = x - 2.25 / d;
+        n * x * x + 0.9375
+    }
+    else {
+        let x = x - 2.625 / d;
+        n * x * x + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds()
+    {
+        for i in 0..7 {
+            let easing = Easing::from_integer(i);
+            assert!(easing.apply(0.0).abs() < 1e-5, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+            assert!((easing.apply(2.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn apply()
+    {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::CubicIn.apply(0.5), 0.125);
+        assert_eq!(Easing::CubicOut.apply(0.5), 0.875);
+        assert_eq!(Easing::CubicInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::Step.apply(0.99), 0.0);
+        assert!(Easing::Elastic.apply(0.2) > 1.0);
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use properties::color::Color;
+use crate::markscontainer::Contrast;
+use crate::vector::{self, Item, Paint, Segment, StrokeStyle};
+use crate::truetype::{self, EmbeddedFont, Subset};
+use crate::pdf::{number, shading};
+use crate::triangulation::FillRule;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Longest string of a PostScript interpreter, minus the padding byte of the strings of a Type 42 font.
+const MAX_STRING : usize = 65534;
+
+/// Writes 'path' with the operators of PostScript.
+fn write_path(eps : &mut String, path : &[Segment]) {
+    eps.push_str("newpath\n");
+    for segment in path {
+        let _ = match segment {
+            Segment::Move(p) => writeln!(eps, "{} {} moveto", number(p[0]), number(p[1])),
+            Segment::Line(p) => writeln!(eps, "{} {} lineto", number(p[0]), number(p[1])),
+            Segment::Cubic(c1, c2, p) => writeln!(eps, "{} {} {} {} {} {} curveto", number(c1[0]), number(c1[1]),
+                                                  number(c2[0]), number(c2[1]), number(p[0]), number(p[1])),
+            Segment::Close => writeln!(eps, "closepath")
+        };
+    }
+}
+
+fn write_color(eps : &mut String, color : &Color) {
+    let _ = writeln!(eps, "{} {} {} setrgbcolor", number(color.r), number(color.g), number(color.b));
+}
+
+/// Writes the width, the cap, the join, the miter limit and the dash pattern of a stroke.
+fn write_stroke_style(eps : &mut String, width : f32, style : &StrokeStyle) {
+    let (cap, join, limit) = style.codes();
+    let dashes : Vec<String> = style.dashes.iter().map(|length| number(*length)).collect();
+    let _ = writeln!(eps, "{} setlinewidth {} setlinecap {} setlinejoin {} setmiterlimit [{}] {} setdash", number(width), cap, join,
+                     number(limit), dashes.join(" "), number(style.dash_offset));
+}
+
+/// Splits the font into strings ending at the start of a table or of a glyph,
+/// as required by the Type 42 fonts.
+fn sfnts(subset : &Subset) -> Vec<&[u8]> {
+    let (mut strings, mut start, mut last) = (Vec::new(), 0, 0);
+    for &boundary in subset.boundaries.iter().chain(Some(&subset.data.len())) {
+        if boundary - start > MAX_STRING && last > start {
+            strings.push(&subset.data[start..last]);
+            start = last;
+        }
+        last = boundary;
+    }
+    strings.push(&subset.data[start..]);
+    strings
+}
+
+/// Writes the definition of the font as a Type 42 font, whose glyphs are named after their index.
+fn write_font(eps : &mut String, font : &EmbeddedFont, name : &str) {
+    let subset = font.subset();
+    let em = font.font.units_per_em as f32;
+    let bbox = font.font.bounding_box();
+    let _ = write!(eps, "%%BeginResource: font {0}\n11 dict begin\n/FontName /{0} def\n/FontType 42 def\n/PaintType 0 def\n\
+                         /FontMatrix [1 0 0 1 0 0] def\n/FontBBox [{1} {2} {3} {4}] def\n\
+                         /Encoding 256 array 0 1 255 {{ 1 index exch /.notdef put }} for def\n\
+                         /CharStrings {5} dict dup begin\n/.notdef 0 def\n",
+                   name, number(bbox[0] as f32 / em), number(bbox[1] as f32 / em), number(bbox[2] as f32 / em), number(bbox[3] as f32 / em),
+                   font.glyphs.len() + 1);
+    for gid in font.glyphs.keys() {
+        let _ = writeln!(eps, "/g{0} {0} def", gid);
+    }
+    eps.push_str("end def\n/sfnts [\n");
+    for string in sfnts(&subset) {
+        eps.push('<');
+        for (i, byte) in string.iter().chain(Some(&0)).enumerate() {
+            if i % 40 == 0 { eps.push('\n'); }
+            let _ = write!(eps, "{:02X}", byte);
+        }
+        eps.push_str("\n>\n");
+    }
+    eps.push_str("] def\nFontName currentdict end definefont pop\n%%EndResource\n");
+}
+
+impl Contrast {
+    /// Returns the marks of contrast as an Encapsulated PostScript document of 'width' x 'height'
+    /// points, like export_pdf. The fonts are embedded as Type 42 fonts, which need PostScript
+    /// level 2, and the gradients are shadings, which need PostScript level 3. PostScript has
+    /// no transparency, so the opacity of the colors is ignored.
+    /// Returns an error if a font is not a TrueType font or cannot be read.
+    pub fn export_eps(&self, width : u32, height : u32) -> Result<String> {
+        let fonts = self.fonts.borrow();
+        let items = vector::items(self, &fonts);
+        let fonts = truetype::embedded_fonts(&items)?;
+
+        let gradients = items.iter().any(|item| matches!(item, Item::Fill(_, _, Paint::Gradient(..)) | Item::InnerStroke(_, _, _, Paint::Gradient(..))));
+        let mut eps = format!("%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 0 0 {0} {1}\n%%LanguageLevel: {2}\n%%Pages: 1\n%%EndComments\n\
+                               %%BeginProlog\n", width, height, if gradients { 3 } else { 2 });
+        let names : Vec<String> = fonts.iter().enumerate().map(|(i, font)| font.name(i)).collect();
+        for (font, name) in fonts.iter().zip(&names) {
+            write_font(&mut eps, font, name);
+        }
+        let _ = write!(eps, "%%EndProlog\n%%Page: 1 1\ngsave\n0 {} translate 1 -1 scale\n", height);
+
+        for item in &items {
+            eps.push_str("gsave\n");
+            match item {
+                Item::Fill(path, rule, Paint::Color(color)) => {
+                    write_color(&mut eps, color);
+                    write_path(&mut eps, path);
+                    eps.push_str(if *rule == FillRule::EvenOdd { "eofill\n" } else { "fill\n" });
+                },
+                Item::Fill(path, rule, Paint::Gradient(color, gradient)) => {
+                    // The shading is clipped by the path.
+                    write_path(&mut eps, path);
+                    eps.push_str(if *rule == FillRule::EvenOdd { "eoclip\n" } else { "clip\n" });
+                    let _ = writeln!(eps, "{} shfill", shading(color, gradient));
+                },
+                Item::Stroke(path, width, style, color) => {
+                    write_color(&mut eps, color);
+                    write_stroke_style(&mut eps, *width, style);
+                    write_path(&mut eps, path);
+                    eps.push_str("stroke\n");
+                },
+                Item::InnerStroke(path, rule, width, paint) => {
+                    // A stroke twice as wide is clipped by the path.
+                    write_path(&mut eps, path);
+                    eps.push_str(if *rule == FillRule::EvenOdd { "eoclip\n" } else { "clip\n" });
+                    write_color(&mut eps, &paint.color());
+                    write_stroke_style(&mut eps, 2.0 * width, &StrokeStyle::polygon());
+                    write_path(&mut eps, path);
+                    match paint {
+                        Paint::Color(_) => eps.push_str("stroke\n"),
+                        // The shading is also clipped by the outline of the stroke.
+                        Paint::Gradient(color, gradient) => { let _ = writeln!(eps, "strokepath clip\n{} shfill", shading(color, gradient)); }
+                    }
+                },
+                Item::Text(face, position, text, color) => {
+                    let index = truetype::font_index(&fonts, face);
+                    write_color(&mut eps, color);
+                    // The glyphs are flipped back.
+                    let _ = writeln!(eps, "{} {} translate 1 -1 scale\n/{} findfont {} scalefont setfont\n0 0 moveto",
+                                     number(position.x), number(position.y), names[index], face.police);
+                    for gid in fonts[index].glyph_indices(text) {
+                        if gid == 0 { eps.push_str("/.notdef glyphshow\n"); } else { let _ = writeln!(eps, "/g{} glyphshow", gid); }
+                    }
+                }
+            }
+            eps.push_str("grestore\n");
+        }
+        eps.push_str("grestore\nshowpage\n%%EOF\n");
+        Ok(eps)
+    }
+
+    /// Same behavior than export_eps, then save the document at 'path'.
+    pub fn save_eps<P : AsRef <Path>>(&self, width : u32, height : u32, path : P) -> Result<()> {
+        let eps = self.export_eps(width, height)?;
+        let file = File::create(path).map_err(|err| Error::ImageWrite(err.to_string()))?;
+        BufWriter::new(file).write_all(eps.as_bytes()).map_err(|err| Error::ImageWrite(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marks::pointmark::Shape;
+    use crate::marks::linemark::{LineCap, LineJoin};
+    use crate::marks::polygonmark::Gradient;
+    use crate::MarkMacro;
+
+    #[test]
+    fn export_eps()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_point_mark().set_shape(Shape::Circle).set_size((10.0, 10.0)).set_position((10.0, 10.0, 0.0)).set_color((0.0, 1.0, 0.0, 0.5));
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((1.0, 1.0, 0.0)).set_thickness(3.0);
+
+        let eps = c.export_eps(200, 100).unwrap();
+        assert!(eps.starts_with("%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 0 0 200 100\n"));
+        assert!(eps.contains("0 100 translate 1 -1 scale\n"));
+        assert!(eps.contains("0 1 0 setrgbcolor\nnewpath\n13 10 moveto\n"));
+        assert_eq!(eps.matches("curveto").count(), 4);
+        assert!(eps.contains("3 setlinewidth 0 setlinecap 0 setlinejoin 4 setmiterlimit [] 0 setdash\nnewpath\n0 0 moveto\n1 1 lineto\nstroke\n"));
+        assert!(eps.ends_with("showpage\n%%EOF\n"));
+    }
+
+    #[test]
+    fn export_eps_line_style()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((1.0, 1.0, 0.0)).set_thickness(2.0)
+         .set_cap(LineCap::Square).set_join(LineJoin::Miter(2.5)).set_dashes(&[4.0]).unwrap();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((1.0, 1.0, 0.0)).set_dashes(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+
+        let eps = c.export_eps(200, 100).unwrap();
+        assert!(eps.contains("2 setlinewidth 2 setlinecap 0 setlinejoin 2.5 setmiterlimit [4 4] 0 setdash\n"));
+        assert!(eps.contains("[1 2 3 4] 0 setdash\n"));
+    }
+
+    #[test]
+    fn export_eps_gradient()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_fill()
+         .set_color((1.0, 0.0, 0.0, 1.0)).set_gradient(Gradient::Linear((0.0, 0.0).into(), (5.0, 0.0).into(), Color::blue()));
+        assert!(c.export_eps(200, 100).unwrap().contains("%%LanguageLevel: 3\n"));
+        assert!(c.export_eps(200, 100).unwrap().contains("h\nclip\n<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 5 0] \
+                                                          /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >> \
+                                                          /Extend [true true] >> shfill\n"));
+    }
+
+    #[test]
+    fn export_eps_text()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.register_font("serif", "../contrast-renderer/crimson-b.ttf", 20).unwrap();
+        c.add_text_mark().set_font("serif").set_text("a\u{10FFFF}").set_position((5.0, 15.0, 0.0));
+
+        let eps = c.export_eps(100, 50).unwrap();
+        assert!(eps.contains("/FontName /AAAAAA+serif def\n/FontType 42 def"));
+        assert!(eps.contains("5 15 translate 1 -1 scale\n/AAAAAA+serif findfont 20 scalefont setfont\n0 0 moveto\n/g"));
+        assert!(eps.contains("glyphshow\n/.notdef glyphshow\n"));
+    }
+
+    #[test]
+    fn sfnts()
+    {
+        let subset = Subset { data : vec![0; 150_000], boundaries : vec![0, 40_000, 70_000, 90_000] };
+        let strings = super::sfnts(&subset);
+        assert_eq!(strings.iter().map(|string| string.len()).collect::<Vec<_>>(), vec![40_000, 50_000, 60_000]);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use properties::markid::MarkId;
+use crate::marks::linemark::MAX_DASHES;
+
+/// Every error that contrast can return to the user instead of panicking.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The MarkId does not designate any mark, either because it was
+    /// removed or because it was never given by contrast.
+    UnknownMark(MarkId),
+    /// There is no layer at this index.
+    UnknownLayer(usize),
+    /// No font was registered with this name.
+    UnknownFont(String),
+    /// The font file at this path could not be loaded.
+    FontLoad(String, freetype::Error),
+    /// An error returned by FreeType.
+    FreeType(freetype::Error),
+    /// The glyph of this character was not loaded in the font cache.
+    GlyphMissing(char),
+    /// There is no room left in the texture atlas of this font.
+    AtlasFull(String),
+    /// The renderer could not create its window, for example without any display.
+    Window(String),
+    /// The renderer could not create the framebuffer to render without a window.
+    Framebuffer(String),
+    /// An image could not be encoded or written.
+    ImageWrite(String),
+    /// An image could not be read or decoded.
+    ImageRead(String),
+    /// The font file at this path could not be embedded in an exported document.
+    FontEmbed(String, String),
+    /// This path or this outline has no area, so it cannot be a custom shape.
+    InvalidShape(String),
+    /// There is no room left in the texture atlas of the custom shapes.
+    ShapeAtlasFull,
+    /// There is no room left in the texture atlas of the images for the image with this name.
+    ImageAtlasFull(String),
+    /// A dash pattern has this number of lengths once repeated, more than MAX_DASHES.
+    TooManyDashes(usize)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownMark(markid) => write!(f, "unknown mark {:?}", markid),
+            Error::UnknownLayer(layer_index) => write!(f, "unknown layer {}", layer_index),
+            Error::UnknownFont(name) => write!(f, "unknown font \"{}\"", name),
+            Error::FontLoad(path, err) => write!(f, "cannot load the font \"{}\" : {}", path, err),
+            Error::FreeType(err) => write!(f, "freetype error : {}", err),
+            Error::GlyphMissing(c) => write!(f, "missing glyph for the character {:?}", c),
+            Error::AtlasFull(name) => write!(f, "the texture atlas of the font \"{}\" is full", name),
+            Error::Window(err) => write!(f, "cannot create the window : {}", err),
+            Error::Framebuffer(err) => write!(f, "cannot create the framebuffer : {}", err),
+            Error::ImageWrite(err) => write!(f, "cannot write the image : {}", err),
+            Error::ImageRead(err) => write!(f, "cannot read the image : {}", err),
+            Error::FontEmbed(path, err) => write!(f, "cannot embed the font \"{}\" : {}", path, err),
+            Error::InvalidShape(shape) => write!(f, "cannot make a shape of \"{}\"", shape),
+            Error::ShapeAtlasFull => write!(f, "the texture atlas of the custom shapes is full"),
+            Error::ImageAtlasFull(name) => write!(f, "no room left for the image \"{}\" in the texture atlas of the images", name),
+            Error::TooManyDashes(len) => write!(f, "the dash pattern has {} lengths, at most {} can be drawn", len, MAX_DASHES)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<freetype::Error> for Error {
+    fn from(err : freetype::Error) -> Error {
+        Error::FreeType(err)
+    }
+}
+
+/// Result type returned by the fallible functions of contrast.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,199 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use properties::color::Color;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Image produced by rendering a scene without a window.
+/// The pixels are stored row by row from the top left corner, each one
+/// on 4 bytes (red, green, blue and alpha).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Image {
+    width : u32,
+    height : u32,
+    pixels : Vec<u8>
+}
+
+impl Image {
+    /// Returns a transparent image of 'width' x 'height' pixels.
+    /// Panics if the size of its pixels overflows, like a vector too large.
+    pub fn new(width : u32, height : u32) -> Self {
+        let len = Image::len(width, height).expect("image too large");
+        Image { width, height, pixels : vec![0; len] }
+    }
+
+    /// Returns an image from its pixels, or None if there are not
+    /// exactly 'width' x 'height' pixels of 4 bytes.
+    pub fn from_pixels(width : u32, height : u32, pixels : Vec<u8>) -> Option<Self> {
+        if Image::len(width, height) == Some(pixels.len()) {
+            Some(Image { width, height, pixels })
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the pixel at the column 'x' and the row 'y', or None if it is
+    /// outside of the image.
+    pub fn get_pixel(&self, x : u32, y : u32) -> Option<[u8; 4]> {
+        let i = self.index(x, y)?;
+        Some([self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]])
+    }
+
+    /// Set the pixel at the column 'x' and the row 'y'. Does nothing if it
+    /// is outside of the image. Each component of the color is clamped between 0 and 1.
+    pub fn set_pixel<C : Into <Color>>(&mut self, x : u32, y : u32, color : C) -> &mut Self {
+        if let Some(i) = self.index(x, y) {
+            for (pixel, component) in self.pixels[i..i + 4].iter_mut().zip(color.into().to_array()) {
+                *pixel = (component.max(0.0).min(1.0) * 255.0).round() as u8;
+            }
+        }
+        self
+    }
+
+    /// Returns the largest difference between a component of a pixel of this image
+    /// and the same component of 'other', or None if the images do not have the same size.
+    /// Allows to compare a rendering with a reference image, with a tolerance
+    /// since two implementations of OpenGL do not exactly give the same pixels.
+    pub fn max_difference(&self, other : &Image) -> Option<u8> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+        Some(self.pixels.iter().zip(&other.pixels).map(|(a, b)| if a > b { a - b } else { b - a }).max().unwrap_or(0))
+    }
+
+    /// Encode the image in PNG into 'writer'.
+    pub fn write_png<W : Write>(&self, writer : W) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|err| Error::ImageWrite(err.to_string()))?;
+        writer.write_image_data(&self.pixels).map_err(|err| Error::ImageWrite(err.to_string()))
+    }
+
+    /// Decode a PNG image from 'reader'. Every color type and bit depth is converted
+    /// to 4 bytes per pixel, keeping the most significant byte of the 16 bits channels.
+    pub fn read_png<R : Read>(reader : R) -> Result<Self> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set(png::Transformations::EXPAND);
+        let (info, mut reader) = decoder.read_info().map_err(|err| Error::ImageRead(err.to_string()))?;
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).map_err(|err| Error::ImageRead(err.to_string()))?;
+
+        let bytes = match info.bit_depth { png::BitDepth::Sixteen => 2, _ => 1 };
+        let channels = match info.color_type {
+            png::ColorType::RGBA => 4,
+            png::ColorType::RGB => 3,
+            png::ColorType::GrayscaleAlpha => 2,
+            _ => 1
+        };
+        let len = Image::len(info.width, info.height).ok_or_else(|| Error::ImageRead("image too large".to_string()))?;
+        let mut pixels = Vec::with_capacity(len);
+        for row in data.chunks(info.line_size.max(1)).take(info.height as usize) {
+            for pixel in row.chunks(channels * bytes).take(info.width as usize) {
+                let c = |i : usize| pixel[i * bytes];
+                pixels.extend_from_slice(&match channels {
+                    4 => [c(0), c(1), c(2), c(3)],
+                    3 => [c(0), c(1), c(2), 255],
+                    2 => [c(0), c(0), c(0), c(1)],
+                    _ => [c(0), c(0), c(0), 255]
+                });
+            }
+        }
+        Image::from_pixels(info.width, info.height, pixels).ok_or_else(|| Error::ImageRead("truncated image data".to_string()))
+    }
+
+    /// Load the PNG image at 'path'.
+    pub fn load_png<P : AsRef <Path>>(path : P) -> Result<Self> {
+        let file = File::open(path).map_err(|err| Error::ImageRead(err.to_string()))?;
+        Image::read_png(BufReader::new(file))
+    }
+
+    /// Save the image in PNG at 'path'.
+    pub fn save_png<P : AsRef <Path>>(&self, path : P) -> Result<()> {
+        let file = File::create(path).map_err(|err| Error::ImageWrite(err.to_string()))?;
+        self.write_png(BufWriter::new(file))
+    }
+
+    /// Returns the number of bytes of the pixels of an image of 'width' x 'height' pixels,
+    /// or None if it overflows.
+    fn len(width : u32, height : u32) -> Option<usize> {
+        (width as usize).checked_mul(height as usize)?.checked_mul(4)
+    }
+
+    fn index(&self, x : u32, y : u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some((y as usize * self.width as usize + x as usize) * 4)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels()
+    {
+        let mut image = Image::new(3, 2);
+        image.set_pixel(2, 1, Color::red()).set_pixel(3, 0, Color::blue());
+
+        assert_eq!(image.get_pixel(2, 1), Some([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(0, 0), Some([0, 0, 0, 0]));
+        assert_eq!(image.get_pixel(3, 0), None);
+        assert_eq!(image.get_pixels()[20..], [255, 0, 0, 255]);
+        assert!(Image::from_pixels(3, 2, vec![0; 23]).is_none());
+        // The number of bytes of a large image does not overflow.
+        assert!(Image::from_pixels(65536, 65536, Vec::new()).is_none());
+        assert!(Image::from_pixels(u32::MAX, u32::MAX, Vec::new()).is_none());
+    }
+
+    #[test]
+    fn write_png()
+    {
+        let mut png = Vec::new();
+        Image::new(4, 4).write_png(&mut png).unwrap();
+        assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn read_png()
+    {
+        let mut image = Image::new(3, 2);
+        image.set_pixel(1, 0, Color::red()).set_pixel(2, 1, Color { r : 0.0, g : 0.0, b : 1.0, a : 0.5 });
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+
+        assert_eq!(Image::read_png(&png[..]), Ok(image));
+        assert!(Image::read_png(&b"not a png"[..]).is_err());
+    }
+
+    #[test]
+    fn max_difference()
+    {
+        let mut image = Image::new(2, 2);
+        let mut other = image.clone();
+        assert_eq!(image.max_difference(&other), Some(0));
+
+        image.set_pixel(0, 0, Color { r : 0.5, g : 0.0, b : 0.0, a : 1.0 });
+        other.set_pixel(0, 0, Color { r : 0.4, g : 0.0, b : 0.0, a : 1.0 });
+        assert_eq!(image.max_difference(&other), Some(26));
+        assert_eq!(other.max_difference(&image), Some(26));
+        assert_eq!(image.max_difference(&Image::new(2, 1)), None);
+    }
+}
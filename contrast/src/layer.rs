@@ -1,10 +1,15 @@
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
-use std::mem;
+use std::ops::{Deref, DerefMut};
 use properties::markid::MarkId;
 use properties::position::Position;
 use crate::marks::mark::Mark;
 use crate::markscontainer::Contrast;
+use crate::markscontainer::MarkLocation;
+use crate::markscontainer::record_dirty;
+use crate::MarkMacro;
+use crate::error::Error;
+use crate::error::Result;
 
 /// Structure representing a layer.
 /// A Layer has a vector containing his marks and a depth, 0 means 
@@ -15,8 +20,7 @@ use crate::markscontainer::Contrast;
 pub struct Layer {
     pub(crate) marks : Vec<Mark>,
     pub(crate) depth : usize,
-    pub(crate) invalid_indexes : BinaryHeap<usize>,
-    pub(crate) contrast : *mut Contrast
+    pub(crate) invalid_indexes : BinaryHeap<usize>
 }
 
 impl Ord for Layer
@@ -48,75 +52,14 @@ impl Eq for Layer {}
 impl Layer {
     /// Simply returns a new instance of Layer, initializing
     /// all attributes to their default value, except the depth.
-    pub(crate) fn new(depth : usize, contrast : *mut Contrast) -> Self {
+    pub(crate) fn new(depth : usize) -> Self {
         Layer {
             marks : Vec::<Mark>::new(),
             depth,
-            invalid_indexes : BinaryHeap::new(),
-            contrast
+            invalid_indexes : BinaryHeap::new()
         }
     }
 
-    /// Apply a function to each mark of this layer.
-    pub fn apply_to_marks(&mut self, f: fn(&mut Mark)) {
-        for mut mark in &mut self.marks {
-            f(&mut mark);
-        }
-    }
-
-    /// Add a mark into the layer.
-    pub fn add_mark(&mut self, markid : &mut MarkId) {
-        // If the mark is already in the layer, returns
-        if markid.layer_index == self.depth { return; }
-
-        // Retrieve a copy of the mark in his current layer
-        let mut mark;
-        unsafe {
-            mark = (*self.contrast).layers.get_mut(markid.layer_index).unwrap().invalidate_and_get_mark(markid);
-        }
-
-        // Update the mark according to his new layer
-        let new_mark_index;
-        let mut dirty_layer = false;
-        if self.invalid_indexes.is_empty() {
-            new_mark_index = self.marks.len();
-        }
-        else {
-            dirty_layer = true;
-            new_mark_index = self.invalid_indexes.pop().unwrap();
-        }
-
-        mark.set_mark_index(new_mark_index);
-        mark.set_layer_index(self.depth);
-        mark.set_valid(true);
-
-        // Update the markid passed as parameter so it stays coherent
-        markid.mark_index = new_mark_index;
-        markid.layer_index = self.depth;
-        markid.valid = true;
-            
-        // Add the mark to the layer
-        if !dirty_layer {
-            self.marks.push(mark);
-        }
-        else {
-            let invalid_mark = self.marks.get_mut(new_mark_index).unwrap();
-            mem::replace(invalid_mark, mark);
-        }
-    }
-
-    /// Move every mark of the Layer.
-    /// Example : if 'position' is (50.0, 0.0, 0.0), every point of the mark 
-    /// will move 50 pixels to the right.
-    pub fn move_of<P : Into <Position>>(&mut self, position : P) -> &mut Self {
-        let position : Position = position.into();
-
-        for mark in &mut self.marks {
-            mark.move_of(position);
-        }
-        self
-    }
-
     /// Returns a reference of the vector containing all the marks.
     pub fn get_all_marks(&self) -> &Vec<Mark> {
         &self.marks
@@ -128,69 +71,145 @@ impl Layer {
         self.marks.len() - self.invalid_indexes.len()
     }
 
-    /// Add a mark which was just created and is not in any layer.
-    pub(crate) fn force_add_mark(&mut self, mut mark : Mark) {
+    /// Add a mark which is not in any layer and returns its index in the layer.
+    pub(crate) fn force_add_mark(&mut self, mut mark : Mark) -> usize {
         mark.set_layer_index(self.depth);
+        mark.set_valid(true);
         // If there is no invalid indexes, just push the mark
         if self.invalid_indexes.is_empty() {
-            mark.set_mark_index(self.marks.len());
             self.marks.push(mark);
+            self.marks.len() - 1
         }
         // Else, replace the invalid mark with the new mark
         else {
             let first_invalid_index = self.invalid_indexes.pop().unwrap();
-            mark.set_mark_index(first_invalid_index);
             let invalid_mark = self.marks.get_mut(first_invalid_index).unwrap();
-            mem::replace(invalid_mark, mark);
+            *invalid_mark = mark;
+            first_invalid_index
         }
     }
 
-    /// Invalidate the mark represented by markid, making it invisible.
-    pub(crate) fn invalidate_mark(&mut self, markid : &mut MarkId) {
-        if self.contains(markid) {
-            markid.valid = false;
-            self.get_mark_mut(markid).unwrap().set_valid(false);
-            self.invalid_indexes.push(markid.mark_index);
+    /// Invalidate the mark at the index 'mark_index', making it invisible.
+    pub(crate) fn invalidate_mark(&mut self, mark_index : usize) {
+        if let Some(mark) = self.get_mark_mut(mark_index) {
+            mark.set_valid(false);
+            self.invalid_indexes.push(mark_index);
         }
     }
 
-    /// Invalidate the mark represented by markid, making it invisible, and returns a clone of it.
-    pub(crate) fn invalidate_and_get_mark(&mut self, markid : &mut MarkId) -> Mark {
-        self.get_mark_mut(markid).unwrap().set_valid(false);
-        self.invalid_indexes.push(markid.mark_index);
-        self.get_mark_mut(markid).unwrap().clone()
+    /// Invalidate the mark at the index 'mark_index', making it invisible, and returns a clone of it.
+    pub(crate) fn invalidate_and_get_mark(&mut self, mark_index : usize) -> Mark {
+        self.invalidate_mark(mark_index);
+        self.get_mark(mark_index).unwrap().clone()
     }
 
     /// Returns a reference wrapped into an Option of the mark
-    /// represented by 'markid'.
-    pub(crate) fn get_mark(&self, markid : &MarkId) -> Option<&Mark> {
-        self.marks.get(markid.mark_index)
+    /// at the index 'mark_index'.
+    pub(crate) fn get_mark(&self, mark_index : usize) -> Option<&Mark> {
+        self.marks.get(mark_index)
     }
 
     /// Returns a mutable reference wrapped into an Option of the mark
-    /// represented by 'markid'.
-    pub(crate) fn get_mark_mut(&mut self, markid : &MarkId) -> Option<&mut Mark> {
-        self.marks.get_mut(markid.mark_index)
+    /// at the index 'mark_index'.
+    pub(crate) fn get_mark_mut(&mut self, mark_index : usize) -> Option<&mut Mark> {
+        self.marks.get_mut(mark_index)
     }
+}
 
-    /// Returns a mutable reference of the last added mark.
-    pub(crate) fn get_last_mark_mut(&mut self) -> &mut Mark {
-        self.marks.last_mut().unwrap()
+/// Mutable reference of a layer returned by Contrast::get_layer_mut.
+/// It can be used as a mutable reference of the layer, and gives the operations
+/// on its marks which need the rest of contrast : the clock for the animations,
+/// the other layers and the record of the modified marks.
+pub struct LayerGuard<'a> {
+    contrast : &'a mut Contrast,
+    layer_index : usize
+}
+
+impl<'a> Deref for LayerGuard<'a> {
+    type Target = Layer;
+
+    fn deref(&self) -> &Layer {
+        &self.contrast.layers[self.layer_index]
     }
+}
 
-    /// Indicate whether or not the layer contains the mark represented by 'markid'.
-    pub(crate) fn contains(&self, markid : &MarkId) -> bool {
-        if let None = self.marks.get(markid.mark_index) {
-            return false;
+impl<'a> DerefMut for LayerGuard<'a> {
+    fn deref_mut(&mut self) -> &mut Layer {
+        &mut self.contrast.layers[self.layer_index]
+    }
+}
+
+impl<'a> LayerGuard<'a> {
+    /// Returns a guard of the layer at the index 'layer_index', which must exist.
+    pub(crate) fn new(contrast : &'a mut Contrast, layer_index : usize) -> Self {
+        LayerGuard { contrast, layer_index }
+    }
+
+    /// Apply a function to each mark of this layer.
+    pub fn apply_to_marks(&mut self, f: fn(&mut Mark)) {
+        let time = self.contrast.get_time();
+        for mark in &mut self.contrast.layers[self.layer_index].marks {
+            mark.set_time(time);
+            f(mark);
+        }
+        self.mark_dirty();
+    }
+
+    /// Add a mark into the layer, removing it from its previous layer.
+    /// The identifier of the mark stays the same, so every copy of it
+    /// remains valid after the move.
+    /// Returns an error if 'markid' does not designate any mark.
+    pub fn add_mark(&mut self, markid : &MarkId) -> Result<()> {
+        let contrast = &mut *self.contrast;
+        // Retrieve the current location of the mark
+        let location = contrast.locate(markid).ok_or(Error::UnknownMark(*markid))?;
+
+        // If the mark is already in the layer, returns
+        if location.layer_index == self.layer_index { return Ok(()); }
+
+        // Retrieve a copy of the mark in his current layer
+        let mark = contrast.layers.get_mut(location.layer_index)
+            .ok_or(Error::UnknownLayer(location.layer_index))?
+            .invalidate_and_get_mark(location.mark_index);
+
+        // Add the mark to the layer
+        let ty = mark.get_type();
+        let mark_index = contrast.layers[self.layer_index].force_add_mark(mark);
+
+        // Update the indirection table so the markid stays coherent,
+        // and rebuild the vertices since the order of the marks changed
+        contrast.relocate(markid, MarkLocation { layer_index : self.layer_index, mark_index });
+        contrast.mark_type_dirty(ty);
+        Ok(())
+    }
+
+    /// Move every mark of the Layer.
+    /// Example : if 'position' is (50.0, 0.0, 0.0), every point of the mark 
+    /// will move 50 pixels to the right.
+    pub fn move_of<P : Into <Position>>(&mut self, position : P) -> &mut Self {
+        let position : Position = position.into();
+
+        let time = self.contrast.get_time();
+        for mark in &mut self.contrast.layers[self.layer_index].marks {
+            mark.set_time(time);
+            mark.move_of(position);
+        }
+        self.mark_dirty();
+        self
+    }
+
+    /// Indicate to contrast that every valid mark of the layer was modified.
+    fn mark_dirty(&mut self) {
+        let contrast = &mut *self.contrast;
+        for mark in contrast.layers[self.layer_index].marks.iter().filter(|mark| mark.is_valid()) {
+            record_dirty(&mut contrast.update, mark.get_type(), mark.get_id());
         }
-        true
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::MarkMacro;
     use crate::marks::pointmark::VertexPoint;
     use properties::color::Color;
 
@@ -198,7 +217,7 @@ mod tests {
     {
         if v1.0 == v2.0 && v1.1 == v2.1 && v1.2 == v2.2 && v1.3 == v2.3 && v1.4 == v2.4 && v1.5 == v2.5 
             && v1.6 == v2.6 && v1.7 == v2.7 && v1.8 == v2.8 && v1.9 == v2.9 && v1.10 == v2.10 && v1.11 == v2.11 
-            && v1.12 == v2.12 && v1.13 == v2.13 && v1.14 == v2.14 {
+            && v1.12 == v2.12 && v1.13 == v2.13 && v1.14 == v2.14 && v1.15 == v2.15 {
             return true;
         }
         false
@@ -219,7 +238,7 @@ mod tests {
         c.add_text_mark();
         c.add_polygon_mark();
 
-        let layer_0 = c.layers.get_mut(0).unwrap();
+        let mut layer_0 = c.get_layer_mut(0).unwrap();
         
         for m in layer_0.get_all_marks() {
             assert_eq!(m.get_color(), Color::default());
@@ -232,6 +251,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn apply_to_marks_marks_dirty()
+    {
+        use std::collections::HashSet;
+        use crate::marks::mark::MarkTy;
+        use crate::markscontainer::Update;
+
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark().get_id();
+        let m2 = c.add_point_mark().get_id();
+        let m3 = c.add_line_mark().get_id();
+        c.remove_mark(&m2).unwrap();
+        c.fetch_update();
+
+        c.get_layer_mut(0).unwrap().apply_to_marks(color_red_marks);
+
+        let update = c.fetch_update();
+        let mut expected_points = HashSet::new();
+        expected_points.insert(m1);
+        let mut expected_lines = HashSet::new();
+        expected_lines.insert(m3);
+        assert_eq!(update.get(&MarkTy::Point), Some(&Update::Marks(expected_points.clone())));
+        assert_eq!(update.get(&MarkTy::Line), Some(&Update::Marks(expected_lines.clone())));
+
+        c.get_layer_mut(0).unwrap().move_of((10.0, 0.0));
+
+        let update = c.fetch_update();
+        assert_eq!(update.get(&MarkTy::Point), Some(&Update::Marks(expected_points)));
+        assert_eq!(update.get(&MarkTy::Line), Some(&Update::Marks(expected_lines)));
+    }
+
+    #[test]
+    fn moved_contrast()
+    {
+        use crate::marks::mark::MarkTy;
+        use crate::markscontainer::Update;
+
+        // The layers do not point back to contrast, so it can be moved once initialized.
+        let build = || {
+            let mut c = Contrast::new();
+            c.init();
+            c.add_layers(1);
+            c
+        };
+        let mut c = Box::new(build());
+        let m1 = c.add_point_mark().get_id();
+        c.fetch_update();
+
+        c.get_layer_mut(1).unwrap().add_mark(&m1).unwrap();
+        assert_eq!(c.get_mark(&m1).unwrap().get_layer_index(), 1);
+        assert_eq!(c.fetch_update().get(&MarkTy::Point), Some(&Update::All));
+    }
+
     #[test]
     fn add_mark()
     {
@@ -239,30 +313,36 @@ mod tests {
         c.init();
         c.add_layers(2);
 
-        let mut m1 = c.add_point_mark().set_position((100.0, 150.0, 0.0)).get_id();
-        let mut m2 = c.add_point_mark().set_position((200.0, 250.0, 1.0)).get_id();
-        let mut m3 = c.add_point_mark().set_position((300.0, 350.0, 2.0)).get_id();
+        let m1 = c.add_point_mark().set_position((100.0, 150.0, 0.0)).get_id();
+        let m2 = c.add_point_mark().set_position((200.0, 250.0, 1.0)).get_id();
+        let m3 = c.add_point_mark().set_position((300.0, 350.0, 2.0)).get_id();
 
         for i in 0..3 {
             for j in 0..3 {
                 for k in 0..3 {
-                    c.get_layer_mut(i).unwrap().add_mark(&mut m1);
-                    c.get_layer_mut(j).unwrap().add_mark(&mut m2);
-                    c.get_layer_mut(k).unwrap().add_mark(&mut m3);
+                    c.get_layer_mut(i).unwrap().add_mark(&m1).unwrap();
+                    c.get_layer_mut(j).unwrap().add_mark(&m2).unwrap();
+                    c.get_layer_mut(k).unwrap().add_mark(&m3).unwrap();
 
                     let marks_properties = c.get_pointmarks_properties();
                     assert_eq!(marks_properties.len(), 3);
                     assert_eq!(c.get_mark(&m1).unwrap().get_id(), m1);
                     assert_eq!(c.get_mark(&m2).unwrap().get_id(), m2);
                     assert_eq!(c.get_mark(&m3).unwrap().get_id(), m3);
+                    assert_eq!(c.get_mark(&m1).unwrap().get_layer_index(), i);
+                    assert_eq!(c.get_mark(&m2).unwrap().get_layer_index(), j);
+                    assert_eq!(c.get_mark(&m3).unwrap().get_layer_index(), k);
 
                     if i != j && j != k && i != k {
-                        assert!(vertex_point_is_equal(marks_properties[i], ([0.0, 0.0, 0.0], [100.0, 150.0, 0.0], -10.0, [0.0, 0.0], [0.0, 0.0], 
-                            0.0, [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], 0.0, 0.0, 0.0, 0.0, 0, 0, 0.0)));
-                        assert!(vertex_point_is_equal(marks_properties[j], ([0.0, 0.0, 0.0], [200.0, 250.0, 1.0], -10.0, [0.0, 0.0], [0.0, 0.0], 
-                            0.0, [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], 0.0, 0.0, 0.0, 0.0, 0, 0, 0.0)));
-                        assert!(vertex_point_is_equal(marks_properties[k], ([0.0, 0.0, 0.0], [300.0, 350.0, 2.0], -10.0, [0.0, 0.0], [0.0, 0.0], 
-                            0.0, [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], 0.0, 0.0, 0.0, 0.0, 0, 0, 0.0)));
+                        assert!(vertex_point_is_equal(marks_properties[i], ([100.0, 150.0, 0.0], [100.0, 150.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0], [0.0, 0.0], 
+                            [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                            [1.0, 1.0, 1.0, 1.0], [0, 0, 0, 0], [0, 1], [0.0, 0.0], [0.0, 1.0, 0.0])));
+                        assert!(vertex_point_is_equal(marks_properties[j], ([200.0, 250.0, 1.0], [200.0, 250.0, 1.0], [0.0, 1.0, 0.0], [0.0, 0.0], [0.0, 0.0], 
+                            [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                            [1.0, 1.0, 1.0, 1.0], [0, 0, 0, 0], [0, 1], [0.0, 0.0], [0.0, 1.0, 0.0])));
+                        assert!(vertex_point_is_equal(marks_properties[k], ([300.0, 350.0, 2.0], [300.0, 350.0, 2.0], [0.0, 1.0, 0.0], [0.0, 0.0], [0.0, 0.0], 
+                            [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                            [1.0, 1.0, 1.0, 1.0], [0, 0, 0, 0], [0, 1], [0.0, 0.0], [0.0, 1.0, 0.0])));
                     }
                 }
             }
@@ -1,7 +1,3 @@
-#[macro_use]
-extern crate lazy_static;
-use std::time::Instant;
-
 // Remove the dependency to properties
 pub mod properties {
     pub use properties::color::*;
@@ -11,25 +7,29 @@ pub mod properties {
     pub use properties::size::*;
 }
 
-/// Timer accessible everywhere in the library.
-lazy_static! {
-    static ref TIMER: Instant = Instant::now();
-}
-
+/// Default duration in seconds of the animations.
 const ANIM_DURATION : f32 = 1.0;
 
-/// Returns the number of milliseconds passed since launch.
-pub fn elapsed_time_float() -> f32 {
-    let elapsed = TIMER.elapsed();
-    let t64 = elapsed.as_secs() as f64 + (elapsed.subsec_millis() as f64 * 1e-3);
-    let t = t64 as f32;
-    t
-}
-
+pub mod error;
+pub mod easing;
+pub mod clock;
 pub mod markscontainer;
 pub mod marks;
 pub mod layer;
 pub mod camera;
+pub mod animation;
+pub mod triangulation;
+pub mod curve;
+pub mod image;
+pub mod svg;
+mod truetype;
+mod vector;
+pub mod pdf;
+pub mod eps;
+
+pub use crate::error::Error;
+pub use crate::easing::Easing;
+pub use crate::clock::Clock;
 
 
 /// This is the trait that all mark types will derive
@@ -0,0 +1,303 @@
+use crate::ANIM_DURATION;
+use crate::easing::Easing;
+use properties::position::Position;
+use properties::color::Color;
+use properties::size::Size;
+
+/// This trait is implemented by every type of property that can be animated.
+/// It computes the value displayed at a given progress of an animation,
+/// the same way the shaders do.
+pub(crate) trait Interpolate {
+    /// Returns the value between 'self' (progress 0) and 'target' (progress 1).
+    fn interpolate(&self, target : &Self, progress : f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, target : &f32, progress : f32) -> f32 {
+        self + (target - self) * progress
+    }
+}
+
+impl Interpolate for Position {
+    fn interpolate(&self, target : &Position, progress : f32) -> Position {
+        Position {
+            x : self.x.interpolate(&target.x, progress),
+            y : self.y.interpolate(&target.y, progress),
+            z : self.z.interpolate(&target.z, progress)
+        }
+    }
+}
+
+impl Interpolate for Size {
+    fn interpolate(&self, target : &Size, progress : f32) -> Size {
+        Size {
+            width : self.width.interpolate(&target.width, progress),
+            height : self.height.interpolate(&target.height, progress)
+        }
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, target : &Color, progress : f32) -> Color {
+        Color {
+            r : self.r.interpolate(&target.r, progress),
+            g : self.g.interpolate(&target.g, progress),
+            b : self.b.interpolate(&target.b, progress),
+            a : self.a.interpolate(&target.a, progress)
+        }
+    }
+}
+
+/// The points of a line or a polygon are interpolated one by one.
+/// Both vectors always have the same length, see set_points.
+impl Interpolate for Vec<Position> {
+    fn interpolate(&self, target : &Vec<Position>, progress : f32) -> Vec<Position> {
+        self.iter().zip(target).map(|(old, target)| old.interpolate(target, progress)).collect()
+    }
+}
+
+/// The colors of the points of a line are interpolated one by one.
+/// Both vectors always have the same length, see LineMark::set_colors.
+impl Interpolate for Vec<Color> {
+    fn interpolate(&self, target : &Vec<Color>, progress : f32) -> Vec<Color> {
+        self.iter().zip(target).map(|(old, target)| old.interpolate(target, progress)).collect()
+    }
+}
+
+/// Structure used to handle animations.
+/// 'old_value' refers to the value of the attribute before the start of an animation.
+/// 'target_value' refers to the value of the attribute at the end of an animation.
+/// 'start_anim' refers to the time in seconds when the animation started.
+/// 'duration' refers to the time in seconds that the animation lasts.
+/// 'easing' refers to the curve followed by the animation.
+/// 'transition' overrides 'duration' and 'easing' for the running animation only. It is set by
+/// the timelines so that a keyframe does not change the timing of the next updates of the user.
+/// We will use those attributes in the shaders to perform a smooth animation.
+/// To perform an animation, we make an interpolation between 'old_value' and 'target_value' in
+/// the shaders, whose progress is computed from 'start_anim' and 'duration' then eased.
+#[derive(PartialEq, Clone, Debug)]
+pub(crate) struct AnimationAttribute<A> {
+    pub(crate) old_value : A,
+    pub(crate) target_value : A,
+    pub(crate) start_anim : f32,
+    pub(crate) duration : f32,
+    pub(crate) easing : Easing,
+    pub(crate) transition : Option<(f32, Easing)>
+}
+
+impl<A : Clone> AnimationAttribute<A> {
+    /// Returns a new instance of AnimationAttribute which is not animated.
+    pub(crate) fn new(value : A) -> Self {
+        AnimationAttribute {
+            old_value : value.clone(),
+            target_value : value,
+            start_anim : 0.0,
+            duration : ANIM_DURATION,
+            easing : Easing::Linear,
+            transition : None
+        }
+    }
+
+    /// Returns the value displayed at the time 't', in seconds.
+    pub(crate) fn value_at(&self, t : f32) -> A where A : Interpolate {
+        let (duration, easing) = self.current_timing();
+        let progress = if duration > 0.0 { (t - self.start_anim) / duration } else { 1.0 };
+        self.old_value.interpolate(&self.target_value, easing.apply(progress))
+    }
+
+    /// Returns the duration and the easing of the running animation.
+    fn current_timing(&self) -> (f32, Easing) {
+        self.transition.unwrap_or((self.duration, self.easing))
+    }
+
+    /// Starts at the time 'now' an animation from the value currently displayed to 'value'.
+    /// If the mark has never been displayed, the value is changed without animation.
+    pub(crate) fn set_value(&mut self, value : A, is_displayed : bool, now : f32) where A : Interpolate {
+        // If an animation is running, the new one starts from the value currently
+        // displayed, so that no update is lost and the motion stays continuous.
+        if is_displayed {
+            self.old_value = self.value_at(now);
+            self.start_anim = now;
+        }
+        // A mark never displayed has nothing to animate from: the old and the target values
+        // are the same, so there is no transition whatever its start and its duration.
+        else {
+            self.old_value = value.clone();
+        }
+        self.target_value = value;
+        self.transition = None;
+    }
+
+    /// Sets the duration and the easing of the running animation only.
+    /// The next call to set_value uses 'duration' and 'easing' again.
+    pub(crate) fn set_transition(&mut self, duration : f32, easing : Easing) {
+        self.transition = Some((duration.max(0.0), easing));
+    }
+
+    /// Returns an attribute animated at the same time as this one, from 'old' to 'target'.
+    pub(crate) fn with_values<B>(&self, old : B, target : B) -> AnimationAttribute<B> {
+        AnimationAttribute {
+            old_value : old,
+            target_value : target,
+            start_anim : self.start_anim,
+            duration : self.duration,
+            easing : self.easing,
+            transition : self.transition
+        }
+    }
+
+    /// Returns the triple (start, duration, easing) given to the shaders.
+    pub(crate) fn timing(&self) -> [f32; 3] {
+        let (duration, easing) = self.current_timing();
+        [self.start_anim, duration, easing as u32 as f32]
+    }
+}
+
+impl AnimationAttribute<Vec<Position>> {
+    /// Same as set_value for the points of a line ('closed' is false) or a polygon ('closed' is true).
+    /// If the number of points changes, the points currently displayed are resampled along
+    /// the shape so that the old and the new points can be interpolated one by one.
+    pub(crate) fn set_points(&mut self, points : Vec<Position>, closed : bool, is_displayed : bool, now : f32) {
+        let current = if is_displayed { self.value_at(now) } else { Vec::new() };
+        self.set_value(points.clone(), is_displayed, now);
+        self.old_value = if current.is_empty() { points } else { resample(&current, self.target_value.len(), closed) };
+    }
+}
+
+/// Returns 'count' points evenly spaced along the line going through 'points'.
+/// If 'closed' is true, the line goes back from the last point to the first one.
+/// The points are returned as they are if their number does not change.
+pub(crate) fn resample(points : &[Position], count : usize, closed : bool) -> Vec<Position> {
+    if points.len() == count || points.is_empty() {
+        return points.to_vec();
+    }
+
+    let mut segments : Vec<(Position, Position)> = points.windows(2).map(|w| (w[0], w[1])).collect();
+    if closed {
+        segments.push((points[points.len() - 1], points[0]));
+    }
+    let lengths : Vec<f32> = segments.iter().map(|(a, b)| ((b.x - a.x).powi(2) + (b.y - a.y).powi(2) + (b.z - a.z).powi(2)).sqrt()).collect();
+    let total : f32 = lengths.iter().sum();
+
+    if total == 0.0 {
+        return vec![points[0]; count];
+    }
+
+    // A closed line ends where it starts, so the last sample must not be on the end.
+    let steps = if closed { count } else { count.max(2) - 1 };
+    let mut resampled = Vec::with_capacity(count);
+    let mut segment = 0;
+    let mut covered = 0.0;
+    for i in 0..count {
+        let distance = total * i as f32 / steps as f32;
+        while segment < segments.len() - 1 && covered + lengths[segment] < distance {
+            covered += lengths[segment];
+            segment += 1;
+        }
+        let (a, b) = segments[segment];
+        let progress = if lengths[segment] > 0.0 { ((distance - covered) / lengths[segment]).min(1.0) } else { 0.0 };
+        resampled.push(a.interpolate(&b, progress));
+    }
+    resampled
+}
+
+/// Packs the timings of the properties of a line or a polygon mark, as returned by
+/// AnimationAttribute::timing, into the starts, the durations and the easings given
+/// to the shaders. Each easing takes 4 bits in the order of the properties.
+pub(crate) fn pack_timings(timings : &[[f32; 3]]) -> ([f32; 4], [f32; 4], u32) {
+    let mut starts = [0.0; 4];
+    let mut durations = [0.0; 4];
+    let mut easings = 0;
+    for (i, timing) in timings.iter().enumerate().take(4) {
+        starts[i] = timing[0];
+        durations[i] = timing[1];
+        easings |= (timing[2] as u32) << (4 * i);
+    }
+    (starts, durations, easings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_at()
+    {
+        let mut attribute = AnimationAttribute::new(Position { x : 0.0, y : 0.0, z : 0.0 });
+        attribute.target_value = Position { x : 10.0, y : 20.0, z : 0.0 };
+        attribute.start_anim = 2.0;
+
+        assert_eq!(attribute.value_at(1.0), Position { x : 0.0, y : 0.0, z : 0.0 });
+        assert_eq!(attribute.value_at(2.5), Position { x : 5.0, y : 10.0, z : 0.0 });
+        assert_eq!(attribute.value_at(4.0), Position { x : 10.0, y : 20.0, z : 0.0 });
+
+        attribute.easing = Easing::CubicIn;
+        assert_eq!(attribute.value_at(2.5), Position { x : 1.25, y : 2.5, z : 0.0 });
+
+        attribute.duration = 0.0;
+        assert_eq!(attribute.value_at(2.0), Position { x : 10.0, y : 20.0, z : 0.0 });
+    }
+
+    #[test]
+    fn long_duration()
+    {
+        let mut attribute = AnimationAttribute::new(1.0);
+        attribute.duration = 20.0;
+
+        // A value set before the first display is not animated, whatever the duration.
+        attribute.set_value(5.0, false, 0.0);
+        assert_eq!(attribute.value_at(0.0), 5.0);
+        assert_eq!(attribute.value_at(15.0), 5.0);
+
+        // Once displayed, the animation lasts the whole duration.
+        attribute.set_value(9.0, true, 2.0);
+        assert_eq!(attribute.value_at(2.0), 5.0);
+        assert_eq!(attribute.value_at(17.0), 8.0);
+        assert_eq!(attribute.value_at(22.0), 9.0);
+    }
+
+    #[test]
+    fn resample_open()
+    {
+        let points = vec!(Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 0.0, z : 0.0 },
+                          Position { x : 10.0, y : 10.0, z : 0.0 });
+
+        assert_eq!(resample(&points, 3, false), points);
+        assert_eq!(resample(&points, 5, false), vec!(Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 5.0, y : 0.0, z : 0.0 },
+            Position { x : 10.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 5.0, z : 0.0 }, Position { x : 10.0, y : 10.0, z : 0.0 }));
+        assert_eq!(resample(&points, 2, false), vec!(Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 10.0, z : 0.0 }));
+    }
+
+    #[test]
+    fn resample_closed()
+    {
+        let points = vec!(Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 0.0, z : 0.0 },
+                          Position { x : 10.0, y : 10.0, z : 0.0 }, Position { x : 0.0, y : 10.0, z : 0.0 });
+
+        assert_eq!(resample(&points, 8, true)[1], Position { x : 5.0, y : 0.0, z : 0.0 });
+        assert_eq!(resample(&points, 8, true)[7], Position { x : 0.0, y : 5.0, z : 0.0 });
+        assert_eq!(resample(&points, 2, true), vec!(Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 10.0, z : 0.0 }));
+    }
+
+    #[test]
+    fn set_points()
+    {
+        let mut points = AnimationAttribute::new(Vec::new());
+        points.set_points(vec!(Position::default(); 3), true, false, 0.0);
+        assert_eq!(points.old_value.len(), 3);
+
+        points.set_points(vec!(Position::default(); 5), true, true, 2.0);
+        assert_eq!(points.old_value.len(), 5);
+        assert_eq!(points.target_value.len(), 5);
+        assert_eq!(points.start_anim, 2.0);
+    }
+
+    #[test]
+    fn pack_timings()
+    {
+        let (starts, durations, easings) = super::pack_timings(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(starts, [1.0, 4.0, 0.0, 0.0]);
+        assert_eq!(durations, [2.0, 5.0, 0.0, 0.0]);
+        assert_eq!(easings, 3 | (6 << 4));
+    }
+}
@@ -0,0 +1,491 @@
+use std::f32::consts::PI;
+use std::fmt::Write;
+use crate::easing::Easing;
+use crate::marks::animationattribute::AnimationAttribute;
+use properties::position::Position;
+use properties::color::Color;
+use properties::markid::MarkId;
+
+/// This is the type that will receive our shaders when we will want to render our arc marks.
+/// We could describe it this way to be clearer :
+/// type VertexArc = (old_center, target_center, anim_center, old_color, target_color, anim_color,
+///                   (old_inner_radius, target_inner_radius, old_outer_radius, target_outer_radius), anim_inner_radius,
+///                   anim_outer_radius, (old_start_angle, target_start_angle, old_end_angle, target_end_angle), anim_start_angle,
+///                   anim_end_angle, (old_corner_radius, target_corner_radius, old_pad_angle, target_pad_angle),
+///                   anim_corner_radius, anim_pad_angle).
+/// Each 'anim_*' attribute is the triple (start, duration, easing) of the animation of the property.
+pub type VertexArc = ([f32; 3], [f32; 3], [f32; 3], [f32; 4], [f32; 4], [f32; 3], [f32; 4], [f32; 3], [f32; 3], [f32; 4], [f32; 3],
+                      [f32; 3], [f32; 4], [f32; 3], [f32; 3]);
+
+/// Macro allowing to set an animated property of our arc mark, like the one of the point marks.
+macro_rules! anim_set {
+    ($arc:ident, $property:ident, $param:expr) => {
+        {
+            $arc.$property.set_value($param, $arc.is_displayed, $arc.time);
+            $arc
+        }
+    }
+}
+
+/// This enum describes every animated property of an arc mark.
+/// It allows to set the duration and the easing of the animations
+/// of one property only.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ArcProperty {
+    Color,
+    Position,
+    InnerRadius,
+    OuterRadius,
+    StartAngle,
+    EndAngle,
+    CornerRadius,
+    PadAngle
+}
+
+const ARC_PROPERTIES : [ArcProperty; 8] = [ArcProperty::Color, ArcProperty::Position, ArcProperty::InnerRadius, ArcProperty::OuterRadius,
+                                           ArcProperty::StartAngle, ArcProperty::EndAngle, ArcProperty::CornerRadius, ArcProperty::PadAngle];
+
+/// This is the structure that describes the marks of type Arc.
+/// An arc mark draws the part of a ring between two angles, for the pie and the donut charts :
+/// a pie slice when its inner radius is 0, or a whole ring when its angles are 2π apart.
+/// The angles are in radians, going clockwise from the top of its center.
+/// The pad angle is removed from the arc, half on each side, to separate the adjacent arcs,
+/// and the corners of the arc can be rounded.
+/// Every property is animated like the properties of the point marks, so an arc going
+/// to new angles sweeps around its center.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ArcMark {
+    pub(crate) markid : MarkId,
+    pub(crate) layer_index : usize,
+    pub(crate) valid : bool,
+    pub(crate) color : AnimationAttribute<Color>,
+    pub(crate) center : AnimationAttribute<Position>,
+    pub(crate) inner_radius : AnimationAttribute<f32>,
+    pub(crate) outer_radius : AnimationAttribute<f32>,
+    pub(crate) start_angle : AnimationAttribute<f32>,
+    pub(crate) end_angle : AnimationAttribute<f32>,
+    pub(crate) corner_radius : AnimationAttribute<f32>,
+    pub(crate) pad_angle : AnimationAttribute<f32>,
+    pub(crate) is_displayed : bool,
+    pub(crate) time : f32
+}
+
+/// Shape of an arc at a given time, with the pad angle removed and the corner radius
+/// reduced like in arc.geom.
+pub(crate) struct ArcShape {
+    center : Position,
+    inner : f32,
+    outer : f32,
+    middle : f32,
+    /// Half of the angle drawn, π for a whole ring.
+    aperture : f32,
+    corner : f32
+}
+
+impl ArcShape {
+    /// Returns the shape drawn with these values, or None if nothing is drawn.
+    pub(crate) fn new(center : Position, radii : [f32; 2], angles : [f32; 2], corner_radius : f32, pad_angle : f32) -> Option<Self> {
+        let (inner, outer) = (radii[0].min(radii[1]), radii[0].max(radii[1]));
+        let (start, end) = (angles[0].min(angles[1]), angles[0].max(angles[1]));
+        let aperture = if end - start >= 2.0 * PI { PI } else { (end - start - pad_angle.max(0.0)) / 2.0 };
+        if aperture <= 0.0 || outer <= 0.0 {
+            return None;
+        }
+        let mut corner = corner_radius.max(0.0).min((outer - inner) / 2.0);
+        if aperture < PI / 2.0 {
+            corner = corner.min(outer * aperture.sin() / (1.0 + aperture.sin()));
+        }
+        Some(ArcShape { center, inner, outer, middle : (start + end) / 2.0, aperture, corner })
+    }
+
+    /// Returns the position on the screen of the point 'p' of the frame of arc.geom, whose y axis
+    /// goes from the center to the middle of the arc and whose x axis goes towards its end.
+    fn place(&self, p : [f32; 2]) -> [f32; 2] {
+        let (sin, cos) = self.middle.sin_cos();
+        [self.center.x + p[0] * cos + p[1] * sin, self.center.y + p[0] * sin - p[1] * cos]
+    }
+
+    /// Returns the outline of the arc as SVG path data, in pixels. It follows the boundary of the
+    /// eroded arc of arc.frag : each corner is a quarter of circle at most, tangent to its sides.
+    pub(crate) fn path(&self) -> String {
+        let mut path = String::new();
+        let point = |path : &mut String, command : &str, p : [f32; 2]| {
+            let p = self.place(p);
+            let _ = write!(path, "{}{} {}", command, p[0], p[1]);
+        };
+        let arc = |path : &mut String, radius : f32, large : bool, sweep : bool, p : [f32; 2]| {
+            let _ = write!(path, "A{} {} 0 {} {} ", radius, radius, large as u8, sweep as u8);
+            point(path, "", p);
+        };
+        if self.aperture >= PI {
+            // A whole ring, whose hole goes the other way round.
+            for &(radius, sweep) in &[(self.outer, true), (self.inner, false)] {
+                if radius > 0.0 {
+                    point(&mut path, "M", [0.0, radius]);
+                    arc(&mut path, radius, true, sweep, [0.0, -radius]);
+                    arc(&mut path, radius, true, sweep, [0.0, radius]);
+                    path.push('Z');
+                }
+            }
+            return path;
+        }
+
+        let c = self.corner;
+        let (sin, cos) = self.aperture.sin_cos();
+        let (side, normal) = ([sin, cos], [cos, -sin]);
+        let mirror = |p : [f32; 2]| [-p[0], p[1]];
+        let along = |t : f32, offset : f32| [t * side[0] + offset * normal[0], t * side[1] + offset * normal[1]];
+        let scale = |p : [f32; 2], s : f32| [p[0] * s, p[1] * s];
+        // Angle of the end of the eroded arc of radius 'r'.
+        let end = |r : f32| self.aperture - (c / r).asin();
+
+        // The outer corner of the end side : its center, its point on the outer circle and its point on the side.
+        let outer = self.outer - c;
+        let outer_center = along((outer * outer - c * c).max(0.0).sqrt(), -c);
+        let outer_tangent = scale(outer_center, self.outer / outer);
+        let outer_side = along((outer * outer - c * c).max(0.0).sqrt(), 0.0);
+
+        point(&mut path, "M", mirror(outer_tangent));
+        arc(&mut path, self.outer, 2.0 * end(outer) > PI, true, outer_tangent);
+        if c > 0.0 {
+            arc(&mut path, c, false, true, outer_side);
+        }
+        let inner = self.inner + c;
+        if inner > 0.0 && (self.aperture > PI / 2.0 || c <= inner * sin) {
+            // The inner corners, then the inner arc going back. Without inner circle, the
+            // corners are reduced to its center.
+            let t = (inner * inner - c * c).max(0.0).sqrt();
+            let inner_tangent = scale(along(t, -c), self.inner / inner);
+            point(&mut path, "L", along(t, 0.0));
+            if self.inner > 0.0 {
+                if c > 0.0 {
+                    arc(&mut path, c, false, true, inner_tangent);
+                }
+                arc(&mut path, self.inner, 2.0 * end(inner) > PI, false, mirror(inner_tangent));
+                if c > 0.0 {
+                    arc(&mut path, c, false, true, mirror(along(t, 0.0)));
+                }
+            }
+        } else {
+            // The sides meet before the inner circle, with one corner centered on the axis.
+            let apex = [0.0, c / sin];
+            let apex_side = [apex[0] + c * normal[0], apex[1] + c * normal[1]];
+            point(&mut path, "L", apex_side);
+            if c > 0.0 {
+                arc(&mut path, c, false, true, mirror(apex_side));
+            }
+        }
+        point(&mut path, "L", mirror(outer_side));
+        if c > 0.0 {
+            arc(&mut path, c, false, true, mirror(outer_tangent));
+        }
+        path.push('Z');
+        path
+    }
+}
+
+impl ArcMark {
+    /// Simply returns a new instance of ArcMark, initializing
+    /// all attributes to their default value.
+    pub fn new() -> Self {
+        ArcMark {
+            markid : MarkId::default(),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute::new(Color::default()),
+            center : AnimationAttribute::new(Position::default()),
+            inner_radius : AnimationAttribute::new(0.0),
+            outer_radius : AnimationAttribute::new(0.0),
+            start_angle : AnimationAttribute::new(0.0),
+            end_angle : AnimationAttribute::new(0.0),
+            corner_radius : AnimationAttribute::new(0.0),
+            pad_angle : AnimationAttribute::new(0.0),
+            is_displayed : false,
+            time : 0.0
+        }
+    }
+
+    /// Converts an ArcMark into a VertexArc, which is a type understandable
+    /// by the renderer, then returns it.
+    pub fn to_vertex(&self) -> VertexArc {
+        (*self.center.old_value.to_array(), *self.center.target_value.to_array(), self.center.timing(),
+            *self.color.old_value.to_array(), *self.color.target_value.to_array(), self.color.timing(),
+            [self.inner_radius.old_value, self.inner_radius.target_value, self.outer_radius.old_value, self.outer_radius.target_value],
+            self.inner_radius.timing(), self.outer_radius.timing(),
+            [self.start_angle.old_value, self.start_angle.target_value, self.end_angle.old_value, self.end_angle.target_value],
+            self.start_angle.timing(), self.end_angle.timing(),
+            [self.corner_radius.old_value, self.corner_radius.target_value, self.pad_angle.old_value, self.pad_angle.target_value],
+            self.corner_radius.timing(), self.pad_angle.timing())
+    }
+
+    /// Returns the shape of the arc displayed at the time 'now', or None if nothing is drawn.
+    pub(crate) fn shape_at(&self, now : f32) -> Option<ArcShape> {
+        ArcShape::new(self.center.value_at(now), [self.inner_radius.value_at(now), self.outer_radius.value_at(now)],
+                      [self.start_angle.value_at(now), self.end_angle.value_at(now)],
+                      self.corner_radius.value_at(now), self.pad_angle.value_at(now))
+    }
+
+    /// Set the color of a mark. You can pass as argument a tuple of 4 floats or
+    /// a Color directly.
+    pub fn set_color<C : Into <Color>>(&mut self, color : C) -> &mut Self {
+        anim_set!(self, color, color.into())
+    }
+
+    /// Set the position of the center of the arc. You can pass as argument a tuple of 2 or 3 floats, or
+    /// a Position directly.
+    pub fn set_position<P : Into <Position>>(&mut self, center : P) -> &mut Self {
+        anim_set!(self, center, center.into())
+    }
+
+    /// Set the radius in pixels of the inside of the arc, 0 for a pie slice.
+    pub fn set_inner_radius(&mut self, inner_radius : f32) -> &mut Self {
+        anim_set!(self, inner_radius, inner_radius.max(0.0))
+    }
+
+    /// Set the radius in pixels of the outside of the arc.
+    pub fn set_outer_radius(&mut self, outer_radius : f32) -> &mut Self {
+        anim_set!(self, outer_radius, outer_radius.max(0.0))
+    }
+
+    /// Set the angle in radians where the arc starts, clockwise from the top.
+    pub fn set_start_angle(&mut self, start_angle : f32) -> &mut Self {
+        anim_set!(self, start_angle, start_angle)
+    }
+
+    /// Set the angle in radians where the arc ends, clockwise from the top.
+    pub fn set_end_angle(&mut self, end_angle : f32) -> &mut Self {
+        anim_set!(self, end_angle, end_angle)
+    }
+
+    /// Set the radius in pixels of the corners of the arc. It is reduced to the half of
+    /// the width of the ring, and to what the angle of the arc allows. The default radius is 0.
+    pub fn set_corner_radius(&mut self, corner_radius : f32) -> &mut Self {
+        anim_set!(self, corner_radius, corner_radius.max(0.0))
+    }
+
+    /// Set the angle in radians left empty between this arc and the adjacent ones,
+    /// half of it on each side. A whole ring is not padded.
+    pub fn set_pad_angle(&mut self, pad_angle : f32) -> &mut Self {
+        anim_set!(self, pad_angle, pad_angle.max(0.0))
+    }
+
+    /// Set the duration in seconds of the animations of every property of a mark.
+    pub fn set_duration(&mut self, duration : f32) -> &mut Self {
+        for property in &ARC_PROPERTIES {
+            self.set_property_duration(*property, duration);
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of every property of a mark.
+    pub fn set_easing(&mut self, easing : Easing) -> &mut Self {
+        for property in &ARC_PROPERTIES {
+            self.set_property_easing(*property, easing);
+        }
+        self
+    }
+
+    /// Set the duration in seconds of the animations of one property of a mark.
+    /// A negative duration is treated as zero, that is no animation.
+    pub fn set_property_duration(&mut self, property : ArcProperty, duration : f32) -> &mut Self {
+        let duration = duration.max(0.0);
+        match property {
+            ArcProperty::Color => self.color.duration = duration,
+            ArcProperty::Position => self.center.duration = duration,
+            ArcProperty::InnerRadius => self.inner_radius.duration = duration,
+            ArcProperty::OuterRadius => self.outer_radius.duration = duration,
+            ArcProperty::StartAngle => self.start_angle.duration = duration,
+            ArcProperty::EndAngle => self.end_angle.duration = duration,
+            ArcProperty::CornerRadius => self.corner_radius.duration = duration,
+            ArcProperty::PadAngle => self.pad_angle.duration = duration
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of one property of a mark.
+    pub fn set_property_easing(&mut self, property : ArcProperty, easing : Easing) -> &mut Self {
+        match property {
+            ArcProperty::Color => self.color.easing = easing,
+            ArcProperty::Position => self.center.easing = easing,
+            ArcProperty::InnerRadius => self.inner_radius.easing = easing,
+            ArcProperty::OuterRadius => self.outer_radius.easing = easing,
+            ArcProperty::StartAngle => self.start_angle.easing = easing,
+            ArcProperty::EndAngle => self.end_angle.easing = easing,
+            ArcProperty::CornerRadius => self.corner_radius.easing = easing,
+            ArcProperty::PadAngle => self.pad_angle.easing = easing
+        }
+        self
+    }
+
+    /// Set the duration and the easing of the running animation of one property of a mark.
+    /// Used by the timelines: the next updates of the property use its own duration and easing again.
+    pub(crate) fn set_property_transition(&mut self, property : ArcProperty, duration : f32, easing : Easing) -> &mut Self {
+        match property {
+            ArcProperty::Color => self.color.set_transition(duration, easing),
+            ArcProperty::Position => self.center.set_transition(duration, easing),
+            ArcProperty::InnerRadius => self.inner_radius.set_transition(duration, easing),
+            ArcProperty::OuterRadius => self.outer_radius.set_transition(duration, easing),
+            ArcProperty::StartAngle => self.start_angle.set_transition(duration, easing),
+            ArcProperty::EndAngle => self.end_angle.set_transition(duration, easing),
+            ArcProperty::CornerRadius => self.corner_radius.set_transition(duration, easing),
+            ArcProperty::PadAngle => self.pad_angle.set_transition(duration, easing)
+        }
+        self
+    }
+
+    /// Returns the duration in seconds of the animations of a property.
+    pub fn get_property_duration(&self, property : ArcProperty) -> f32 {
+        match property {
+            ArcProperty::Color => self.color.duration,
+            ArcProperty::Position => self.center.duration,
+            ArcProperty::InnerRadius => self.inner_radius.duration,
+            ArcProperty::OuterRadius => self.outer_radius.duration,
+            ArcProperty::StartAngle => self.start_angle.duration,
+            ArcProperty::EndAngle => self.end_angle.duration,
+            ArcProperty::CornerRadius => self.corner_radius.duration,
+            ArcProperty::PadAngle => self.pad_angle.duration
+        }
+    }
+
+    /// Returns the easing curve of the animations of a property.
+    pub fn get_property_easing(&self, property : ArcProperty) -> Easing {
+        match property {
+            ArcProperty::Color => self.color.easing,
+            ArcProperty::Position => self.center.easing,
+            ArcProperty::InnerRadius => self.inner_radius.easing,
+            ArcProperty::OuterRadius => self.outer_radius.easing,
+            ArcProperty::StartAngle => self.start_angle.easing,
+            ArcProperty::EndAngle => self.end_angle.easing,
+            ArcProperty::CornerRadius => self.corner_radius.easing,
+            ArcProperty::PadAngle => self.pad_angle.easing
+        }
+    }
+
+    pub fn get_id(&self) -> MarkId {
+        self.markid
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color.target_value
+    }
+
+    pub fn get_position(&self) -> &Position {
+        &self.center.target_value
+    }
+
+    pub fn get_inner_radius(&self) -> f32 {
+        self.inner_radius.target_value
+    }
+
+    pub fn get_outer_radius(&self) -> f32 {
+        self.outer_radius.target_value
+    }
+
+    pub fn get_start_angle(&self) -> f32 {
+        self.start_angle.target_value
+    }
+
+    pub fn get_end_angle(&self) -> f32 {
+        self.end_angle.target_value
+    }
+
+    pub fn get_corner_radius(&self) -> f32 {
+        self.corner_radius.target_value
+    }
+
+    pub fn get_pad_angle(&self) -> f32 {
+        self.pad_angle.target_value
+    }
+
+    pub fn get_layer_index(&self) -> usize {
+        self.layer_index
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    pub(crate) fn set_displayed(&mut self, is_displayed : bool) {
+        self.is_displayed = is_displayed;
+    }
+
+    pub(crate) fn set_time(&mut self, time : f32) {
+        self.time = time;
+    }
+}
+
+impl Default for ArcMark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rounds the numbers of SVG path data, whose trigonometry is not exact.
+    fn rounded(path : &str) -> String {
+        let mut result = String::new();
+        let mut number = String::new();
+        for c in path.chars().chain(std::iter::once(' ')) {
+            if c.is_ascii_digit() || c == '.' || c == '-' || c == 'e' {
+                number.push(c);
+                continue;
+            }
+            if !number.is_empty() {
+                let value = (number.parse::<f32>().unwrap() * 100.0).round() / 100.0;
+                let _ = write!(result, "{}", if value == 0.0 { 0.0 } else { value });
+                number.clear();
+            }
+            result.push(c);
+        }
+        result.trim_end().to_string()
+    }
+
+    #[test]
+    fn to_vertex()
+    {
+        let mut arc = ArcMark::new();
+        arc.set_position((10.0, 20.0)).set_inner_radius(5.0).set_outer_radius(15.0).set_start_angle(1.0).set_end_angle(2.0)
+           .set_corner_radius(2.0).set_pad_angle(-0.5).set_property_duration(ArcProperty::EndAngle, 3.0);
+        let vertex = arc.to_vertex();
+        assert_eq!(vertex.1, [10.0, 20.0, 0.0]);
+        assert_eq!([vertex.6[1], vertex.6[3]], [5.0, 15.0]);
+        assert_eq!([vertex.9[1], vertex.9[3]], [1.0, 2.0]);
+        assert_eq!(vertex.11[1], 3.0);
+        assert_eq!([vertex.12[1], vertex.12[3]], [2.0, 0.0]);
+    }
+
+    #[test]
+    fn shape()
+    {
+        let center = Position::default();
+        assert!(ArcShape::new(center, [0.0, 10.0], [1.0, 1.0], 0.0, 0.0).is_none());
+        assert!(ArcShape::new(center, [0.0, 10.0], [0.0, 1.0], 0.0, 2.0).is_none());
+        assert!(ArcShape::new(center, [0.0, 0.0], [0.0, 1.0], 0.0, 0.0).is_none());
+
+        // The radii and the angles may be given in any order, and a whole ring has no pad.
+        let ring = ArcShape::new(center, [10.0, 4.0], [2.0 * PI, 0.0], 5.0, 1.0).unwrap();
+        assert_eq!((ring.inner, ring.outer, ring.aperture, ring.corner), (4.0, 10.0, PI, 3.0));
+
+        // The corners of a thin slice stay inside it.
+        let slice = ArcShape::new(center, [0.0, 10.0], [0.0, PI / 3.0], 8.0, 0.0).unwrap();
+        assert!((slice.corner - 10.0 / 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn path()
+    {
+        let pie = ArcShape::new(Position::default(), [0.0, 10.0], [0.0, PI / 2.0], 0.0, 0.0).unwrap();
+        assert_eq!(rounded(&pie.path()), "M0 -10A10 10 0 0 1 10 0L0 0L0 -10Z");
+
+        let ring = ArcShape::new((5.0, 5.0).into(), [2.0, 4.0], [0.0, 2.0 * PI], 0.0, 0.0).unwrap();
+        assert_eq!(rounded(&ring.path()), "M5 9A4 4 0 1 1 5 1A4 4 0 1 1 5 9ZM5 7A2 2 0 1 0 5 3A2 2 0 1 0 5 7Z");
+
+        // A half donut with rounded corners.
+        let donut = ArcShape::new(Position::default(), [4.0, 10.0], [-PI / 2.0, PI / 2.0], 1.0, 0.0).unwrap();
+        assert_eq!(rounded(&donut.path()), "M-9.94 -1.11A10 10 0 0 1 9.94 -1.11A1 1 0 0 1 8.94 0L4.9 0A1 1 0 0 1 3.92 -0.8\
+                                           A4 4 0 0 0 -3.92 -0.8A1 1 0 0 1 -4.9 0L-8.94 0A1 1 0 0 1 -9.94 -1.11Z");
+    }
+}
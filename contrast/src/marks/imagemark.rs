@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use crate::easing::Easing;
+use crate::error::{Error, Result};
+use crate::image::Image;
+use crate::marks::animationattribute::AnimationAttribute;
+use properties::position::Position;
+use properties::color::Color;
+use properties::size::Size;
+use properties::markid::MarkId;
+use rect_packer::{Packer, Rect};
+
+/// Width and height in pixels of the texture atlas shared by every image.
+pub const IMAGE_ATLAS_SIZE : u32 = 2048;
+/// Config of the atlas of the images. The padding keeps the filtering of an image from
+/// reading the pixels of its neighbours.
+const IMAGE_ATLAS : rect_packer::Config = rect_packer::Config { width : IMAGE_ATLAS_SIZE as i32, height : IMAGE_ATLAS_SIZE as i32,
+                                                                border_padding : 1, rectangle_padding : 2 };
+
+/// This is the type that will receive our shaders when we will want to render our image marks.
+/// We could describe it this way to be clearer :
+/// type VertexImage = (old_center, target_center, anim_center, old_size, target_size, anim_size, old_color, target_color,
+///                     anim_color, (old_rotation, target_rotation), anim_rotation, texture coordinates).
+/// Each 'anim_*' attribute is the triple (start, duration, easing) of the animation of the property.
+/// The texture coordinates are the ones of the top left and of the bottom right corners of the image in the atlas.
+pub type VertexImage = ([f32; 3], [f32; 3], [f32; 3], [f32; 2], [f32; 2], [f32; 3], [f32; 4], [f32; 4], [f32; 3], [f32; 2], [f32; 3],
+                        [f32; 4]);
+
+/// Pixels of an image to upload at their place in the texture atlas of the images.
+/// They are stored row by row from the top, each one on 4 floats between 0 and 1.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImageTexels {
+    pub rect : Rect,
+    pub texels : Vec<f32>
+}
+
+/// The images registered in contrast, packed in one texture atlas. Each image is kept with
+/// its place in the atlas to export the marks, and the pixels not given to the renderer yet
+/// are kept apart.
+/// The places of the replaced images are kept to be reused, since the packer cannot free them.
+pub(crate) struct ImageAtlas {
+    packer : Packer,
+    pub(crate) images : HashMap<String, (Image, Rect)>,
+    free : Vec<Rect>,
+    pending : Vec<ImageTexels>
+}
+
+impl ImageAtlas {
+    pub(crate) fn new() -> Self {
+        ImageAtlas { packer : Packer::new(IMAGE_ATLAS), images : HashMap::new(), free : Vec::new(), pending : Vec::new() }
+    }
+
+    /// Packs 'image' in the atlas with the name 'name'. An image registered again with the same name
+    /// keeps the place of the previous one if it fits in it. Otherwise it takes a free place or a new
+    /// one, and the previous place is freed for the next images.
+    pub(crate) fn add(&mut self, name : &str, image : Image) -> Result<()> {
+        let (width, height) = (image.get_width() as i32, image.get_height() as i32);
+        let fits = |place : &Rect| width <= place.width && height <= place.height;
+        let place = match self.images.get(name) {
+            Some((_, previous)) if fits(previous) => *previous,
+            previous => {
+                let previous = previous.map(|(_, place)| *place);
+                let place = match self.free.iter().position(fits) {
+                    Some(i) => self.free.swap_remove(i),
+                    None => self.packer.pack(width, height, false).ok_or_else(|| Error::ImageAtlasFull(name.to_string()))?
+                };
+                self.free.extend(previous);
+                place
+            }
+        };
+        let texels = image.get_pixels().iter().map(|&byte| byte as f32 / 255.0).collect();
+        self.pending.push(ImageTexels { rect : Rect { x : place.x, y : place.y, width, height }, texels });
+        self.images.insert(name.to_string(), (image, place));
+        Ok(())
+    }
+
+    pub(crate) fn contains(&self, name : &str) -> bool {
+        self.images.contains_key(name)
+    }
+
+    /// Returns the texture coordinates of the image 'name', see VertexImage.
+    /// They are moved by half a texel inside the image, so the filtering does not read the padding.
+    pub(crate) fn coordinates(&self, name : &str) -> Option<[f32; 4]> {
+        let (image, place) = self.images.get(name)?;
+        let (width, height) = (image.get_width() as i32, image.get_height() as i32);
+        let size = IMAGE_ATLAS_SIZE as f32;
+        Some([(place.x as f32 + 0.5) / size, (place.y as f32 + 0.5) / size,
+              (place.x + width) as f32 / size - 0.5 / size, (place.y + height) as f32 / size - 0.5 / size])
+    }
+
+    /// Returns the texture coordinates of every image.
+    pub(crate) fn all_coordinates(&self) -> HashMap<String, [f32; 4]> {
+        self.images.keys().filter_map(|name| Some((name.clone(), self.coordinates(name)?))).collect()
+    }
+
+    /// Returns the pixels of the images registered since the last call.
+    pub(crate) fn take_pending(&mut self) -> Vec<ImageTexels> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Macro allowing to set an animated property of our image mark, like the one of the point marks.
+macro_rules! anim_set {
+    ($image:ident, $property:ident, $param:expr) => {
+        {
+            $image.$property.set_value($param, $image.is_displayed, $image.time);
+            $image
+        }
+    }
+}
+
+/// This enum describes every animated property of an image mark.
+/// It allows to set the duration and the easing of the animations
+/// of one property only.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ImageProperty {
+    Color,
+    Size,
+    Rotation,
+    Position
+}
+
+const IMAGE_PROPERTIES : [ImageProperty; 4] = [ImageProperty::Color, ImageProperty::Size, ImageProperty::Rotation, ImageProperty::Position];
+
+/// This is the structure that describes the marks of type Image.
+/// An image mark draws an image registered in contrast, see Contrast::register_image,
+/// stretched to its size, centered on its position and rotated around it.
+/// Like the text marks, the mark only contains the name of its image.
+/// Its color multiplies the pixels of the image, so it is white by default and its
+/// alpha fades the image.
+/// The color, the size, the rotation and the position are animated like the properties
+/// of the point marks.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ImageMark {
+    pub(crate) markid : MarkId,
+    pub(crate) layer_index : usize,
+    pub(crate) valid : bool,
+    pub(crate) color : AnimationAttribute<Color>,
+    pub(crate) size : AnimationAttribute<Size>,
+    pub(crate) rotation : AnimationAttribute<f32>,
+    pub(crate) center : AnimationAttribute<Position>,
+    pub(crate) image : String,
+    pub(crate) is_displayed : bool,
+    pub(crate) time : f32
+}
+
+impl ImageMark {
+    /// Simply returns a new instance of ImageMark, without image.
+    pub fn new() -> Self {
+        ImageMark {
+            markid : MarkId::default(),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute::new(Color::white()),
+            size : AnimationAttribute::new(Size::default()),
+            rotation : AnimationAttribute::new(0.0),
+            center : AnimationAttribute::new(Position::default()),
+            image : String::new(),
+            is_displayed : false,
+            time : 0.0
+        }
+    }
+
+    /// Converts an ImageMark into a VertexImage, with the texture coordinates
+    /// of its image in the atlas.
+    pub fn to_vertex(&self, coordinates : [f32; 4]) -> VertexImage {
+        (*self.center.old_value.to_array(), *self.center.target_value.to_array(), self.center.timing(),
+            *self.size.old_value.to_array(), *self.size.target_value.to_array(), self.size.timing(),
+            *self.color.old_value.to_array(), *self.color.target_value.to_array(), self.color.timing(),
+            [self.rotation.old_value, self.rotation.target_value], self.rotation.timing(), coordinates)
+    }
+
+    /// Set the name of the image drawn by the mark. The mark is not drawn while no image
+    /// is registered with this name.
+    pub fn set_image(&mut self, name : &str) -> &mut Self {
+        self.image = name.to_string();
+        self
+    }
+
+    /// Set the color multiplying the pixels of the image. You can pass as argument
+    /// a tuple of 4 floats or a Color directly.
+    pub fn set_color<C : Into <Color>>(&mut self, color : C) -> &mut Self {
+        anim_set!(self, color, color.into())
+    }
+
+    /// Set the size in pixels of the image. You can pass as argument a tuple of 2 floats or
+    /// a Size directly.
+    pub fn set_size<S : Into <Size>>(&mut self, size : S) -> &mut Self {
+        anim_set!(self, size, size.into())
+    }
+
+    /// Set the rotation in radians of the image around its center.
+    pub fn set_rotation(&mut self, rotation : f32) -> &mut Self {
+        anim_set!(self, rotation, rotation)
+    }
+
+    /// Set the position of the center of the image. You can pass as argument a tuple of 2 or 3 floats, or
+    /// a Position directly.
+    pub fn set_position<P : Into <Position>>(&mut self, center : P) -> &mut Self {
+        anim_set!(self, center, center.into())
+    }
+
+    /// Set the duration in seconds of the animations of every property of a mark.
+    pub fn set_duration(&mut self, duration : f32) -> &mut Self {
+        for property in &IMAGE_PROPERTIES {
+            self.set_property_duration(*property, duration);
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of every property of a mark.
+    pub fn set_easing(&mut self, easing : Easing) -> &mut Self {
+        for property in &IMAGE_PROPERTIES {
+            self.set_property_easing(*property, easing);
+        }
+        self
+    }
+
+    /// Set the duration in seconds of the animations of one property of a mark.
+    /// A negative duration is treated as zero, that is no animation.
+    pub fn set_property_duration(&mut self, property : ImageProperty, duration : f32) -> &mut Self {
+        let duration = duration.max(0.0);
+        match property {
+            ImageProperty::Color => self.color.duration = duration,
+            ImageProperty::Size => self.size.duration = duration,
+            ImageProperty::Rotation => self.rotation.duration = duration,
+            ImageProperty::Position => self.center.duration = duration
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of one property of a mark.
+    pub fn set_property_easing(&mut self, property : ImageProperty, easing : Easing) -> &mut Self {
+        match property {
+            ImageProperty::Color => self.color.easing = easing,
+            ImageProperty::Size => self.size.easing = easing,
+            ImageProperty::Rotation => self.rotation.easing = easing,
+            ImageProperty::Position => self.center.easing = easing
+        }
+        self
+    }
+
+    /// Set the duration and the easing of the running animation of one property of a mark.
+    /// Used by the timelines: the next updates of the property use its own duration and easing again.
+    pub(crate) fn set_property_transition(&mut self, property : ImageProperty, duration : f32, easing : Easing) -> &mut Self {
+        match property {
+            ImageProperty::Color => self.color.set_transition(duration, easing),
+            ImageProperty::Size => self.size.set_transition(duration, easing),
+            ImageProperty::Rotation => self.rotation.set_transition(duration, easing),
+            ImageProperty::Position => self.center.set_transition(duration, easing)
+        }
+        self
+    }
+
+    pub fn get_id(&self) -> MarkId {
+        self.markid
+    }
+
+    pub fn get_image(&self) -> &String {
+        &self.image
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color.target_value
+    }
+
+    pub fn get_size(&self) -> Size {
+        self.size.target_value
+    }
+
+    pub fn get_rotation(&self) -> f32 {
+        self.rotation.target_value
+    }
+
+    pub fn get_position(&self) -> &Position {
+        &self.center.target_value
+    }
+
+    /// Returns the duration in seconds of the animations of a property.
+    pub fn get_property_duration(&self, property : ImageProperty) -> f32 {
+        match property {
+            ImageProperty::Color => self.color.duration,
+            ImageProperty::Size => self.size.duration,
+            ImageProperty::Rotation => self.rotation.duration,
+            ImageProperty::Position => self.center.duration
+        }
+    }
+
+    /// Returns the easing curve of the animations of a property.
+    pub fn get_property_easing(&self, property : ImageProperty) -> Easing {
+        match property {
+            ImageProperty::Color => self.color.easing,
+            ImageProperty::Size => self.size.easing,
+            ImageProperty::Rotation => self.rotation.easing,
+            ImageProperty::Position => self.center.easing
+        }
+    }
+
+    pub fn get_layer_index(&self) -> usize {
+        self.layer_index
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    pub(crate) fn set_displayed(&mut self, is_displayed : bool) {
+        self.is_displayed = is_displayed;
+    }
+
+    pub(crate) fn set_time(&mut self, time : f32) {
+        self.time = time;
+    }
+}
+
+impl Default for ImageMark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atlas()
+    {
+        let mut atlas = ImageAtlas::new();
+        let mut image = Image::new(4, 2);
+        image.set_pixel(1, 0, Color::red());
+        atlas.add("logo", image).unwrap();
+        atlas.add("icon", Image::new(8, 8)).unwrap();
+
+        let pending = atlas.take_pending();
+        assert_eq!(pending.len(), 2);
+        assert_eq!((pending[0].rect.width, pending[0].rect.height), (4, 2));
+        assert_eq!(pending[0].texels[4..8], [1.0, 0.0, 0.0, 1.0]);
+        assert!(atlas.take_pending().is_empty());
+
+        // The rectangles do not overlap, and the coordinates stay inside them.
+        let (a, b) = (pending[0].rect, pending[1].rect);
+        assert!(a.x + a.width <= b.x || b.x + b.width <= a.x || a.y + a.height <= b.y || b.y + b.height <= a.y);
+        let size = IMAGE_ATLAS_SIZE as f32;
+        assert_eq!(atlas.coordinates("logo"), Some([(a.x as f32 + 0.5) / size, (a.y as f32 + 0.5) / size,
+                                                    (a.x as f32 + 3.5) / size, (a.y as f32 + 1.5) / size]));
+        assert!(atlas.contains("icon"));
+        assert_eq!(atlas.coordinates("unknown"), None);
+
+        let too_large = Image::new(IMAGE_ATLAS_SIZE + 1, 1);
+        assert_eq!(atlas.add("large", too_large), Err(Error::ImageAtlasFull("large".to_string())));
+    }
+
+    #[test]
+    fn atlas_replace()
+    {
+        let mut atlas = ImageAtlas::new();
+        atlas.add("logo", Image::new(8, 8)).unwrap();
+        let place = atlas.take_pending()[0].rect;
+
+        // A smaller image keeps the place of the previous one.
+        atlas.add("logo", Image::new(4, 2)).unwrap();
+        let pending = atlas.take_pending();
+        assert_eq!(pending[0].rect, Rect { x : place.x, y : place.y, width : 4, height : 2 });
+        let size = IMAGE_ATLAS_SIZE as f32;
+        assert_eq!(atlas.coordinates("logo").unwrap()[2], (place.x as f32 + 3.5) / size);
+        atlas.add("logo", Image::new(8, 8)).unwrap();
+        assert_eq!(atlas.take_pending()[0].rect, place);
+
+        // A larger image takes a new place, and the previous one goes to the next image fitting in it.
+        atlas.add("logo", Image::new(16, 16)).unwrap();
+        let rect = atlas.take_pending()[0].rect;
+        assert_ne!((rect.x, rect.y), (place.x, place.y));
+        atlas.add("icon", Image::new(8, 4)).unwrap();
+        assert_eq!(atlas.take_pending()[0].rect, Rect { x : place.x, y : place.y, width : 8, height : 4 });
+
+        // Registering the same image many times does not fill the atlas.
+        for _ in 0..1000 {
+            atlas.add("logo", Image::new(16, 16)).unwrap();
+        }
+    }
+
+    #[test]
+    fn to_vertex()
+    {
+        let mut image = ImageMark::new();
+        image.set_image("logo").set_position((10.0, 20.0)).set_size((30.0, 40.0)).set_rotation(0.5);
+        image.set_property_duration(ImageProperty::Size, 2.0);
+        assert_eq!(image.get_color(), Color::white());
+
+        let vertex = image.to_vertex([0.0, 0.0, 0.5, 0.25]);
+        assert_eq!(vertex.1, [10.0, 20.0, 0.0]);
+        assert_eq!(vertex.4, [30.0, 40.0]);
+        assert_eq!(vertex.7, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(vertex.9, [0.5, 0.5]);
+        assert_eq!(vertex.11, [0.0, 0.0, 0.5, 0.25]);
+        assert_eq!(image.get_property_duration(ImageProperty::Size), 2.0);
+        assert_eq!(image.get_image(), "logo");
+    }
+}
@@ -1,25 +1,136 @@
 use crate::MarkMacro;
+use crate::easing::Easing;
+use crate::marks::animationattribute::{AnimationAttribute, Interpolate, pack_timings};
+use crate::marks::pointmark::{PointMark, Shape, VertexPoint};
+use crate::marks::animationattribute::resample;
+use crate::curve::{self, Curve, TOLERANCE};
 use properties::position::Position;
+use properties::size::Size;
 use properties::color::Color;
 use properties::markid::MarkId;
 use mark_macro_derive::MarkMacro;
+use crate::error::{Error, Result};
 
 /// This is the type that will receive our shaders when we will want to render our line marks.
 /// We could describe it this way to be more clear :
-/// type VertexSubLine = (size, color, rotation, origin, target, previous, next, thickness, line_mode).
-pub type VertexSubLine = ([f32; 4], [f32; 3], [f32; 3], [f32; 3], [f32; 3], f32);
+/// type VertexSubLine = ((old_origin_color, origin_color, old_target_color, target_color), old_origin, origin, old_target, target,
+///                       old_previous, previous, old_next, next, (old_thickness, thickness, miter_limit, dash_offset),
+///                       (old_distance, distance, old_length, length), dashes, anim_start, anim_duration, (anim_easing, style)).
+/// The colors at the origin and at the target of the sub-line are packed by Color::to_rgba8,
+/// and interpolated along the sub-line.
+/// 'distance' is the distance along the line from its first point to 'origin', and 'length' the length of the line.
+/// 'dashes' holds the lengths of the dashes and of the gaps following them, padded with zeros, or only zeros if the line is solid.
+/// 'anim_start' and 'anim_duration' hold the start and the duration of the animations of the color
+/// (or of the colors of the points), the points and the thickness, in this order. 'anim_easing' holds their easings on 4 bits each.
+/// 'style' holds the cap on its 2 lowest bits, then the join : 0 for a miter, 1 for a round join and 2 for a bevel.
+pub type VertexSubLine = ([u32; 4], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3],
+                          [f32; 4], [f32; 4], [f32; 4], [f32; 4], [f32; 4], [u32; 2]);
 
+/// Number of lengths of dashes and gaps that a dash pattern can hold, as many as the shaders draw.
+pub const MAX_DASHES : usize = 4;
+
+/// This enum describes the shape of the ends of a line, and of the ends of its dashes.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum LineCap {
+    /// The line stops at its ends.
+    Butt,
+    /// A half disc is added at each end.
+    Round,
+    /// A half square is added at each end.
+    Square
+}
+
+impl Default for LineCap {
+    fn default() -> Self {
+        LineCap::Butt
+    }
+}
+
+/// This enum describes how two consecutive sub-lines are joined.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum LineJoin {
+    /// The sides of the sub-lines are extended until they meet, unless the miter would be longer
+    /// than this limit times the thickness : the join is then beveled, like in SVG.
+    Miter(f32),
+    Round,
+    /// The corner between the sub-lines is cut.
+    Bevel
+}
+
+impl Default for LineJoin {
+    fn default() -> Self {
+        LineJoin::Miter(4.0)
+    }
+}
+
+/// This enum describes the markers drawn at the ends or at the inner points of a line.
+/// They are drawn like point marks of the color of the line, sized relative to its thickness
+/// and turned along the line.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Marker {
+    None,
+    /// A triangle whose tip is on the point, pointing out of the line at its ends.
+    Arrow,
+    Circle,
+    /// A bar across the line, as thick as the line.
+    Bar,
+    /// Any shape of the point marks, whose x axis follows the line.
+    Shape(Shape)
+}
+
+impl Default for Marker {
+    fn default() -> Self {
+        Marker::None
+    }
+}
+
+/// This enum describes every animated property of a line mark.
+/// It allows to set the duration and the easing of the animations
+/// of one property only.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum LineProperty {
+    Color,
+    Points,
+    Thickness
+}
+
+const LINE_PROPERTIES : [LineProperty; 3] = [LineProperty::Color, LineProperty::Points, LineProperty::Thickness];
 
 /// This is the structure that describes the marks of type Line (or polyline).
-/// Each type of mark share some properties, that is an id and a color.
-/// Line marks also have a vector of positions representing its points,
-/// a thickness and a mode to draw them differently.
+/// Each type of mark share some properties, that is an id, the index
+/// of its layer, a boolean telling whether or not it is valid and a color.
+/// Line marks also have a vector of positions representing its points, the curve going through them,
+/// an optional color for each point, a thickness, the shape of their ends and of their joins, a dash pattern,
+/// and the markers drawn at their ends and at their inner points.
+/// The color, the colors of the points, the points and the thickness are animated like the properties
+/// of the point marks.
 #[derive(MarkMacro, PartialEq, Clone, Debug)]
 pub struct LineMark {
     pub(crate) markid : MarkId,
-    pub(crate) color : Color,
-    pub(crate) points : Vec<Position>,
-    pub(crate) thickness : f32,
+    pub(crate) layer_index : usize,
+    pub(crate) valid : bool,
+    pub(crate) color : AnimationAttribute<Color>,
+    pub(crate) points : AnimationAttribute<Vec<Position>>,
+    /// Colors of the points, or empty if the whole line has its color.
+    /// Animated with the duration and the easing of the color.
+    pub(crate) colors : AnimationAttribute<Vec<Color>>,
+    pub(crate) thickness : AnimationAttribute<f32>,
+    pub(crate) cap : LineCap,
+    pub(crate) join : LineJoin,
+    /// Lengths of the dashes and of the gaps, alternately, starting with a dash.
+    /// Its length is even, and it is empty if the line is solid.
+    pub(crate) dashes : Vec<f32>,
+    pub(crate) dash_offset : f32,
+    pub(crate) start_marker : Marker,
+    pub(crate) end_marker : Marker,
+    pub(crate) vertex_marker : Marker,
+    /// Size of the markers, relative to the thickness of the line.
+    pub(crate) marker_size : f32,
+    pub(crate) curve : Curve,
+    /// Scale of the camera the line was last converted for, see Contrast::set_scale.
+    pub(crate) scale : f32,
+    pub(crate) is_displayed : bool,
+    pub(crate) time : f32
 }
 
 impl LineMark {
@@ -27,63 +138,605 @@ impl LineMark {
     /// all attributes to their default value.
     pub fn new() -> Self {
         LineMark {
-            markid : MarkId::new(),
-            color : Color::default(),
-            points : Vec::<Position>::new(),
-            thickness : 1.0,
+            markid : MarkId::default(),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute::new(Color::default()),
+            points : AnimationAttribute::new(Vec::<Position>::new()),
+            colors : AnimationAttribute::new(Vec::<Color>::new()),
+            thickness : AnimationAttribute::new(1.0),
+            cap : LineCap::Butt,
+            join : LineJoin::default(),
+            dashes : Vec::new(),
+            dash_offset : 0.0,
+            start_marker : Marker::None,
+            end_marker : Marker::None,
+            vertex_marker : Marker::None,
+            marker_size : 4.0,
+            curve : Curve::Linear,
+            scale : 1.0,
+            is_displayed : false,
+            time : 0.0
         }
     }
 
+    /// Returns the distance along the line made of 'points' from its first point to each point.
+    fn distances(points : &[Position]) -> Vec<f32> {
+        let mut distance = 0.0;
+        let mut distances = Vec::with_capacity(points.len());
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                let previous = points[i - 1];
+                distance += ((point.x - previous.x).powi(2) + (point.y - previous.y).powi(2)).sqrt();
+            }
+            distances.push(distance);
+        }
+        distances
+    }
+
     /// Converts a LineMark into a VertexSubLine, which is a type
     /// understandable by the renderer.
     pub fn to_subline(&self) -> Vec<VertexSubLine> {
         let mut sublines : Vec<VertexSubLine> = Vec::<VertexSubLine>::new();
-        if self.points.len()>=2 {
-            let mut previous = self.points[0];
-            let mut origin = self.points[0];
-            let mut target = self.points[0];
-            for next in self.points.clone() {
+        let (old, points, knots) = self.drawn_points();
+        let (old, points) = (&old, &points);
+        let color_timing = if self.colors.target_value.is_empty() { self.color.timing() } else { self.colors.timing() };
+        let (starts, durations, easings) = pack_timings(&[color_timing, self.points.timing(), self.thickness.timing()]);
+        if points.len()>=2 && old.len() == points.len() {
+            let (old_colors, colors) = self.drawn_colors(&knots);
+            let (old_distances, distances) = (LineMark::distances(old), LineMark::distances(points));
+            let last = points.len() - 1;
+            let (miter_limit, join) = match self.join {
+                LineJoin::Miter(limit) => (limit, 0),
+                LineJoin::Round => (0.0, 1),
+                LineJoin::Bevel => (0.0, 2)
+            };
+            let style = self.cap as u32 | join << 2;
+            // set_dashes keeps the pattern short enough for the shaders to draw all of it.
+            let mut dashes = [0.0; MAX_DASHES];
+            for (dash, length) in dashes.iter_mut().zip(&self.dashes) {
+                *dash = *length;
+            }
+            for i in 0..last {
+                // Each sub-line goes from the point 'i' to the next one, and knows its neighbours to compute the miters.
+                let (previous, next) = (i.max(1) - 1, (i + 2).min(last));
                 let vl : VertexSubLine = (
-                *self.color.to_array(), *origin.to_array(), *target.to_array(),
-                *previous.to_array(), *next.to_array(),self.thickness);
+                [old_colors[i].to_rgba8(), colors[i].to_rgba8(), old_colors[i + 1].to_rgba8(), colors[i + 1].to_rgba8()],
+                *old[i].to_array(), *points[i].to_array(), *old[i + 1].to_array(), *points[i + 1].to_array(),
+                *old[previous].to_array(), *points[previous].to_array(), *old[next].to_array(), *points[next].to_array(),
+                [self.thickness.old_value, self.thickness.target_value, miter_limit, self.dash_offset],
+                [old_distances[i], distances[i], old_distances[last], distances[last]], dashes, starts, durations, [easings, style]);
                 sublines.push(vl);
-                previous = origin;
-                origin = target;
-                target = next;
             }
-            let vl : VertexSubLine = (
-            *self.color.to_array(), *origin.to_array(), *target.to_array(),
-            *previous.to_array(), *self.points[self.points.len()-1].to_array(),
-            self.thickness);
-            sublines.push(vl);
-            sublines.remove(0);
-            sublines.remove(0);
         }
         sublines
     }
 
+    /// Returns the points of the polyline drawing the line through 'points' at the scale of the
+    /// camera, and the index in this polyline of each point the curve goes through.
+    fn flattened(&self, points : &[Position], scale : f32) -> (Vec<Position>, Vec<usize>) {
+        curve::flatten(points, self.curve, TOLERANCE / scale)
+    }
+
+    /// Returns the old and the target points of the polyline drawing the line, which have as many
+    /// points so the shaders can interpolate them, and the indices of the points of the curve.
+    pub(crate) fn drawn_points(&self) -> (Vec<Position>, Vec<Position>, Vec<usize>) {
+        let (points, knots) = self.flattened(&self.points.target_value, self.scale);
+        let (old, _) = self.flattened(&self.points.old_value, self.scale);
+        (resample(&old, points.len(), false), points, knots)
+    }
+
+    /// Returns the points of the polyline drawing the line at the time 'now', for an unzoomed camera.
+    pub(crate) fn drawn_points_at(&self, now : f32) -> Vec<Position> {
+        self.flattened(&self.points.value_at(now), 1.0).0
+    }
+
+    /// Returns 'colors' extended to 'count' colors with its last color, or 'color' if it is empty.
+    fn extended(colors : &[Color], count : usize, color : Color) -> Vec<Color> {
+        let last = colors.last().copied().unwrap_or(color);
+        colors.iter().copied().chain(std::iter::repeat(last)).take(count.max(colors.len())).collect()
+    }
+
+    /// Returns the color of each point of the polyline whose points on the curve are 'knots',
+    /// from 'colors', the colors of the points of the line, or 'color' if it is empty.
+    /// Between two points on the curve, the colors are interpolated, and the colors
+    /// of the control points of the Bézier curves are not used.
+    fn colors_along(&self, colors : &[Color], color : Color, knots : &[usize]) -> Vec<Color> {
+        let count = self.points.target_value.len();
+        let colors = LineMark::extended(colors, count, color);
+        let inputs = curve::knot_points(count, self.curve);
+        let mut drawn = vec![colors[inputs[0]]];
+        for (k, w) in knots.windows(2).enumerate() {
+            let (a, b) = (colors[inputs[k]], colors[inputs[k + 1]]);
+            let steps = w[1] - w[0];
+            drawn.extend((1..=steps).map(|j| a.interpolate(&b, j as f32 / steps as f32)));
+        }
+        drawn
+    }
+
+    /// Returns the old and the target colors of the points of the polyline drawing the line.
+    fn drawn_colors(&self, knots : &[usize]) -> (Vec<Color>, Vec<Color>) {
+        (self.colors_along(&self.colors.old_value, self.color.old_value, knots),
+         self.colors_along(&self.colors.target_value, self.color.target_value, knots))
+    }
+
+    /// Returns the colors of the points of drawn_points_at at the time 'now',
+    /// or None if the whole line has its color.
+    pub(crate) fn drawn_colors_at(&self, now : f32) -> Option<Vec<Color>> {
+        if self.colors.target_value.is_empty() {
+            return None;
+        }
+        let (_, knots) = self.flattened(&self.points.value_at(now), 1.0);
+        Some(self.colors_along(&self.colors.value_at(now), self.color.value_at(now), &knots))
+    }
+
+    /// Returns the direction of the line at its point 'i', going out of the line at its ends,
+    /// and along the bisector of the sub-lines at the inner points.
+    fn direction(points : &[Position], i : usize) -> (f32, f32) {
+        let unit = |a : &Position, b : &Position| {
+            let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+            if length > 0.0 { ((b.x - a.x) / length, (b.y - a.y) / length) } else { (0.0, 0.0) }
+        };
+        let last = points.len() - 1;
+        if i == 0 {
+            unit(&points[1], &points[0])
+        }
+        else if i == last {
+            unit(&points[last - 1], &points[last])
+        }
+        else {
+            let (before, after) = (unit(&points[i - 1], &points[i]), unit(&points[i], &points[i + 1]));
+            (before.0 + after.0, before.1 + after.1)
+        }
+    }
+
+    /// Returns the center and the rotation of 'marker' of width 'size' at the point 'i' of 'points'.
+    fn place(marker : Marker, points : &[Position], i : usize, size : f32) -> (Position, f32) {
+        let (dx, dy) = LineMark::direction(points, i);
+        let angle = dy.atan2(dx);
+        let point = points[i];
+        match marker {
+            // The tip of the triangle is on its y axis, half its size from its center.
+            Marker::Arrow => {
+                let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                let offset = size / 2.0 / length;
+                (Position { x : point.x - dx * offset, y : point.y - dy * offset, z : point.z }, angle - std::f32::consts::FRAC_PI_2)
+            },
+            _ => (point, angle)
+        }
+    }
+
+    /// Returns the point marks drawing the markers of the line, animated with the line :
+    /// their position and their rotation follow its points, their size its thickness, and their
+    /// color the color of the line at their point. The markers of the inner points come first, then the start and the end ones.
+    pub(crate) fn marker_points(&self) -> Vec<PointMark> {
+        let (old, points, knots) = self.drawn_points();
+        let (old, points) = (&old, &points);
+        let mut markers = Vec::new();
+        if points.len() < 2 || old.len() != points.len() {
+            return markers;
+        }
+        let (old_colors, colors) = self.drawn_colors(&knots);
+        let last = points.len() - 1;
+        let mut placed : Vec<(Marker, usize)> = knots[1..knots.len() - 1].iter().map(|i| (self.vertex_marker, *i)).collect();
+        placed.push((self.start_marker, 0));
+        placed.push((self.end_marker, last));
+        for (marker, i) in placed {
+            let shape = match marker {
+                Marker::None => continue,
+                Marker::Arrow => Shape::Triangle,
+                Marker::Circle => Shape::Circle,
+                Marker::Bar => Shape::Rectangle,
+                Marker::Shape(shape) => shape
+            };
+            let size = |thickness : f32| {
+                let width = self.marker_size * thickness;
+                if marker == Marker::Bar { Size { width : thickness, height : width } } else { Size { width, height : width } }
+            };
+            let (old_center, old_rotation) = LineMark::place(marker, old, i, size(self.thickness.old_value).height);
+            let (center, rotation) = LineMark::place(marker, points, i, size(self.thickness.target_value).height);
+            // The marker turns the shortest way.
+            let turn = (rotation - old_rotation + std::f32::consts::PI).rem_euclid(2.0 * std::f32::consts::PI) - std::f32::consts::PI;
+            let mut point = PointMark::new();
+            point.markid = self.markid;
+            point.layer_index = self.layer_index;
+            point.color = if self.colors.target_value.is_empty() { self.color.clone() }
+                          else { self.colors.with_values(old_colors[i], colors[i]) };
+            point.center = self.points.with_values(old_center, center);
+            point.rotation = self.points.with_values(old_rotation, old_rotation + turn);
+            point.size = self.thickness.with_values(size(self.thickness.old_value), size(self.thickness.target_value));
+            point.shape = AnimationAttribute::new(shape);
+            markers.push(point);
+        }
+        markers
+    }
+
+    /// Converts the markers of a LineMark into VertexPoints, drawn by the renderer like the point marks.
+    pub fn to_markers(&self) -> Vec<VertexPoint> {
+        self.marker_points().iter().map(PointMark::to_vertex).collect()
+    }
+
     /// Add a point to a line. You can pass as argument a tuple of 3 floats or
     /// a Position directly
     pub fn add_point<P : Into <Position>>(&mut self, point : P) -> &mut Self {
-        self.points.push(point.into());
+        let mut points = self.points.target_value.clone();
+        points.push(point.into());
+        self.set_points(points)
+    }
+
+    /// Set all the points of a line. If the line is displayed, each point moves
+    /// from its current position. If the number of points changes, the current
+    /// line is resampled to have as many points as the new one.
+    pub fn set_points(&mut self, points : Vec<Position>) -> &mut Self {
+        self.points.set_points(points, false, self.is_displayed, self.time);
+        self
+    }
+
+    /// Set the color of each point of a line, the colors being interpolated along the line
+    /// between the points. The line keeps the color of its last given point after it, and
+    /// the colors of the control points of the Bézier curves are not used. The colors are
+    /// animated with the duration and the easing of LineProperty::Color. An empty vector gives
+    /// back its color to the whole line, without animation.
+    pub fn set_colors(&mut self, colors : Vec<Color>) -> &mut Self {
+        if colors.is_empty() {
+            self.colors.old_value.clear();
+            self.colors.target_value.clear();
+            return self;
+        }
+        // The old and the target colors are extended to as many colors, so they can be interpolated one by one.
+        let count = colors.len().max(self.colors.target_value.len());
+        let color = self.color.value_at(self.time);
+        self.colors.old_value = LineMark::extended(&self.colors.old_value, count, color);
+        self.colors.target_value = LineMark::extended(&self.colors.target_value, count, color);
+        self.colors.set_value(LineMark::extended(&colors, count, color), self.is_displayed, self.time);
         self
     }
 
     pub fn set_thickness(&mut self, thickness : f32) -> &mut Self {
-        self.thickness = thickness;
+        self.thickness.set_value(thickness, self.is_displayed, self.time);
         self
     }
 
-    pub fn get_thickness(&self) -> f32 {
-        self.thickness
+    /// Set the shape of the ends of the line and of its dashes.
+    pub fn set_cap(&mut self, cap : LineCap) -> &mut Self {
+        self.cap = cap;
+        self
     }
 
-    pub(crate) fn get_points_mut(&mut self) -> &mut Vec<Position> {
-        &mut self.points
+    /// Set how the sub-lines are joined. The default join is a miter limited to 4 times the thickness.
+    pub fn set_join(&mut self, join : LineJoin) -> &mut Self {
+        self.join = join;
+        self
+    }
+
+    /// Set the lengths of the dashes and of the gaps between them, alternately, starting with a dash.
+    /// Like in SVG, a list of odd length is repeated to have an even length, and the pattern
+    /// can then hold at most MAX_DASHES lengths, so the renderers and the exports all draw it whole.
+    /// A line is solid when the list is empty or when its lengths are all zero, and the negative
+    /// lengths are treated as zero.
+    /// Returns an error if the pattern is longer than MAX_DASHES, keeping the previous one.
+    pub fn set_dashes(&mut self, dashes : &[f32]) -> Result<&mut Self> {
+        let mut dashes : Vec<f32> = dashes.iter().map(|length| length.max(0.0)).collect();
+        if dashes.len() % 2 == 1 {
+            dashes.extend(dashes.clone());
+        }
+        if dashes.len() > MAX_DASHES {
+            return Err(Error::TooManyDashes(dashes.len()));
+        }
+        self.dashes = if dashes.iter().sum::<f32>() > 0.0 { dashes } else { Vec::new() };
+        Ok(self)
+    }
+
+    /// Set the distance along the dash pattern at which the line starts.
+    pub fn set_dash_offset(&mut self, offset : f32) -> &mut Self {
+        self.dash_offset = offset;
+        self
+    }
+
+    /// Set how the line goes through its points : straight sub-lines, Bézier curves or splines.
+    /// The curves are drawn with as many sub-lines as needed to look smooth at the scale of the camera.
+    pub fn set_curve(&mut self, curve : Curve) -> &mut Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Set the marker drawn at the first point of the line.
+    pub fn set_start_marker(&mut self, marker : Marker) -> &mut Self {
+        self.start_marker = marker;
+        self
+    }
+
+    /// Set the marker drawn at the last point of the line.
+    pub fn set_end_marker(&mut self, marker : Marker) -> &mut Self {
+        self.end_marker = marker;
+        self
+    }
+
+    /// Set the marker drawn at each point of the line except the first and the last ones,
+    /// like the data points of a line chart.
+    pub fn set_vertex_marker(&mut self, marker : Marker) -> &mut Self {
+        self.vertex_marker = marker;
+        self
+    }
+
+    /// Set the size of the markers, relative to the thickness of the line. The default size is 4.
+    pub fn set_marker_size(&mut self, size : f32) -> &mut Self {
+        self.marker_size = size.max(0.0);
+        self
+    }
+
+    /// Set the duration in seconds of the animations of every property of a line.
+    pub fn set_duration(&mut self, duration : f32) -> &mut Self {
+        for property in &LINE_PROPERTIES {
+            self.set_property_duration(*property, duration);
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of every property of a line.
+    pub fn set_easing(&mut self, easing : Easing) -> &mut Self {
+        for property in &LINE_PROPERTIES {
+            self.set_property_easing(*property, easing);
+        }
+        self
+    }
+
+    /// Set the duration in seconds of the animations of one property of a line.
+    /// A negative duration is treated as zero, that is no animation.
+    pub fn set_property_duration(&mut self, property : LineProperty, duration : f32) -> &mut Self {
+        let duration = duration.max(0.0);
+        match property {
+            LineProperty::Color => {
+                self.color.duration = duration;
+                self.colors.duration = duration;
+            },
+            LineProperty::Points => self.points.duration = duration,
+            LineProperty::Thickness => self.thickness.duration = duration
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of one property of a line.
+    pub fn set_property_easing(&mut self, property : LineProperty, easing : Easing) -> &mut Self {
+        match property {
+            LineProperty::Color => {
+                self.color.easing = easing;
+                self.colors.easing = easing;
+            },
+            LineProperty::Points => self.points.easing = easing,
+            LineProperty::Thickness => self.thickness.easing = easing
+        }
+        self
+    }
+
+    /// Set the duration and the easing of the running animation of one property of a line.
+    /// Used by the timelines: the next updates of the property use its own duration and easing again.
+    pub(crate) fn set_property_transition(&mut self, property : LineProperty, duration : f32, easing : Easing) -> &mut Self {
+        match property {
+            LineProperty::Color => {
+                self.color.set_transition(duration, easing);
+                self.colors.set_transition(duration, easing);
+            },
+            LineProperty::Points => self.points.set_transition(duration, easing),
+            LineProperty::Thickness => self.thickness.set_transition(duration, easing)
+        }
+        self
+    }
+
+    pub fn get_thickness(&self) -> f32 {
+        self.thickness.target_value
     }
 
     pub fn get_points(&self) -> &Vec<Position> {
-        &self.points
+        &self.points.target_value
+    }
+
+    /// Returns the colors of the points of a line, or an empty vector if the whole line has its color.
+    pub fn get_colors(&self) -> &Vec<Color> {
+        &self.colors.target_value
+    }
+
+    pub fn get_cap(&self) -> LineCap {
+        self.cap
+    }
+
+    pub fn get_join(&self) -> LineJoin {
+        self.join
+    }
+
+    /// Returns the lengths of the dashes and of the gaps, see set_dashes.
+    pub fn get_dashes(&self) -> &Vec<f32> {
+        &self.dashes
+    }
+
+    pub fn get_dash_offset(&self) -> f32 {
+        self.dash_offset
+    }
+
+    pub fn get_curve(&self) -> Curve {
+        self.curve
+    }
+
+    pub fn get_start_marker(&self) -> Marker {
+        self.start_marker
+    }
+
+    pub fn get_end_marker(&self) -> Marker {
+        self.end_marker
     }
 
+    pub fn get_vertex_marker(&self) -> Marker {
+        self.vertex_marker
+    }
+
+    pub fn get_marker_size(&self) -> f32 {
+        self.marker_size
+    }
+
+    /// Returns the duration in seconds of the animations of a property.
+    pub fn get_property_duration(&self, property : LineProperty) -> f32 {
+        match property {
+            LineProperty::Color => self.color.duration,
+            LineProperty::Points => self.points.duration,
+            LineProperty::Thickness => self.thickness.duration
+        }
+    }
+
+    /// Returns the easing curve of the animations of a property.
+    pub fn get_property_easing(&self, property : LineProperty) -> Easing {
+        match property {
+            LineProperty::Color => self.color.easing,
+            LineProperty::Points => self.points.easing,
+            LineProperty::Thickness => self.thickness.easing
+        }
+    }
+
+    pub(crate) fn set_displayed(&mut self, is_displayed : bool) {
+        self.is_displayed = is_displayed;
+    }
+
+    pub(crate) fn set_time(&mut self, time : f32) {
+        self.time = time;
+    }
+
+    pub(crate) fn set_scale(&mut self, scale : f32) {
+        self.scale = scale;
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_dashes()
+    {
+        let mut line = LineMark::new();
+        line.set_dashes(&[3.0]).unwrap();
+        assert_eq!(line.get_dashes(), &vec![3.0, 3.0]);
+        line.set_dashes(&[1.0, -2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(line.get_dashes(), &vec![1.0, 0.0, 3.0, 4.0]);
+        // The patterns which the shaders cannot draw whole are refused.
+        assert_eq!(line.set_dashes(&[1.0, 2.0, 3.0]).err(), Some(Error::TooManyDashes(6)));
+        assert_eq!(line.set_dashes(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).err(), Some(Error::TooManyDashes(6)));
+        assert_eq!(line.get_dashes(), &vec![1.0, 0.0, 3.0, 4.0]);
+        line.set_dashes(&[0.0, 0.0]).unwrap();
+        assert!(line.get_dashes().is_empty());
+    }
+
+    #[test]
+    fn to_subline()
+    {
+        let mut line = LineMark::new();
+        line.add_point((0.0, 0.0, 0.0)).add_point((3.0, 4.0, 0.0)).add_point((3.0, 6.0, 0.0));
+        line.set_cap(LineCap::Square).set_join(LineJoin::Bevel).set_dashes(&[2.0, 1.0]).unwrap().set_dash_offset(0.5);
+
+        let sublines = line.to_subline();
+        assert_eq!(sublines.len(), 2);
+        // The distances along the line and its length.
+        assert_eq!(sublines[0].10, [0.0, 0.0, 7.0, 7.0]);
+        assert_eq!(sublines[1].10, [5.0, 5.0, 7.0, 7.0]);
+        assert_eq!(sublines[1].9[3], 0.5);
+        assert_eq!(sublines[1].11, [2.0, 1.0, 0.0, 0.0]);
+        assert_eq!(sublines[1].14[1], 2 | 2 << 2);
+
+        // The whole pattern is given to the shaders.
+        line.set_dashes(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(line.to_subline()[0].11, [1.0, 2.0, 3.0, 4.0]);
+
+        line.set_join(LineJoin::Miter(2.0));
+        assert_eq!(line.to_subline()[0].9[2], 2.0);
+        assert_eq!(line.to_subline()[0].14[1], 2);
+    }
+
+    #[test]
+    fn marker_points()
+    {
+        let mut line = LineMark::new();
+        line.add_point((0.0, 0.0, 0.0)).add_point((10.0, 0.0, 0.0)).add_point((10.0, 10.0, 0.0)).set_thickness(2.0);
+        assert!(line.marker_points().is_empty());
+
+        line.set_start_marker(Marker::Bar).set_end_marker(Marker::Arrow).set_vertex_marker(Marker::Circle);
+        let markers = line.marker_points();
+        assert_eq!(markers.len(), 3);
+        // The marker of the inner point follows the bisector of the sub-lines.
+        assert_eq!(markers[0].get_shape(), &Shape::Circle);
+        assert_eq!(markers[0].get_position(), &Position { x : 10.0, y : 0.0, z : 0.0 });
+        assert!((markers[0].get_rotation() - std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+        assert_eq!(markers[0].get_size(), Size { width : 8.0, height : 8.0 });
+        // The bar is across the line, as thick as the line, and turned out of the line.
+        assert_eq!(markers[1].get_shape(), &Shape::Rectangle);
+        assert_eq!(markers[1].get_size(), Size { width : 2.0, height : 8.0 });
+        assert!((markers[1].get_rotation() - std::f32::consts::PI).abs() < 1e-6);
+        // The tip of the arrow is on the last point.
+        assert_eq!(markers[2].get_shape(), &Shape::Triangle);
+        assert_eq!(markers[2].get_position(), &Position { x : 10.0, y : 6.0, z : 0.0 });
+        assert!(markers[2].get_rotation().abs() < 1e-6);
+
+        line.set_marker_size(2.0);
+        assert_eq!(line.to_markers()[0].4, [4.0, 4.0]);
+    }
+
+    #[test]
+    fn marker_points_turn()
+    {
+        // The markers turn the shortest way when the line moves.
+        let mut line = LineMark::new();
+        line.add_point((0.0, 0.0, 0.0)).add_point((-10.0, 1.0, 0.0)).set_end_marker(Marker::Circle);
+        line.set_displayed(true);
+        line.set_points(vec![(0.0, 0.0).into(), (-10.0, -1.0).into()]);
+        let marker = &line.marker_points()[0];
+        assert!((marker.rotation.target_value - marker.rotation.old_value).abs() < 0.5);
+    }
+
+    #[test]
+    fn to_subline_curve()
+    {
+        let mut line = LineMark::new();
+        line.add_point((0.0, 0.0, 0.0)).add_point((50.0, 100.0, 0.0)).add_point((100.0, 0.0, 0.0));
+        assert_eq!(line.to_subline().len(), 2);
+
+        line.set_curve(Curve::QuadraticBezier).set_vertex_marker(Marker::Circle).set_end_marker(Marker::Circle);
+        let sublines = line.to_subline().len();
+        assert!(sublines > 10);
+        // The control point is not on the curve, so it has no marker.
+        assert_eq!(line.marker_points().len(), 1);
+        // The curve is smoother when the camera zooms in.
+        line.set_scale(4.0);
+        assert!(line.to_subline().len() > sublines);
+        // Without animation, the points of the curve go from where they are.
+        assert!(line.to_subline().iter().all(|subline| subline.1 == subline.2));
+    }
+
+    #[test]
+    fn set_colors()
+    {
+        let (red, blue) = (Color::red(), Color::blue());
+        let mut line = LineMark::new();
+        line.add_point((0.0, 0.0, 0.0)).add_point((10.0, 0.0, 0.0)).add_point((10.0, 10.0, 0.0)).set_color(Color::green());
+        line.set_colors(vec![red, blue]);
+        // The last point keeps the color of the last given point.
+        let sublines = line.to_subline();
+        assert_eq!((sublines[0].0[1], sublines[0].0[3]), (red.to_rgba8(), blue.to_rgba8()));
+        assert_eq!(sublines[1].0[3], blue.to_rgba8());
+
+        // The colors of a curve are interpolated between the points it goes through.
+        line.set_curve(Curve::QuadraticBezier).set_end_marker(Marker::Circle);
+        let sublines = line.to_subline();
+        assert_eq!(sublines[0].0[1], red.to_rgba8());
+        let middle = Color::from_rgba8(sublines[sublines.len() / 2].0[1]);
+        assert!(middle.r > 0.0 && middle.b > 0.0);
+        assert_eq!(line.marker_points()[0].get_color(), blue);
+
+        // The colors animate from the current ones.
+        line.set_displayed(true);
+        line.set_colors(vec![blue]);
+        assert_eq!(line.colors.old_value, vec![red, blue]);
+        assert_eq!(line.get_colors(), &vec![blue, blue]);
+
+        line.set_colors(Vec::new());
+        assert_eq!(line.to_subline()[0].0[1], Color::green().to_rgba8());
+    }
 }
+
@@ -6,6 +6,8 @@ use crate::marks::pointmark::PointMark;
 use crate::marks::linemark::LineMark;
 use crate::marks::textmark::TextMark;
 use crate::marks::polygonmark::PolygonMark;
+use crate::marks::imagemark::ImageMark;
+use crate::marks::arcmark::ArcMark;
 use self::MarkTy::*;
 use std::slice::Iter;
 
@@ -16,7 +18,9 @@ pub enum Mark {
 	Point(PointMark),
 	Line(LineMark),
     Text(TextMark),
-	Polygon(PolygonMark)
+	Polygon(PolygonMark),
+    Image(ImageMark),
+    Arc(ArcMark)
 }
 
 /// Pure enum to distinguish the type of marks.
@@ -26,7 +30,9 @@ pub enum MarkTy
     Point,
     Line,
     Text,
-	Polygon
+	Polygon,
+    Image,
+    Arc
 }
 
 impl MarkTy
@@ -34,7 +40,7 @@ impl MarkTy
     /// Return an iterator on all values of MarkTy.
     pub fn values() -> Iter<'static, MarkTy>
     {
-        static MARKS: [MarkTy;  4] = [Point, Line, Text, Polygon];
+        static MARKS: [MarkTy;  6] = [Point, Line, Text, Polygon, Image, Arc];
         MARKS.into_iter()
     }
 }
@@ -49,7 +55,9 @@ macro_rules! mark_get {
             Mark::Point(p) => p.$get(),
             Mark::Line(l)  => l.$get(),
             Mark::Text(t)  => t.$get(),
-			Mark::Polygon(poly) => poly.$get()
+			Mark::Polygon(poly) => poly.$get(),
+            Mark::Image(i) => i.$get(),
+            Mark::Arc(a) => a.$get()
         }
     )
 }
@@ -65,14 +73,16 @@ macro_rules! mark_set {
                 Mark::Point(p) => { p.$set($param); } ,
                 Mark::Line(l)  => { l.$set($param); },
                 Mark::Text(t)  => { t.$set($param); },
-				Mark::Polygon(poly)  => { poly.$set($param); }
+				Mark::Polygon(poly)  => { poly.$set($param); },
+                Mark::Image(i) => { i.$set($param); },
+                Mark::Arc(a) => { a.$set($param); }
             }
             $mark
         }
     }
 }
 
-/// Macro able to cast a Mark into any other type of Mark (Point, Line, Polygon, Text)
+/// Macro able to cast a Mark into any other type of Mark (Point, Line, Polygon, Text, Image, Arc)
 /// that returns a reference of it wrapped into an Option.
 macro_rules! cast {
     ($mark:ident, $type:ident) => (
@@ -85,7 +95,7 @@ macro_rules! cast {
     )
 }
 
-/// Macro able to cast a Mark into any other type of Mark (Point, Line, Polygon, Text)
+/// Macro able to cast a Mark into any other type of Mark (Point, Line, Polygon, Text, Image, Arc)
 /// that returns a mutable reference of it wrapped into an Option.
 macro_rules! cast_mut {
     ($mark:ident, $type:ident) => (
@@ -98,7 +108,7 @@ macro_rules! cast_mut {
     )
 }
 
-/// Macro able to cast a Mark into any other type of Mark (Point, Line, Polygon, Text)
+/// Macro able to cast a Mark into any other type of Mark (Point, Line, Polygon, Text, Image, Arc)
 /// that either returns a reference of it if the Mark is the good type, or panic.
 macro_rules! cast_unchecked {
     ($mark:ident, $type:ident) => (
@@ -111,7 +121,7 @@ macro_rules! cast_unchecked {
     )
 }
 
-/// Macro able to cast a Mark into any other type of mark (Point, Line, Polygon, Text)
+/// Macro able to cast a Mark into any other type of mark (Point, Line, Polygon, Text, Image, Arc)
 /// that either returns a mutable reference of it if the Mark is the good type, or panic.
 macro_rules! cast_mut_unchecked {
     ($mark:ident, $type:ident) => (
@@ -190,6 +200,50 @@ impl Mark {
         cast_mut_unchecked!(self, Text)
     }
 
+    pub fn as_image_mark(&self) -> Option<&ImageMark> {
+        cast!(self, Image)
+    }
+
+    pub fn as_image_mark_mut(&mut self) -> Option<&mut ImageMark> {
+        cast_mut!(self, Image)
+    }
+
+    pub fn as_image_mark_unchecked(&self) -> &ImageMark {
+        cast_unchecked!(self, Image)
+    }
+
+    pub fn as_image_mark_mut_unchecked(&mut self) -> &mut ImageMark {
+        cast_mut_unchecked!(self, Image)
+    }
+
+    pub fn as_arc_mark(&self) -> Option<&ArcMark> {
+        cast!(self, Arc)
+    }
+
+    pub fn as_arc_mark_mut(&mut self) -> Option<&mut ArcMark> {
+        cast_mut!(self, Arc)
+    }
+
+    pub fn as_arc_mark_unchecked(&self) -> &ArcMark {
+        cast_unchecked!(self, Arc)
+    }
+
+    pub fn as_arc_mark_mut_unchecked(&mut self) -> &mut ArcMark {
+        cast_mut_unchecked!(self, Arc)
+    }
+
+    /// Returns the type of the mark.
+    pub fn get_type(&self) -> MarkTy {
+        match self {
+            Mark::Point(_) => MarkTy::Point,
+            Mark::Line(_) => MarkTy::Line,
+            Mark::Text(_) => MarkTy::Text,
+			Mark::Polygon(_) => MarkTy::Polygon,
+            Mark::Image(_) => MarkTy::Image,
+            Mark::Arc(_) => MarkTy::Arc
+        }
+    }
+
     /// Move the mark according to the 'position'. Used by Layer to move
     /// all his marks.
     /// Example : if 'position' is (50.0, 0.0, 0.0), every point of the mark
@@ -202,47 +256,71 @@ impl Mark {
                 p.set_position(*p.get_position() + position);
             },
             Mark::Line(l) => {
-                for pt in l.get_points_mut() {
-                    *pt += position.into();
-                }
+                let points = l.get_points().iter().map(|pt| *pt + position).collect();
+                l.set_points(points);
             },
 			Mark::Polygon(poly) => {
-                for pt in poly.get_points_mut() {
-                    *pt += position.into();
-                }
+                let rings = poly.get_rings().iter().map(|ring| ring.iter().map(|pt| *pt + position).collect()).collect();
+                poly.set_rings(rings);
             },
             Mark::Text(t) => {
                 t.set_position(*t.get_position() + position);
+            },
+            Mark::Image(i) => {
+                i.set_position(*i.get_position() + position);
+            },
+            Mark::Arc(a) => {
+                a.set_position(*a.get_position() + position);
             }
         }
     }
 
-    pub(crate) fn set_mark_index(&mut self, mark_index : usize) -> &mut Self {
+    pub(crate) fn set_id(&mut self, markid : MarkId) -> &mut Self {
         match self {
-            Mark::Point(p) => p.markid.mark_index = mark_index,
-            Mark::Line(l) => l.markid.mark_index = mark_index,
-            Mark::Text(t) => t.markid.mark_index = mark_index,
-			Mark::Polygon(poly) => poly.markid.mark_index = mark_index
+            Mark::Point(p) => p.markid = markid,
+            Mark::Line(l) => l.markid = markid,
+            Mark::Text(t) => t.markid = markid,
+			Mark::Polygon(poly) => poly.markid = markid,
+            Mark::Image(i) => i.markid = markid,
+            Mark::Arc(a) => a.markid = markid
         }
         self
     }
 
     pub(crate) fn set_layer_index(&mut self, layer_index : usize) -> &mut Self {
          match self {
-            Mark::Point(p) => p.markid.layer_index = layer_index,
-            Mark::Line(l) => l.markid.layer_index = layer_index,
-            Mark::Text(t) => t.markid.layer_index = layer_index,
-			Mark::Polygon(poly) => poly.markid.layer_index = layer_index
+            Mark::Point(p) => p.layer_index = layer_index,
+            Mark::Line(l) => l.layer_index = layer_index,
+            Mark::Text(t) => t.layer_index = layer_index,
+			Mark::Polygon(poly) => poly.layer_index = layer_index,
+            Mark::Image(i) => i.layer_index = layer_index,
+            Mark::Arc(a) => a.layer_index = layer_index
+        }
+        self
+    }
+
+    /// Set the time at which the animations started by the setters of the mark begin.
+    /// Contrast gives it the time of its clock before lending the mark to the user.
+    pub(crate) fn set_time(&mut self, time : f32) -> &mut Self {
+        match self {
+            Mark::Point(p) => p.set_time(time),
+            Mark::Line(l) => l.set_time(time),
+            Mark::Text(t) => t.set_time(time),
+			Mark::Polygon(poly) => poly.set_time(time),
+            Mark::Image(i) => i.set_time(time),
+            Mark::Arc(a) => a.set_time(time)
         }
         self
     }
 
     pub(crate) fn set_valid(&mut self, valid : bool) -> &mut Self {
         match self {
-            Mark::Point(p) => p.markid.valid = valid,
-            Mark::Line(l) => l.markid.valid = valid,
-            Mark::Text(t) => t.markid.valid = valid,
-			Mark::Polygon(poly) => poly.markid.valid = valid
+            Mark::Point(p) => p.valid = valid,
+            Mark::Line(l) => l.valid = valid,
+            Mark::Text(t) => t.valid = valid,
+			Mark::Polygon(poly) => poly.valid = valid,
+            Mark::Image(i) => i.valid = valid,
+            Mark::Arc(a) => a.valid = valid
         }
         self
     }
@@ -446,6 +524,18 @@ mod tests {
         mark.as_polygon_mark_mut_unchecked().set_stroke_width(33.0);
     }
 
+    #[test]
+    fn move_of_rings() {
+        let mut polygon = PolygonMark::new();
+        polygon.set_points(vec![(0.0, 0.0).into(), (4.0, 0.0).into(), (0.0, 4.0).into()]).add_ring(vec![(1.0, 1.0).into()]);
+        let mut mark = Mark::Polygon(polygon);
+        mark.move_of((10.0, 0.0));
+        let rings = mark.as_polygon_mark_unchecked().get_rings();
+        assert_eq!(rings.len(), 2);
+        assert_eq!(rings[0][1], Position { x : 14.0, y : 0.0, z : 0.0 });
+        assert_eq!(rings[1][0], Position { x : 11.0, y : 1.0, z : 0.0 });
+    }
+
 
 
     #[test]
@@ -503,4 +593,34 @@ mod tests {
         let mut mark = Mark::Point(PointMark::new());
         mark.as_text_mark_mut_unchecked().set_text("test123");
     }
+
+    #[test]
+    fn as_image_mark() {
+        let mut mark = Mark::Image(ImageMark::new());
+        assert!(mark.as_image_mark().is_some());
+        assert!(mark.as_point_mark().is_none());
+        mark.as_image_mark_mut().unwrap().set_image("logo");
+        assert_eq!(mark.as_image_mark_unchecked().get_image(), "logo");
+        assert_eq!(mark.get_type(), MarkTy::Image);
+    }
+
+    #[test]
+    fn move_of_image() {
+        let mut mark = Mark::Image(ImageMark::new());
+        mark.as_image_mark_mut_unchecked().set_position((1.0, 2.0));
+        mark.move_of((10.0, 0.0));
+        assert_eq!(*mark.as_image_mark_unchecked().get_position(), Position { x : 11.0, y : 2.0, z : 0.0 });
+    }
+
+    #[test]
+    fn as_arc_mark() {
+        let mut mark = Mark::Arc(ArcMark::new());
+        assert!(mark.as_arc_mark().is_some());
+        assert!(mark.as_image_mark().is_none());
+        mark.as_arc_mark_mut().unwrap().set_outer_radius(10.0).set_position((1.0, 2.0));
+        mark.move_of((10.0, 0.0));
+        assert_eq!(mark.as_arc_mark_unchecked().get_outer_radius(), 10.0);
+        assert_eq!(*mark.as_arc_mark_unchecked().get_position(), Position { x : 11.0, y : 2.0, z : 0.0 });
+        assert_eq!(mark.get_type(), MarkTy::Arc);
+    }
 }
@@ -1,5 +1,9 @@
 pub mod mark;
+pub(crate) mod animationattribute;
 pub mod pointmark;
+pub mod shapeatlas;
 pub mod linemark;
 pub mod polygonmark;
-pub mod textmark;
\ No newline at end of file
+pub mod textmark;
+pub mod imagemark;
+pub mod arcmark;
\ No newline at end of file
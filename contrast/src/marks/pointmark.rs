@@ -1,5 +1,6 @@
-use crate::elapsed_time_float;
-use crate::ANIM_DURATION;
+use crate::easing::Easing;
+use crate::marks::animationattribute::{AnimationAttribute, Interpolate, pack_timings};
+use crate::marks::shapeatlas::FIRST_CUSTOM_SHAPE;
 use properties::position::Position;
 use properties::color::Color;
 use properties::size::Size;
@@ -8,62 +9,67 @@ use rand::Rng;
 
 /// This is the type that will receive our shaders when we will want to render our point marks.
 /// We could describe it this way to be clearer :
-/// type VertexPoint = (old_center, target_center, start_center, old_size, target_size, start_size, old_color, target_color, 
-///                      start_color, old_rotation, target_rotation, start_rotation, old_shape, target_shape, start_shape).
-pub type VertexPoint = ([f32; 3], [f32; 3], f32, [f32; 2], [f32; 2], f32, [f32; 4], [f32; 4], f32, f32, f32, f32, u32, u32, f32);
+/// type VertexPoint = (old_center, target_center, anim_center, old_size, target_size, anim_size, old_color, target_color,
+///                      anim_color, (old_rotation, target_rotation, old_stroke_width, target_stroke_width), anim_start,
+///                      anim_duration, (old_shape, target_shape, old_stroke_color, target_stroke_color), (anim_easing, fill),
+///                      (old_corner_radius, target_corner_radius), anim_corner_radius).
+/// Each 'anim_*' triple is the (start, duration, easing) of the animation of the property, and 'anim_start',
+/// 'anim_duration' and 'anim_easing' hold the ones of the rotation, the stroke width, the shape and the stroke color,
+/// in this order, packed like those of the polygons so that we stay within the number of vertex attributes guaranteed by OpenGL.
+/// The stroke colors are packed by Color::to_rgba8, and 'fill' is 1 when the inside of the shape is drawn.
+pub type VertexPoint = ([f32; 3], [f32; 3], [f32; 3], [f32; 2], [f32; 2], [f32; 3], [f32; 4], [f32; 4], [f32; 3], [f32; 4], [f32; 4],
+                        [f32; 4], [u32; 4], [u32; 2], [f32; 2], [f32; 3]);
 
-/// This enum describes every shape that should be drawable.
+/// This enum describes every shape that should be drawable : the shapes drawn by the shaders,
+/// and the custom shapes registered in contrast, see Contrast::register_shape_path.
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Shape {
-    None = 0,
-    Rectangle = 1,
-    Triangle = 2,
-    Circle = 3,
-    Point = 4,
-    Squircle = 5,
-    Diamond = 6,
-    Donut = 7,
-    Pin = 8,
-    Club = 9,
-    Heart = 10,
-    Spade = 11,
-    Chevron = 12,
-    Clover = 13,
-    Ring = 14,
-    Tag = 15,
-    Cross = 16,
-    Asterisk = 17,
-    Infinity = 18,
-    Arrow = 19
+    None,
+    Rectangle,
+    Triangle,
+    Circle,
+    Point,
+    Squircle,
+    Diamond,
+    Donut,
+    Pin,
+    Club,
+    Heart,
+    Spade,
+    Chevron,
+    Clover,
+    Ring,
+    Tag,
+    Cross,
+    Asterisk,
+    Infinity,
+    Arrow,
+    /// The custom shape registered with this index.
+    Custom(u32)
 }
 
+/// The shapes drawn by the shaders, in the order of their integers.
+const SHAPES : [Shape; 20] = [Shape::None, Shape::Rectangle, Shape::Triangle, Shape::Circle, Shape::Point, Shape::Squircle,
+                              Shape::Diamond, Shape::Donut, Shape::Pin, Shape::Club, Shape::Heart, Shape::Spade, Shape::Chevron,
+                              Shape::Clover, Shape::Ring, Shape::Tag, Shape::Cross, Shape::Asterisk, Shape::Infinity, Shape::Arrow];
+
 impl Shape {
     pub fn from_integer(i : u32) -> Self {
         match i {
-            0 => Shape::None,
-            1 => Shape::Rectangle,
-            2 => Shape::Triangle,
-            3 => Shape::Circle,
-            4 => Shape::Point,
-            5 => Shape::Squircle,
-            6 => Shape::Diamond,
-            7 => Shape::Donut,
-            8 => Shape::Pin,
-            9 => Shape::Club,
-            10 => Shape::Heart,
-            11 => Shape::Spade,
-            12 => Shape::Chevron,
-            13 => Shape::Clover,
-            14 => Shape::Ring,
-            15 => Shape::Tag,
-            16 => Shape::Cross,
-            17 => Shape::Asterisk,
-            18 => Shape::Infinity,
-            19 => Shape::Arrow,
+            i if (i as usize) < SHAPES.len() => SHAPES[i as usize],
+            i if i >= FIRST_CUSTOM_SHAPE => Shape::Custom(i - FIRST_CUSTOM_SHAPE),
             _ => Shape::None
         }
     }
 
+    /// Returns the integer identifying the shape in the shaders, the inverse of from_integer.
+    pub fn to_integer(&self) -> u32 {
+        match self {
+            Shape::Custom(index) => FIRST_CUSTOM_SHAPE + index,
+            shape => SHAPES.iter().position(|s| s == shape).unwrap_or(0) as u32
+        }
+    }
+
     /// Simply returns a random Shape.
     pub fn rand<R: Rng>(rng: &mut R) -> Self {
         Shape::from_integer(rng.gen_range(1, 20))
@@ -78,60 +84,71 @@ impl Shape {
 macro_rules! anim_set {
     ($point:ident, $property:ident, $param:expr) => {
         {
-            // Change the property only if the previous animation is finished
-            if elapsed_time_float() > $point.$property.start_anim + ANIM_DURATION && $point.is_displayed {
-                $point.$property.old_value = $point.$property.target_value;
-                $point.$property.target_value = $param;
-                $point.$property.start_anim = elapsed_time_float();
-            }
-            // Prevent the animations done within the first second to negate the transition effect
-            // Without this code, there would be an animation from the default value of the property
-            // to the targeted property when we display the mark for the first time.
-            else if !$point.is_displayed {
-                $point.$property.old_value = $point.$property.target_value;
-                $point.$property.target_value = $param;
-                // Saying that the animation started 10 seconds before the launch of the timer
-                // ensures there will be no animation.
-                $point.$property.start_anim = -10.0;
-            }
+            $point.$property.set_value($param, $point.is_displayed, $point.time);
             $point
         }
     }
 }
 
-/// Structure used to handle animations.
-/// 'old_value' refers to the value of the attribute before the start of an animation.
-/// 'target_value' refers to the value of the attribute at the end of an animation.
-/// 'start_anim' refers to the time in seconds when the animation started.
-/// We will use those attributes in the shaders to perform a smooth animation.
-/// To perform an animation, we make a linear interpolation between 'old_value' and 'target_value' in
-/// the shaders and we use 'start_anim' to make it look smooth.
-#[derive(PartialEq, Clone, Debug)]
-pub(crate) struct AnimationAttribute<A> {
-    pub(crate) old_value : A,
-    pub(crate) target_value : A,
-    pub(crate) start_anim : f32
+/// The shaders can only morph between two shapes, so an interrupted
+/// shape animation starts from the nearest of them.
+impl Interpolate for Shape {
+    fn interpolate(&self, target : &Shape, progress : f32) -> Shape {
+        if progress < 0.5 { *self } else { *target }
+    }
+}
+
+/// This enum describes every animated property of a point mark.
+/// It allows to set the duration and the easing of the animations
+/// of one property only.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum PointProperty {
+    Color,
+    Size,
+    Rotation,
+    Position,
+    Shape,
+    StrokeColor,
+    StrokeWidth,
+    CornerRadius
 }
 
+const POINT_PROPERTIES : [PointProperty; 8] = [PointProperty::Color, PointProperty::Size, PointProperty::Rotation,
+                                               PointProperty::Position, PointProperty::Shape, PointProperty::StrokeColor,
+                                               PointProperty::StrokeWidth, PointProperty::CornerRadius];
+
 /// This is the structure that describes the marks of type Point.
-/// Each type of mark share some properties, that is an id and a
+/// Each type of mark share some properties, that is an id, the index
+/// of its layer, a boolean telling whether or not it is valid and a
 /// color.
 /// Point marks also have a size, a rotation, a position and a shape.
+/// Their shape can be outlined by a stroke, drawn inside the shape with
+/// its own color and width, and its inside can be left empty.
+/// The corners of the rectangles can be rounded, for the bars of the charts.
 /// To support animations, we must use additionnal attributes for
 /// each property of our mark. They are described in details in
 /// AnimationAttribute.
 /// Finally, we need a boolean telling us whether or not the mark
 /// has already been displayed, allowing us to disable the
-/// animation at the first display of our mark.
+/// animation at the first display of our mark, and the time of
+/// the clock of contrast when the mark was last given to the user,
+/// which is when the animations started by its setters begin.
 #[derive(PartialEq, Clone, Debug)]
 pub struct PointMark {
     pub(crate) markid : MarkId,
+    pub(crate) layer_index : usize,
+    pub(crate) valid : bool,
     pub(crate) color : AnimationAttribute<Color>,
     pub(crate) size : AnimationAttribute<Size>,
     pub(crate) rotation : AnimationAttribute<f32>,
     pub(crate) center : AnimationAttribute<Position>,
     pub(crate) shape : AnimationAttribute<Shape>,
-    pub(crate) is_displayed : bool
+    pub(crate) stroke_color : AnimationAttribute<Color>,
+    pub(crate) stroke_width : AnimationAttribute<f32>,
+    pub(crate) corner_radius : AnimationAttribute<f32>,
+    pub(crate) fill : bool,
+    pub(crate) is_displayed : bool,
+    pub(crate) time : f32
 }
 
 impl PointMark {
@@ -139,44 +156,35 @@ impl PointMark {
     /// all attributes to their default value.
     pub fn new() -> Self {
         PointMark {
-            markid : MarkId::new(),
-            size : AnimationAttribute {
-                old_value : Size::default(),
-                target_value : Size::default(),
-                start_anim : 0.0
-            },
-            color : AnimationAttribute {
-                old_value : Color::default(),
-                target_value : Color::default(),
-                start_anim : 0.0
-            },
-            rotation : AnimationAttribute {
-                old_value : 0.0,
-                target_value : 0.0,
-                start_anim : 0.0
-            },
-            center : AnimationAttribute {
-                old_value : Position::default(),
-                target_value : Position::default(),
-                start_anim : 0.0
-            },
-            shape : AnimationAttribute {
-                old_value : Shape::None,
-                target_value : Shape::None,
-                start_anim : 0.0
-            },
-            is_displayed : false
+            markid : MarkId::default(),
+            layer_index : 0,
+            valid : true,
+            size : AnimationAttribute::new(Size::default()),
+            color : AnimationAttribute::new(Color::default()),
+            rotation : AnimationAttribute::new(0.0),
+            center : AnimationAttribute::new(Position::default()),
+            shape : AnimationAttribute::new(Shape::None),
+            stroke_color : AnimationAttribute::new(Color::default()),
+            stroke_width : AnimationAttribute::new(0.0),
+            corner_radius : AnimationAttribute::new(0.0),
+            fill : true,
+            is_displayed : false,
+            time : 0.0
         }
     }
 
     /// Converts a PointMark into a VertexPoint, which is a type understandable
     /// by the renderer, then returns it.
     pub fn to_vertex(&self) -> VertexPoint {
-        (*self.center.old_value.to_array(), *self.center.target_value.to_array(), self.center.start_anim,
-            *self.size.old_value.to_array(), *self.size.target_value.to_array(), self.size.start_anim,
-            *self.color.old_value.to_array(), *self.color.target_value.to_array(), self.color.start_anim,
-            self.rotation.old_value, self.rotation.target_value, self.rotation.start_anim,
-            self.shape.old_value as u32, self.shape.target_value as u32, self.shape.start_anim)
+        let (starts, durations, easings) = pack_timings(&[self.rotation.timing(), self.stroke_width.timing(),
+                                                          self.shape.timing(), self.stroke_color.timing()]);
+        (*self.center.old_value.to_array(), *self.center.target_value.to_array(), self.center.timing(),
+            *self.size.old_value.to_array(), *self.size.target_value.to_array(), self.size.timing(),
+            *self.color.old_value.to_array(), *self.color.target_value.to_array(), self.color.timing(),
+            [self.rotation.old_value, self.rotation.target_value, self.stroke_width.old_value, self.stroke_width.target_value],
+            starts, durations,
+            [self.shape.old_value.to_integer(), self.shape.target_value.to_integer(), self.stroke_color.old_value.to_rgba8(), self.stroke_color.target_value.to_rgba8()],
+            [easings, self.fill as u32], [self.corner_radius.old_value, self.corner_radius.target_value], self.corner_radius.timing())
     }
 
     /// Set the color of a mark. You can pass as argument a tuple of 4 floats or
@@ -205,6 +213,100 @@ impl PointMark {
         anim_set!(self, shape, shape)
     }
 
+    /// Set the color of the outline of the shape. You can pass as argument a tuple of 4 floats or
+    /// a Color directly.
+    pub fn set_stroke_color<C : Into <Color>>(&mut self, color : C) -> &mut Self {
+        anim_set!(self, stroke_color, color.into())
+    }
+
+    /// Set the width in pixels of the outline of the shape, drawn inside the shape.
+    /// The default width is 0, that is no outline.
+    pub fn set_stroke_width(&mut self, stroke_width : f32) -> &mut Self {
+        anim_set!(self, stroke_width, stroke_width.max(0.0))
+    }
+
+    /// Set the radius in pixels of the corners of a rectangle, which is reduced to the half of
+    /// its smallest side if it is larger. The other shapes are not rounded. The default radius is 0.
+    pub fn set_corner_radius(&mut self, corner_radius : f32) -> &mut Self {
+        anim_set!(self, corner_radius, corner_radius.max(0.0))
+    }
+
+    /// The inside of the shape is drawn with the color of the mark. This is the default.
+    pub fn set_fill(&mut self) -> &mut Self {
+        self.fill = true;
+        self
+    }
+
+    /// Only the outline of the shape is drawn, for hollow markers.
+    pub fn set_empty(&mut self) -> &mut Self {
+        self.fill = false;
+        self
+    }
+
+    /// Set the duration in seconds of the animations of every property of a mark.
+    pub fn set_duration(&mut self, duration : f32) -> &mut Self {
+        for property in &POINT_PROPERTIES {
+            self.set_property_duration(*property, duration);
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of every property of a mark.
+    pub fn set_easing(&mut self, easing : Easing) -> &mut Self {
+        for property in &POINT_PROPERTIES {
+            self.set_property_easing(*property, easing);
+        }
+        self
+    }
+
+    /// Set the duration in seconds of the animations of one property of a mark.
+    /// A negative duration is treated as zero, that is no animation.
+    pub fn set_property_duration(&mut self, property : PointProperty, duration : f32) -> &mut Self {
+        let duration = duration.max(0.0);
+        match property {
+            PointProperty::Color => self.color.duration = duration,
+            PointProperty::Size => self.size.duration = duration,
+            PointProperty::Rotation => self.rotation.duration = duration,
+            PointProperty::Position => self.center.duration = duration,
+            PointProperty::Shape => self.shape.duration = duration,
+            PointProperty::StrokeColor => self.stroke_color.duration = duration,
+            PointProperty::StrokeWidth => self.stroke_width.duration = duration,
+            PointProperty::CornerRadius => self.corner_radius.duration = duration
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of one property of a mark.
+    pub fn set_property_easing(&mut self, property : PointProperty, easing : Easing) -> &mut Self {
+        match property {
+            PointProperty::Color => self.color.easing = easing,
+            PointProperty::Size => self.size.easing = easing,
+            PointProperty::Rotation => self.rotation.easing = easing,
+            PointProperty::Position => self.center.easing = easing,
+            PointProperty::Shape => self.shape.easing = easing,
+            PointProperty::StrokeColor => self.stroke_color.easing = easing,
+            PointProperty::StrokeWidth => self.stroke_width.easing = easing,
+            PointProperty::CornerRadius => self.corner_radius.easing = easing
+        }
+        self
+    }
+
+    /// Set the duration and the easing of the running animation of one property of a mark.
+    /// Used by the timelines: the next updates of the property use its own duration and easing again.
+    pub(crate) fn set_property_transition(&mut self, property : PointProperty, duration : f32, easing : Easing) -> &mut Self {
+        match property {
+            PointProperty::Color => self.color.set_transition(duration, easing),
+            PointProperty::Size => self.size.set_transition(duration, easing),
+            PointProperty::Rotation => self.rotation.set_transition(duration, easing),
+            PointProperty::Position => self.center.set_transition(duration, easing),
+            PointProperty::Shape => self.shape.set_transition(duration, easing),
+            PointProperty::StrokeColor => self.stroke_color.set_transition(duration, easing),
+            PointProperty::StrokeWidth => self.stroke_width.set_transition(duration, easing),
+            PointProperty::CornerRadius => self.corner_radius.set_transition(duration, easing)
+        }
+        self
+    }
+
     pub fn get_id(&self) -> MarkId {
         self.markid
     }
@@ -229,16 +331,156 @@ impl PointMark {
         &self.shape.target_value
     }
 
+    pub fn get_stroke_color(&self) -> Color {
+        self.stroke_color.target_value
+    }
+
+    pub fn get_stroke_width(&self) -> f32 {
+        self.stroke_width.target_value
+    }
+
+    pub fn get_corner_radius(&self) -> f32 {
+        self.corner_radius.target_value
+    }
+
+    pub fn is_fill(&self) -> bool {
+        self.fill
+    }
+
+    /// Returns the duration in seconds of the animations of a property.
+    pub fn get_property_duration(&self, property : PointProperty) -> f32 {
+        match property {
+            PointProperty::Color => self.color.duration,
+            PointProperty::Size => self.size.duration,
+            PointProperty::Rotation => self.rotation.duration,
+            PointProperty::Position => self.center.duration,
+            PointProperty::Shape => self.shape.duration,
+            PointProperty::StrokeColor => self.stroke_color.duration,
+            PointProperty::StrokeWidth => self.stroke_width.duration,
+            PointProperty::CornerRadius => self.corner_radius.duration
+        }
+    }
+
+    /// Returns the easing curve of the animations of a property.
+    pub fn get_property_easing(&self, property : PointProperty) -> Easing {
+        match property {
+            PointProperty::Color => self.color.easing,
+            PointProperty::Size => self.size.easing,
+            PointProperty::Rotation => self.rotation.easing,
+            PointProperty::Position => self.center.easing,
+            PointProperty::Shape => self.shape.easing,
+            PointProperty::StrokeColor => self.stroke_color.easing,
+            PointProperty::StrokeWidth => self.stroke_width.easing,
+            PointProperty::CornerRadius => self.corner_radius.easing
+        }
+    }
+
     pub fn get_layer_index(&self) -> usize {
-        self.markid.layer_index
+        self.layer_index
     }
 
     pub fn is_valid(&self) -> bool {
-        self.markid.valid
+        self.valid
     }
 
     pub(crate) fn set_displayed(&mut self, is_displayed : bool) {
         self.is_displayed = is_displayed;
     }
 
-}
\ No newline at end of file
+    pub(crate) fn set_time(&mut self, time : f32) {
+        self.time = time;
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markscontainer::Contrast;
+    use crate::clock::ManualClock;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    #[test]
+    fn retarget()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let clock = Rc::new(RefCell::new(ManualClock::new()));
+        c.set_clock(clock.clone());
+        let markid = c.add_point_mark().set_duration(2.0).get_id();
+        c.get_pointmarks_properties();
+
+        c.get_mark_mut(&markid).unwrap().as_point_mark_mut_unchecked().set_position((10.0, 0.0, 0.0));
+        clock.borrow_mut().advance(1.0);
+        let mut mark = c.get_mark_mut(&markid).unwrap();
+        let point = mark.as_point_mark_mut_unchecked();
+        let mid = point.center.value_at(1.0);
+        point.set_position((20.0, 10.0, 0.0));
+
+        // The second update is not dropped and starts from where the first animation is.
+        assert_eq!(*point.get_position(), Position { x : 20.0, y : 10.0, z : 0.0 });
+        assert_eq!(point.center.old_value, mid);
+        assert_ne!(mid, Position { x : 0.0, y : 0.0, z : 0.0 });
+        assert_ne!(mid, Position { x : 10.0, y : 0.0, z : 0.0 });
+        assert_eq!(point.center.start_anim, 1.0);
+
+        point.set_shape(Shape::Circle);
+        point.set_shape(Shape::Ring);
+        assert_eq!(*point.get_shape(), Shape::Ring);
+        assert_eq!(point.shape.old_value, Shape::None);
+    }
+
+    #[test]
+    fn shape_integer()
+    {
+        assert_eq!(Shape::from_integer(19), Shape::Arrow);
+        assert_eq!(Shape::Arrow.to_integer(), 19);
+        assert_eq!(Shape::from_integer(FIRST_CUSTOM_SHAPE + 2), Shape::Custom(2));
+        assert_eq!(Shape::Custom(2).to_integer(), FIRST_CUSTOM_SHAPE + 2);
+        assert_eq!(Shape::from_integer(20), Shape::None);
+    }
+
+    #[test]
+    fn stroke()
+    {
+        let mut point = PointMark::new();
+        assert!(point.is_fill());
+        assert_eq!(point.to_vertex().9[2..], [0.0, 0.0]);
+
+        point.set_stroke_color(Color::red()).set_stroke_width(2.0).set_empty();
+        point.set_property_duration(PointProperty::StrokeWidth, 0.5);
+        assert_eq!(point.get_stroke_color(), Color::red());
+        assert_eq!(point.get_stroke_width(), 2.0);
+        assert!(!point.is_fill());
+
+        let vertex = point.to_vertex();
+        assert_eq!(vertex.9[3], 2.0);
+        assert_eq!(vertex.11[1], 0.5);
+        assert_eq!(vertex.12[3], Color::red().to_rgba8());
+        assert_eq!(vertex.13[1], 0);
+
+        // A negative width is no outline.
+        point.set_stroke_width(-1.0);
+        assert_eq!(point.get_stroke_width(), 0.0);
+    }
+
+    #[test]
+    fn corner_radius()
+    {
+        let mut point = PointMark::new();
+        point.set_shape(Shape::Rectangle).set_corner_radius(4.0).set_rotation(1.0);
+        point.set_property_duration(PointProperty::CornerRadius, 0.25).set_property_easing(PointProperty::Rotation, Easing::Step);
+        assert_eq!(point.get_corner_radius(), 4.0);
+
+        let vertex = point.to_vertex();
+        assert_eq!(vertex.14[1], 4.0);
+        assert_eq!(vertex.15[1..], [0.25, 0.0]);
+        // The timing of the rotation is the first one packed.
+        assert_eq!(vertex.9[1], 1.0);
+        assert_eq!(vertex.13, [Easing::Step as u32, 1]);
+
+        point.set_corner_radius(-2.0);
+        assert_eq!(point.get_corner_radius(), 0.0);
+    }
+}
@@ -1,4 +1,7 @@
 use crate::MarkMacro;
+use crate::easing::Easing;
+use crate::marks::animationattribute::{AnimationAttribute, pack_timings, resample};
+use crate::triangulation::{triangulate, is_hole, FillRule};
 use properties::position::Position;
 use properties::color::Color;
 use properties::markid::MarkId;
@@ -7,22 +10,90 @@ use mark_macro_derive::MarkMacro;
 
 /// This is the type that will receive our shaders when we will want to render our polygon marks.
 /// We could describe it this way to be more clear :
-/// type VertexPolygon = (color, rotation, origin).                                             //centroid
-pub type VertexPolygon = ([f32; 4], f32, [f32; 3], [f32; 3], [f32; 3], [f32; 3], f32, [f32; 3], u32);
+/// type VertexPolygon = ((old_color, color, old_gradient_color, gradient_color), gradient, old_origin, origin, old_target, target, old_previous, previous, old_next, next,
+///                       old_centroid, centroid, (old_rotation, rotation, old_stroke_width, stroke_width),
+///                       anim_start, anim_duration, (anim_easing, fill)).
+/// The colors are packed by Color::to_rgba8. 'gradient' holds the two positions of a linear gradient, or the
+/// center and the radius of a radial one, whose kind is on the bits of 'fill' above its 2 lowest bits :
+/// 0 for no gradient, 1 for a linear one and 2 for a radial one.
+/// 'anim_start' and 'anim_duration' hold the start and the duration of the animations of the color,
+/// the points, the rotation and the stroke width, in this order. 'anim_easing' holds their easings on 4 bits each.
+/// When the polygon is filled, each vertex is a triangle made of 'origin', 'target' and 'centroid' instead of a side,
+/// and 'previous' and 'next' are not used. Otherwise, 'fill' is 2 for the sides of a hole, whose stroke goes away from
+/// the centroid of the hole, and 0 for the other sides.
+pub type VertexPolygon = ([u32; 4], [f32; 4], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3], [f32; 3],
+                          [f32; 3], [f32; 3], [f32; 4], [f32; 4], [f32; 4], [u32; 2]);
+
+/// This enum describes how the color of a polygon changes across it, from the color of the
+/// polygon to the color of the gradient. The positions are in the coordinates of the marks,
+/// and the color is constant beyond them.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Gradient {
+    /// The polygon has a single color.
+    None,
+    /// The color of the polygon at the first position goes to the color at the second one,
+    /// and is constant along the lines perpendicular to them.
+    Linear(Position, Position, Color),
+    /// The color of the polygon at the center goes to the color at this radius around it.
+    Radial(Position, f32, Color)
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient::None
+    }
+}
+
+impl Gradient {
+    /// Returns the kind of the gradient and its geometry given to the shaders.
+    fn to_vertex(self) -> (u32, [f32; 4]) {
+        match self {
+            Gradient::None => (0, [0.0; 4]),
+            Gradient::Linear(from, to, _) => (1, [from.x, from.y, to.x, to.y]),
+            Gradient::Radial(center, radius, _) => (2, [center.x, center.y, radius, 0.0])
+        }
+    }
+}
+
+/// This enum describes every animated property of a polygon mark.
+/// It allows to set the duration and the easing of the animations
+/// of one property only.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum PolygonProperty {
+    Color,
+    Points,
+    Rotation,
+    StrokeWidth
+}
+
+const POLYGON_PROPERTIES : [PolygonProperty; 4] = [PolygonProperty::Color, PolygonProperty::Points, PolygonProperty::Rotation,
+                                                   PolygonProperty::StrokeWidth];
 
 /// This is the structure that describes the marks of type Polygon.
-/// Each type of mark share some properties, that is an id and a color.
+/// Each type of mark share some properties, that is an id, the index
+/// of its layer, a boolean telling whether or not it is valid and a color.
 /// Polygon marks also have a vector of positions representing its points,
-/// a stroke width and a boolean to indicate whether or not we must
-/// draw the stroke.
+/// made of one or several rings : the outlines of its parts and of their holes,
+/// one after the other. They also have a stroke width, a boolean to indicate whether or not we must
+/// draw the stroke, the rule telling where the inside of a filled
+/// polygon crossing itself is, and an optional gradient. The color, the rotation, the points and the stroke
+/// width are animated like the properties of the point marks.
 #[derive(MarkMacro, PartialEq, Clone, Debug)]
 pub struct PolygonMark {
     pub(crate) markid : MarkId,
-    pub(crate) color : Color,
-    pub(crate) rotation : f32,
-    pub(crate) points : Vec<Position>,
-    pub(crate) stroke_width : f32,
-    pub(crate) fill : bool
+    pub(crate) layer_index : usize,
+    pub(crate) valid : bool,
+    pub(crate) color : AnimationAttribute<Color>,
+    pub(crate) rotation : AnimationAttribute<f32>,
+    pub(crate) points : AnimationAttribute<Vec<Position>>,
+    /// The number of points of each ring.
+    pub(crate) rings : Vec<usize>,
+    pub(crate) stroke_width : AnimationAttribute<f32>,
+    pub(crate) fill : bool,
+    pub(crate) fill_rule : FillRule,
+    pub(crate) gradient : Gradient,
+    pub(crate) is_displayed : bool,
+    pub(crate) time : f32
 }
 
 impl PolygonMark {
@@ -30,12 +101,19 @@ impl PolygonMark {
     /// all attributes to their default value.
     pub fn new() -> Self {
         PolygonMark {
-            markid : MarkId::new(),
-            color : Color::default(),
-            rotation : 0.0,
-            points : Vec::<Position>::new(),
-            stroke_width : 15.0,
-            fill : false
+            markid : MarkId::default(),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute::new(Color::default()),
+            rotation : AnimationAttribute::new(0.0),
+            points : AnimationAttribute::new(Vec::<Position>::new()),
+            rings : Vec::new(),
+            stroke_width : AnimationAttribute::new(15.0),
+            fill : false,
+            fill_rule : FillRule::NonZero,
+            gradient : Gradient::None,
+            is_displayed : false,
+            time : 0.0
         }
     }
 
@@ -43,70 +121,130 @@ impl PolygonMark {
     /// and return the centroid
     /// which is the arithmetic mean position
     /// of all the points in the polygon
-    fn compute_centroid(&self) -> Position {
+    fn compute_centroid(points : &[Position]) -> Position {
         let mut centroid = Position { x : 0.0, y : 0.0, z : 0.0 };
-        if self.points.len()>2 {
-            for point in self.points.clone() {
-                centroid += point;
+        if points.len()>2 {
+            for point in points {
+                centroid += *point;
             }
-            centroid = Position { x : centroid.x / self.points.len() as f32,
-                                  y : centroid.y / self.points.len() as f32,
-                                  z : self.points[0].z as f32};
+            centroid = Position { x : centroid.x / points.len() as f32,
+                                  y : centroid.y / points.len() as f32,
+                                  z : points[0].z as f32};
         }
         centroid
     }
 
+    /// Splits 'points', which are the points of every ring, into the rings.
+    pub(crate) fn split<'a>(&self, points : &'a [Position]) -> Vec<&'a [Position]> {
+        let mut start = 0;
+        self.rings.iter().map(|&count| {
+            let ring = &points[start..start + count];
+            start += count;
+            ring
+        }).collect()
+    }
+
     /// Converts a PolygonMark into a VertexPolygon, which is a type
-    /// understandable by the renderer.
+    /// understandable by the renderer. A filled polygon is split into triangles,
+    /// following the shape of its target points, whereas an empty one gives the sides
+    /// of its rings. The rings with less than 3 points are not drawn.
     pub fn as_vertex(&self) -> Vec<VertexPolygon> {
+        let old = &self.points.old_value;
+        let points = &self.points.target_value;
+        if old.len() != points.len() {
+            return Vec::new();
+        }
+        let (starts, durations, easings) = pack_timings(&[self.color.timing(), self.points.timing(),
+                                                          self.rotation.timing(), self.stroke_width.timing()]);
+        let rotation_stroke = [self.rotation.old_value, self.rotation.target_value, self.stroke_width.old_value, self.stroke_width.target_value];
+        let (kind, gradient) = self.gradient.to_vertex();
+        let end = match self.gradient {
+            Gradient::Linear(_, _, color) | Gradient::Radial(_, _, color) => color,
+            Gradient::None => self.color.target_value
+        }.to_rgba8();
+        let colors = [self.color.old_value.to_rgba8(), self.color.target_value.to_rgba8(), end, end];
+        let rings = self.split(points);
+
+        if self.fill {
+            return triangulate(&rings, self.fill_rule).iter().map(|[a, b, c]| {
+                (colors, gradient, *a.position(old).to_array(), *a.position(points).to_array(),
+                 *b.position(old).to_array(), *b.position(points).to_array(), [0.0; 3], [0.0; 3], [0.0; 3], [0.0; 3],
+                 *c.position(old).to_array(), *c.position(points).to_array(), rotation_stroke, starts, durations, [easings, 1 | kind << 2])
+            }).collect();
+        }
+
         let mut vertex_polygon : Vec<VertexPolygon> = Vec::<VertexPolygon>::new();
-        let centroid : Position = self.compute_centroid();
-        if self.points.len()>2 {
-            let mut previous = self.points[0];
-            let mut origin = self.points[0];
-            let mut target = self.points[0];
-            let mut fill_mode = 0;
-            if self.fill  {
-                fill_mode = 1;
+        for (i, (old, points)) in self.split(old).into_iter().zip(&rings).enumerate() {
+            if points.len() <= 2 {
+                continue;
             }
-            for next in self.points.clone() {
-                if previous == origin { previous = self.points[self.points.len()-1]}
-                let vl : VertexPolygon = (*self.color.to_array(),
-                self.rotation,
-                *origin.to_array(), *target.to_array(), *previous.to_array(),
-                *next.to_array(),self.stroke_width, *centroid.to_array(), fill_mode);
-                vertex_polygon.push(vl);
-                previous = origin;
-                origin = target;
-                target = next;
+            let old_centroid : Position = PolygonMark::compute_centroid(old);
+            let centroid : Position = PolygonMark::compute_centroid(points);
+            let side = (if is_hole(&rings, i) { 2 } else { 0 }) | kind << 2;
+            let n = points.len();
+            for i in 0..n {
+                // Each side goes from the point 'i' to the next one, and knows its neighbours to compute the miters.
+                let (target, previous, next) = ((i + 1) % n, (i + n - 1) % n, (i + 2) % n);
+                vertex_polygon.push((colors, gradient, *old[i].to_array(), *points[i].to_array(), *old[target].to_array(), *points[target].to_array(),
+                                     *old[previous].to_array(), *points[previous].to_array(), *old[next].to_array(), *points[next].to_array(),
+                                     *old_centroid.to_array(), *centroid.to_array(), rotation_stroke, starts, durations, [easings, side]));
             }
-            let vl : VertexPolygon = (*self.color.to_array(),
-            self.rotation,
-            *origin.to_array(), *target.to_array(),
-            *previous.to_array(), *self.points[0].to_array(),self.stroke_width,
-            *centroid.to_array(), fill_mode);
-            vertex_polygon.push(vl);
-            let vr : VertexPolygon = (*self.color.to_array(),
-            self.rotation,
-            *self.points[self.points.len()-1].to_array(), *self.points[0].to_array(),
-            *self.points[self.points.len()-2].to_array(), *self.points[1].to_array(),
-            self.stroke_width, *centroid.to_array(), fill_mode);
-            vertex_polygon.push(vr);
-            vertex_polygon.remove(0);
-            vertex_polygon.remove(0);
         }
         vertex_polygon
     }
 
-    /// Add a point to a polygon. You can pass as argument a tuple of 3 floats or
+    /// Add a point to the last ring of a polygon. You can pass as argument a tuple of 3 floats or
     /// a Position directly
     pub fn add_point<P : Into <Position>>(&mut self, point : P) -> &mut Self {
-        self.points.push(point.into());
+        let mut rings = self.get_rings();
+        match rings.last_mut() {
+            Some(ring) => ring.push(point.into()),
+            None => rings.push(vec![point.into()])
+        }
+        self.set_rings(rings)
+    }
+
+    /// Set all the points of a polygon, made of a single ring. If the polygon is displayed,
+    /// each point moves from its current position. If the number of points changes, the current
+    /// polygon is resampled along its sides to have as many points as the new one.
+    pub fn set_points(&mut self, points : Vec<Position>) -> &mut Self {
+        self.set_rings(vec![points])
+    }
+
+    /// Add a ring to a polygon : another part of the polygon, or a hole in it.
+    /// Whether it is a part or a hole depends on the fill rule : with FillRule::EvenOdd,
+    /// a ring inside another one is always a hole, whereas with FillRule::NonZero, it must
+    /// go around in the opposite direction.
+    pub fn add_ring(&mut self, ring : Vec<Position>) -> &mut Self {
+        let mut rings = self.get_rings();
+        rings.push(ring);
+        self.set_rings(rings)
+    }
+
+    /// Set all the rings of a polygon, see add_ring. If the polygon is displayed, each ring
+    /// moves from the current ring with the same index, resampled to have as many points as the
+    /// new ring like in set_points. The new rings without a current one do not move.
+    pub fn set_rings(&mut self, rings : Vec<Vec<Position>>) -> &mut Self {
+        let current : Vec<Vec<Position>> = if self.is_displayed {
+            let points = self.points.value_at(self.time);
+            self.split(&points).into_iter().map(|ring| ring.to_vec()).collect()
+        }
+        else {
+            Vec::new()
+        };
+        let old : Vec<Position> = rings.iter().enumerate().flat_map(|(i, ring)| match current.get(i) {
+            Some(current) if !current.is_empty() => resample(current, ring.len(), true),
+            _ => ring.clone()
+        }).collect();
+
+        self.rings = rings.iter().map(|ring| ring.len()).collect();
+        self.points.set_value(rings.concat(), self.is_displayed, self.time);
+        self.points.old_value = old;
         self
     }
 
     pub fn set_stroke_width(&mut self, stroke_width : f32) -> &mut Self {
-        self.stroke_width = stroke_width;
+        self.stroke_width.set_value(stroke_width, self.is_displayed, self.time);
         self
     }
 
@@ -120,29 +258,134 @@ impl PolygonMark {
         self
     }
 
+    /// Set the rule telling which parts of a filled polygon are inside,
+    /// when its outline crosses itself or is made of several rings.
+    /// The default rule is FillRule::NonZero.
+    pub fn set_fill_rule(&mut self, rule : FillRule) -> &mut Self {
+        self.fill_rule = rule;
+        self
+    }
+
+    /// Set how the color of a polygon changes across it, filled or not. Unlike its color,
+    /// the gradient is not animated.
+    pub fn set_gradient(&mut self, gradient : Gradient) -> &mut Self {
+        self.gradient = gradient;
+        self
+    }
+
     pub fn set_rotation(&mut self, rotation : f32) -> &mut Self {
-        self.rotation = rotation;
+        self.rotation.set_value(rotation, self.is_displayed, self.time);
         self
     }
 
-    pub fn get_stroke_width(&self) -> f32 {
-        self.stroke_width
+    /// Set the duration in seconds of the animations of every property of a polygon.
+    pub fn set_duration(&mut self, duration : f32) -> &mut Self {
+        for property in &POLYGON_PROPERTIES {
+            self.set_property_duration(*property, duration);
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of every property of a polygon.
+    pub fn set_easing(&mut self, easing : Easing) -> &mut Self {
+        for property in &POLYGON_PROPERTIES {
+            self.set_property_easing(*property, easing);
+        }
+        self
+    }
+
+    /// Set the duration in seconds of the animations of one property of a polygon.
+    /// A negative duration is treated as zero, that is no animation.
+    pub fn set_property_duration(&mut self, property : PolygonProperty, duration : f32) -> &mut Self {
+        let duration = duration.max(0.0);
+        match property {
+            PolygonProperty::Color => self.color.duration = duration,
+            PolygonProperty::Points => self.points.duration = duration,
+            PolygonProperty::Rotation => self.rotation.duration = duration,
+            PolygonProperty::StrokeWidth => self.stroke_width.duration = duration
+        }
+        self
     }
 
-    pub(crate) fn get_points_mut(&mut self) -> &mut Vec<Position> {
-        &mut self.points
+    /// Set the easing curve of the animations of one property of a polygon.
+    pub fn set_property_easing(&mut self, property : PolygonProperty, easing : Easing) -> &mut Self {
+        match property {
+            PolygonProperty::Color => self.color.easing = easing,
+            PolygonProperty::Points => self.points.easing = easing,
+            PolygonProperty::Rotation => self.rotation.easing = easing,
+            PolygonProperty::StrokeWidth => self.stroke_width.easing = easing
+        }
+        self
     }
 
+    /// Set the duration and the easing of the running animation of one property of a polygon.
+    /// Used by the timelines: the next updates of the property use its own duration and easing again.
+    pub(crate) fn set_property_transition(&mut self, property : PolygonProperty, duration : f32, easing : Easing) -> &mut Self {
+        match property {
+            PolygonProperty::Color => self.color.set_transition(duration, easing),
+            PolygonProperty::Points => self.points.set_transition(duration, easing),
+            PolygonProperty::Rotation => self.rotation.set_transition(duration, easing),
+            PolygonProperty::StrokeWidth => self.stroke_width.set_transition(duration, easing)
+        }
+        self
+    }
+
+    pub fn get_stroke_width(&self) -> f32 {
+        self.stroke_width.target_value
+    }
+
+    /// Returns the points of every ring of the polygon, one ring after the other.
     pub fn get_points(&mut self) -> &Vec<Position> {
-        &self.points
+        &self.points.target_value
+    }
+
+    /// Returns the points of each ring of the polygon.
+    pub fn get_rings(&self) -> Vec<Vec<Position>> {
+        self.split(&self.points.target_value).into_iter().map(|ring| ring.to_vec()).collect()
     }
 
     pub fn is_fill(&mut self) -> bool {
         self.fill
     }
 
+    pub fn get_fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
+
+    pub fn get_gradient(&self) -> Gradient {
+        self.gradient
+    }
+
     pub fn get_rotation(&mut self) -> f32 {
-        self.rotation
+        self.rotation.target_value
+    }
+
+    /// Returns the duration in seconds of the animations of a property.
+    pub fn get_property_duration(&self, property : PolygonProperty) -> f32 {
+        match property {
+            PolygonProperty::Color => self.color.duration,
+            PolygonProperty::Points => self.points.duration,
+            PolygonProperty::Rotation => self.rotation.duration,
+            PolygonProperty::StrokeWidth => self.stroke_width.duration
+        }
+    }
+
+    /// Returns the easing curve of the animations of a property.
+    pub fn get_property_easing(&self, property : PolygonProperty) -> Easing {
+        match property {
+            PolygonProperty::Color => self.color.easing,
+            PolygonProperty::Points => self.points.easing,
+            PolygonProperty::Rotation => self.rotation.easing,
+            PolygonProperty::StrokeWidth => self.stroke_width.easing
+        }
+    }
+
+    pub(crate) fn set_displayed(&mut self, is_displayed : bool) {
+        self.is_displayed = is_displayed;
+    }
+
+    pub(crate) fn set_time(&mut self, time : f32) {
+        self.time = time;
     }
 
 }
@@ -162,6 +405,58 @@ mod tests {
         assert_eq!(c.layers.len(), 0);
     }*/
 
+    use super::*;
+
+    #[test]
+    fn as_vertex()
+    {
+        let mut polygon = PolygonMark::new();
+        polygon.set_points(vec![(0.0, 0.0).into(), (3.0, 0.0).into(), (3.0, 3.0).into(), (2.0, 1.0).into(), (0.0, 3.0).into()]);
+        assert_eq!(polygon.as_vertex().len(), 5);
+
+        // The triangles of a filled polygon cover it without going in its notch.
+        polygon.set_fill();
+        let vertices = polygon.as_vertex();
+        let area : f32 = vertices.iter().map(|v| {
+            let (a, b, c) = (v.3, v.5, v.11);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.0
+        }).sum();
+        assert!((area - 6.0).abs() < 1e-5);
+        assert!(vertices.iter().all(|v| v.15[1] == 1));
+
+        polygon.set_gradient(Gradient::Radial((1.0, 2.0).into(), 3.0, Color::red()));
+        let vertex = polygon.as_vertex()[0];
+        assert_eq!(vertex.0[2], Color::red().to_rgba8());
+        assert_eq!(vertex.1, [1.0, 2.0, 3.0, 0.0]);
+        assert_eq!(vertex.15[1], 1 | 2 << 2);
+
+        polygon.set_points(vec![(0.0, 0.0).into(), (1.0, 1.0).into()]);
+        assert!(polygon.as_vertex().is_empty());
+    }
+
+    #[test]
+    fn rings()
+    {
+        let square = |x : f32, size : f32| -> Vec<Position> {
+            vec![(x, x).into(), (x + size, x).into(), (x + size, x + size).into(), (x, x + size).into()]
+        };
+        let mut polygon = PolygonMark::new();
+        polygon.set_points(square(0.0, 4.0)).add_ring(square(1.0, 2.0)).add_point((2.0, 1.5));
+        assert_eq!(polygon.rings, vec![4, 5]);
+        assert_eq!(polygon.get_rings()[1][4], Position { x : 2.0, y : 1.5, z : 0.0 });
 
+        // The sides of the hole are marked to be stroked outside it.
+        let vertices = polygon.as_vertex();
+        assert_eq!(vertices.len(), 9);
+        assert!(vertices[..4].iter().all(|v| v.15[1] == 0));
+        assert!(vertices[4..].iter().all(|v| v.15[1] == 2));
+
+        // Each ring moves from the current one, resampled.
+        polygon.set_displayed(true);
+        polygon.set_rings(vec![square(0.0, 8.0), square(2.0, 1.0)]);
+        assert_eq!(polygon.points.old_value.len(), 8);
+        assert_eq!(polygon.points.old_value[..4], square(0.0, 4.0)[..]);
+        assert_eq!(polygon.points.old_value[4], Position { x : 1.0, y : 1.0, z : 0.0 });
+    }
 }
 //TODO : ajouter test compute_centroid
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use properties::position::Position;
+use crate::marks::pointmark::Shape;
+use crate::vector::{self, Point, Segment};
+use crate::error::{Error, Result};
+
+/// Side in texels of the signed distance field of a custom shape.
+pub const SHAPE_SIZE : u32 = 64;
+/// Side in texels of the texture atlas holding the signed distance fields of the custom shapes.
+pub const SHAPE_ATLAS_SIZE : u32 = 1024;
+/// Integer identifying the first custom shape in the shaders, the next ones following it.
+pub const FIRST_CUSTOM_SHAPE : u32 = 256;
+/// Number of custom shapes fitting in the atlas.
+pub const MAX_CUSTOM_SHAPES : u32 = (SHAPE_ATLAS_SIZE / SHAPE_SIZE) * (SHAPE_ATLAS_SIZE / SHAPE_SIZE);
+
+/// Number of sub-lines of a curve of a path, when the distances to the path are computed.
+const CURVE_STEPS : usize = 16;
+
+/// Signed distance field of a custom shape, to upload at its place in the texture atlas of the shapes.
+/// The field covers the square from (-1, -1) to (1, 1) where a point mark draws its shape, row by row
+/// from the top, with the distances in the units of this square, negative inside the shape.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShapeField {
+    pub index : u32,
+    pub field : Vec<f32>
+}
+
+impl ShapeField {
+    /// Returns the position in the atlas of the first texel of the field.
+    pub fn origin(&self) -> [u32; 2] {
+        let per_row = SHAPE_ATLAS_SIZE / SHAPE_SIZE;
+        [(self.index % per_row) * SHAPE_SIZE, (self.index / per_row) * SHAPE_SIZE]
+    }
+}
+
+/// The custom shapes registered in contrast : the outline of each one as SVG path data, kept
+/// to export the point marks in vector formats, and the fields not given to the renderer yet.
+#[derive(Default)]
+pub(crate) struct ShapeAtlas {
+    pub(crate) outlines : Vec<String>,
+    pending : Vec<ShapeField>
+}
+
+/// Returns the position in the square of the shapes of the center of the texel 'i'.
+fn texel_center(i : i64) -> f32 {
+    -1.0 + (i as f32 + 0.5) * 2.0 / SHAPE_SIZE as f32
+}
+
+/// Returns the field of 'distance' sampled at the center of each texel.
+fn sample<F : Fn(f32, f32) -> f32>(distance : F) -> Vec<f32> {
+    let size = SHAPE_SIZE as i64;
+    (0..size * size).map(|i| distance(texel_center(i % size), texel_center(i / size))).collect()
+}
+
+impl ShapeAtlas {
+    /// Registers a shape from SVG path data, scaled to fit the square of the shapes.
+    pub(crate) fn add_path(&mut self, data : &str) -> Result<Shape> {
+        let path = vector::parse_path(data, |p| p);
+        let fit = fit(&rings(&path)).ok_or_else(|| Error::InvalidShape(data.to_string()))?;
+        let path = vector::parse_path(data, fit);
+        let rings = rings(&path);
+        self.add(vector::path_data(&path), sample(|x, y| signed_distance(&rings, [x, y])))
+    }
+
+    /// Registers a shape from the points of a polygon, scaled to fit the square of the shapes.
+    pub(crate) fn add_outline(&mut self, points : &[Position]) -> Result<Shape> {
+        let ring : Vec<Point> = points.iter().map(|p| [p.x, p.y]).collect();
+        let fit = fit(std::slice::from_ref(&ring)).ok_or_else(|| Error::InvalidShape(format!("{} points", points.len())))?;
+        let rings = vec![ring.into_iter().map(fit).collect()];
+        self.add(outline(&rings), sample(|x, y| signed_distance(&rings, [x, y])))
+    }
+
+    /// Registers a shape from its signed distance function in the square of the shapes.
+    /// The outline of the shape is traced in the field.
+    pub(crate) fn add_function<F : Fn(f32, f32) -> f32>(&mut self, distance : F) -> Result<Shape> {
+        let field = sample(distance);
+        self.add(outline(&contours(&field)), field)
+    }
+
+    fn add(&mut self, outline : String, field : Vec<f32>) -> Result<Shape> {
+        let index = self.outlines.len() as u32;
+        if index >= MAX_CUSTOM_SHAPES {
+            return Err(Error::ShapeAtlasFull);
+        }
+        self.outlines.push(outline);
+        self.pending.push(ShapeField { index, field });
+        Ok(Shape::Custom(index))
+    }
+
+    /// Returns the fields registered since the last call.
+    pub(crate) fn take_pending(&mut self) -> Vec<ShapeField> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Returns the transformation scaling and moving 'rings' into the square of the shapes, centered
+/// and keeping their proportions, or None if they have no area.
+fn fit(rings : &[Vec<Point>]) -> Option<impl Fn(Point) -> Point> {
+    let points = rings.iter().filter(|ring| ring.len() >= 3).flatten();
+    let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+    for p in points {
+        min = [min[0].min(p[0]), min[1].min(p[1])];
+        max = [max[0].max(p[0]), max[1].max(p[1])];
+    }
+    let size = (max[0] - min[0]).max(max[1] - min[1]);
+    if size <= 0.0 || !size.is_finite() {
+        return None;
+    }
+    let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+    Some(move |p : Point| [(p[0] - center[0]) * 2.0 / size, (p[1] - center[1]) * 2.0 / size])
+}
+
+/// Returns the rings of 'path', with its curves cut into sub-lines. Each ring is implicitly closed.
+fn rings(path : &[Segment]) -> Vec<Vec<Point>> {
+    let mut rings = Vec::new();
+    let mut ring : Vec<Point> = Vec::new();
+    let mut start = [0.0, 0.0];
+    for segment in path {
+        if ring.is_empty() && !matches!(segment, Segment::Move(_)) {
+            ring.push(start);
+        }
+        match segment {
+            Segment::Move(p) => {
+                if ring.len() > 1 {
+                    rings.push(std::mem::take(&mut ring));
+                }
+                ring = vec![*p];
+                start = *p;
+            },
+            Segment::Line(p) => ring.push(*p),
+            Segment::Cubic(c1, c2, p) => {
+                let a = *ring.last().unwrap();
+                ring.extend((1..=CURVE_STEPS).map(|i| {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    let u = 1.0 - t;
+                    let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    [w0 * a[0] + w1 * c1[0] + w2 * c2[0] + w3 * p[0], w0 * a[1] + w1 * c1[1] + w2 * c2[1] + w3 * p[1]]
+                }));
+            },
+            Segment::Close => {
+                rings.push(std::mem::take(&mut ring));
+            }
+        }
+    }
+    rings.push(ring);
+    rings.into_iter().filter(|ring| ring.len() >= 3).collect()
+}
+
+/// Returns the outline made of 'rings' as SVG path data.
+fn outline(rings : &[Vec<Point>]) -> String {
+    let path : Vec<Segment> = rings.iter().flat_map(|ring| {
+        ring.iter().enumerate().map(|(i, p)| if i == 0 { Segment::Move(*p) } else { Segment::Line(*p) })
+            .chain(std::iter::once(Segment::Close))
+    }).collect();
+    vector::path_data(&path)
+}
+
+/// Returns the distance from 'p' to the closed 'rings', negative inside them following the nonzero rule.
+fn signed_distance(rings : &[Vec<Point>], p : Point) -> f32 {
+    let mut distance = f32::MAX;
+    let mut winding = 0;
+    for ring in rings {
+        for (i, a) in ring.iter().enumerate() {
+            let b = ring[(i + 1) % ring.len()];
+            let (ab, ap) = ([b[0] - a[0], b[1] - a[1]], [p[0] - a[0], p[1] - a[1]]);
+            let length = ab[0] * ab[0] + ab[1] * ab[1];
+            let t = if length > 0.0 { ((ap[0] * ab[0] + ap[1] * ab[1]) / length).clamp(0.0, 1.0) } else { 0.0 };
+            distance = distance.min(((ap[0] - t * ab[0]).powi(2) + (ap[1] - t * ab[1]).powi(2)).sqrt());
+            let cross = ab[0] * ap[1] - ab[1] * ap[0];
+            if a[1] <= p[1] && b[1] > p[1] && cross > 0.0 {
+                winding += 1;
+            }
+            else if a[1] > p[1] && b[1] <= p[1] && cross < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    if winding != 0 { -distance } else { distance }
+}
+
+/// Returns the closed outlines where the signed distance 'field' is zero, with the marching squares.
+/// The field is surrounded by the outside, so that every outline is closed, and the outlines go
+/// round the same way around the inside, so the holes are kept by the nonzero rule.
+fn contours(field : &[f32]) -> Vec<Vec<Point>> {
+    let size = SHAPE_SIZE as i64;
+    let value = |i : i64, j : i64| if i < 0 || j < 0 || i >= size || j >= size { 1.0 } else { field[(j * size + i) as usize] };
+    let node = |i : i64, j : i64| (j + 1) * (size + 2) + i + 1;
+
+    // Each piece of outline goes from the point where the border of a cell leaves the inside to the
+    // point where it enters it again, the border being walked clockwise from the top left corner.
+    // A point is identified by the two nodes of the grid around it.
+    let mut next = HashMap::new();
+    let mut points = HashMap::new();
+    for j in -1..size {
+        for i in -1..size {
+            let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+            let mut crossings = Vec::new();
+            for k in 0..4 {
+                let ((xa, ya), (xb, yb)) = (corners[k], corners[(k + 1) % 4]);
+                let (va, vb) = (value(xa, ya), value(xb, yb));
+                if (va < 0.0) != (vb < 0.0) {
+                    let t = va / (va - vb);
+                    let p = [texel_center(xa) + (texel_center(xb) - texel_center(xa)) * t,
+                             texel_center(ya) + (texel_center(yb) - texel_center(ya)) * t];
+                    let key = (node(xa, ya).min(node(xb, yb)), node(xa, ya).max(node(xb, yb)));
+                    points.insert(key, p);
+                    crossings.push((key, va < 0.0));
+                }
+            }
+            // When the four corners are crossed, the center tells whether the inside goes through the cell.
+            let center = corners.iter().map(|(x, y)| value(*x, *y)).sum::<f32>() < 0.0;
+            let n = crossings.len();
+            for (m, (key, leaves)) in crossings.iter().enumerate() {
+                if *leaves {
+                    let other = if center || n == 2 { (m + 1) % n } else { (m + n - 1) % n };
+                    next.insert(*key, crossings[other].0);
+                }
+            }
+        }
+    }
+
+    let mut contours = Vec::new();
+    while let Some(&first) = next.keys().next() {
+        let mut contour = Vec::new();
+        let mut key = first;
+        while let Some(following) = next.remove(&key) {
+            contour.push(points[&key]);
+            key = following;
+        }
+        if contour.len() >= 3 {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_path()
+    {
+        let mut atlas = ShapeAtlas::default();
+        // The square is scaled to fill the square of the shapes, and its hole is kept.
+        let shape = atlas.add_path("M10 10h20v20h-20zM15 15v10h10v-10z").unwrap();
+        assert_eq!(shape, Shape::Custom(0));
+        assert!(atlas.outlines[0].starts_with("M-1 -1L1 -1L1 1L-1 1Z"));
+        let fields = atlas.take_pending();
+        assert_eq!(fields.len(), 1);
+        assert!(atlas.take_pending().is_empty());
+
+        let size = SHAPE_SIZE as usize;
+        let at = |x : usize, y : usize| fields[0].field[y * size + x];
+        assert!(at(2, size / 2) < 0.0);
+        assert!(at(size / 2, size / 2) > 0.0);
+        assert!((at(size / 2, size / 2) - 0.5).abs() < 0.02);
+
+        assert!(matches!(atlas.add_path("M0 0L1 1"), Err(Error::InvalidShape(_))));
+    }
+
+    #[test]
+    fn add_outline()
+    {
+        let mut atlas = ShapeAtlas::default();
+        atlas.add_path("M0 0L1 0L0 1Z").unwrap();
+        let triangle = [Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 4.0, y : 0.0, z : 0.0 }, Position { x : 2.0, y : 2.0, z : 0.0 }];
+        // The triangle is twice as wide as high, and centered.
+        assert_eq!(atlas.add_outline(&triangle).unwrap(), Shape::Custom(1));
+        assert_eq!(atlas.outlines[1], "M-1 -0.5L1 -0.5L0 0.5Z");
+        assert_eq!(atlas.take_pending()[1].origin(), [SHAPE_SIZE, 0]);
+    }
+
+    #[test]
+    fn add_function()
+    {
+        let mut atlas = ShapeAtlas::default();
+        // A ring, whose hole goes the other way round.
+        atlas.add_function(|x, y| ((x * x + y * y).sqrt() - 0.6).abs() - 0.2).unwrap();
+        let path = vector::parse_path(&atlas.outlines[0], |p| p);
+        let rings = rings(&path);
+        assert_eq!(rings.len(), 2);
+        for ring in &rings {
+            let radius = (ring[0][0].powi(2) + ring[0][1].powi(2)).sqrt();
+            assert!((radius - 0.8).abs() < 0.02 || (radius - 0.4).abs() < 0.02);
+        }
+        assert!(signed_distance(&rings, [0.0, 0.0]) > 0.0);
+        assert!(signed_distance(&rings, [0.6, 0.0]) < 0.0);
+        assert!(signed_distance(&rings, [0.95, 0.0]) > 0.0);
+    }
+}
@@ -3,23 +3,110 @@ use properties::position::Position;
 use properties::color::Color;
 use properties::markid::MarkId;
 use mark_macro_derive::MarkMacro;
+use crate::error::Error;
+use crate::error::Result;
+use crate::easing::Easing;
+use crate::marks::animationattribute::AnimationAttribute;
 
 use std::collections::HashMap;
 use std::collections::LinkedList;
 
 use rect_packer::{Packer, Rect};
 
+/// Width and height in pixels of the texture atlas of each font.
+pub const ATLAS_SIZE: u32 = 1024;
 /// Texture atlas size.
-const SIZE: &'static f32 = &1024.0;
+const SIZE: &'static f32 = &(ATLAS_SIZE as f32);
 /// Static atlas config.
-const ATLAS: &'static rect_packer::Config = &rect_packer::Config{width: 1024, height: 1024, border_padding: 5, rectangle_padding: 10};
+const ATLAS: &'static rect_packer::Config = &rect_packer::Config{width: ATLAS_SIZE as i32, height: ATLAS_SIZE as i32, border_padding: 5, rectangle_padding: 10};
 /// Default ascii chars.
 const ASCII: &'static str = &"!\"#$%&\'()*+,-./:;<=>?[]\\|{}^~_@`abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
 
 /// This is the type that will receive our shaders when we will want to render our text marks.
 /// We could describe it this way to be clearer :
-/// type VertexText = (position, texture coordinates).
-pub type VertexText = ([f32; 3],[f32; 2]);
+/// type VertexText = (position relative to the origin of the text, texture coordinates, old_origin, origin, anim_origin).
+/// 'anim_origin' is the triple (start, duration, easing) of the animation of the origin.
+pub type VertexText = ([f32; 3],[f32; 2],[f32; 3],[f32; 3],[f32; 3]);
+
+/// Vertex of a glyph built by FaceCache::drawing_commands.
+/// type GlyphVertex = (position, texture coordinates).
+pub type GlyphVertex = ([f32; 3],[f32; 2]);
+
+/// Horizontal alignment of the lines of a text on its position.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum TextAlign
+{
+    Left,
+    Center,
+    Right
+}
+
+/// Vertical anchoring of a text on its position : the top of its first line, the middle
+/// of its lines, the baseline of its first line or the bottom of its last line.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum TextAnchor
+{
+    Top,
+    Middle,
+    Baseline,
+    Bottom
+}
+
+/// How the lines of a text are placed around its position.
+/// The line spacing multiplies the height of the lines of the font, and the lines longer
+/// than the maximum width in pixels, if any, are wrapped between their words.
+/// By default, a text starts at its position on the baseline of its first line.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct TextLayout
+{
+    pub align: TextAlign,
+    pub anchor: TextAnchor,
+    pub line_spacing: f32,
+    pub max_width: Option<f32>
+}
+
+impl Default for TextLayout
+{
+    fn default() -> Self
+    {
+        TextLayout{align: TextAlign::Left, anchor: TextAnchor::Baseline, line_spacing: 1.0, max_width: None}
+    }
+}
+
+/// Line of a text laid out by FaceCache::layout : its content, the origin of its baseline
+/// relative to the position of the text, and its width, all in pixels.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct TextLine<'t>
+{
+    pub text: &'t str,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32
+}
+
+/// Box in pixels from its top left corner, the y axis going down like on the screen.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct TextBounds
+{
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32
+}
+
+/// Size of a text measured by FaceCache::measure, in pixels.
+/// 'width' is the advance of its longest line, and 'height' goes from the top of its
+/// first line to the bottom of its last one. The ascent and the descent are the ones of the font.
+/// 'bounds' is the tight box of its glyphs, relative to the position of the text.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct TextMetrics
+{
+    pub width: f32,
+    pub height: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub bounds: TextBounds
+}
 
 /// Cache Font & Freetype library.
 pub struct FontCache
@@ -43,11 +130,18 @@ pub struct Glyph
 /// Stores a font face & all her loaded glyphs.
 /// A face is a font loaded with a police size.
 /// Generally contrast calls a font name, the key that stores a FaceCache.
+/// The path of the font file and the police are kept to export the texts.
+/// The ascent and the descent of the face, and the height of its lines, are in pixels.
 #[derive(Clone)]
 pub struct FaceCache
 {
     pub(crate) face: freetype::Face,
     pub(crate) name: String,
+    pub(crate) font: String,
+    pub(crate) police: u32,
+    pub(crate) ascent: f32,
+    pub(crate) descent: f32,
+    pub(crate) line_height: f32,
     pub(crate) chars: HashMap<char,Glyph>,
     pub(crate) atlas: Packer,
     pub(crate) writable: LinkedList<Glyph>
@@ -71,19 +165,20 @@ impl Glyph
 impl FontCache
 {
     /// Creates & initialize Freetype.
-    pub fn new() -> FontCache
+    pub fn new() -> Result<FontCache>
     {
-        FontCache{library: freetype::Library::init().unwrap(), cached: HashMap::new()}
+        Ok(FontCache{library: freetype::Library::init()?, cached: HashMap::new()})
     }
 
     /// Create a FaceCache and store it with the associated font name.
     /// Be careful, the font name is unique and does not match the actual name of the font, it just serves to store a FaceCache.
-    pub fn create_face(&mut self, name: &str, font: &str, police: u32)
+    pub fn create_face(&mut self, name: &str, font: &str, police: u32) -> Result<()>
     {
-        let face = self.library.new_face(font, 0).unwrap();
-        face.set_pixel_sizes(0, police).unwrap();
+        let face = self.library.new_face(font, 0).map_err(|err| Error::FontLoad(font.to_string(), err))?;
+        face.set_pixel_sizes(0, police)?;
 
-        self.cached.insert(name.to_string(), FaceCache::new(face, name.to_string()));
+        self.cached.insert(name.to_string(), FaceCache::new(face, name.to_string(), font.to_string(), police)?);
+        Ok(())
     }
 
     /// Returns a FaceCache, if it exists.
@@ -101,50 +196,96 @@ impl FontCache
 
 impl FaceCache
 {
-    /// Stores a new face & loads her glyphs.
-    pub fn new(face: freetype::Face, name: String) -> FaceCache
+    /// Stores a new face, loaded from the file 'font' with the police 'police', & loads her glyphs.
+    pub fn new(face: freetype::Face, name: String, font: String, police: u32) -> Result<FaceCache>
     {
-        let mut cache = FaceCache{face, name, chars: HashMap::new(), atlas: Packer::new(*ATLAS), writable: LinkedList::new()};
+        // The metrics are given in font units, the fonts without them take the police.
+        let (ascent, descent, line_height) = match face.em_size()
+        {
+            0 => (police as f32, 0.0, police as f32),
+            em_size =>
+            {
+                let scale = police as f32 / em_size as f32;
+                let (ascent, descent) = (face.ascender() as f32 * scale, -(face.descender() as f32) * scale);
+                (ascent, descent, (face.height() as f32 * scale).max(ascent + descent))
+            }
+        };
+        let mut cache = FaceCache{face, name, font, police, ascent, descent, line_height, chars: HashMap::new(), atlas: Packer::new(*ATLAS),
+                                  writable: LinkedList::new()};
         // Default load ascii characters.
-        cache.prepare_string(ASCII);
-        cache
+        cache.prepare_string(ASCII)?;
+        Ok(cache)
     }
 
     /// Loads char glyphs contained in the string.
-    pub fn prepare_string(&mut self, s: &str)
+    /// Fails if a glyph cannot be rendered or if the atlas is full.
+    pub fn prepare_string(&mut self, s: &str) -> Result<()>
     {
         for c in s.chars()
         {
             if self.chars.contains_key(&c) { continue; }
-            self.face.load_char(c as usize, freetype::face::LoadFlag::RENDER).unwrap();
+            self.face.load_char(c as usize, freetype::face::LoadFlag::RENDER)?;
             let glyph = self.face.glyph();
 
-            if let Some(rect) = self.atlas.pack(glyph.bitmap().width(), glyph.bitmap().rows(), false)
-            {
-                let n = rect.width as usize;
-                let mut bitmap = Vec::with_capacity(n.pow(2));
-                let data = glyph.bitmap().buffer().iter().map(|&x| { x as f32}).collect::<Vec<_>>();
-                let mut chunks = data.chunks(n).collect::<Vec<_>>();
-                chunks.reverse();
-                for v in chunks { bitmap.extend_from_slice(v); }
-
-                let g =  Glyph::new(self.name.clone(), bitmap, rect, glyph.advance().x.into(), glyph.bitmap_left(), glyph.bitmap_top());
-                if c == '!' { self.chars.insert(' ', g.clone()); }
-                self.chars.insert(c, g.clone());
-                self.writable.push_front(g);
-            }
+            let rect = self.atlas.pack(glyph.bitmap().width(), glyph.bitmap().rows(), false)
+                .ok_or_else(|| Error::AtlasFull(self.name.clone()))?;
+
+            let n = rect.width as usize;
+            let mut bitmap = Vec::with_capacity(n.pow(2));
+            let data = glyph.bitmap().buffer().iter().map(|&x| { x as f32}).collect::<Vec<_>>();
+            let mut chunks = data.chunks(n).collect::<Vec<_>>();
+            chunks.reverse();
+            for v in chunks { bitmap.extend_from_slice(v); }
+
+            let g =  Glyph::new(self.name.clone(), bitmap, rect, glyph.advance().x.into(), glyph.bitmap_left(), glyph.bitmap_top());
+            if c == '!' { self.chars.insert(' ', g.clone()); }
+            self.chars.insert(c, g.clone());
+            self.writable.push_front(g);
         }
+        Ok(())
     }
 
-    /// Builds vertices in accordance with a position & a content.
-    pub fn drawing_commands(&self, x: i32, y: i32, z: f32, text: &str) -> Vec<VertexText>
+    /// Splits a text into lines placed in accordance with 'layout'.
+    /// The chars whose glyph was not loaded with prepare_string take no room.
+    pub fn layout<'t>(&self, text: &'t str, layout: &TextLayout) -> Vec<TextLine<'t>>
+    {
+        layout_lines(text, layout, (self.ascent, self.descent, self.line_height), |c| self.chars.get(&c).map_or(0, |glyph| glyph.adv >> 6))
+    }
+
+    /// Measures a text laid out with 'layout', from the metrics of its glyphs.
+    /// The chars whose glyph was not loaded with prepare_string take no room.
+    pub fn measure(&self, text: &str, layout: &TextLayout) -> TextMetrics
+    {
+        let lines = self.layout(text, layout);
+        let width = lines.iter().map(|line| line.width).max().unwrap_or(0) as f32;
+        let block = (lines.len() - 1) as f32 * self.line_height * layout.line_spacing;
+        TextMetrics{width, height: self.ascent + block + self.descent, ascent: self.ascent, descent: self.descent,
+                    bounds: glyph_bounds(&lines, |c| self.chars.get(&c))}
+    }
+
+    /// Builds vertices in accordance with a position, a content & a layout.
+    /// Fails if a glyph of the content was not loaded with prepare_string.
+    pub fn drawing_commands(&self, x: i32, y: i32, z: f32, text: &str, layout: &TextLayout) -> Result<Vec<GlyphVertex>>
     {
-        let mut x = x;
         let mut vertices = Vec::new();
 
+        for line in self.layout(text, layout)
+        {
+            let (x, y) = (x + line.x, y + line.y);
+            self.line_vertices(&mut vertices, x, y, z, line.text)?;
+        }
+
+        Ok(vertices)
+    }
+
+    /// Builds the vertices of one line starting at a position.
+    fn line_vertices(&self, vertices: &mut Vec<GlyphVertex>, x: i32, y: i32, z: f32, text: &str) -> Result<()>
+    {
+        let mut x = x;
+
         for c in text.chars()
         {
-            let glyph = self.chars.get(&c).unwrap();
+            let glyph = self.chars.get(&c).ok_or(Error::GlyphMissing(c))?;
 
             let xpos = (x + glyph.bx) as f32;
             let ypos = (y - glyph.by) as f32;
@@ -169,7 +310,7 @@ impl FaceCache
             vertices.push(([xpos+w, ypos+h, z], [u2, v ]));
         }
 
-        vertices
+        Ok(())
     }
 
     /// Returns news loaded glyphs that need to be updated on a texture.
@@ -181,15 +322,107 @@ impl FaceCache
     }
 }
 
+/// Splits 'text' at its line breaks, then between its words when a line is longer than the maximum width.
+/// 'metrics' are the ascent, the descent and the line height of the font, and 'advance' gives the advance of a char,
+/// all in pixels. A word longer than the maximum width stays alone on its line.
+fn layout_lines<'t, F>(text: &'t str, layout: &TextLayout, metrics: (f32, f32, f32), advance: F) -> Vec<TextLine<'t>>
+    where F: Fn(char) -> i32
+{
+    let width = |s: &str| s.chars().map(&advance).sum::<i32>();
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n')
+    {
+        let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+        let max_width = match layout.max_width
+        {
+            Some(max_width) => max_width,
+            None =>
+            {
+                lines.push(paragraph);
+                continue;
+            }
+        };
+        // The line is paragraph[start..end], the spaces where it breaks are dropped.
+        let (mut start, mut end, mut offset) = (0, 0, 0);
+        for word in paragraph.split(' ')
+        {
+            let (word_start, word_end) = (offset, offset + word.len());
+            offset = word_end + 1;
+            if end == start || width(paragraph[start..word_end].trim_end_matches(' ')) as f32 <= max_width
+            {
+                end = word_end;
+            }
+            else if !word.is_empty()
+            {
+                lines.push(paragraph[start..end].trim_end_matches(' '));
+                start = word_start;
+                end = word_end;
+            }
+        }
+        lines.push(paragraph[start..end].trim_end_matches(' '));
+    }
+
+    let (ascent, descent, line_height) = metrics;
+    let step = line_height * layout.line_spacing;
+    let block = (lines.len() - 1) as f32 * step;
+    let first = match layout.anchor
+    {
+        TextAnchor::Top => ascent,
+        TextAnchor::Middle => (ascent - block - descent) / 2.0,
+        TextAnchor::Baseline => 0.0,
+        TextAnchor::Bottom => -(block + descent)
+    };
+    lines.into_iter().enumerate().map(|(i, text)|
+    {
+        let width = width(text);
+        let x = match layout.align
+        {
+            TextAlign::Left => 0,
+            TextAlign::Center => -(width as f32 / 2.0).round() as i32,
+            TextAlign::Right => -width
+        };
+        TextLine{text, x, y: (first + i as f32 * step).round() as i32, width}
+    }).collect()
+}
+
+/// Returns the box of the glyphs of the lines, placed like by FaceCache::drawing_commands.
+/// The spaces and the glyphs not found by 'glyph' are not drawn, so they only widen the box when they are
+/// between other glyphs. Without any glyph drawn, the box is empty at the origin.
+fn glyph_bounds<'g, F>(lines: &[TextLine], glyph: F) -> TextBounds
+    where F: Fn(char) -> Option<&'g Glyph>
+{
+    let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+    for line in lines
+    {
+        let mut x = line.x;
+        for c in line.text.chars()
+        {
+            let glyph = match glyph(c) { Some(glyph) => glyph, None => continue };
+            if c != ' ' && glyph.rect.width > 0 && glyph.rect.height > 0
+            {
+                let (left, top) = ((x + glyph.bx) as f32, (line.y - glyph.by) as f32);
+                min = [min[0].min(left), min[1].min(top)];
+                max = [max[0].max(left + glyph.rect.width as f32), max[1].max(top + glyph.rect.height as f32)];
+            }
+            x += glyph.adv >> 6;
+        }
+    }
+    if min[0] > max[0]
+    {
+        return TextBounds::default();
+    }
+    TextBounds{x: min[0], y: min[1], width: max[0] - min[0], height: max[1] - min[1]}
+}
+
 /// Command needed to draw a mark text correctly.
 /// Store :
 /// - The font name (key to FaceCache & Texture)
-/// - The color
+/// - The animated color
 /// - Indexes in vertices
 pub struct TextMarkCmd
 {
     pub name: String,
-    pub color: Color,
+    color: AnimationAttribute<Color>,
     pub start: usize,
     pub end: usize
 }
@@ -197,24 +430,47 @@ pub struct TextMarkCmd
 impl TextMarkCmd
 {
     /// Creates a new Cmd.
-    pub fn new(name: &str, color: Color, start: usize, end: usize) -> TextMarkCmd
+    pub(crate) fn new(name: &str, color: AnimationAttribute<Color>, start: usize, end: usize) -> TextMarkCmd
     {
         TextMarkCmd{name: name.to_string(), color, start, end}
     }
+
+    /// Returns the color of the text at the time 't', in seconds.
+    pub fn color_at(&self, t: f32) -> Color
+    {
+        self.color.value_at(t)
+    }
+}
+
+/// This enum describes every animated property of a text mark.
+/// It allows to set the duration and the easing of the animations
+/// of one property only.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum TextProperty
+{
+    Color,
+    Position
 }
 
 /// This is the structure that describes the marks of type Text.
 /// Structure directly manipulable by the user.
 /// The font is only a string to avoid copy.
 /// The mark only contains the key to the FontCache.
+/// The color and the position are animated like the properties of the point marks.
+/// The layout places its lines around its position.
 #[derive(PartialEq, Debug, MarkMacro, Clone)]
 pub struct TextMark
 {
     pub(crate) markid : MarkId,
-    pub(crate) color : Color,
+    pub(crate) layer_index : usize,
+    pub(crate) valid : bool,
+    pub(crate) color : AnimationAttribute<Color>,
     pub(crate) face: String,
     pub(crate) text: String,
-    pub(crate) pos: Position
+    pub(crate) pos: AnimationAttribute<Position>,
+    pub(crate) layout: TextLayout,
+    pub(crate) is_displayed : bool,
+    pub(crate) time : f32
 }
 
 impl TextMark
@@ -222,8 +478,18 @@ impl TextMark
     /// Returns a new empty instance of TextMark.
     pub fn new() -> TextMark
     {
-        TextMark{markid: MarkId::new(), color : Color::default(), face: String::from(""), text: String::from("")
-                , pos: Position{x: 0.0, y: 0.0, z:0.0}}
+        TextMark{markid: MarkId::default(), layer_index: 0, valid: true, color : AnimationAttribute::new(Color::default()), face: String::from(""),
+                text: String::from(""), pos: AnimationAttribute::new(Position{x: 0.0, y: 0.0, z:0.0}), layout: TextLayout::default(), is_displayed: false, time: 0.0}
+    }
+
+    /// Converts the glyphs of the text, built at the origin by FaceCache::drawing_commands,
+    /// into vertices understandable by the renderer.
+    pub fn to_vertices(&self, glyphs: Vec<GlyphVertex>) -> Vec<VertexText>
+    {
+        let old = *self.pos.old_value.to_array();
+        let target = *self.pos.target_value.to_array();
+        let anim = self.pos.timing();
+        glyphs.into_iter().map(|(pos, tex)| (pos, tex, old, target, anim)).collect()
     }
 
     /// Setter of the font.
@@ -243,14 +509,94 @@ impl TextMark
     /// Setter of the position
     pub fn set_position<P : Into <Position>>(&mut self, position: P) -> &mut Self
     {
-        self.pos = position.into();
+        self.pos.set_value(position.into(), self.is_displayed, self.time);
+        self
+    }
+
+    /// Setter of the horizontal alignment of the lines on the position.
+    pub fn set_align(&mut self, align: TextAlign) -> &mut Self
+    {
+        self.layout.align = align;
+        self
+    }
+
+    /// Setter of the vertical anchoring of the text on the position.
+    pub fn set_anchor(&mut self, anchor: TextAnchor) -> &mut Self
+    {
+        self.layout.anchor = anchor;
+        self
+    }
+
+    /// Setter of the line spacing, multiplying the height of the lines of the font.
+    /// A negative spacing is treated as zero.
+    pub fn set_line_spacing(&mut self, line_spacing: f32) -> &mut Self
+    {
+        self.layout.line_spacing = line_spacing.max(0.0);
+        self
+    }
+
+    /// Setter of the maximum width in pixels of the lines, wrapped between their words.
+    /// A width of zero or less removes the wrapping.
+    pub fn set_max_width(&mut self, max_width: f32) -> &mut Self
+    {
+        self.layout.max_width = if max_width > 0.0 { Some(max_width) } else { None };
+        self
+    }
+
+    /// Set the duration in seconds of the animations of every property of a text.
+    pub fn set_duration(&mut self, duration: f32) -> &mut Self
+    {
+        self.set_property_duration(TextProperty::Color, duration);
+        self.set_property_duration(TextProperty::Position, duration)
+    }
+
+    /// Set the easing curve of the animations of every property of a text.
+    pub fn set_easing(&mut self, easing: Easing) -> &mut Self
+    {
+        self.set_property_easing(TextProperty::Color, easing);
+        self.set_property_easing(TextProperty::Position, easing)
+    }
+
+    /// Set the duration in seconds of the animations of one property of a text.
+    /// A negative duration is treated as zero, that is no animation.
+    pub fn set_property_duration(&mut self, property: TextProperty, duration: f32) -> &mut Self
+    {
+        let duration = duration.max(0.0);
+        match property
+        {
+            TextProperty::Color => self.color.duration = duration,
+            TextProperty::Position => self.pos.duration = duration
+        }
+        self
+    }
+
+    /// Set the easing curve of the animations of one property of a text.
+    pub fn set_property_easing(&mut self, property: TextProperty, easing: Easing) -> &mut Self
+    {
+        match property
+        {
+            TextProperty::Color => self.color.easing = easing,
+            TextProperty::Position => self.pos.easing = easing
+        }
+        self
+    }
+
+    /// Set the duration and the easing of the running animation of one property of a text.
+    /// Used by the timelines: the next updates of the property use its own duration and easing again.
+    pub(crate) fn set_property_transition(&mut self, property: TextProperty, duration: f32, easing: Easing) -> &mut Self
+    {
+        match property
+        {
+            TextProperty::Color => self.color.set_transition(duration, easing),
+            TextProperty::Position => self.pos.set_transition(duration, easing)
+        }
         self
     }
 
     /// Borrow the position.
     pub fn get_position(&self) -> &Position
     {
-        &self.pos
+        &self.pos.target_value
     }
 
     /// Borrow the font name.
@@ -265,12 +611,107 @@ impl TextMark
         &self.text
     }
 
+    /// Borrow the layout of the lines.
+    pub fn get_layout(&self) -> &TextLayout
+    {
+        &self.layout
+    }
+
+    /// Returns the box on the screen of the glyphs of the text, in pixels from the top left corner,
+    /// at the position displayed at 'time' and zoomed by 'scale', the scale of the camera.
+    /// The glyphs are measured with 'face', which should be the face of its font.
+    pub fn get_bounds(&self, face: &FaceCache, time: f32, scale: f32) -> TextBounds
+    {
+        let bounds = face.measure(&self.text, &self.layout).bounds;
+        let position = self.pos.value_at(time);
+        TextBounds{x: (position.x + bounds.x) * scale, y: (position.y + bounds.y) * scale, width: bounds.width * scale,
+                   height: bounds.height * scale}
+    }
+
+    /// Returns the duration in seconds of the animations of a property.
+    pub fn get_property_duration(&self, property: TextProperty) -> f32
+    {
+        match property
+        {
+            TextProperty::Color => self.color.duration,
+            TextProperty::Position => self.pos.duration
+        }
+    }
+
+    /// Returns the easing curve of the animations of a property.
+    pub fn get_property_easing(&self, property: TextProperty) -> Easing
+    {
+        match property
+        {
+            TextProperty::Color => self.color.easing,
+            TextProperty::Position => self.pos.easing
+        }
+    }
+
     /// Get the X-coord in i32.
-    pub fn get_x(&self) -> i32 { self.pos.x as i32 }
+    pub fn get_x(&self) -> i32 { self.pos.target_value.x as i32 }
 
     /// Get the Y-coord in i32.
-    pub fn get_y(&self) -> i32 { self.pos.y as i32 }
+    pub fn get_y(&self) -> i32 { self.pos.target_value.y as i32 }
 
     /// Get the Z-coord in f32.
-    pub fn get_z(&self) -> f32 { self.pos.z }
-}
\ No newline at end of file
+    pub fn get_z(&self) -> f32 { self.pos.target_value.z }
+
+    pub(crate) fn set_displayed(&mut self, is_displayed: bool)
+    {
+        self.is_displayed = is_displayed;
+    }
+
+    pub(crate) fn set_time(&mut self, time: f32)
+    {
+        self.time = time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out a text whose chars are 10 pixels wide, with a font 8 pixels above and 2 below its baseline.
+    fn lines<'t>(text: &'t str, layout: &TextLayout) -> Vec<(&'t str, i32, i32)>
+    {
+        super::layout_lines(text, layout, (8.0, 2.0, 12.0), |_| 10).into_iter().map(|line| (line.text, line.x, line.y)).collect()
+    }
+
+    #[test]
+    fn layout_lines()
+    {
+        let layout = TextLayout::default();
+        assert_eq!(lines("abc", &layout), vec![("abc", 0, 0)]);
+        assert_eq!(lines("ab\r\ncd\n", &layout), vec![("ab", 0, 0), ("cd", 0, 12), ("", 0, 24)]);
+
+        let layout = TextLayout{align: TextAlign::Center, anchor: TextAnchor::Middle, line_spacing: 2.0, max_width: None};
+        assert_eq!(lines("abc\nde", &layout), vec![("abc", -15, -9), ("de", -10, 15)]);
+
+        let layout = TextLayout{align: TextAlign::Right, anchor: TextAnchor::Top, ..TextLayout::default()};
+        assert_eq!(lines("ab\nc", &layout), vec![("ab", -20, 8), ("c", -10, 20)]);
+        let layout = TextLayout{anchor: TextAnchor::Bottom, ..TextLayout::default()};
+        assert_eq!(lines("ab\nc", &layout), vec![("ab", 0, -14), ("c", 0, -2)]);
+    }
+
+    #[test]
+    fn glyph_bounds()
+    {
+        // Glyphs 8 pixels wide and 10 high, 1 pixel after the pen and 8 above the baseline.
+        let glyph = Glyph::new(String::from("font"), Vec::new(), Rect::new(0, 0, 8, 10), 640, 1, 8);
+        let glyphs = |c: char| if c == '?' { None } else { Some(&glyph) };
+        let text = super::layout_lines("ab c?\nd", &TextLayout::default(), (8.0, 2.0, 12.0), |_| 10);
+        assert_eq!(super::glyph_bounds(&text, glyphs), TextBounds{x: 1.0, y: -8.0, width: 38.0, height: 22.0});
+        let text = super::layout_lines(" ?", &TextLayout::default(), (8.0, 2.0, 12.0), |_| 10);
+        assert_eq!(super::glyph_bounds(&text, glyphs), TextBounds::default());
+    }
+
+    #[test]
+    fn wrap_lines()
+    {
+        let layout = TextLayout{max_width: Some(50.0), ..TextLayout::default()};
+        assert_eq!(lines("ab cd ef", &layout), vec![("ab cd", 0, 0), ("ef", 0, 12)]);
+        // The spaces where the lines break are dropped, and the long words are not cut.
+        assert_eq!(lines("ab   abcdefgh c\nd", &layout), vec![("ab", 0, 0), ("abcdefgh", 0, 12), ("c", 0, 24), ("d", 0, 36)]);
+    }
+}
@@ -1,6 +1,12 @@
+use std::cell::RefCell;
 use std::collections::LinkedList;
 use std::collections::HashSet;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::path::Path;
 use properties::markid::MarkId;
+use properties::position::Position;
 use crate::marks::mark::Mark;
 use crate::marks::mark::MarkTy;
 use crate::marks::pointmark::PointMark;
@@ -15,81 +21,327 @@ use crate::marks::textmark::FontCache;
 use crate::marks::textmark::VertexText;
 use crate::marks::textmark::TextMarkCmd;
 use crate::marks::textmark::Glyph;
-use crate::layer::Layer;
+use crate::marks::textmark::{TextLayout, TextMetrics, TextBounds};
+use crate::marks::pointmark::Shape;
+use crate::marks::shapeatlas::{ShapeAtlas, ShapeField};
+use crate::marks::imagemark::{ImageMark, ImageAtlas, ImageTexels, VertexImage};
+use crate::marks::arcmark::{ArcMark, VertexArc};
+use crate::image::Image;
+use crate::layer::{Layer, LayerGuard};
+use crate::animation::{Animation, AnimationId, Timeline};
+use crate::clock::{Clock, RealTimeClock};
+use crate::error::Error;
+use crate::error::Result;
 use crate::MarkMacro;
 
 
+/// Location of a mark in contrast, that is the index of its layer
+/// and its index inside this layer.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) struct MarkLocation {
+    pub(crate) layer_index : usize,
+    pub(crate) mark_index : usize
+}
+
+/// Entry of the indirection table of contrast. A MarkId designates
+/// an entry, and is valid only if its generation is the same as the
+/// generation of the entry.
+/// The generation is incremented when the mark is removed, so the
+/// entry can be reused by another mark without making the old
+/// identifiers designate the new mark.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct MarkSlot {
+    pub(crate) generation : u32,
+    pub(crate) location : Option<MarkLocation>
+}
+
+/// Describes which vertices of a type of mark must be refreshed by the renderer.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Update {
+    /// Every vertex must be rebuilt, for instance because marks were added, removed
+    /// or moved to another layer.
+    All,
+    /// Only the vertices of these marks must be rebuilt.
+    Marks(HashSet<MarkId>)
+}
+
+/// Record that the mark represented by 'markid', of type 'ty', was modified.
+pub(crate) fn record_dirty(update : &mut HashMap<MarkTy, Update>, ty : MarkTy, markid : MarkId) {
+    match update.entry(ty).or_insert_with(|| Update::Marks(HashSet::new())) {
+        Update::Marks(markids) => { markids.insert(markid); },
+        Update::All => ()
+    }
+}
+
+/// Mutable reference of a mark returned by Contrast::get_mark_mut.
+/// It can be used as a mutable reference of the mark, and indicates to
+/// contrast that the mark was modified when it goes out of scope.
+pub struct MarkGuard<'a> {
+    mark : &'a mut Mark,
+    update : &'a mut HashMap<MarkTy, Update>
+}
+
+impl<'a> Deref for MarkGuard<'a> {
+    type Target = Mark;
+
+    fn deref(&self) -> &Mark {
+        self.mark
+    }
+}
+
+impl<'a> DerefMut for MarkGuard<'a> {
+    fn deref_mut(&mut self) -> &mut Mark {
+        self.mark
+    }
+}
+
+impl<'a> Drop for MarkGuard<'a> {
+    fn drop(&mut self) {
+        record_dirty(self.update, self.mark.get_type(), self.mark.get_id());
+    }
+}
+
 /// This is the main structure of the library. It contains all the layers
 /// added by the user, as well as the current layer.
 /// The current layer is the layer to which contrast will add marks by
 /// default. It is by default the layer 0, on first plan.
 /// The user can add, get, remove and modify marks as he wishes, as well
 /// as to retrieve the layers to apply some functions on its marks.
+/// Contrast also keeps an indirection table giving the location of
+/// each mark from its identifier, as well as the free entries of
+/// this table to reuse them later.
+/// Finally, contrast records which marks were modified since the last
+/// refresh, and where the vertices of each mark are in the vertices
+/// it last built, so the renderer only has to rebuild the modified ones.
+/// The clock of contrast gives the time to the animations and to the
+/// renderer, see set_clock.
+/// The fonts load their glyphs when a text is measured, so they can be
+/// borrowed mutably while contrast is not, see get_text_bounds.
 pub struct Contrast {
     pub(crate) layers : Vec<Layer>,
     pub(crate) current_layer_index : usize,
-    pub(crate) update: HashSet<MarkTy>,
-    pub(crate) fonts: FontCache
+    pub(crate) slots : Vec<MarkSlot>,
+    pub(crate) free_slots : Vec<usize>,
+    pub(crate) update: HashMap<MarkTy, Update>,
+    pub(crate) ranges: HashMap<MarkId, (usize, usize)>,
+    pub(crate) fonts: RefCell<FontCache>,
+    pub(crate) shapes: ShapeAtlas,
+    pub(crate) images: ImageAtlas,
+    pub(crate) animations: Timeline,
+    pub(crate) clock: Box<dyn Clock>,
+    pub(crate) scale: f32
 }
 
 impl Contrast {
     /// Simply returns a new instance of Contrast, initializing
     /// the vector containing all the layers.
+    /// Panics if FreeType cannot be initialized, see try_new.
     pub fn new() -> Self {
-        Contrast {
+        Contrast::try_new().expect("FreeType initialization failed")
+    }
+
+    /// Same behavior than new but returns an error if FreeType
+    /// cannot be initialized.
+    pub fn try_new() -> Result<Self> {
+        Ok(Contrast {
             layers : Vec::<Layer>::new(),
             current_layer_index : 0,
-            update: HashSet::new(),
-            fonts: FontCache::new()
-        }
+            slots : Vec::<MarkSlot>::new(),
+            free_slots : Vec::<usize>::new(),
+            update: HashMap::new(),
+            ranges: HashMap::new(),
+            fonts: RefCell::new(FontCache::new()?),
+            shapes: ShapeAtlas::default(),
+            images: ImageAtlas::new(),
+            animations: Timeline::new(),
+            clock: Box::new(RealTimeClock::new()),
+            scale: 1.0
+        })
     }
 
     /// Initialize contrast. All this does is add a first layer to Contrast.
     pub fn init(&mut self) {
-        let layer_0 = Layer::new(0, self);
+        let layer_0 = Layer::new(0);
         self.layers.push(layer_0);
     }
 
     /// Register font & police with one key name.
-    pub fn register_font(&mut self, name: &str, font: &str, police: u32)
+    /// Returns an error if the font cannot be loaded.
+    pub fn register_font(&mut self, name: &str, font: &str, police: u32) -> Result<()>
     {
-        self.fonts.create_face(name, font, police);
+        self.fonts.get_mut().create_face(name, font, police)
     }
 
     pub fn contains_font(&self, name: &str) -> bool
     {
-        self.fonts.contains(name)
+        self.fonts.borrow().contains(name)
+    }
+
+    /// Measures 'text' written with the font registered as 'font', its lines being only
+    /// broken at its line breaks, from the metrics of the glyphs.
+    /// Returns an error if the font is not registered or if the glyphs cannot be loaded.
+    pub fn measure_text(&self, font: &str, text: &str) -> Result<TextMetrics>
+    {
+        let mut fonts = self.fonts.borrow_mut();
+        let face = fonts.get_face(font).ok_or_else(|| Error::UnknownFont(font.to_string()))?;
+        face.prepare_string(text)?;
+        Ok(face.measure(text, &TextLayout::default()))
+    }
+
+    /// Returns the box on the screen of the glyphs of the text mark represented by 'markid', in pixels
+    /// from the top left corner, to place other marks around it. The box is the one displayed at the
+    /// current time, with the layout of the text and zoomed by the scale of the camera (see set_scale).
+    /// Returns an error if 'markid' does not designate a text mark, if its font is not
+    /// registered or if its glyphs cannot be loaded.
+    pub fn get_text_bounds(&self, markid: &MarkId) -> Result<TextBounds>
+    {
+        let text = match self.get_mark(markid) {
+            Some(Mark::Text(text)) => text,
+            _ => return Err(Error::UnknownMark(*markid))
+        };
+        let mut fonts = self.fonts.borrow_mut();
+        let face = fonts.get_face(text.get_font()).ok_or_else(|| Error::UnknownFont(text.get_font().clone()))?;
+        face.prepare_string(text.get_text())?;
+        Ok(text.get_bounds(face, self.get_time(), self.scale))
+    }
+
+    /// Register a custom shape of point marks from SVG path data, filled with the nonzero rule.
+    /// The path is scaled to fit the square where the shapes are drawn, keeping its proportions,
+    /// with the y axis going down. The shape is drawn from its signed distance field, computed once
+    /// and stored in a texture atlas, and the exports keep its path.
+    /// Returns the shape to give to PointMark::set_shape, or an error if the path has no area
+    /// or if MAX_CUSTOM_SHAPES shapes are already registered.
+    pub fn register_shape_path(&mut self, data: &str) -> Result<Shape>
+    {
+        self.shapes.add_path(data)
+    }
+
+    /// Same behavior than register_shape_path, with the points of a polygon as outline.
+    pub fn register_shape_outline(&mut self, points: &[Position]) -> Result<Shape>
+    {
+        self.shapes.add_outline(points)
+    }
+
+    /// Register a custom shape of point marks from its signed distance function, giving the
+    /// distance to the outline of the shape in the square from (-1, -1) to (1, 1) where the
+    /// shapes are drawn, negative inside the shape. The exports use the outline traced in its field.
+    /// Returns an error if MAX_CUSTOM_SHAPES shapes are already registered.
+    pub fn register_shape_sdf<F: Fn(f32, f32) -> f32>(&mut self, distance: F) -> Result<Shape>
+    {
+        self.shapes.add_function(distance)
+    }
+
+    /// Returns the signed distance fields of the custom shapes registered since the last call,
+    /// for the renderer to upload them in its texture atlas of the shapes.
+    pub fn get_new_shapes(&mut self) -> Vec<ShapeField>
+    {
+        self.shapes.take_pending()
+    }
+
+    /// Register an image with one key name, packing it in the texture atlas shared by the images.
+    /// Registering another image with the same name replaces it in the image marks, and its place
+    /// in the atlas is reused by the new image if it fits, or by the next images otherwise.
+    /// Returns an error if there is no room left for the image in the atlas.
+    pub fn register_image(&mut self, name: &str, image: Image) -> Result<()>
+    {
+        self.images.add(name, image)?;
+        self.mark_type_dirty(MarkTy::Image);
+        Ok(())
     }
 
-    /// Append one dirty mark container.
-    pub fn mark_dirty(&mut self, id: MarkId)
+    /// Same behavior than register_image, with the image loaded from the PNG file at 'path'.
+    pub fn register_image_png<P: AsRef<Path>>(&mut self, name: &str, path: P) -> Result<()>
     {
-        match self.get_mark(&id)
+        self.register_image(name, Image::load_png(path)?)
+    }
+
+    pub fn contains_image(&self, name: &str) -> bool
+    {
+        self.images.contains(name)
+    }
+
+    /// Returns the pixels of the images registered since the last call,
+    /// for the renderer to upload them in its texture atlas of the images.
+    pub fn get_new_images(&mut self) -> Vec<ImageTexels>
+    {
+        self.images.take_pending()
+    }
+
+    /// Replace the clock of contrast, which follows the real time by default.
+    /// The animations already started keep their start time, so the new clock
+    /// should usually start where the previous one is.
+    /// Example : set_clock(ManualClock::fixed_step(1.0 / 60.0)) to render each frame
+    /// at an exact time, or give a Rc<RefCell<ScaledClock<RealTimeClock>>> and keep
+    /// a copy of it to pause and resume the animations.
+    pub fn set_clock<C : Clock + 'static>(&mut self, clock : C)
+    {
+        self.clock = Box::new(clock);
+    }
+
+    /// Returns the current time of the clock of contrast, in seconds.
+    pub fn get_time(&self) -> f32
+    {
+        self.clock.now()
+    }
+
+    /// Let the clock know that a new frame begins. Called by the renderer at each frame.
+    pub fn tick(&mut self)
+    {
+        self.clock.tick();
+    }
+
+    /// Indicate that the mark represented by 'id' was modified, so only
+    /// its vertices will be rebuilt at the next refresh.
+    /// Marks are already marked dirty when they are added, removed, modified
+    /// through get_mark_mut or through their layer, so this is only needed
+    /// to force a refresh.
+    /// Returns an error if 'id' does not designate any mark.
+    pub fn mark_dirty(&mut self, id: MarkId) -> Result<()>
+    {
+        let ty = match self.get_mark(&id)
         {
-            Some(m) => {
-                match m
-                {
-                    Mark::Point(_) => self.update.insert(MarkTy::Point),
-                    Mark::Line(_) => self.update.insert(MarkTy::Line),
-                    Mark::Text(_) => self.update.insert(MarkTy::Text),
-                    Mark::Polygon(_) => self.update.insert(MarkTy::Polygon),
-                }
-            },
-            None => panic!("Invalid MarkId")
+            Some(m) => m.get_type(),
+            None => return Err(Error::UnknownMark(id))
         };
+
+        record_dirty(&mut self.update, ty, id);
+        Ok(())
+    }
+
+    /// Indicate that every vertex of the type 'ty' must be rebuilt at the next refresh.
+    pub(crate) fn mark_type_dirty(&mut self, ty : MarkTy)
+    {
+        self.update.insert(ty, Update::All);
     }
 
     // Append all dirty mark containers.
     pub fn mark_dirty_all(&mut self)
     {
-        for mark in MarkTy::values() { self.update.insert(mark.clone()); }
+        for mark in MarkTy::values() { self.mark_type_dirty(mark.clone()); }
+    }
+
+    /// Set the scale of the camera, in pixels per unit of the marks, see Camera::set_scale.
+    /// The curves of the line marks are drawn with more sub-lines when the camera zooms in,
+    /// so every line mark is rebuilt when the scale changes.
+    pub fn set_scale(&mut self, scale : f32)
+    {
+        if scale > 0.0 && scale != self.scale
+        {
+            self.scale = scale;
+            self.mark_type_dirty(MarkTy::Line);
+        }
     }
 
-    /// Fetch updated mark and reset.
-    pub fn fetch_update(&mut self) -> HashSet<MarkTy>
+    pub fn get_scale(&self) -> f32
     {
-        let update = self.update.clone();
-        self.update.clear();
+        self.scale
+    }
+
+    /// Fetch what must be refreshed for each type of mark and reset.
+    pub fn fetch_update(&mut self) -> HashMap<MarkTy, Update>
+    {
+        let mut update = HashMap::new();
+        std::mem::swap(&mut update, &mut self.update);
         update
     }
 
@@ -100,9 +352,8 @@ impl Contrast {
     /// similar to this : add_point_mark.set_rotation(90.0).
     pub fn add_point_mark(&mut self) -> &mut PointMark {
         let point = Mark::Point(PointMark::new());
-        self.layers.get_mut(self.current_layer_index).unwrap().force_add_mark(point);
 
-        match self.layers.get_mut(self.current_layer_index).unwrap().get_last_mark_mut() {
+        match self.add_mark(point) {
             Mark::Point(p) => p,
             _ => panic!("A problem occured when adding a new point mark!")
         }
@@ -111,9 +362,8 @@ impl Contrast {
     /// Same behavior than add_point_mark but it adds a mark of type "Line".
     pub fn add_line_mark(&mut self) -> &mut LineMark {
         let line = Mark::Line(LineMark::new());
-        self.layers.get_mut(self.current_layer_index).unwrap().force_add_mark(line);
 
-        match self.layers.get_mut(self.current_layer_index).unwrap().get_last_mark_mut() {
+        match self.add_mark(line) {
             Mark::Line(l) => l,
             _ => panic!("A problem occured when adding a new line mark!")
         }
@@ -123,9 +373,8 @@ impl Contrast {
     pub fn add_text_mark(&mut self) -> &mut TextMark
     {
         let text = Mark::Text(TextMark::new());
-        self.layers.get_mut(self.current_layer_index).unwrap().force_add_mark(text);
 
-        match self.layers.get_mut(self.current_layer_index).unwrap().get_last_mark_mut() {
+        match self.add_mark(text) {
             Mark::Text(t) => t,
             _ => panic!("A problem occured when adding a new text mark!")
         }
@@ -134,37 +383,149 @@ impl Contrast {
     /// Same behavior than add_point_mark but it adds a mark of type "Polygon".
     pub fn add_polygon_mark(&mut self) -> &mut PolygonMark {
         let polygon = Mark::Polygon(PolygonMark::new());
-        self.layers.get_mut(self.current_layer_index).unwrap().force_add_mark(polygon);
 
-        match self.layers.get_mut(self.current_layer_index).unwrap().get_last_mark_mut() {
+        match self.add_mark(polygon) {
             Mark::Polygon(poly) => poly,
             _ => panic!("A problem occured when adding a new polygon mark!")
         }
     }
 
-    /// Returns a reference wrapped into an Option of the mark at the index "id".
-    /// If there is no mark having this id, returns None.
-    pub fn get_mark(&mut self, markid : &MarkId) -> Option<&Mark> {
-        if markid.valid {
-            return self.layers.get(markid.layer_index).unwrap().get_mark(markid);
+    /// Same behavior than add_point_mark but it adds a mark of type "Image".
+    pub fn add_image_mark(&mut self) -> &mut ImageMark {
+        let image = Mark::Image(ImageMark::new());
+
+        match self.add_mark(image) {
+            Mark::Image(i) => i,
+            _ => panic!("A problem occured when adding a new image mark!")
         }
-        None
     }
 
-    /// Returns a mutable reference wrapped into an Option of the mark represented by 'markid'.
-    /// If there is no mark having this id, or if this mark is invalid, returns None.
-    pub fn get_mark_mut(&mut self, markid : &MarkId) -> Option<&mut Mark> {
-        if markid.valid {
-            return self.layers.get_mut(markid.layer_index).unwrap().get_mark_mut(markid);
+    /// Same behavior than add_point_mark but it adds a mark of type "Arc".
+    pub fn add_arc_mark(&mut self) -> &mut ArcMark {
+        let arc = Mark::Arc(ArcMark::new());
+
+        match self.add_mark(arc) {
+            Mark::Arc(a) => a,
+            _ => panic!("A problem occured when adding a new arc mark!")
+        }
+    }
+
+    /// Give a new identifier to the mark, add it into the current layer
+    /// and returns a mutable reference of it.
+    /// The current layer is created if it does not exist yet.
+    fn add_mark(&mut self, mut mark : Mark) -> &mut Mark {
+        if self.current_layer_index >= self.layers.len() {
+            self.set_current_layer(self.current_layer_index);
+        }
+
+        let markid = self.new_markid();
+        mark.set_id(markid);
+        self.mark_type_dirty(mark.get_type());
+
+        let layer_index = self.current_layer_index;
+        let mark_index = self.layers.get_mut(layer_index).unwrap().force_add_mark(mark);
+        self.relocate(&markid, MarkLocation { layer_index, mark_index });
+
+        self.layers.get_mut(layer_index).unwrap().get_mark_mut(mark_index).unwrap()
+    }
+
+    /// Returns a new identifier, reusing a free entry of the indirection
+    /// table if there is one.
+    fn new_markid(&mut self) -> MarkId {
+        match self.free_slots.pop() {
+            Some(slot) => MarkId::new(slot, self.slots[slot].generation),
+            None => {
+                self.slots.push(MarkSlot { generation : 1, location : None });
+                MarkId::new(self.slots.len() - 1, 1)
+            }
         }
-        None
+    }
+
+    /// Returns the location of the mark represented by 'markid', or None
+    /// if this identifier does not designate any mark anymore.
+    pub(crate) fn locate(&self, markid : &MarkId) -> Option<MarkLocation> {
+        match self.slots.get(markid.slot()) {
+            Some(slot) if slot.generation == markid.generation() => slot.location,
+            _ => None
+        }
+    }
+
+    /// Update the location of the mark represented by 'markid'.
+    pub(crate) fn relocate(&mut self, markid : &MarkId, location : MarkLocation) {
+        if let Some(slot) = self.slots.get_mut(markid.slot()) {
+            if slot.generation == markid.generation() {
+                slot.location = Some(location);
+            }
+        }
+    }
+
+    /// Returns a reference wrapped into an Option of the mark represented by 'markid'.
+    /// If there is no mark having this id, or if this mark was removed, returns None.
+    pub fn get_mark(&self, markid : &MarkId) -> Option<&Mark> {
+        let location = self.locate(markid)?;
+        self.layers.get(location.layer_index)?.get_mark(location.mark_index)
+    }
+
+    /// Returns a mutable reference wrapped into an Option of the mark represented by 'markid'.
+    /// If there is no mark having this id, or if this mark was removed, returns None.
+    /// Once the returned guard goes out of scope, the mark is considered modified
+    /// and its vertices will be rebuilt at the next refresh.
+    pub fn get_mark_mut(&mut self, markid : &MarkId) -> Option<MarkGuard<'_>> {
+        let location = self.locate(markid)?;
+        let time = self.get_time();
+        let mark = self.layers.get_mut(location.layer_index)?.get_mark_mut(location.mark_index)?;
+        mark.set_time(time);
+        Some(MarkGuard { mark, update : &mut self.update })
     }
 
     /// Remove the mark with the id mark. This does not actually removes the mark from the container
     /// but it asks the layer to invalidate the mark, implying this mark won't be displayed and the
     /// user won't be allowed to retrieve it.
-    pub fn remove_mark(&mut self, markid : &mut MarkId) {
-        self.layers.get_mut(markid.layer_index).unwrap().invalidate_mark(markid);
+    /// Every copy of 'markid' becomes invalid, even once its entry is reused by another mark.
+    /// Returns an error if 'markid' does not designate any mark.
+    pub fn remove_mark(&mut self, markid : &MarkId) -> Result<()> {
+        let location = self.locate(markid).ok_or(Error::UnknownMark(*markid))?;
+        let layer = self.layers.get_mut(location.layer_index).ok_or(Error::UnknownLayer(location.layer_index))?;
+        let ty = layer.get_mark(location.mark_index).unwrap().get_type();
+        layer.invalidate_mark(location.mark_index);
+        self.mark_type_dirty(ty);
+        self.ranges.remove(markid);
+
+        let slot = &mut self.slots[markid.slot()];
+        slot.generation = slot.generation.wrapping_add(1).max(1);
+        slot.location = None;
+        self.free_slots.push(markid.slot());
+        Ok(())
+    }
+
+    /// Plays 'animation' on the marks represented by 'markids', from now.
+    /// With a stagger, the animation starts later on each mark, in the order of 'markids'.
+    /// The returned identifier is given back by update_animations once the animation is finished.
+    pub fn play(&mut self, animation : Animation, markids : &[MarkId]) -> AnimationId {
+        self.animations.play(animation, markids, self.get_time())
+    }
+
+    /// Stops an animation. The transitions already started are not interrupted.
+    /// Returns false if the animation is not played, for instance because it is finished.
+    pub fn stop(&mut self, animation : AnimationId) -> bool {
+        self.animations.stop(animation)
+    }
+
+    /// Returns true if the animation is played.
+    pub fn is_playing(&self, animation : AnimationId) -> bool {
+        self.animations.is_playing(animation)
+    }
+
+    /// Starts the transitions of the animations reaching a keyframe, and returns the
+    /// animations finished since the last call. Called by the renderer at each frame.
+    pub fn update_animations(&mut self) -> Vec<AnimationId> {
+        let (actions, finished) = self.animations.update(self.get_time());
+        for action in actions {
+            if let Some(mut mark) = self.get_mark_mut(&action.markid) {
+                action.value.apply(&mut mark, action.duration, action.easing);
+            }
+        }
+        finished
     }
 
     /// Set the current layer. The current layer is the layer where contrast will push
@@ -181,7 +542,7 @@ impl Contrast {
     /// Add new layers into contrast.
     pub fn add_layers(&mut self, nb : usize) {
         for _ in 0..nb {
-            let new_layer = Layer::new(self.layers.len(), self);
+            let new_layer = Layer::new(self.layers.len());
             self.layers.push(new_layer);
         }
     }
@@ -193,9 +554,10 @@ impl Contrast {
     }
 
     /// Returns a mutable reference wrapped into an Option of the Layer
-    /// at the index 'layer_index'.
-    pub fn get_layer_mut(&mut self, layer_index : usize) -> Option<&mut Layer> {
-        self.layers.get_mut(layer_index)
+    /// at the index 'layer_index'. The guard also moves marks into the layer
+    /// and modifies all its marks, see LayerGuard.
+    pub fn get_layer_mut(&mut self, layer_index : usize) -> Option<LayerGuard<'_>> {
+        if layer_index < self.layers.len() { Some(LayerGuard::new(self, layer_index)) } else { None }
     }
 
     /// Convert the MarkPoints contained in the main vector into a vector
@@ -208,6 +570,7 @@ impl Contrast {
                 if let Mark::Point(ref mut p) = mark {
                     p.set_displayed(true);
                     if p.is_valid() {
+                        self.ranges.insert(p.get_id(), (properties.len(), properties.len() + 1));
                         properties.push(p.to_vertex());
                     }
                 }
@@ -221,11 +584,34 @@ impl Contrast {
     pub fn get_linemarks_properties(&mut self) -> Vec<VertexSubLine> {
         self.layers.sort();
         let mut properties : Vec<VertexSubLine> = Vec::<VertexSubLine>::new();
-        for layer in &self.layers {
-            for mark in &layer.marks {
-                if let Mark::Line(l) = mark {
+        for layer in &mut self.layers {
+            for mark in &mut layer.marks {
+                if let Mark::Line(ref mut l) = mark {
+                    l.set_displayed(true);
+                    l.set_scale(self.scale);
                     if l.is_valid() {
+                        let start = properties.len();
                         properties.append(&mut l.to_subline());
+                        self.ranges.insert(l.get_id(), (start, properties.len()));
+                    }
+                }
+            }
+        }
+        properties
+    }
+
+    /// Returns the markers of the LineMarks contained in the main vector, as point
+    /// vertices drawn by the renderer with the point marks. They must be fetched
+    /// again each time the line marks are updated.
+    pub fn get_linemarks_markers(&mut self) -> Vec<VertexPoint> {
+        self.layers.sort();
+        let mut properties : Vec<VertexPoint> = Vec::<VertexPoint>::new();
+        for layer in &mut self.layers {
+            for mark in &mut layer.marks {
+                if let Mark::Line(ref mut l) = mark {
+                    l.set_scale(self.scale);
+                    if l.is_valid() {
+                        properties.append(&mut l.to_markers());
                     }
                 }
             }
@@ -238,11 +624,34 @@ impl Contrast {
     pub fn get_polygonmarks_properties(&mut self) -> Vec<VertexPolygon> {
         self.layers.sort();
         let mut properties : Vec<VertexPolygon> = Vec::<VertexPolygon>::new();
-        for layer in &self.layers {
-            for mark in &layer.marks {
-                if let Mark::Polygon(poly) = mark {
+        for layer in &mut self.layers {
+            for mark in &mut layer.marks {
+                if let Mark::Polygon(ref mut poly) = mark {
+                    poly.set_displayed(true);
                     if poly.is_valid() {
+                        let start = properties.len();
                         properties.append(&mut poly.as_vertex());
+                        self.ranges.insert(poly.get_id(), (start, properties.len()));
+                    }
+                }
+            }
+        }
+        properties
+    }
+
+    /// Convert the ImageMarks contained in the main vector into a vector of vertices
+    /// understandable by the renderer, then returns it. The marks whose image is not
+    /// registered are not drawn.
+    pub fn get_imagemarks_properties(&mut self) -> Vec<VertexImage> {
+        self.layers.sort();
+        let mut properties : Vec<VertexImage> = Vec::<VertexImage>::new();
+        for layer in &mut self.layers {
+            for mark in &mut layer.marks {
+                if let Mark::Image(ref mut i) = mark {
+                    i.set_displayed(true);
+                    if let (true, Some(coordinates)) = (i.is_valid(), self.images.coordinates(i.get_image())) {
+                        self.ranges.insert(i.get_id(), (properties.len(), properties.len() + 1));
+                        properties.push(i.to_vertex(coordinates));
                     }
                 }
             }
@@ -250,22 +659,114 @@ impl Contrast {
         properties
     }
 
+    /// Convert the ArcMarks contained in the main vector into a vector of vertices
+    /// understandable by the renderer, then returns it.
+    pub fn get_arcmarks_properties(&mut self) -> Vec<VertexArc> {
+        self.layers.sort();
+        let mut properties : Vec<VertexArc> = Vec::<VertexArc>::new();
+        for layer in &mut self.layers {
+            for mark in &mut layer.marks {
+                if let Mark::Arc(ref mut a) = mark {
+                    a.set_displayed(true);
+                    if a.is_valid() {
+                        self.ranges.insert(a.get_id(), (properties.len(), properties.len() + 1));
+                        properties.push(a.to_vertex());
+                    }
+                }
+            }
+        }
+        properties
+    }
+
+    /// Returns the vertices of the point marks represented by 'markids', each one with
+    /// the index of its first vertex in the vertices built by the last call to
+    /// get_pointmarks_properties. Returns None if every vertex must be rebuilt instead.
+    pub fn get_pointmarks_sub_properties(&mut self, markids : &HashSet<MarkId>) -> Option<Vec<(usize, Vec<VertexPoint>)>> {
+        self.get_sub_properties(markids, |mark| {
+            let p = mark.as_point_mark_mut()?;
+            p.set_displayed(true);
+            Some(vec!(p.to_vertex()))
+        })
+    }
+
+    /// Same behavior than get_pointmarks_sub_properties but for the line marks.
+    pub fn get_linemarks_sub_properties(&mut self, markids : &HashSet<MarkId>) -> Option<Vec<(usize, Vec<VertexSubLine>)>> {
+        let scale = self.scale;
+        self.get_sub_properties(markids, |mark| {
+            let l = mark.as_line_mark_mut()?;
+            l.set_displayed(true);
+            l.set_scale(scale);
+            Some(l.to_subline())
+        })
+    }
+
+    /// Same behavior than get_pointmarks_sub_properties but for the polygon marks.
+    pub fn get_polygonmarks_sub_properties(&mut self, markids : &HashSet<MarkId>) -> Option<Vec<(usize, Vec<VertexPolygon>)>> {
+        self.get_sub_properties(markids, |mark| {
+            let poly = mark.as_polygon_mark_mut()?;
+            poly.set_displayed(true);
+            Some(poly.as_vertex())
+        })
+    }
+
+    /// Same behavior than get_pointmarks_sub_properties but for the image marks.
+    pub fn get_imagemarks_sub_properties(&mut self, markids : &HashSet<MarkId>) -> Option<Vec<(usize, Vec<VertexImage>)>> {
+        let coordinates = self.images.all_coordinates();
+        self.get_sub_properties(markids, |mark| {
+            let i = mark.as_image_mark_mut()?;
+            i.set_displayed(true);
+            Some(vec!(i.to_vertex(*coordinates.get(i.get_image())?)))
+        })
+    }
+
+    /// Same behavior than get_pointmarks_sub_properties but for the arc marks.
+    pub fn get_arcmarks_sub_properties(&mut self, markids : &HashSet<MarkId>) -> Option<Vec<(usize, Vec<VertexArc>)>> {
+        self.get_sub_properties(markids, |mark| {
+            let a = mark.as_arc_mark_mut()?;
+            a.set_displayed(true);
+            Some(vec!(a.to_vertex()))
+        })
+    }
+
+    /// Convert each mark represented by 'markids' with 'convert'. Returns None if
+    /// a mark has no vertices yet, or if its number of vertices changed, meaning
+    /// every vertex must be rebuilt.
+    fn get_sub_properties<V, F>(&mut self, markids : &HashSet<MarkId>, convert : F) -> Option<Vec<(usize, Vec<V>)>>
+        where F : Fn(&mut Mark) -> Option<Vec<V>>
+    {
+        let mut properties = Vec::new();
+        for markid in markids {
+            let (start, end) = *self.ranges.get(markid)?;
+            let location = self.locate(markid)?;
+            let mark = self.layers.get_mut(location.layer_index)?.get_mark_mut(location.mark_index)?;
+            let vertices = convert(mark)?;
+            if vertices.len() != end - start {
+                return None;
+            }
+            properties.push((start, vertices));
+        }
+        Some(properties)
+    }
+
     /// Convert the MarkTexts contained in the main vector into a vector of a lot of things...
-    pub fn get_textmarks_properties(&mut self) -> (Vec<VertexText>,LinkedList<TextMarkCmd>,LinkedList<Glyph>) {
+    /// Returns an error if the glyphs of a text cannot be loaded.
+    pub fn get_textmarks_properties(&mut self) -> Result<(Vec<VertexText>,LinkedList<TextMarkCmd>,LinkedList<Glyph>)> {
         let mut chars = LinkedList::new();
         let mut commands = LinkedList::new();
         let mut properties = Vec::new();
         let mut cur: usize = 0;
-        for layer in &self.layers {
-            for mark in layer.get_all_marks() {
-                if let Mark::Text(t) = mark {
-                    if t.is_valid() && self.contains_font(t.get_font())
+        let fonts = self.fonts.get_mut();
+        for layer in &mut self.layers {
+            for mark in &mut layer.marks {
+                if let Mark::Text(ref mut t) = mark {
+                    t.set_displayed(true);
+                    if t.is_valid() && fonts.contains(t.get_font())
                     {
-                        let face = self.fonts.get_face(t.get_font()).unwrap();
-                        face.prepare_string(t.get_text());
-                        let vtx = face.drawing_commands(t.get_x(), t.get_y(), t.get_z(), t.get_text());
-                        let color = mark.get_color().clone();
-                        commands.push_front(TextMarkCmd::new(t.get_font(), color, cur, vtx.len()));
+                        let face = fonts.get_face(t.get_font()).unwrap();
+                        face.prepare_string(t.get_text())?;
+                        // The glyphs are built at the origin, the shaders move them to the animated position.
+                        let vtx = t.to_vertices(face.drawing_commands(0, 0, 0.0, t.get_text(), t.get_layout())?);
+                        commands.push_front(TextMarkCmd::new(t.get_font(), t.color.clone(), cur, vtx.len()));
                         chars.extend(face.get_writable());
                         cur+= vtx.len();
                         properties.extend(vtx);
@@ -273,13 +774,7 @@ impl Contrast {
                 }
             }
         }
-        (properties,commands,chars)
-    }
-
-    /// Useful only for the tests.
-    #[allow(dead_code)]
-    pub(crate) fn get_pointer(&mut self) -> *mut Contrast {
-        self as *mut Contrast
+        Ok((properties,commands,chars))
     }
 
 }
@@ -291,15 +786,26 @@ mod tests {
     use properties::color::Color;
     use properties::size::Size;
     use std::collections::BinaryHeap;
+    use crate::marks::textmark::TextAnchor;
     use crate::marks::pointmark::*;
     use crate::marks::linemark::*;
     use crate::MarkMacro;
+    use crate::easing::Easing;
+    use crate::animation::{Track, Value};
+    use crate::clock::ManualClock;
+    use crate::triangulation::FillRule;
+    use crate::curve::Curve;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use crate::marks::animationattribute::AnimationAttribute;
+    use crate::marks::polygonmark::{PolygonMark, Gradient};
+    use crate::marks::textmark::TextMark;
 
     fn vertex_point_is_equal(v1 : VertexPoint, v2 : VertexPoint) -> bool
     {
         if v1.0 == v2.0 && v1.1 == v2.1 && v1.2 == v2.2 && v1.3 == v2.3 && v1.4 == v2.4 && v1.5 == v2.5
             && v1.6 == v2.6 && v1.7 == v2.7 && v1.8 == v2.8 && v1.9 == v2.9 && v1.10 == v2.10 && v1.11 == v2.11
-            && v1.12 == v2.12 && v1.13 == v2.13 && v1.14 == v2.14 {
+            && v1.12 == v2.12 && v1.13 == v2.13 && v1.14 == v2.14 && v1.15 == v2.15 {
             return true;
         }
         false
@@ -348,19 +854,19 @@ mod tests {
         let mut c = Contrast::new();
         c.init();
 
-        let mut m1 = c.add_point_mark().get_id();
-        let mut m2 = c.add_point_mark().get_id();
+        let m1 = c.add_point_mark().get_id();
+        let m2 = c.add_point_mark().get_id();
 
-        c.remove_mark(&mut m1);
+        c.remove_mark(&m1).unwrap();
 
         assert_eq!(c.get_pointmarks_properties().len(), 1);
 
         let mut _m3 = c.add_point_mark().get_id();
-        let mut m4 = c.add_point_mark().get_id();
+        let m4 = c.add_point_mark().get_id();
         let mut _m5 = c.add_point_mark().get_id();
 
-        c.remove_mark(&mut m2);
-        c.remove_mark(&mut m4);
+        c.remove_mark(&m2).unwrap();
+        c.remove_mark(&m4).unwrap();
 
         assert_eq!(c.get_pointmarks_properties().len(), 2);
     }
@@ -378,12 +884,33 @@ mod tests {
 
         let marks_properties = c.get_pointmarks_properties();
 
-        assert!(vertex_point_is_equal(marks_properties[0], ([0.0, 0.0, 0.0], [1.0, 5.0, 9.0], -10.0, [0.0, 0.0], [0.0, 0.0],
-            0.0, [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], 0.0, 0.0, 0.0, 0.0, 0, 0, 0.0)));
-        assert!(vertex_point_is_equal(marks_properties[1], ([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], 0.0, [0.0, 0.0], [0.0, 0.0],
-            0.0, [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], 0.0, 0.0, 0.0, 0.0, 0, 1, -10.0)));
-        assert!(vertex_point_is_equal(marks_properties[2], ([0.0, 0.0, 0.0], [3.6, 5.0, 9.2], -10.0, [0.0, 0.0], [0.5, 0.3],
-            -10.0, [0.0, 0.0, 0.0, 0.0], [1.0, 0.0, 0.5, 1.0], -10.0, 0.0, 90.0, -10.0, 0, 2, -10.0)));
+        assert!(vertex_point_is_equal(marks_properties[0], ([1.0, 5.0, 9.0], [1.0, 5.0, 9.0], [0.0, 1.0, 0.0], [0.0, 0.0], [0.0, 0.0],
+            [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0], [0, 0, 0, 0], [0, 1], [0.0, 0.0], [0.0, 1.0, 0.0])));
+        assert!(vertex_point_is_equal(marks_properties[1], ([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0], [0.0, 0.0],
+            [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0], [1, 1, 0, 0], [0, 1], [0.0, 0.0], [0.0, 1.0, 0.0])));
+        assert!(vertex_point_is_equal(marks_properties[2], ([3.6, 5.0, 9.2], [3.6, 5.0, 9.2], [0.0, 1.0, 0.0], [0.5, 0.3], [0.5, 0.3],
+            [0.0, 1.0, 0.0], [1.0, 0.0, 0.5, 1.0], [1.0, 0.0, 0.5, 1.0], [0.0, 1.0, 0.0], [90.0, 90.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0], [2, 2, 0, 0], [0, 1], [0.0, 0.0], [0.0, 1.0, 0.0])));
+    }
+
+    #[test]
+    fn set_scale()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((50.0, 100.0, 0.0)).add_point((100.0, 0.0, 0.0))
+            .set_curve(Curve::CatmullRom);
+        let sublines = c.get_linemarks_properties().len();
+        c.fetch_update();
+
+        c.set_scale(3.0);
+        assert_eq!(c.get_scale(), 3.0);
+        assert_eq!(c.fetch_update().get(&MarkTy::Line), Some(&Update::All));
+        assert!(c.get_linemarks_properties().len() > sublines);
+        c.set_scale(3.0);
+        assert!(c.fetch_update().is_empty());
     }
 
     #[test]
@@ -411,10 +938,10 @@ mod tests {
         let mut c = Contrast::new();
         c.init();
 
-        let mut m1 = c.add_line_mark().get_id();
+        let m1 = c.add_line_mark().get_id();
 
         assert_eq!(c.get_linemarks_properties().len(), 0);
-        c.remove_mark(&mut m1);
+        c.remove_mark(&m1).unwrap();
 
         let pos1 = Position { x : 200.0, y : 200.0, z : 0.0 };
         assert_eq!(c.get_linemarks_properties().len(), 0);
@@ -424,13 +951,13 @@ mod tests {
             .add_point((pos1.x + 70.0, pos1.y + 100.0, pos1.z))
             .add_point((pos1.x, pos1.y + 30.0, pos1.z))
             .get_id();
-        let mut m3 = c.add_line_mark()
+        let m3 = c.add_line_mark()
                     .add_point((pos1.x + 100.0, pos1.y, pos1.z))
                     .add_point((pos1.x + 70.0, pos1.y + 100.0, pos1.z))
                     .add_point((pos1.x, pos1.y + 30.0, pos1.z))
                     .get_id();
 
-        c.remove_mark(&mut m3);
+        c.remove_mark(&m3).unwrap();
 
         c.add_line_mark().get_id();
         c.add_line_mark().get_id();
@@ -463,10 +990,10 @@ mod tests {
         let mut c = Contrast::new();
         c.init();
 
-        let mut m1 = c.add_polygon_mark().get_id();
+        let m1 = c.add_polygon_mark().get_id();
 
         assert_eq!(c.get_polygonmarks_properties().len(), 0);
-        c.remove_mark(&mut m1);
+        c.remove_mark(&m1).unwrap();
 
         let pos1 = Position { x : 200.0, y : 200.0, z : 0.0 };
         assert_eq!(c.get_polygonmarks_properties().len(), 0);
@@ -477,13 +1004,13 @@ mod tests {
             .add_point((pos1.x, pos1.y + 30.0, pos1.z))
             .get_id();
 
-        let mut m3 = c.add_polygon_mark()
+        let m3 = c.add_polygon_mark()
                     .add_point((pos1.x + 100.0, pos1.y, pos1.z))
                     .add_point((pos1.x + 70.0, pos1.y + 100.0, pos1.z))
                     .add_point((pos1.x, pos1.y + 30.0, pos1.z))
                     .get_id();
 
-        c.remove_mark(&mut m3);
+        c.remove_mark(&m3).unwrap();
 
         c.add_polygon_mark().get_id();
         c.add_polygon_mark().get_id();
@@ -498,7 +1025,7 @@ mod tests {
         c.init();
         c.add_layers(1);
 
-        let mut m1 = c.add_point_mark().get_id();
+        let m1 = c.add_point_mark().get_id();
         let m2 = c.add_point_mark().get_id();
 
         assert_eq!(m1, c.get_layer(0).unwrap().marks.get(0).unwrap().get_id());
@@ -512,7 +1039,7 @@ mod tests {
         let m3 = c.add_point_mark().get_id();
         assert_eq!(m3, c.get_layer(1).unwrap().marks.get(0).unwrap().get_id());
 
-        c.get_layer_mut(1).unwrap().add_mark(&mut m1);
+        c.get_layer_mut(1).unwrap().add_mark(&m1).unwrap();
 
         assert!(!c.get_layer(0).unwrap().invalid_indexes.is_empty());
         assert!(c.get_layer(1).unwrap().invalid_indexes.is_empty());
@@ -551,22 +1078,19 @@ mod tests {
         let expected_layer_0 = Layer {
             marks : vec!(Mark::Polygon(PolygonMark::new())),
             depth : 0,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
          let expected_layer_1 = Layer {
             marks : Vec::<Mark>::new(),
             depth : 1,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
         let expected_layer_2 = Layer {
             marks : vec!(Mark::Point(PointMark::new()), Mark::Line(LineMark::new())),
             depth : 2,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
         assert_eq!(*c.get_layer(0).unwrap(), expected_layer_0);
@@ -590,22 +1114,19 @@ mod tests {
         let expected_layer_0 = Layer {
             marks : vec!(Mark::Polygon(PolygonMark::new())),
             depth : 0,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
          let expected_layer_1 = Layer {
             marks : Vec::<Mark>::new(),
             depth : 1,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
         let expected_layer_2 = Layer {
             marks : vec!(Mark::Point(PointMark::new()), Mark::Line(LineMark::new())),
             depth : 2,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
         assert_eq!(*c.get_layer_mut(0).unwrap(), expected_layer_0);
@@ -642,57 +1163,176 @@ mod tests {
                     .get_id();
 
         let expected_m1 = PolygonMark {
-            markid : MarkId { mark_index : 0, layer_index : 0, valid : true },
-            color : Color::red(),
-            rotation : 0.0,
-            points : vec!(Position { x : 1.0, y : 2.0, z : 3.0 }, Position { x : -10.3, y : 25.7, z : 3.9 }),
-            stroke_width : 3.0,
-            fill : false
+            markid : MarkId::new(0, 1),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute {
+                old_value : Color::red(),
+                target_value : Color::red(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            rotation : AnimationAttribute {
+                old_value : 0.0,
+                target_value : 0.0,
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            points : AnimationAttribute {
+                old_value : vec!(Position { x : 1.0, y : 2.0, z : 3.0 }, Position { x : -10.3, y : 25.7, z : 3.9 }),
+                target_value : vec!(Position { x : 1.0, y : 2.0, z : 3.0 }, Position { x : -10.3, y : 25.7, z : 3.9 }),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            rings : vec![2],
+            stroke_width : AnimationAttribute {
+                old_value : 3.0,
+                target_value : 3.0,
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            fill : false,
+            fill_rule : FillRule::NonZero,
+            gradient : Gradient::None,
+            is_displayed : false,
+            time : 0.0
         };
 
         let expected_m2 = PointMark {
-            markid : MarkId { mark_index : 1, layer_index : 0, valid : true },
+            markid : MarkId::new(1, 1),
+            layer_index : 0,
+            valid : true,
             size : AnimationAttribute {
                 old_value : Size::default(),
                 target_value : Size::default(),
-                start_anim : 0.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             color : AnimationAttribute {
-                old_value : Color::default(),
+                old_value : Color::blue(),
                 target_value : Color::blue(),
-                start_anim : -10.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             rotation : AnimationAttribute {
                 old_value : 0.0,
                 target_value : 0.0,
-                start_anim : 0.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             center : AnimationAttribute {
                 old_value : Position::default(),
                 target_value : Position::default(),
-                start_anim : 0.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             shape : AnimationAttribute {
-                old_value : Shape::None,
+                old_value : Shape::Point,
                 target_value : Shape::Point,
-                start_anim : -10.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
-            is_displayed : false
+            stroke_color : AnimationAttribute::new(Color::default()),
+            stroke_width : AnimationAttribute::new(0.0),
+            corner_radius : AnimationAttribute::new(0.0),
+            fill : true,
+            is_displayed : false,
+            time : 0.0
         };
 
         let expected_m3 = TextMark{
-                markid : MarkId { mark_index : 2, layer_index : 0, valid : true },
-                color : Color::default(),
-                face : String::from(""),
-                text : String::from("Test123"),
-                pos : Position { x : 10.0, y : 20.5, z : 0.0}
+            markid : MarkId::new(2, 1),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute {
+                old_value : Color::default(),
+                target_value : Color::default(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            face : String::from(""),
+            text : String::from("Test123"),
+            pos : AnimationAttribute {
+                old_value : Position { x : 10.0, y : 20.5, z : 0.0},
+                target_value : Position { x : 10.0, y : 20.5, z : 0.0},
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            layout : TextLayout::default(),
+            is_displayed : false,
+            time : 0.0
         };
 
         let expected_m4 = LineMark {
-            markid : MarkId { mark_index : 3, layer_index : 0, valid : true },
-            color : Color::default(),
-            points : vec!(Position { x : 5.0, y : 5.0, z : 5.0 }),
-            thickness : 12.0
+            markid : MarkId::new(3, 1),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute {
+                old_value : Color::default(),
+                target_value : Color::default(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            points : AnimationAttribute {
+                old_value : vec!(Position { x : 5.0, y : 5.0, z : 5.0 }),
+                target_value : vec!(Position { x : 5.0, y : 5.0, z : 5.0 }),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            colors : AnimationAttribute {
+                old_value : Vec::new(),
+                target_value : Vec::new(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            thickness : AnimationAttribute {
+                old_value : 12.0,
+                target_value : 12.0,
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            cap : LineCap::Butt,
+            join : LineJoin::Miter(4.0),
+            dashes : Vec::new(),
+            dash_offset : 0.0,
+            start_marker : Marker::None,
+            end_marker : Marker::None,
+            vertex_marker : Marker::None,
+            marker_size : 4.0,
+            curve : Curve::Linear,
+            scale : 1.0,
+            is_displayed : false,
+            time : 0.0
         };
 
         assert_eq!(*c.get_mark(&m1).unwrap().as_polygon_mark_unchecked(), expected_m1);
@@ -706,6 +1346,7 @@ mod tests {
     {
         let mut c = Contrast::new();
         c.init();
+        c.set_clock(ManualClock::new());
 
         let m1 = c.add_polygon_mark()
                     .set_color(Color::red())
@@ -730,57 +1371,176 @@ mod tests {
                     .get_id();
 
         let expected_m1 = PolygonMark {
-            markid : MarkId { mark_index : 0, layer_index : 0, valid : true },
-            color : Color::red(),
-            rotation : 0.0,
-            points : vec!(Position { x : 1.0, y : 2.0, z : 3.0 }, Position { x : -10.3, y : 25.7, z : 3.9 }),
-            stroke_width : 3.0,
-            fill : false
+            markid : MarkId::new(0, 1),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute {
+                old_value : Color::red(),
+                target_value : Color::red(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            rotation : AnimationAttribute {
+                old_value : 0.0,
+                target_value : 0.0,
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            points : AnimationAttribute {
+                old_value : vec!(Position { x : 1.0, y : 2.0, z : 3.0 }, Position { x : -10.3, y : 25.7, z : 3.9 }),
+                target_value : vec!(Position { x : 1.0, y : 2.0, z : 3.0 }, Position { x : -10.3, y : 25.7, z : 3.9 }),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            rings : vec![2],
+            stroke_width : AnimationAttribute {
+                old_value : 3.0,
+                target_value : 3.0,
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            fill : false,
+            fill_rule : FillRule::NonZero,
+            gradient : Gradient::None,
+            is_displayed : false,
+            time : 0.0
         };
 
         let expected_m2 = PointMark {
-            markid : MarkId { mark_index : 1, layer_index : 0, valid : true },
+            markid : MarkId::new(1, 1),
+            layer_index : 0,
+            valid : true,
             size : AnimationAttribute {
                 old_value : Size::default(),
                 target_value : Size::default(),
-                start_anim : 0.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             color : AnimationAttribute {
-                old_value : Color::default(),
+                old_value : Color::blue(),
                 target_value : Color::blue(),
-                start_anim : -10.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             rotation : AnimationAttribute {
                 old_value : 0.0,
                 target_value : 0.0,
-                start_anim : 0.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             center : AnimationAttribute {
                 old_value : Position::default(),
                 target_value : Position::default(),
-                start_anim : 0.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
             shape : AnimationAttribute {
-                old_value : Shape::None,
+                old_value : Shape::Point,
                 target_value : Shape::Point,
-                start_anim : -10.0
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
             },
-            is_displayed : false
+            stroke_color : AnimationAttribute::new(Color::default()),
+            stroke_width : AnimationAttribute::new(0.0),
+            corner_radius : AnimationAttribute::new(0.0),
+            fill : true,
+            is_displayed : false,
+            time : 0.0
         };
 
         let expected_m3 = TextMark{
-                markid : MarkId { mark_index : 2, layer_index : 0, valid : true },
-                color : Color::default(),
-                face : String::from(""),
-                text : String::from("Test123"),
-                pos : Position { x : 10.0, y : 20.5, z : 0.0}
+            markid : MarkId::new(2, 1),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute {
+                old_value : Color::default(),
+                target_value : Color::default(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            face : String::from(""),
+            text : String::from("Test123"),
+            pos : AnimationAttribute {
+                old_value : Position { x : 10.0, y : 20.5, z : 0.0},
+                target_value : Position { x : 10.0, y : 20.5, z : 0.0},
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            layout : TextLayout::default(),
+            is_displayed : false,
+            time : 0.0
         };
 
         let expected_m4 = LineMark {
-            markid : MarkId { mark_index : 3, layer_index : 0, valid : true },
-            color : Color::default(),
-            points : vec!(Position { x : 5.0, y : 5.0, z : 5.0 }),
-            thickness : 12.0
+            markid : MarkId::new(3, 1),
+            layer_index : 0,
+            valid : true,
+            color : AnimationAttribute {
+                old_value : Color::default(),
+                target_value : Color::default(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            points : AnimationAttribute {
+                old_value : vec!(Position { x : 5.0, y : 5.0, z : 5.0 }),
+                target_value : vec!(Position { x : 5.0, y : 5.0, z : 5.0 }),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            colors : AnimationAttribute {
+                old_value : Vec::new(),
+                target_value : Vec::new(),
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            thickness : AnimationAttribute {
+                old_value : 12.0,
+                target_value : 12.0,
+                start_anim : 0.0,
+                duration : 1.0,
+                easing : Easing::Linear,
+                transition : None
+            },
+            cap : LineCap::Butt,
+            join : LineJoin::Miter(4.0),
+            dashes : Vec::new(),
+            dash_offset : 0.0,
+            start_marker : Marker::None,
+            end_marker : Marker::None,
+            vertex_marker : Marker::None,
+            marker_size : 4.0,
+            curve : Curve::Linear,
+            scale : 1.0,
+            is_displayed : false,
+            time : 0.0
         };
 
         assert_eq!(*c.get_mark_mut(&m1).unwrap().as_polygon_mark_unchecked(), expected_m1);
@@ -797,46 +1557,43 @@ mod tests {
         c.init();
         c.add_layers(2);
 
-        let mut p1 = c.add_point_mark().set_position((30.0, 12.7, 0.0)).set_shape(Shape::Triangle).get_id();
-        let mut t1 = c.add_text_mark().set_position((10.0, 13.0, 1.0)).set_text("this is a text").get_id();
+        let p1 = c.add_point_mark().set_position((30.0, 12.7, 0.0)).set_shape(Shape::Triangle).get_id();
+        let t1 = c.add_text_mark().set_position((10.0, 13.0, 1.0)).set_text("this is a text").get_id();
         let t2 = c.add_text_mark().set_text("this is another text").get_id();
 
         c.set_current_layer(2);
 
         let poly1 = c.add_polygon_mark().set_stroke_width(3.5).get_id();
-        c.remove_mark(&mut t1);
-        let mut p2 = c.add_point_mark().set_color(Color::blue()).get_id();
-        c.remove_mark(&mut p1);
-        c.remove_mark(&mut p2);
+        c.remove_mark(&t1).unwrap();
+        let p2 = c.add_point_mark().set_color(Color::blue()).get_id();
+        c.remove_mark(&p1).unwrap();
+        c.remove_mark(&p2).unwrap();
         let l1 = c.add_line_mark().add_point((12.0, 12.0, 12.0)).add_point((22.0, 22.0, 22.0)).get_id();
 
         // Test if the state in which is the contrast structure is correct
         let mut expected_heap_0 = BinaryHeap::new();
-        expected_heap_0.push(t1.mark_index);
-        expected_heap_0.push(p1.mark_index);
+        expected_heap_0.push(1);
+        expected_heap_0.push(0);
 
         let mut expected_heap_2 = BinaryHeap::new();
-        expected_heap_2.push(p2.mark_index);
+        expected_heap_2.push(1);
 
         let expected_layer_0 = Layer {
             marks : vec!(c.get_mark(&t2).unwrap().clone()),
             depth : 0,
-            invalid_indexes : expected_heap_0,
-            contrast : c.get_pointer()
+            invalid_indexes : expected_heap_0
         };
 
         let expected_layer_1 = Layer {
             marks : Vec::new(),
             depth : 1,
-            invalid_indexes : BinaryHeap::new(),
-            contrast : c.get_pointer()
+            invalid_indexes : BinaryHeap::new()
         };
 
         let expected_layer_2 = Layer {
             marks : vec!(c.get_mark(&poly1).unwrap().clone(), c.get_mark(&l1).unwrap().clone()),
             depth : 2,
-            invalid_indexes : expected_heap_2,
-            contrast : c.get_pointer()
+            invalid_indexes : expected_heap_2
         };
 
         // Check the content of the contrast struct
@@ -844,7 +1601,8 @@ mod tests {
         assert_eq!(*c.layers.get(1).unwrap(), expected_layer_1);
         assert_eq!(*c.layers.get(2).unwrap(), expected_layer_2);
         assert_eq!(c.current_layer_index, 2);
-        assert_eq!(c.update, HashSet::new());
+        assert_eq!(c.update.len(), 4);
+        assert!(c.update.values().all(|update| *update == Update::All));
     }
 
     #[test]
@@ -854,18 +1612,243 @@ mod tests {
         c.init();
 
         let m1 = c.add_text_mark().get_id();
-        let mut m2 = c.add_text_mark().get_id();
+        let m2 = c.add_text_mark().get_id();
 
-        let expected_m1_id = MarkId { mark_index : 0, layer_index : 0, valid : true };
-        let expected_m2_id = MarkId { mark_index : 1, layer_index : 0, valid : true };
+        let expected_m1_id = MarkId::new(0, 1);
+        let expected_m2_id = MarkId::new(1, 1);
 
         assert_eq!(m1, expected_m1_id);
         assert_eq!(m2, expected_m2_id);
 
-        c.remove_mark(&mut m2);
+        c.remove_mark(&m2).unwrap();
 
-        let expected_m2_id = MarkId { mark_index : 1, layer_index : 0, valid : false };
-        assert_eq!(m2, expected_m2_id);
+        assert!(c.get_mark(&m2).is_none());
+        assert!(c.get_mark(&m1).is_some());
+    }
+
+    #[test]
+    fn stale_markid()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark().set_size((10.0, 20.0)).get_id();
+        c.remove_mark(&m1).unwrap();
+
+        // The new mark reuses the entry and the index of the removed one
+        let m2 = c.add_point_mark().set_size((30.0, 40.0)).get_id();
+
+        assert_eq!(m1.slot(), m2.slot());
+        assert_ne!(m1, m2);
+        assert!(c.get_mark(&m1).is_none());
+        assert!(c.get_mark_mut(&m1).is_none());
+        assert_eq!(c.get_mark(&m2).unwrap().as_point_mark_unchecked().get_size(), Size { width : 30.0, height : 40.0 });
+
+        // Removing a stale id does not remove the new mark
+        assert_eq!(c.remove_mark(&m1), Err(Error::UnknownMark(m1)));
+        assert!(c.get_mark(&m2).is_some());
+        assert_eq!(c.get_pointmarks_properties().len(), 1);
+    }
+
+    #[test]
+    fn markid_survives_layer_move()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_layers(1);
+
+        let m1 = c.add_point_mark().set_size((10.0, 20.0)).get_id();
+        let copy = m1;
+
+        c.get_layer_mut(1).unwrap().add_mark(&m1).unwrap();
+
+        assert_eq!(c.get_mark(&copy).unwrap().get_id(), m1);
+        assert_eq!(c.get_mark(&copy).unwrap().get_layer_index(), 1);
+
+        c.remove_mark(&copy).unwrap();
+        assert!(c.get_mark(&m1).is_none());
+        assert_eq!(c.get_layer(1).unwrap().get_marks_nb(), 0);
+    }
+
+    #[test]
+    fn mark_dirty()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark().get_id();
+        let m2 = c.add_line_mark().get_id();
+        c.remove_mark(&m2).unwrap();
+
+        // Adding and removing marks requires to rebuild every vertex
+        let update = c.fetch_update();
+        assert_eq!(update.get(&MarkTy::Point), Some(&Update::All));
+        assert_eq!(update.get(&MarkTy::Line), Some(&Update::All));
+        assert!(c.fetch_update().is_empty());
+
+        assert_eq!(c.mark_dirty(m1), Ok(()));
+        assert_eq!(c.mark_dirty(m2), Err(Error::UnknownMark(m2)));
+
+        let mut expected = HashSet::new();
+        expected.insert(m1);
+        let update = c.fetch_update();
+        assert_eq!(update.len(), 1);
+        assert_eq!(update.get(&MarkTy::Point), Some(&Update::Marks(expected)));
+    }
+
+    #[test]
+    fn get_mark_mut_marks_dirty()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark().get_id();
+        let m2 = c.add_polygon_mark().get_id();
+        c.fetch_update();
+
+        c.get_mark_mut(&m1).unwrap().set_color(Color::red());
+        c.get_mark_mut(&m2).unwrap().set_color(Color::red());
+        c.get_mark(&m1).unwrap();
+
+        let update = c.fetch_update();
+        let mut expected_m1 = HashSet::new();
+        expected_m1.insert(m1);
+        let mut expected_m2 = HashSet::new();
+        expected_m2.insert(m2);
+        assert_eq!(update.get(&MarkTy::Point), Some(&Update::Marks(expected_m1)));
+        assert_eq!(update.get(&MarkTy::Polygon), Some(&Update::Marks(expected_m2)));
+        assert_eq!(update.get(&MarkTy::Line), None);
+    }
+
+    #[test]
+    fn mark_guard()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_text_mark().get_id();
+        c.fetch_update();
+
+        {
+            let mut mark = c.get_mark_mut(&m1).unwrap();
+            mark.as_text_mark_mut_unchecked().set_text("test123");
+        }
+
+        let mut expected = HashSet::new();
+        expected.insert(m1);
+        assert_eq!(c.fetch_update().get(&MarkTy::Text), Some(&Update::Marks(expected)));
+        assert_eq!(c.get_mark(&m1).unwrap().as_text_mark_unchecked().get_text(), "test123");
+    }
+
+    #[test]
+    fn builder_marks_dirty()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        c.add_point_mark().set_position((1.0, 5.0, 9.0)).set_shape(Shape::Triangle);
+
+        let update = c.fetch_update();
+        assert_eq!(update.len(), 1);
+        assert_eq!(update.get(&MarkTy::Point), Some(&Update::All));
+    }
+
+    #[test]
+    fn get_sub_properties()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark().get_id();
+        let m2 = c.add_point_mark().set_position((1.0, 5.0, 9.0)).get_id();
+        let l1 = c.add_line_mark().add_point((0.0, 0.0)).add_point((1.0, 1.0)).add_point((2.0, 0.0)).get_id();
+        let l2 = c.add_line_mark().add_point((0.0, 0.0)).add_point((1.0, 1.0)).get_id();
+
+        assert_eq!(c.get_pointmarks_properties().len(), 2);
+        assert_eq!(c.get_linemarks_properties().len(), 3);
+
+        let mut markids = HashSet::new();
+        markids.insert(m2);
+        let changes = c.get_pointmarks_sub_properties(&markids).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, 1);
+        assert_eq!(changes[0].1[0].1, [1.0, 5.0, 9.0]);
+
+        // The vertices of the second line start after the 2 sub-lines of the first one
+        let mut markids = HashSet::new();
+        markids.insert(l2);
+        let changes = c.get_linemarks_sub_properties(&markids).unwrap();
+        assert_eq!(changes[0].0, 2);
+        assert_eq!(changes[0].1.len(), 1);
+
+        // Adding a point changes the number of vertices, so everything must be rebuilt
+        c.get_mark_mut(&l1).unwrap().as_line_mark_mut_unchecked().add_point((3.0, 3.0));
+        let mut markids = HashSet::new();
+        markids.insert(l1);
+        assert!(c.get_linemarks_sub_properties(&markids).is_none());
+
+        // A mark whose vertices were never built cannot be updated alone
+        let m3 = c.add_point_mark().get_id();
+        let mut markids = HashSet::new();
+        markids.insert(m1);
+        markids.insert(m3);
+        assert!(c.get_pointmarks_sub_properties(&markids).is_none());
+    }
+
+    #[test]
+    fn register_unknown_font()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        match c.register_font("font", "this/font/does/not/exist.ttf", 20) {
+            Err(Error::FontLoad(path, _)) => assert_eq!(path, "this/font/does/not/exist.ttf"),
+            _ => panic!("The font should not have been loaded")
+        }
+        assert!(!c.contains_font("font"));
+    }
+
+    #[test]
+    fn measure_text()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.register_font("serif", "../contrast-renderer/crimson-b.ttf", 20).unwrap();
+        assert_eq!(c.measure_text("unknown", "a"), Err(Error::UnknownFont(String::from("unknown"))));
+
+        let metrics = c.measure_text("serif", "Axis\nlabel").unwrap();
+        assert!(metrics.ascent > 0.0 && metrics.descent >= 0.0);
+        assert!(metrics.height > metrics.ascent + metrics.descent);
+
+        // The bounds of a text mark are the measured ones, moved to its position and by its layout.
+        let m1 = c.add_text_mark().set_font("serif").set_text("Axis\nlabel").set_position((40.0, 30.0)).get_id();
+        let bounds = c.get_text_bounds(&m1).unwrap();
+        assert_eq!((bounds.x, bounds.y), (40.0 + metrics.bounds.x, 30.0 + metrics.bounds.y));
+        assert_eq!((bounds.width, bounds.height), (metrics.bounds.width, metrics.bounds.height));
+        c.get_mark_mut(&m1).unwrap().as_text_mark_mut_unchecked().set_anchor(TextAnchor::Top);
+        assert_eq!(c.get_text_bounds(&m1).unwrap().y, 30.0 + metrics.bounds.y + metrics.ascent.round());
+
+        // The bounds follow the position displayed during an animation, and the zoom of the camera.
+        let bounds = c.get_text_bounds(&m1).unwrap();
+        let clock = Rc::new(RefCell::new(ManualClock::new()));
+        c.set_clock(clock.clone());
+        {
+            let mut mark = c.get_mark_mut(&m1).unwrap();
+            let text = mark.as_text_mark_mut_unchecked();
+            text.set_displayed(true);
+            text.set_position((60.0, 30.0));
+        }
+        clock.borrow_mut().advance(0.5);
+        // Measuring a text mark does not modify it, not even its time.
+        let text = c.get_mark(&m1).unwrap().as_text_mark().unwrap().clone();
+        assert_eq!(c.get_text_bounds(&m1).unwrap().x, bounds.x + 10.0);
+        assert_eq!(c.get_mark(&m1).unwrap().as_text_mark(), Some(&text));
+        c.set_scale(2.0);
+        assert_eq!(c.get_text_bounds(&m1).unwrap(), TextBounds { x : (bounds.x + 10.0) * 2.0, y : bounds.y * 2.0,
+            width : bounds.width * 2.0, height : bounds.height * 2.0 });
+
+        let m2 = c.add_point_mark().get_id();
+        assert_eq!(c.get_text_bounds(&m2), Err(Error::UnknownMark(m2)));
     }
 
     #[test]
@@ -906,4 +1889,196 @@ mod tests {
         assert_eq!(c.get_mark(&m1).unwrap().as_point_mark_unchecked().get_rotation(), 90.0);
         assert_eq!(c.get_mark(&m2).unwrap().as_point_mark_unchecked().get_rotation(), 180.0);
     }
+
+    #[test]
+    fn get_and_set_transition()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark()
+                    .set_duration(0.5)
+                    .set_property_easing(PointProperty::Color, Easing::Bounce)
+                    .set_property_duration(PointProperty::Shape, -1.0)
+                    .get_id();
+
+        let mark = c.get_mark(&m1).unwrap().as_point_mark_unchecked();
+        assert_eq!(mark.get_property_duration(PointProperty::Position), 0.5);
+        assert_eq!(mark.get_property_duration(PointProperty::Shape), 0.0);
+        assert_eq!(mark.get_property_easing(PointProperty::Color), Easing::Bounce);
+        assert_eq!(mark.get_property_easing(PointProperty::Size), Easing::Linear);
+
+        let vertex = mark.to_vertex();
+        assert_eq!(vertex.2[1..], [0.5, 0.0]);
+        assert_eq!(vertex.8[1..], [0.5, 5.0]);
+        assert_eq!((vertex.11[2], (vertex.13[0] >> 8) & 15), (0.0, 0));
+    }
+
+    #[test]
+    fn animate_line_and_polygon()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let l1 = c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((10.0, 0.0, 0.0)).get_id();
+        let poly1 = c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((10.0, 0.0, 0.0))
+                        .add_point((10.0, 10.0, 0.0)).get_id();
+
+        assert_eq!(c.get_linemarks_properties().len(), 1);
+        assert_eq!(c.get_polygonmarks_properties().len(), 3);
+
+        // The marks are displayed, so the new points are animated from the resampled current ones.
+        c.get_mark_mut(&l1).unwrap().as_line_mark_mut_unchecked().add_point((10.0, 10.0, 0.0)).set_thickness(5.0);
+        c.get_mark_mut(&poly1).unwrap().as_polygon_mark_mut_unchecked().add_point((0.0, 10.0, 0.0));
+
+        let line = c.get_linemarks_properties();
+        assert_eq!(line.len(), 2);
+        assert_eq!(line[0].1, [0.0, 0.0, 0.0]);
+        assert_eq!(line[0].3, [5.0, 0.0, 0.0]);
+        assert_eq!(line[1].9, [1.0, 5.0, 4.0, 0.0]);
+        assert!(line[1].13[2] >= 0.0);
+
+        let polygon = c.get_polygonmarks_properties();
+        assert_eq!(polygon.len(), 4);
+        assert_eq!(polygon[3].3, [0.0, 10.0, 0.0]);
+        assert_eq!(polygon[3].5, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn play()
+    {
+        let mut c = Contrast::new();
+        c.init();
+
+        let m1 = c.add_point_mark().get_id();
+        let m2 = c.add_line_mark().get_id();
+        c.fetch_update();
+
+        let track = Track::new().keyframe(0.0, Value::Color(Color::red()), Easing::Linear);
+        let id = c.play(Animation::new().track(track), &[m1, m2]);
+
+        assert!(c.is_playing(id));
+        assert_eq!(c.update_animations(), vec!(id));
+        assert!(!c.is_playing(id));
+        assert_eq!(c.get_mark(&m1).unwrap().get_color(), Color::red());
+        assert_eq!(c.get_mark(&m2).unwrap().get_color(), Color::red());
+        assert_eq!(c.fetch_update().len(), 2);
+    }
+
+    #[test]
+    fn clock()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let clock = Rc::new(RefCell::new(ManualClock::fixed_step(0.5)));
+        c.set_clock(clock.clone());
+
+        let m1 = c.add_point_mark().get_id();
+        c.get_pointmarks_properties();
+        c.tick();
+        c.tick();
+        assert_eq!(c.get_time(), 1.0);
+
+        // The animations start at the time of the clock of contrast.
+        c.get_mark_mut(&m1).unwrap().as_point_mark_mut_unchecked().set_position((10.0, 0.0, 0.0));
+        let vertex = c.get_mark(&m1).unwrap().as_point_mark_unchecked().to_vertex();
+        assert_eq!(vertex.2[0], 1.0);
+
+        let track = Track::new().keyframe(1.0, Value::Color(Color::red()), Easing::Linear);
+        let id = c.play(Animation::new().track(track), &[m1]);
+        clock.borrow_mut().advance(0.5);
+        assert!(c.update_animations().is_empty());
+        assert_eq!(c.get_mark(&m1).unwrap().get_color(), Color::default());
+
+        clock.borrow_mut().advance(0.5);
+        assert_eq!(c.update_animations(), vec!(id));
+        let vertex = c.get_mark(&m1).unwrap().as_point_mark_unchecked().to_vertex();
+        assert_eq!(vertex.8[0], 2.0);
+    }
+
+    #[test]
+    fn keyframe_timing()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let clock = Rc::new(RefCell::new(ManualClock::new()));
+        c.set_clock(clock.clone());
+
+        let m1 = c.add_point_mark().set_property_duration(PointProperty::Color, 2.0)
+            .set_property_easing(PointProperty::Color, Easing::CubicIn).get_id();
+        c.get_pointmarks_properties();
+
+        // The keyframes set the timing of their own transitions.
+        let track = Track::new().keyframe(0.0, Value::Color(Color::cyan()), Easing::Linear)
+            .keyframe(1.0, Value::Color(Color::red()), Easing::Linear);
+        let id = c.play(Animation::new().track(track), &[m1]);
+        c.update_animations();
+        let vertex = c.get_mark(&m1).unwrap().as_point_mark_unchecked().to_vertex();
+        assert_eq!(vertex.8, [0.0, 1.0, Easing::Linear as u32 as f32]);
+
+        // Once the timeline has run, the user keeps the timing set before.
+        clock.borrow_mut().advance(1.0);
+        assert_eq!(c.update_animations(), vec!(id));
+        let mut mark = c.get_mark_mut(&m1).unwrap();
+        let point = mark.as_point_mark_mut_unchecked();
+        assert_eq!(point.get_property_duration(PointProperty::Color), 2.0);
+        assert_eq!(point.get_property_easing(PointProperty::Color), Easing::CubicIn);
+        point.set_color(Color::yellow());
+        let vertex = point.to_vertex();
+        assert_eq!(vertex.8, [1.0, 2.0, Easing::CubicIn as u32 as f32]);
+    }
+
+    #[test]
+    fn image_marks()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let m1 = c.add_image_mark().set_image("logo").set_position((10.0, 20.0)).set_size((30.0, 30.0)).get_id();
+        let m2 = c.add_image_mark().set_image("icon").get_id();
+
+        // The marks are only drawn once their image is registered.
+        assert!(c.get_imagemarks_properties().is_empty());
+        c.register_image("logo", Image::new(4, 4)).unwrap();
+        assert!(c.contains_image("logo"));
+        assert_eq!(c.get_new_images().len(), 1);
+        assert_eq!(c.fetch_update().get(&MarkTy::Image), Some(&Update::All));
+        let vertices = c.get_imagemarks_properties();
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(vertices[0].1, [10.0, 20.0, 0.0]);
+        assert_eq!(vertices[0].11, c.images.coordinates("logo").unwrap());
+
+        let mut markids = HashSet::new();
+        markids.insert(m1);
+        c.get_mark_mut(&m1).unwrap().as_image_mark_mut_unchecked().set_rotation(1.0);
+        let changes = c.get_imagemarks_sub_properties(&markids).unwrap();
+        assert_eq!(changes[0].0, 0);
+        assert_eq!(changes[0].1[0].9, [0.0, 1.0]);
+
+        // A mark without vertices yet requires to rebuild every vertex.
+        markids.insert(m2);
+        assert!(c.get_imagemarks_sub_properties(&markids).is_none());
+    }
+
+    #[test]
+    fn arc_marks()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let m1 = c.add_arc_mark().set_position((50.0, 50.0)).set_outer_radius(40.0).set_end_angle(1.0).get_id();
+        c.add_arc_mark().set_inner_radius(10.0).set_outer_radius(20.0);
+        let vertices = c.get_arcmarks_properties();
+        assert_eq!(vertices.len(), 2);
+        assert_eq!(vertices[0].1, [50.0, 50.0, 0.0]);
+        assert_eq!(vertices[0].6[3], 40.0);
+        assert_eq!(vertices[1].6[1], 10.0);
+
+        // Once displayed, the angles sweep from where they are.
+        let mut markids = HashSet::new();
+        markids.insert(m1);
+        c.get_mark_mut(&m1).unwrap().as_arc_mark_mut_unchecked().set_start_angle(0.5).set_end_angle(2.0);
+        let changes = c.get_arcmarks_sub_properties(&markids).unwrap();
+        assert_eq!(changes[0].0, 0);
+        assert_eq!(changes[0].1[0].9, [0.0, 0.5, 1.0, 2.0]);
+        assert_eq!(changes[0].1[0].11[1], 1.0);
+    }
 }
@@ -0,0 +1,348 @@
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use properties::color::Color;
+use crate::markscontainer::Contrast;
+use crate::vector::{self, Item, Paint, Segment, StrokeStyle};
+use crate::marks::polygonmark::Gradient;
+use crate::truetype::{self, EmbeddedFont};
+use crate::triangulation::FillRule;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Number written with at most 3 decimals, without exponent, as PDF and PostScript expect.
+pub(crate) fn number(value : f32) -> String {
+    let text = format!("{:.3}", if value.is_finite() { value } else { 0.0 });
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+/// Writes 'path' with the operators of PDF.
+fn write_path(content : &mut String, path : &[Segment]) {
+    for segment in path {
+        let _ = match segment {
+            Segment::Move(p) => writeln!(content, "{} {} m", number(p[0]), number(p[1])),
+            Segment::Line(p) => writeln!(content, "{} {} l", number(p[0]), number(p[1])),
+            Segment::Cubic(c1, c2, p) => writeln!(content, "{} {} {} {} {} {} c", number(c1[0]), number(c1[1]),
+                                                  number(c2[0]), number(c2[1]), number(p[0]), number(p[1])),
+            Segment::Close => writeln!(content, "h")
+        };
+    }
+}
+
+/// Writes the operators setting the fill or the stroke color, and the opacity if the color is transparent.
+/// The opacities are graphic states of the page, named after their index in 'opacities'.
+fn write_color(content : &mut String, color : &Color, stroke : bool, opacities : &mut Vec<String>) {
+    let alpha = number(color.a.clamp(0.0, 1.0));
+    if alpha != "1" {
+        let index = opacities.iter().position(|opacity| *opacity == alpha).unwrap_or_else(|| {
+            opacities.push(alpha.clone());
+            opacities.len() - 1
+        });
+        let _ = writeln!(content, "/GS{} gs", index);
+    }
+    let _ = writeln!(content, "{} {} {} {}", number(color.r), number(color.g), number(color.b), if stroke { "RG" } else { "rg" });
+}
+
+/// Returns the shading of 'gradient' from 'color', shared by PDF and PostScript, in the current coordinate system.
+/// The colors are extended beyond the ends of the gradient.
+pub(crate) fn shading(color : &Color, gradient : &Gradient) -> String {
+    let (kind, coords, end) = match gradient {
+        Gradient::Linear(from, to, end) => (2, [from.x, from.y, to.x, to.y].iter().map(|v| number(*v)).collect::<Vec<_>>(), end),
+        Gradient::Radial(center, radius, end) => (3, [center.x, center.y, 0.0, center.x, center.y, *radius].iter().map(|v| number(*v)).collect(), end),
+        Gradient::None => return String::new()
+    };
+    let rgb = |c : &Color| format!("{} {} {}", number(c.r), number(c.g), number(c.b));
+    format!("<< /ShadingType {} /ColorSpace /DeviceRGB /Coords [{}] /Function << /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >> \
+             /Extend [true true] >>", kind, coords.join(" "), rgb(color), rgb(end))
+}
+
+/// Writes the operators painting the fills or the strokes with 'paint'. A gradient is a shading pattern
+/// of the page, named after its index in 'patterns', whose opacity is the one of its first color.
+fn write_paint(content : &mut String, paint : &Paint, stroke : bool, height : u32, opacities : &mut Vec<String>, patterns : &mut Vec<String>) {
+    match paint {
+        Paint::Color(color) => write_color(content, color, stroke, opacities),
+        Paint::Gradient(color, gradient) => {
+            write_color(content, color, stroke, opacities);
+            // The patterns are in the coordinate system of the page, which goes up.
+            patterns.push(format!("<< /PatternType 2 /Shading {} /Matrix [1 0 0 -1 0 {}] >>", shading(color, gradient), height));
+            let _ = writeln!(content, "/Pattern {} /P{} {}", if stroke { "CS" } else { "cs" }, patterns.len() - 1, if stroke { "SCN" } else { "scn" });
+        }
+    }
+}
+
+/// Writes the width, the cap, the join, the miter limit and the dash pattern of a stroke.
+fn write_stroke_style(content : &mut String, width : f32, style : &StrokeStyle) {
+    let (cap, join, limit) = style.codes();
+    let dashes : Vec<String> = style.dashes.iter().map(|length| number(*length)).collect();
+    let _ = writeln!(content, "{} w {} J {} j {} M [{}] {} d", number(width), cap, join, number(limit),
+                     dashes.join(" "), number(style.dash_offset));
+}
+
+/// Writes the content of the page : the items, in a coordinate system going down like the one of contrast.
+fn page_content(items : &[Item], fonts : &[EmbeddedFont], height : u32, opacities : &mut Vec<String>, patterns : &mut Vec<String>) -> String {
+    let mut content = format!("1 0 0 -1 0 {} cm\n", height);
+    for item in items {
+        content.push_str("q\n");
+        match item {
+            Item::Fill(path, rule, paint) => {
+                write_paint(&mut content, paint, false, height, opacities, patterns);
+                write_path(&mut content, path);
+                content.push_str(if *rule == FillRule::EvenOdd { "f*\n" } else { "f\n" });
+            },
+            Item::Stroke(path, width, style, color) => {
+                write_color(&mut content, color, true, opacities);
+                write_stroke_style(&mut content, *width, style);
+                write_path(&mut content, path);
+                content.push_str("S\n");
+            },
+            Item::InnerStroke(path, rule, width, paint) => {
+                // A stroke twice as wide is clipped by the path.
+                write_path(&mut content, path);
+                content.push_str(if *rule == FillRule::EvenOdd { "W* n\n" } else { "W n\n" });
+                write_paint(&mut content, paint, true, height, opacities, patterns);
+                write_stroke_style(&mut content, 2.0 * width, &StrokeStyle::polygon());
+                write_path(&mut content, path);
+                content.push_str("S\n");
+            },
+            Item::Text(face, position, text, color) => {
+                let index = truetype::font_index(fonts, face);
+                write_color(&mut content, color, false, opacities);
+                let glyphs : String = fonts[index].glyph_indices(text).iter().map(|gid| format!("{:04X}", gid)).collect();
+                // The text matrix flips the glyphs back.
+                let _ = writeln!(content, "BT /F{} {} Tf 1 0 0 -1 {} {} Tm <{}> Tj ET", index, face.police,
+                                 number(position.x), number(position.y), glyphs);
+            }
+        }
+        content.push_str("Q\n");
+    }
+    content
+}
+
+/// Returns the CMap giving the unicode character of each glyph of 'font', so the
+/// texts can be searched and copied from the document.
+fn to_unicode(font : &EmbeddedFont) -> String {
+    let mut cmap = String::from("/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+                                 /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+                                 /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+                                 1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n");
+    let glyphs : Vec<_> = font.glyphs.iter().filter(|&(&gid, _)| gid != 0).collect();
+    for chunk in glyphs.chunks(100) {
+        let _ = writeln!(cmap, "{} beginbfchar", chunk.len());
+        for (gid, c) in chunk {
+            let unicode : String = c.encode_utf16(&mut [0; 2]).iter().map(|unit| format!("{:04X}", unit)).collect();
+            let _ = writeln!(cmap, "<{:04X}> <{}>", gid, unicode);
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+/// Writer of the objects of a PDF file, remembering where each one starts for the cross-reference table.
+struct PdfWriter {
+    data : Vec<u8>,
+    offsets : Vec<usize>
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        PdfWriter { data : b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec(), offsets : Vec::new() }
+    }
+
+    /// Writes the next object, whose number is the number of objects written so far plus 1.
+    fn object(&mut self, content : &str) {
+        self.offsets.push(self.data.len());
+        self.data.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", self.offsets.len(), content).as_bytes());
+    }
+
+    /// Writes the next object as a stream of 'bytes', with the entries 'dictionary'.
+    fn stream(&mut self, dictionary : &str, bytes : &[u8]) {
+        self.offsets.push(self.data.len());
+        self.data.extend_from_slice(format!("{} 0 obj\n<< {}/Length {} >>\nstream\n", self.offsets.len(), dictionary, bytes.len()).as_bytes());
+        self.data.extend_from_slice(bytes);
+        self.data.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    /// Writes the cross-reference table and the trailer, then returns the file.
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.data.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(table, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", self.offsets.len() + 1, xref);
+        self.data.extend_from_slice(table.as_bytes());
+        self.data
+    }
+}
+
+impl Contrast {
+    /// Returns the marks of contrast as a PDF document of one page of 'width' x 'height' points,
+    /// one point for each pixel, with the values displayed at the current time of the clock.
+    /// The marks are drawn in the order of export_svg, with the vector operators of PDF.
+    /// The fonts of the text marks are embedded, keeping only the glyphs of the texts.
+    /// Returns an error if a font is not a TrueType font or cannot be read.
+    pub fn export_pdf(&self, width : u32, height : u32) -> Result<Vec<u8>> {
+        let cache = self.fonts.borrow();
+        let items = vector::items(self, &cache);
+        let fonts = truetype::embedded_fonts(&items)?;
+        let (mut opacities, mut patterns) = (Vec::new(), Vec::new());
+        let content = page_content(&items, &fonts, height, &mut opacities, &mut patterns);
+
+        // Objects : catalog, pages, page, content, then 5 objects for each font.
+        let mut resources = String::from("<< /Font <<");
+        for i in 0..fonts.len() {
+            let _ = write!(resources, " /F{} {} 0 R", i, 5 + 5 * i);
+        }
+        resources.push_str(" >> /ExtGState <<");
+        for (i, opacity) in opacities.iter().enumerate() {
+            let _ = write!(resources, " /GS{} << /ca {1} /CA {1} >>", i, opacity);
+        }
+        resources.push_str(" >> /Pattern <<");
+        for (i, pattern) in patterns.iter().enumerate() {
+            let _ = write!(resources, " /P{} {}", i, pattern);
+        }
+        resources.push_str(" >> >>");
+
+        let mut pdf = PdfWriter::new();
+        pdf.object("<< /Type /Catalog /Pages 2 0 R >>");
+        pdf.object("<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+        pdf.object(&format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources {} /Contents 4 0 R >>", width, height, resources));
+        pdf.stream("", content.as_bytes());
+
+        for (i, font) in fonts.iter().enumerate() {
+            let id = 5 + 5 * i;
+            let name = font.name(i);
+            let scale = |value : i32| value * 1000 / font.font.units_per_em as i32;
+            let widths : String = font.glyphs.keys().map(|&gid| format!("{} [{}] ", gid, scale(font.font.advance(gid) as i32))).collect();
+            let bbox = font.font.bounding_box();
+            let (ascent, descent) = font.font.ascent_descent();
+            let subset = font.subset();
+
+            pdf.object(&format!("<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                                name, id + 1, id + 4));
+            pdf.object(&format!("<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                                 /FontDescriptor {} 0 R /W [{}] /CIDToGIDMap /Identity >>", name, id + 2, widths));
+            pdf.object(&format!("<< /Type /FontDescriptor /FontName /{} /Flags 4 /FontBBox [{} {} {} {}] /ItalicAngle 0 /Ascent {} /Descent {} \
+                                 /CapHeight {} /StemV 80 /FontFile2 {} 0 R >>", name, scale(bbox[0] as i32), scale(bbox[1] as i32),
+                                scale(bbox[2] as i32), scale(bbox[3] as i32), scale(ascent as i32), scale(descent as i32), scale(ascent as i32), id + 3));
+            pdf.stream(&format!("/Length1 {} ", subset.data.len()), &subset.data);
+            pdf.stream("", to_unicode(font).as_bytes());
+        }
+        Ok(pdf.finish())
+    }
+
+    /// Same behavior than export_pdf, then save the document at 'path'.
+    pub fn save_pdf<P : AsRef <Path>>(&self, width : u32, height : u32, path : P) -> Result<()> {
+        let pdf = self.export_pdf(width, height)?;
+        let file = File::create(path).map_err(|err| Error::ImageWrite(err.to_string()))?;
+        BufWriter::new(file).write_all(&pdf).map_err(|err| Error::ImageWrite(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marks::linemark::{LineCap, LineJoin};
+    use crate::marks::pointmark::Shape;
+    use crate::MarkMacro;
+
+    fn text(pdf : &[u8]) -> String {
+        String::from_utf8_lossy(pdf).into_owned()
+    }
+
+    #[test]
+    fn number()
+    {
+        assert_eq!(super::number(1.0), "1");
+        assert_eq!(super::number(-0.0001), "0");
+        assert_eq!(super::number(2.5), "2.5");
+        assert_eq!(super::number(1.0 / 3.0), "0.333");
+        assert_eq!(super::number(f32::NAN), "0");
+    }
+
+    #[test]
+    fn export_pdf()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_point_mark().set_shape(Shape::Rectangle).set_size((2.0, 4.0)).set_position((10.0, 10.0, 0.0)).set_color((1.0, 0.0, 0.0, 0.5));
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((1.0, 1.0, 0.0)).set_thickness(3.0);
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0));
+
+        let pdf = text(&c.export_pdf(200, 100).unwrap());
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/MediaBox [0 0 200 100]"));
+        assert!(pdf.contains("1 0 0 -1 0 100 cm\n"));
+        assert!(pdf.contains("/GS0 gs\n1 0 0 rg\n9 8 m\n11 8 l\n11 12 l\n9 12 l\nh\nf\n"));
+        assert!(pdf.contains("/GS0 << /ca 0.5 /CA 0.5 >>"));
+        assert!(pdf.contains("3 w 0 J 0 j 4 M [] 0 d\n0 0 m\n1 1 l\nS\n"));
+        assert!(pdf.contains("h\nW n\n"));
+        assert!(pdf.contains("30 w 0 J 0 j 1000 M [] 0 d"));
+
+        // Each object is where the cross-reference table says.
+        let xref = pdf.rfind("xref\n").unwrap();
+        for (i, line) in pdf[xref..].lines().skip(3).take(4).enumerate() {
+            let offset : usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+
+    #[test]
+    fn export_pdf_line_style()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((1.0, 1.0, 0.0)).set_thickness(2.0)
+         .set_cap(LineCap::Round).set_join(LineJoin::Bevel).set_dashes(&[3.0, 1.5]).unwrap().set_dash_offset(1.0);
+
+        let pdf = text(&c.export_pdf(200, 100).unwrap());
+        assert!(pdf.contains("2 w 1 J 2 j 10 M [3 1.5] 1 d\n"));
+    }
+
+    #[test]
+    fn export_pdf_gradient()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_fill()
+         .set_color((1.0, 0.0, 0.0, 1.0)).set_gradient(Gradient::Radial((1.0, 2.0).into(), 4.0, Color::blue()));
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0))
+         .set_gradient(Gradient::Linear((0.0, 0.0).into(), (5.0, 0.0).into(), Color::green()));
+
+        let pdf = text(&c.export_pdf(200, 100).unwrap());
+        assert!(pdf.contains("/Pattern cs /P0 scn\n0 0 m\n"));
+        assert!(pdf.contains("/Pattern CS /P1 SCN\n"));
+        assert!(pdf.contains("/P0 << /PatternType 2 /Shading << /ShadingType 3 /ColorSpace /DeviceRGB /Coords [1 2 0 1 2 4] \
+                              /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >> /Extend [true true] >> \
+                              /Matrix [1 0 0 -1 0 100] >>"));
+        assert!(pdf.contains("/ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 5 0]"));
+    }
+
+    #[test]
+    fn export_pdf_text()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.register_font("serif", "../contrast-renderer/crimson-b.ttf", 20).unwrap();
+        c.add_text_mark().set_font("serif").set_text("Aa").set_position((5.0, 15.0, 0.0));
+
+        let pdf = c.export_pdf(100, 50).unwrap();
+        let content = text(&pdf);
+        assert!(content.contains("/BaseFont /AAAAAA+serif /Encoding /Identity-H"));
+        assert!(content.contains("BT /F0 20 Tf 1 0 0 -1 5 15 Tm <"));
+        assert!(content.contains("/FontFile2 8 0 R"));
+        assert!(content.contains("<0041>"));
+        assert!(pdf.len() < std::fs::read("../contrast-renderer/crimson-b.ttf").unwrap().len());
+
+        c.register_font("type1", "../contrast-renderer/uhvr8a.pfb", 20).unwrap();
+        c.add_text_mark().set_font("type1").set_text("b");
+        match c.export_pdf(100, 50) {
+            Err(Error::FontEmbed(path, _)) => assert_eq!(path, "../contrast-renderer/uhvr8a.pfb"),
+            _ => panic!("a Type 1 font cannot be embedded")
+        }
+    }
+}
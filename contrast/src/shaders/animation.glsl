@@ -0,0 +1,76 @@
+// Shared by the shaders of every animated mark, and prepended to them by the renderer.
+uniform float t;
+
+const float PI = 3.14159265359;
+
+float bounce(float x) {
+    const float n = 7.5625;
+    const float d = 2.75;
+
+    if (x < 1.0 / d) {
+        return n * x * x;
+    }
+    else if (x < 2.0 / d) {
+        x -= 1.5 / d;
+        return n * x * x + 0.75;
+    }
+    else if (x < 2.5 / d) {
+        x -= 2.25 / d;
+        return n * x * x + 0.9375;
+    }
+    x -= 2.625 / d;
+    return n * x * x + 0.984375;
+}
+
+// Must stay in sync with Easing::apply in contrast/src/easing.rs.
+float ease(uint easing, float x) {
+    x = clamp(x, 0.0, 1.0);
+
+    if (easing == 1u) { // CubicIn
+        return x * x * x;
+    }
+    else if (easing == 2u) { // CubicOut
+        return 1.0 - pow(1.0 - x, 3.0);
+    }
+    else if (easing == 3u) { // CubicInOut
+        return x < 0.5 ? 4.0 * x * x * x : 1.0 - pow(-2.0 * x + 2.0, 3.0) / 2.0;
+    }
+    else if (easing == 4u) { // Elastic
+        if (x == 0.0 || x == 1.0) {
+            return x;
+        }
+        return pow(2.0, -10.0 * x) * sin((x * 10.0 - 0.75) * (2.0 * PI / 3.0)) + 1.0;
+    }
+    else if (easing == 5u) { // Bounce
+        return bounce(x);
+    }
+    else if (easing == 6u) { // Step
+        return x < 1.0 ? 0.0 : 1.0;
+    }
+
+    return x; // Linear
+}
+
+// Returns the eased progress at the time 't' of an animation.
+float progress(float start, float duration, uint easing) {
+    if (duration <= 0.0) {
+        return 1.0;
+    }
+    return ease(easing, (t - start) / duration);
+}
+
+// 'anim' is the triple (start, duration, easing) of the animation of a property.
+float progress(vec3 anim) {
+    return progress(anim.x, anim.y, uint(anim.z));
+}
+
+// Returns the progress of the animation of the property 'i' of a line or a polygon,
+// whose easings are packed on 4 bits each.
+float progress(vec4 starts, vec4 durations, uint easings, int i) {
+    return progress(starts[i], durations[i], (easings >> uint(4 * i)) & 15u);
+}
+
+// Returns the color packed by Color::to_rgba8, red in the lowest byte.
+vec4 unpack_color(uint c) {
+    return vec4(c & 255u, (c >> 8) & 255u, (c >> 16) & 255u, c >> 24) / 255.0;
+}
@@ -0,0 +1,69 @@
+const float PI = 3.14159265358979323846264;
+const float INFINITY = 1e20;
+
+in vec4 f_color;
+flat in vec2 f_radii;
+flat in float f_aperture;
+flat in float f_corner_radius;
+in vec2 g_local;
+out vec4 frag;
+
+// Distance from 'p' to the arc of radius 'r' going from the axis to the side of the arc
+// eroded by the corner radius 'c', or INFINITY if there is no such arc.
+float distance_arc(vec2 p, float r, float c)
+{
+    if (r <= 0.0 || c > r) {
+        return INFINITY;
+    }
+    float end = f_aperture - asin(c / r);
+    if (end < 0.0) {
+        return INFINITY;
+    }
+    float angle = clamp(atan(p.x, p.y), 0.0, end);
+    return length(p - r * vec2(sin(angle), cos(angle)));
+}
+
+// Signed distance in pixels to the arc, whose right half only is considered since it is
+// symmetric. The arc is eroded by the corner radius, then the exact distance to the eroded
+// arc is reduced by the corner radius, which rounds its corners.
+float distance_shape(vec2 p)
+{
+    p.x = abs(p.x);
+    float len = length(p);
+    if (f_aperture >= PI) {
+        return max(f_radii.x - len, len - f_radii.y);
+    }
+    float c = f_corner_radius;
+    float inner = f_radii.x + c;
+    float outer = f_radii.y - c;
+    // Direction of the side, and its normal going out of the arc.
+    vec2 side = vec2(sin(f_aperture), cos(f_aperture));
+    vec2 normal = vec2(cos(f_aperture), -sin(f_aperture));
+
+    bool inside_side = dot(p, normal) <= -c || (f_aperture > PI / 2.0 && dot(p, side) < 0.0);
+    bool inside = len >= inner && len <= outer && inside_side;
+
+    float d = min(distance_arc(p, inner, c), distance_arc(p, outer, c));
+    // Side eroded by the corner radius, which starts where it meets the other one when the arc is narrow.
+    float first = sqrt(max(inner * inner - c * c, 0.0));
+    if (f_aperture <= PI / 2.0) {
+        first = max(first, c * cos(f_aperture) / sin(f_aperture));
+    }
+    float last = sqrt(max(outer * outer - c * c, 0.0));
+    if (last >= first) {
+        d = min(d, length(p - (clamp(dot(p, side), first, last) * side - c * normal)));
+    }
+    return (inside ? -d : d) - c;
+}
+
+void main()
+{
+    float d = distance_shape(g_local);
+    // Analytic anti-aliasing : the coverage of the pixel by the arc.
+    float g = length(vec2(dFdx(d), dFdy(d)));
+    float coverage = clamp(0.5 - d / max(g, 1e-4), 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    frag = vec4(f_color.rgb, f_color.a * coverage);
+}
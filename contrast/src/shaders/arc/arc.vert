@@ -0,0 +1,30 @@
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 target_pos;
+layout (location = 2) in vec3 anim_pos;
+layout (location = 3) in vec4 color;
+layout (location = 4) in vec4 target_color;
+layout (location = 5) in vec3 anim_color;
+layout (location = 6) in vec4 radii;
+layout (location = 7) in vec3 anim_inner_radius;
+layout (location = 8) in vec3 anim_outer_radius;
+layout (location = 9) in vec4 angles;
+layout (location = 10) in vec3 anim_start_angle;
+layout (location = 11) in vec3 anim_end_angle;
+layout (location = 12) in vec4 corner_pad;
+layout (location = 13) in vec3 anim_corner_radius;
+layout (location = 14) in vec3 anim_pad_angle;
+
+out vec4 v_color;
+out vec2 v_radii;
+out vec2 v_angles;
+out float v_corner_radius;
+out float v_pad_angle;
+
+void main() {
+    gl_Position = mix(vec4(pos, 1.), vec4(target_pos, 1.), progress(anim_pos));
+    v_color = mix(color, target_color, progress(anim_color));
+    v_radii = vec2(mix(radii.x, radii.y, progress(anim_inner_radius)), mix(radii.z, radii.w, progress(anim_outer_radius)));
+    v_angles = vec2(mix(angles.x, angles.y, progress(anim_start_angle)), mix(angles.z, angles.w, progress(anim_end_angle)));
+    v_corner_radius = mix(corner_pad.x, corner_pad.y, progress(anim_corner_radius));
+    v_pad_angle = mix(corner_pad.z, corner_pad.w, progress(anim_pad_angle));
+}
@@ -0,0 +1,10 @@
+in vec4 f_color;
+in vec2 f_tex;
+out vec4 out_color;
+
+uniform sampler2D images;
+
+void main()
+{
+    out_color = f_color * texture(images, f_tex);
+}
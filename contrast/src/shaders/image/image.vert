@@ -0,0 +1,25 @@
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 target_pos;
+layout (location = 2) in vec3 anim_pos;
+layout (location = 3) in vec2 size;
+layout (location = 4) in vec2 target_size;
+layout (location = 5) in vec3 anim_size;
+layout (location = 6) in vec4 color;
+layout (location = 7) in vec4 target_color;
+layout (location = 8) in vec3 anim_color;
+layout (location = 9) in vec2 rotation;
+layout (location = 10) in vec3 anim_rotation;
+layout (location = 11) in vec4 coordinates;
+
+out vec2 v_size;
+out vec4 v_color;
+out float v_rotation;
+flat out vec4 v_coordinates;
+
+void main() {
+    gl_Position = mix(vec4(pos, 1.), vec4(target_pos, 1.), progress(anim_pos));
+    v_size = mix(size, target_size, progress(anim_size));
+    v_color = mix(color, target_color, progress(anim_color));
+    v_rotation = mix(rotation.x, rotation.y, progress(anim_rotation));
+    v_coordinates = coordinates;
+}
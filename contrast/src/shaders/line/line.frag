@@ -1,7 +1,60 @@
 out vec4 out_color;
-in vec4 f_color;
+flat in vec4 f_color;
+flat in vec4 f_target_color;
+flat in vec2 f_span;
+in vec2 f_line;
+flat in float f_width;
+flat in float f_length;
+flat in vec4 f_dashes;
+flat in float f_dash_offset;
+flat in uint f_cap;
+flat in float f_join;
 
+const uint BUTT = 0u;
+const uint ROUND = 1u;
+
+// Returns the distance from 's' to the interval [a, b], which is far away when it is empty.
+float gap_to(float s, float a, float b)
+{
+  if (a > b) {
+    return 1e20;
+  }
+  return max(max(a - s, s - b), 0.0);
+}
+
+// Returns the distance along the line from 's' to the nearest dash,
+// the whole line being a single dash when it is solid.
+// The pattern holds at most 2 dashes and their gaps, see MAX_DASHES in linemark.rs.
+float gap(float s)
+{
+  float pattern = f_dashes.x + f_dashes.y + f_dashes.z + f_dashes.w;
+  if (pattern <= 0.0) {
+    return gap_to(s, 0.0, f_length);
+  }
+  float period = floor((s + f_dash_offset) / pattern) * pattern - f_dash_offset;
+  float result = 1e20;
+  for (int k = -1; k <= 1; k++) {
+    float first = period + float(k) * pattern;
+    float second = first + f_dashes.x + f_dashes.y;
+    result = min(result, gap_to(s, max(first, 0.0), min(first + f_dashes.x, f_length)));
+    result = min(result, gap_to(s, max(second, 0.0), min(second + f_dashes.z, f_length)));
+  }
+  return result;
+}
 
 void main() {
-	out_color = f_color;
+  vec2 line = f_line;
+  if (f_join >= 0.0) {
+    if (length(vec2(line.x - f_join, line.y)) > f_width) {
+      discard;
+    }
+    line = vec2(f_join, 0.0);
+  }
+  // The dashes are extended by their caps : a half square or a half disc.
+  float g = gap(line.x);
+  if ((f_cap == BUTT && g > 0.0) || g > f_width || (f_cap == ROUND && g*g + line.y*line.y > f_width*f_width)) {
+    discard;
+  }
+  float along = f_span.y > f_span.x ? clamp((line.x - f_span.x) / (f_span.y - f_span.x), 0.0, 1.0) : 0.0;
+  out_color = mix(f_color, f_target_color, along);
 }
@@ -1,24 +1,49 @@
-layout (location = 0) in vec4 color;
-layout (location = 1) in vec3 origin;
-layout (location = 2) in vec3 target;
-layout (location = 3) in vec3 previous;
-layout (location = 4) in vec3 next;
-layout (location = 5) in float thickness;
+layout (location = 0) in uvec4 colors;
+layout (location = 1) in vec3 old_origin;
+layout (location = 2) in vec3 origin;
+layout (location = 3) in vec3 old_target;
+layout (location = 4) in vec3 target;
+layout (location = 5) in vec3 old_previous;
+layout (location = 6) in vec3 previous;
+layout (location = 7) in vec3 old_next;
+layout (location = 8) in vec3 next;
+layout (location = 9) in vec4 thickness_miter_dash;
+layout (location = 10) in vec4 distances;
+layout (location = 11) in vec4 dashes;
+layout (location = 12) in vec4 anim_start;
+layout (location = 13) in vec4 anim_duration;
+layout (location = 14) in uvec2 anim_easing_style;
 
 uniform mat4 projection;
 out vec4 v_color;
+out vec4 v_target_color;
 out float v_rotation;
 out float v_thick;
 out uint v_mode;
 out vec4 v_target;
 out vec4 v_previous;
 out vec4 v_next;
+out vec2 v_distance;
+out vec4 v_dashes;
+out float v_dash_offset;
+out float v_miter_limit;
+out uint v_style;
 
 void main() {
-  gl_Position = vec4(origin,1.0);
-  v_target = vec4(target,1.0);
-  v_previous = vec4(previous,1.0);
-  v_next = vec4(next,1.0);
-  v_color = color;
-  v_thick = thickness;
+  // Properties are in this order : color, points and thickness.
+  uint easing = anim_easing_style.x;
+  float p = progress(anim_start, anim_duration, easing, 1);
+  gl_Position = vec4(mix(old_origin, origin, p),1.0);
+  v_target = vec4(mix(old_target, target, p),1.0);
+  v_previous = vec4(mix(old_previous, previous, p),1.0);
+  v_next = vec4(mix(old_next, next, p),1.0);
+  float pc = progress(anim_start, anim_duration, easing, 0);
+  v_color = mix(unpack_color(colors.x), unpack_color(colors.y), pc);
+  v_target_color = mix(unpack_color(colors.z), unpack_color(colors.w), pc);
+  v_thick = mix(thickness_miter_dash.x, thickness_miter_dash.y, progress(anim_start, anim_duration, easing, 2));
+  v_distance = vec2(mix(distances.x, distances.y, p), mix(distances.z, distances.w, p));
+  v_dashes = dashes;
+  v_miter_limit = thickness_miter_dash.z;
+  v_dash_offset = thickness_miter_dash.w;
+  v_style = anim_easing_style.y;
 }
@@ -5,12 +5,21 @@ const float TWO_PI = 2*PI;
 in vec4 f_color;
 flat in uint f_shape;
 flat in uint f_target_shape;
-in float f_start_shape;
+in float f_shape_progress;
+in vec4 f_stroke_color;
+in float f_stroke_width;
+flat in uint f_fill;
+in vec2 f_size;
+in float f_corner_radius;
 
 in vec2 g_uv;
 out vec4 frag;
 
-uniform float t;
+// Signed distance fields of the custom shapes, see contrast/src/marks/shapeatlas.rs.
+uniform sampler2D shapes;
+const uint FIRST_CUSTOM_SHAPE = 256u;
+const float SHAPE_SIZE = 64.;
+const float SHAPE_ATLAS_SIZE = 1024.;
 
 float distance_shape(uint shape)
 {
@@ -19,7 +28,15 @@ float distance_shape(uint shape)
     }
 
     else if (shape == 1u) {//Rectangle
-        return 0.;
+        if (f_corner_radius <= 0.) {
+            return max(abs(g_uv.x), abs(g_uv.y)) - 1.0;
+        }
+        // Rounded rectangle, whose distance is computed in pixels then brought back
+        // to the scale of the unrounded one along its smallest side.
+        vec2 half_size = f_size / 2.;
+        float radius = min(f_corner_radius, min(half_size.x, half_size.y));
+        vec2 q = abs(g_uv * half_size) - half_size + radius;
+        return (length(max(q, 0.)) + min(max(q.x, q.y), 0.) - radius) / min(half_size.x, half_size.y);
     }
 
     else if (shape == 2u) {//Triangle
@@ -210,11 +227,19 @@ float distance_shape(uint shape)
         return min(r3,max(.75*r1,r2));
     }
 
+    else if (shape >= FIRST_CUSTOM_SHAPE) { //Custom, read in its field, without reading the next ones
+        uint index = shape - FIRST_CUSTOM_SHAPE;
+        uint per_row = uint(SHAPE_ATLAS_SIZE / SHAPE_SIZE);
+        vec2 origin = vec2(float(index % per_row), float(index / per_row)) * SHAPE_SIZE;
+        vec2 texel = clamp((g_uv * 0.5 + 0.5) * SHAPE_SIZE, 0.5, SHAPE_SIZE - 0.5);
+        return textureLod(shapes, (origin + texel) / SHAPE_ATLAS_SIZE, 0.).r;
+    }
+
     return length(g_uv.xy);
 }
 
 float interpolation_distance_shape() {
-    return mix(distance_shape(f_shape), distance_shape(f_target_shape), min(t - f_start_shape, 1.0));
+    return mix(distance_shape(f_shape), distance_shape(f_target_shape), f_shape_progress);
 }
 
 void main()
@@ -226,5 +251,19 @@ void main()
     {
         discard;
     }
-    frag = f_color;
+    // Distance in pixels to the outline, inside the shape.
+    float g = length(vec2(dFdx(d), dFdy(d)));
+    float inside = g > 0. ? -d/g : f_stroke_width;
+    if(f_stroke_width > 0. && inside < f_stroke_width)
+    {
+        frag = f_stroke_color;
+    }
+    else if(f_fill == 1u)
+    {
+        frag = f_color;
+    }
+    else
+    {
+        discard;
+    }
 }
@@ -1,18 +1,19 @@
 layout (location = 0) in vec3 pos;
 layout (location = 1) in vec3 target_pos;
-layout (location = 2) in float start_pos;
+layout (location = 2) in vec3 anim_pos;
 layout (location = 3) in vec2 size;
 layout (location = 4) in vec2 target_size;
-layout (location = 5) in float start_size;
+layout (location = 5) in vec3 anim_size;
 layout (location = 6) in vec4 color;
 layout (location = 7) in vec4 target_color;
-layout (location = 8) in float start_color;
-layout (location = 9) in float rotation;
-layout (location = 10) in float target_rotation;
-layout (location = 11) in float start_rotation;
-layout (location = 12) in uint shape;
-layout (location = 13) in uint target_shape;
-layout (location = 14) in float start_shape;
+layout (location = 8) in vec3 anim_color;
+layout (location = 9) in vec4 rotation_stroke;
+layout (location = 10) in vec4 anim_start;
+layout (location = 11) in vec4 anim_duration;
+layout (location = 12) in uvec4 shapes_stroke_colors;
+layout (location = 13) in uvec2 easing_fill;
+layout (location = 14) in vec2 corner_radius;
+layout (location = 15) in vec3 anim_corner_radius;
 
 uniform mat4 projection;
 
@@ -21,17 +22,22 @@ out vec4 v_color;
 out float v_rotation;
 flat out uint v_shape;
 flat out uint v_target_shape;
-out float v_start_shape;
-
-uniform float t;
-
+out float v_shape_progress;
+out vec4 v_stroke_color;
+out float v_stroke_width;
+flat out uint v_fill;
+out float v_corner_radius;
 
 void main() {
-    gl_Position = mix(vec4(pos, 1.), vec4(target_pos, 1.), min(t - start_pos, 1.0));
-    v_size = mix(size, target_size, min(t - start_size, 1.0));
-    v_color = mix(color, target_color, min(t - start_color, 1.0));
-    v_rotation = mix(rotation, target_rotation, min(t - start_rotation, 1.0));
-    v_shape = shape;
-    v_target_shape = target_shape;
-    v_start_shape = start_shape;
+    gl_Position = mix(vec4(pos, 1.), vec4(target_pos, 1.), progress(anim_pos));
+    v_size = mix(size, target_size, progress(anim_size));
+    v_color = mix(color, target_color, progress(anim_color));
+    v_rotation = mix(rotation_stroke.x, rotation_stroke.y, progress(anim_start, anim_duration, easing_fill.x, 0));
+    v_shape = shapes_stroke_colors.x;
+    v_target_shape = shapes_stroke_colors.y;
+    v_shape_progress = progress(anim_start, anim_duration, easing_fill.x, 2);
+    v_stroke_color = mix(unpack_color(shapes_stroke_colors.z), unpack_color(shapes_stroke_colors.w), progress(anim_start, anim_duration, easing_fill.x, 3));
+    v_stroke_width = mix(rotation_stroke.z, rotation_stroke.w, progress(anim_start, anim_duration, easing_fill.x, 1));
+    v_fill = easing_fill.y;
+    v_corner_radius = mix(corner_radius.x, corner_radius.y, progress(anim_corner_radius));
 }
@@ -1,6 +1,27 @@
 out vec4 out_color;
 in vec4 f_color;
+flat in vec4 f_gradient_color;
+flat in vec4 f_gradient;
+flat in uint f_gradient_kind;
+in vec2 f_position;
+
+const uint LINEAR = 1u;
+const uint RADIAL = 2u;
+
+// Returns how far the fragment is from the color of the polygon to the color of the gradient.
+// An empty gradient has the color of the gradient everywhere, like in SVG.
+float gradient_progress()
+{
+  if (f_gradient_kind == LINEAR) {
+    vec2 d = f_gradient.zw - f_gradient.xy;
+    return dot(d, d) > 0.0 ? dot(f_position - f_gradient.xy, d) / dot(d, d) : 1.0;
+  }
+  else if (f_gradient_kind == RADIAL) {
+    return f_gradient.z > 0.0 ? distance(f_position, f_gradient.xy) / f_gradient.z : 1.0;
+  }
+  return 0.0;
+}
 
 void main() {
-	out_color = f_color;
+	out_color = mix(f_color, f_gradient_color, clamp(gradient_progress(), 0.0, 1.0));
 }
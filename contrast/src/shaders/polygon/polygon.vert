@@ -1,14 +1,24 @@
-layout (location = 0) in vec4 color;
-layout (location = 1) in float rotation;
-layout (location = 2) in vec3 origin;
-layout (location = 3) in vec3 target;
-layout (location = 4) in vec3 previous;
-layout (location = 5) in vec3 next;
-layout (location = 6) in float thickness;
-layout (location = 7) in vec3 polygon_centroid;
-layout (location = 8) in int fill;
+layout (location = 0) in uvec4 colors;
+layout (location = 1) in vec4 gradient;
+layout (location = 2) in vec3 old_origin;
+layout (location = 3) in vec3 origin;
+layout (location = 4) in vec3 old_target;
+layout (location = 5) in vec3 target;
+layout (location = 6) in vec3 old_previous;
+layout (location = 7) in vec3 previous;
+layout (location = 8) in vec3 old_next;
+layout (location = 9) in vec3 next;
+layout (location = 10) in vec3 old_centroid;
+layout (location = 11) in vec3 polygon_centroid;
+layout (location = 12) in vec4 rotation_stroke;
+layout (location = 13) in vec4 anim_start;
+layout (location = 14) in vec4 anim_duration;
+layout (location = 15) in uvec2 anim_easing_fill;
 
 out vec4 v_color;
+out vec4 v_gradient_color;
+out vec4 v_gradient;
+out uint v_gradient_kind;
 out float v_rotation;
 out float v_thick;
 out vec4 v_target;
@@ -18,13 +28,20 @@ out vec3 v_centroid;
 out int v_fill;
 
 void main() {
-  gl_Position = vec4(origin,1.0);
-  v_target = vec4(target,1.0);
-  v_previous = vec4(previous,1.0);
-  v_next = vec4(next,1.0);
-  v_color = color;
-  v_rotation = rotation;
-  v_thick = thickness;
-  v_centroid = polygon_centroid;
-  v_fill = fill;
+  // Properties are in this order : color, points, rotation and stroke width.
+  uint easing = anim_easing_fill.x;
+  float p = progress(anim_start, anim_duration, easing, 1);
+  gl_Position = vec4(mix(old_origin, origin, p),1.0);
+  v_target = vec4(mix(old_target, target, p),1.0);
+  v_previous = vec4(mix(old_previous, previous, p),1.0);
+  v_next = vec4(mix(old_next, next, p),1.0);
+  float pc = progress(anim_start, anim_duration, easing, 0);
+  v_color = mix(unpack_color(colors.x), unpack_color(colors.y), pc);
+  v_gradient_color = mix(unpack_color(colors.z), unpack_color(colors.w), pc);
+  v_gradient = gradient;
+  v_gradient_kind = anim_easing_fill.y >> 2;
+  v_rotation = mix(rotation_stroke.x, rotation_stroke.y, progress(anim_start, anim_duration, easing, 2));
+  v_thick = mix(rotation_stroke.z, rotation_stroke.w, progress(anim_start, anim_duration, easing, 3));
+  v_centroid = mix(old_centroid, polygon_centroid, p);
+  v_fill = int(anim_easing_fill.y & 3u);
 }
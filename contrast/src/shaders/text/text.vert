@@ -1,5 +1,8 @@
 layout (location = 0) in vec3 pos;
 layout (location = 1) in vec2 tex;
+layout (location = 2) in vec3 old_origin;
+layout (location = 3) in vec3 origin;
+layout (location = 4) in vec3 anim_origin;
 
 out vec2 TexCoords;
 
@@ -7,6 +10,7 @@ uniform mat4 projection;
 
 void main()
 {
-    gl_Position = projection * vec4(pos, 1.0);
+    // The glyphs are built at the origin of the text, then moved to its animated position.
+    gl_Position = projection * vec4(pos + mix(old_origin, origin, progress(anim_origin)), 1.0);
     TexCoords = tex;
 }
@@ -0,0 +1,548 @@
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use properties::color::Color;
+use properties::position::Position;
+use crate::markscontainer::Contrast;
+use crate::marks::mark::Mark;
+use crate::marks::pointmark::{PointMark, Shape};
+use crate::marks::shapeatlas::ShapeAtlas;
+use crate::marks::linemark::{LineMark, LineCap, LineJoin};
+use crate::marks::polygonmark::PolygonMark;
+use crate::marks::textmark::{TextMark, FaceCache};
+use crate::marks::imagemark::{ImageMark, ImageAtlas};
+use crate::marks::arcmark::ArcMark;
+use crate::triangulation::FillRule;
+use crate::vector::{self, Paint};
+use crate::marks::polygonmark::Gradient;
+use crate::error::Error;
+use crate::error::Result;
+use crate::MarkMacro;
+
+/// Returns the outline of 'shape' as SVG path data, in the square from (-1, -1) to (1, 1)
+/// where the shaders draw the shape of a point mark, with the y axis going down.
+/// The paths are filled with the nonzero rule, so the holes go the other way round.
+/// The outlines follow the distance functions of point.frag, and the custom shapes keep
+/// the outline they were registered with.
+pub(crate) fn shape_path(shape : Shape, shapes : &ShapeAtlas) -> String {
+    let path = match shape {
+        Shape::None => "",
+        Shape::Custom(index) => return shapes.outlines.get(index as usize).cloned().unwrap_or_default(),
+        Shape::Rectangle => "M-1 -1L1 -1L1 1L-1 1Z",
+        Shape::Triangle => "M0 1L-0.866 -0.5L0.866 -0.5Z",
+        Shape::Circle => "M0.6 0A0.6 0.6 0 0 1 -0.6 0A0.6 0.6 0 0 1 0.6 0Z",
+        Shape::Point => "M0.1 0A0.1 0.1 0 0 1 -0.1 0A0.1 0.1 0 0 1 0.1 0Z",
+        Shape::Squircle => return squircle_path(),
+        Shape::Diamond => "M0 -1L1 0L0 1L-1 0Z",
+        Shape::Donut => "M0.9487 0A0.9487 0.9487 0 0 1 -0.9487 0A0.9487 0.9487 0 0 1 0.9487 0Z\
+                         M0.4472 0A0.4472 0.4472 0 0 0 -0.4472 0A0.4472 0.4472 0 0 0 0.4472 0Z",
+        Shape::Pin => "M0.3738 -0.15A0.3738 0.3738 0 0 1 -0.3738 -0.15A0.3738 0.3738 0 0 1 0.3738 -0.15Z\
+                       M-0.343 0L-0.1323 0A0.2 0.2 0 0 0 0.1323 0L0.343 0A2 2 0 0 1 0 0.5341A2 2 0 0 1 -0.343 0Z\
+                       M0.2 -0.15A0.2 0.2 0 0 0 -0.2 -0.15A0.2 0.2 0 0 0 0.2 -0.15Z",
+        Shape::Club => "M0.2353 -0.225A0.2353 0.2353 0 0 1 -0.2353 -0.225A0.2353 0.2353 0 0 1 0.2353 -0.225Z\
+                        M0.4301 0.1125A0.2353 0.2353 0 0 1 -0.0404 0.1125A0.2353 0.2353 0 0 1 0.4301 0.1125Z\
+                        M0.0404 0.1125A0.2353 0.2353 0 0 1 -0.4301 0.1125A0.2353 0.2353 0 0 1 0.0404 0.1125Z\
+                        M0.0295 0.2A0.625 0.625 0 0 0 0.15 0.5L-0.15 0.5A0.625 0.625 0 0 0 -0.0295 0.2Z",
+        Shape::Heart => "M0 -0.4041L0.4041 0L0 0.4041L-0.4041 0Z\
+                         M0.4877 -0.202A0.2857 0.2857 0 0 1 -0.0837 -0.202A0.2857 0.2857 0 0 1 0.4877 -0.202Z\
+                         M0.0837 -0.202A0.2857 0.2857 0 0 1 -0.4877 -0.202A0.2857 0.2857 0 0 1 0.0837 -0.202Z",
+        Shape::Spade => "M0 -0.4808L0.3435 -0.1374L0 0.2061L-0.3435 -0.1374Z\
+                         M0.4146 0.0343A0.2429 0.2429 0 0 1 -0.0711 0.0343A0.2429 0.2429 0 0 1 0.4146 0.0343Z\
+                         M0.0711 0.0343A0.2429 0.2429 0 0 1 -0.4146 0.0343A0.2429 0.2429 0 0 1 0.0711 0.0343Z\
+                         M0.0255 0.1A0.625 0.625 0 0 0 0.15 0.5L-0.15 0.5A0.625 0.625 0 0 0 -0.0255 0.1Z",
+        Shape::Chevron => "M-0.4714 0L0 -0.4714L0.2357 -0.2357L0 0L0.2357 0.2357L0 0.4714Z",
+        Shape::Clover => "M0.2353 -0.225A0.2353 0.2353 0 0 1 -0.2353 -0.225A0.2353 0.2353 0 0 1 0.2353 -0.225Z\
+                          M0.4301 0.1125A0.2353 0.2353 0 0 1 -0.0404 0.1125A0.2353 0.2353 0 0 1 0.4301 0.1125Z\
+                          M0.0404 0.1125A0.2353 0.2353 0 0 1 -0.4301 0.1125A0.2353 0.2353 0 0 1 0.0404 0.1125Z",
+        Shape::Ring => "M0.5 0A0.5 0.5 0 0 1 -0.5 0A0.5 0.5 0 0 1 0.5 0Z\
+                        M0.25 0A0.25 0.25 0 0 0 -0.25 0A0.25 0.25 0 0 0 0.25 0Z",
+        Shape::Tag => "M-0.3333 0L-0.1667 -0.1667L0.5 -0.1667L0.5 0.1667L-0.1667 0.1667Z",
+        Shape::Cross => "M-0.4714 -0.2357L-0.2357 -0.4714L0 -0.2357L0.2357 -0.4714L0.4714 -0.2357L0.2357 0\
+                         L0.4714 0.2357L0.2357 0.4714L0 0.2357L-0.2357 0.4714L-0.4714 0.2357L-0.2357 0Z",
+        Shape::Asterisk => "M-0.4243 0.2828L0.2828 -0.4243L0.4243 -0.2828L-0.2828 0.4243Z\
+                            M-0.4243 -0.2828L-0.2828 -0.4243L0.4243 0.2828L0.2828 0.4243Z\
+                            M-0.5 -0.1L0.5 -0.1L0.5 0.1L-0.5 0.1ZM-0.1 -0.5L0.1 -0.5L0.1 0.5L-0.1 0.5Z",
+        Shape::Infinity => "M0.4982 0A0.2857 0.2857 0 0 1 -0.0732 0A0.2857 0.2857 0 0 1 0.4982 0Z\
+                            M0.3458 0A0.1333 0.1333 0 0 0 0.0792 0A0.1333 0.1333 0 0 0 0.3458 0Z\
+                            M0.0732 0A0.2857 0.2857 0 0 1 -0.4982 0A0.2857 0.2857 0 0 1 0.0732 0Z\
+                            M-0.0792 0A0.1333 0.1333 0 0 0 -0.3458 0A0.1333 0.1333 0 0 0 -0.0792 0Z",
+        Shape::Arrow => "M-0.5 0L0 -0.5L0 -0.25L0.4167 -0.25L0.4167 0.25L0 0.25L0 0.5Z"
+    };
+    path.to_string()
+}
+
+/// The squircle x^4 + y^4 = 1 has no exact SVG path, so it is approximated by a polygon.
+fn squircle_path() -> String {
+    let mut path = String::new();
+    for i in 0..64 {
+        let angle = i as f32 * std::f32::consts::PI / 32.0;
+        let (x, y) = (angle.cos(), angle.sin());
+        let _ = write!(path, "{}{} {}", if i == 0 { 'M' } else { 'L' }, x.signum() * x.abs().sqrt(), y.signum() * y.abs().sqrt());
+    }
+    path.push('Z');
+    path
+}
+
+/// Returns the outline of a rectangle centered on the origin, whose half sides are 'w' and 'h' pixels,
+/// with corners rounded like in point.frag : the radius is reduced to the half of the smallest side.
+pub(crate) fn rounded_rectangle_path(w : f32, h : f32, radius : f32) -> String {
+    let r = radius.min(w).min(h);
+    format!("M{} {}L{} {}A{r} {r} 0 0 1 {} {}L{} {}A{r} {r} 0 0 1 {} {}L{} {}A{r} {r} 0 0 1 {} {}L{} {}A{r} {r} 0 0 1 {} {}Z",
+            r - w, -h, w - r, -h, w, r - h, w, h - r, w - r, h, r - w, h, -w, h - r, -w, r - h, r - w, -h, r = r)
+}
+
+/// Returns the attributes painting the fill or the stroke with 'color'.
+fn paint(attribute : &str, color : &Color) -> String {
+    let component = |c : f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("{}=\"#{:02x}{:02x}{:02x}\" {}-opacity=\"{}\"", attribute, component(color.r), component(color.g), component(color.b),
+            attribute, color.a.clamp(0.0, 1.0))
+}
+
+/// Returns the points of a polyline or of a polygon in the format of SVG.
+fn points(positions : &[Position]) -> String {
+    positions.iter().map(|p| format!("{},{}", p.x, p.y)).collect::<Vec<_>>().join(" ")
+}
+
+/// Encode 'data' in base64, to embed the images in data URLs.
+fn base64(data : &[u8]) -> String {
+    const ALPHABET : &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let word = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            encoded.push(if i <= chunk.len() { ALPHABET[(word >> (18 - 6 * i) & 63) as usize] as char } else { '=' });
+        }
+    }
+    encoded
+}
+
+/// Escape the characters having a meaning in XML.
+fn escape(text : &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl Contrast {
+    /// Returns the marks of contrast as an SVG document of 'width' x 'height' pixels, with
+    /// the values displayed at the current time of the clock.
+    /// The layers are walked from the deepest one, so the marks of the layer 0 are on top,
+    /// and the marks of a layer are written in the order they were added.
+    /// Each point mark is a path with the outline of its shape, stroked inside like the
+    /// renderer does, each line mark a stroked
+    /// polyline, each polygon mark a filled polygon or a polygon stroked inside its sides like
+    /// the renderer does, each text mark a text using the file of its registered font, and each
+    /// image mark its image embedded in PNG.
+    pub fn export_svg(&self, width : u32, height : u32) -> String {
+        let now = self.get_time();
+        let mut layers : Vec<_> = self.layers.iter().collect();
+        layers.sort_by_key(|layer| std::cmp::Reverse(layer.depth));
+
+        let cache = self.fonts.borrow();
+        let mut body = String::new();
+        let mut fonts = Vec::new();
+        let mut ids = 0;
+        for layer in layers {
+            for mark in layer.marks.iter().filter(|mark| mark.is_valid()) {
+                match mark {
+                    Mark::Point(point) => write_point(&mut body, point, &self.shapes, now, &mut ids),
+                    Mark::Line(line) => write_line(&mut body, line, &self.shapes, now, &mut ids),
+                    Mark::Polygon(polygon) => write_polygon(&mut body, polygon, now, &mut ids),
+                    Mark::Image(image) => write_image(&mut body, image, &self.images, now, &mut ids),
+                    Mark::Arc(arc) => write_arc(&mut body, arc, now),
+                    Mark::Text(text) => {
+                        if let Some(face) = cache.cached.get(text.get_font()) {
+                            write_text(&mut body, text, face, now);
+                            if !fonts.contains(&(&face.name, &face.font)) {
+                                fonts.push((&face.name, &face.font));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n", width, height);
+        if !fonts.is_empty() {
+            svg.push_str("<style>\n");
+            for (name, font) in fonts {
+                let _ = writeln!(svg, "@font-face {{ font-family: \"{}\"; src: url(\"{}\"); }}", escape(name), escape(font));
+            }
+            svg.push_str("</style>\n");
+        }
+        svg.push_str(&body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Same behavior than export_svg, then save the document at 'path'.
+    pub fn save_svg<P : AsRef <Path>>(&self, width : u32, height : u32, path : P) -> Result<()> {
+        let file = File::create(path).map_err(|err| Error::ImageWrite(err.to_string()))?;
+        BufWriter::new(file).write_all(self.export_svg(width, height).as_bytes()).map_err(|err| Error::ImageWrite(err.to_string()))
+    }
+}
+
+fn write_point(svg : &mut String, point : &PointMark, shapes : &ShapeAtlas, now : f32, ids : &mut usize) {
+    let shape = point.shape.value_at(now);
+    let size = point.size.value_at(now);
+    if shape == Shape::None || size.width == 0.0 || size.height == 0.0 {
+        return;
+    }
+    let center = point.center.value_at(now);
+    let corner_radius = point.corner_radius.value_at(now);
+    let (scale, path) = if shape == Shape::Rectangle && corner_radius > 0.0 {
+        // The corners are rounded in pixels, so the outline is not scaled.
+        ((1.0, 1.0), rounded_rectangle_path(size.width / 2.0, size.height / 2.0, corner_radius))
+    } else {
+        ((size.width / 2.0, size.height / 2.0), shape_path(shape, shapes))
+    };
+    if point.fill {
+        let _ = writeln!(svg, "<path transform=\"translate({} {}) rotate({}) scale({} {})\" d=\"{}\" {}/>",
+                         center.x, center.y, point.rotation.value_at(now).to_degrees(), scale.0, scale.1,
+                         path, paint("fill", &point.color.value_at(now)));
+    }
+    let stroke_width = point.stroke_width.value_at(now);
+    if stroke_width > 0.0 {
+        // Like the stroke of a polygon, the stroke is clipped by the shape, whose path is in pixels
+        // so that the width of the stroke is not scaled with the shape.
+        let path = match vector::point_path(point, shapes, now) {
+            Some(path) => vector::path_data(&path),
+            None => return
+        };
+        *ids += 1;
+        let _ = writeln!(svg, "<clipPath id=\"point-{0}\"><path d=\"{1}\"/></clipPath>\n\
+                               <path d=\"{1}\" fill=\"none\" {2} stroke-width=\"{3}\" clip-path=\"url(#point-{0})\"/>",
+                         ids, path, paint("stroke", &point.stroke_color.value_at(now)), 2.0 * stroke_width);
+    }
+}
+
+fn write_line(svg : &mut String, line : &LineMark, shapes : &ShapeAtlas, now : f32, ids : &mut usize) {
+    let positions = line.drawn_points_at(now);
+    if positions.len() < 2 {
+        return;
+    }
+    let cap = match line.cap {
+        LineCap::Butt => "butt",
+        LineCap::Round => "round",
+        LineCap::Square => "square"
+    };
+    let join = match line.join {
+        LineJoin::Miter(limit) => format!("miter\" stroke-miterlimit=\"{}", limit.max(1.0)),
+        LineJoin::Round => String::from("round"),
+        LineJoin::Bevel => String::from("bevel")
+    };
+    let dashes = |offset : f32| if line.dashes.is_empty() { String::new() } else {
+        let lengths : Vec<String> = line.dashes.iter().map(|length| length.to_string()).collect();
+        format!(" stroke-dasharray=\"{}\" stroke-dashoffset=\"{}\"", lengths.join(" "), offset)
+    };
+    let write = |svg : &mut String, positions : &[Position], color : &Color, cap : &str, offset : f32| {
+        let _ = writeln!(svg, "<polyline points=\"{}\" fill=\"none\" {} stroke-width=\"{}\" stroke-linecap=\"{}\" stroke-linejoin=\"{}\"{}/>",
+                         points(positions), paint("stroke", color), line.thickness.value_at(now), cap, join, dashes(offset));
+    };
+    match line.drawn_colors_at(now) {
+        // A line whose points have their own colors is drawn in pieces, like in the other vector formats.
+        Some(colors) => for piece in vector::color_pieces(&positions, &colors).iter().filter(|piece| piece.points.len() >= 2) {
+            write(svg, &piece.points, &piece.color, if piece.end { cap } else { "butt" }, line.dash_offset + piece.distance);
+        },
+        None => write(svg, &positions, &line.color.value_at(now), cap, line.dash_offset)
+    }
+    for marker in line.marker_points() {
+        write_point(svg, &marker, shapes, now, ids);
+    }
+}
+
+/// Writes the gradient of 'fill', named after the polygon 'id', and returns the attributes painting
+/// 'attribute' with it. A color needs no gradient.
+fn write_gradient(svg : &mut String, attribute : &str, fill : &Paint, id : usize) -> String {
+    let (color, gradient) = match fill {
+        Paint::Color(color) => return paint(attribute, color),
+        Paint::Gradient(color, gradient) => (color, gradient)
+    };
+    let stop = |offset : u32, color : &Color| {
+        let component = |c : f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!("<stop offset=\"{}\" stop-color=\"#{:02x}{:02x}{:02x}\" stop-opacity=\"{}\"/>", offset, component(color.r),
+                component(color.g), component(color.b), color.a.clamp(0.0, 1.0))
+    };
+    let (element, geometry, end) = match gradient {
+        Gradient::Linear(from, to, end) => ("linearGradient", format!("x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"", from.x, from.y, to.x, to.y), end),
+        Gradient::Radial(center, radius, end) => ("radialGradient", format!("cx=\"{}\" cy=\"{}\" r=\"{}\"", center.x, center.y, radius), end),
+        Gradient::None => return paint(attribute, color)
+    };
+    let _ = writeln!(svg, "<{0} id=\"polygon-{1}-gradient\" gradientUnits=\"userSpaceOnUse\" {2}>{3}{4}</{0}>", element, id, geometry,
+                     stop(0, color), stop(1, end));
+    format!("{}=\"url(#polygon-{}-gradient)\"", attribute, id)
+}
+
+fn write_polygon(svg : &mut String, polygon : &PolygonMark, now : f32, ids : &mut usize) {
+    let positions = polygon.points.value_at(now);
+    let rings : Vec<&[Position]> = polygon.split(&positions).into_iter().filter(|ring| ring.len() >= 3).collect();
+    // A polygon made of several rings is a path with a subpath for each ring.
+    let outline = match rings.len() {
+        0 => return,
+        1 => format!("polygon points=\"{}\"", points(rings[0])),
+        _ => format!("path d=\"{}\"", rings.iter().map(|ring| format!("M{}Z", points(ring).replace(' ', " L"))).collect::<Vec<_>>().join(" "))
+    };
+    let fill = Paint::new(polygon.color.value_at(now), polygon.gradient);
+    let even_odd = polygon.fill_rule == FillRule::EvenOdd;
+    // The clip paths and the gradients are named after the polygons needing them.
+    if !polygon.fill || matches!(fill, Paint::Gradient(..)) {
+        *ids += 1;
+    }
+    if polygon.fill {
+        let rule = if even_odd { " fill-rule=\"evenodd\"" } else { "" };
+        let color = write_gradient(svg, "fill", &fill, *ids);
+        let _ = writeln!(svg, "<{} {}{}/>", outline, color, rule);
+    }
+    else {
+        // The stroke is inside the polygon : a stroke twice as wide is clipped by the polygon.
+        let color = write_gradient(svg, "stroke", &fill, *ids);
+        let rule = if even_odd { " clip-rule=\"evenodd\"" } else { "" };
+        let _ = writeln!(svg, "<clipPath id=\"polygon-{0}\"><{1}{4}/></clipPath>\n\
+                               <{1} fill=\"none\" {2} stroke-width=\"{3}\" stroke-linejoin=\"miter\" \
+                               stroke-miterlimit=\"1000\" clip-path=\"url(#polygon-{0})\"/>",
+                         ids, outline, color, 2.0 * polygon.stroke_width.value_at(now), rule);
+    }
+}
+
+fn write_text(svg : &mut String, text : &TextMark, face : &FaceCache, now : f32) {
+    // Each line is placed at the origin of its baseline, like in SVG.
+    let position = text.pos.value_at(now);
+    for line in face.layout(text.get_text(), text.get_layout()) {
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" {}>{}</text>",
+                         position.x + line.x as f32, position.y + line.y as f32, escape(text.get_font()), face.police,
+                         paint("fill", &text.color.value_at(now)), escape(line.text));
+    }
+}
+
+fn write_image(svg : &mut String, image : &ImageMark, images : &ImageAtlas, now : f32, ids : &mut usize) {
+    let size = image.size.value_at(now);
+    let mut png = Vec::new();
+    match images.images.get(image.get_image()) {
+        Some((pixels, _)) if size.width != 0.0 && size.height != 0.0 && pixels.write_png(&mut png).is_ok() => (),
+        _ => return
+    }
+    // The color multiplies the pixels of the image, which needs a filter unless it is white.
+    let color = image.color.value_at(now);
+    let filter = if color == Color::white() { String::new() } else {
+        *ids += 1;
+        let _ = writeln!(svg, "<filter id=\"image-{}\"><feColorMatrix type=\"matrix\" values=\"{} 0 0 0 0 0 {} 0 0 0 0 0 {} 0 0 0 0 0 {} 0\"/></filter>",
+                         ids, color.r, color.g, color.b, color.a);
+        format!(" filter=\"url(#image-{})\"", ids)
+    };
+    let center = image.center.value_at(now);
+    let _ = writeln!(svg, "<image transform=\"translate({} {}) rotate({})\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                           preserveAspectRatio=\"none\"{} href=\"data:image/png;base64,{}\"/>",
+                     center.x, center.y, image.rotation.value_at(now).to_degrees(), -size.width / 2.0, -size.height / 2.0,
+                     size.width, size.height, filter, base64(&png));
+}
+
+fn write_arc(svg : &mut String, arc : &ArcMark, now : f32) {
+    if let Some(shape) = arc.shape_at(now) {
+        let _ = writeln!(svg, "<path d=\"{}\" {}/>", shape.path(), paint("fill", &arc.color.value_at(now)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::image::Image;
+    use crate::marks::linemark::Marker;
+
+    #[test]
+    fn export_svg()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.set_clock(ManualClock::new());
+        c.add_point_mark().set_position((10.0, 20.0, 0.0)).set_size((4.0, 6.0)).set_shape(Shape::Diamond).set_color((1.0, 0.0, 0.0, 1.0));
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_thickness(2.0);
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_stroke_width(1.5);
+        c.add_point_mark().set_shape(Shape::None);
+
+        let svg = c.export_svg(100, 50);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"100\" height=\"50\" viewBox=\"0 0 100 50\">"));
+        assert!(svg.contains("<path transform=\"translate(10 20) rotate(0) scale(2 3)\" d=\"M0 -1L1 0L0 1L-1 0Z\" fill=\"#ff0000\" fill-opacity=\"1\"/>"));
+        assert!(svg.contains("<polyline points=\"0,0 5,5\""));
+        assert!(svg.contains("stroke-width=\"2\""));
+        assert!(svg.contains("<clipPath id=\"polygon-1\"><polygon points=\"0,0 5,0 5,5\"/></clipPath>"));
+        assert!(svg.contains("stroke-width=\"3\""));
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn export_svg_point_stroke()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_point_mark().set_position((10.0, 20.0, 0.0)).set_size((4.0, 6.0)).set_shape(Shape::Diamond)
+            .set_stroke_width(0.5).set_stroke_color((0.0, 0.0, 1.0, 1.0)).set_empty();
+
+        // A hollow point is only its stroke, clipped by its shape in pixels.
+        let svg = c.export_svg(100, 50);
+        assert!(!svg.contains("transform"));
+        assert!(svg.contains("<clipPath id=\"point-1\"><path d=\"M10 17L12 20L10 23L8 20Z\"/></clipPath>"));
+        assert!(svg.contains("fill=\"none\" stroke=\"#0000ff\" stroke-opacity=\"1\" stroke-width=\"1\" clip-path=\"url(#point-1)\""));
+    }
+
+    #[test]
+    fn export_svg_custom_shape()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        let shape = c.register_shape_path("M0 0L2 0L0 2Z").unwrap();
+        c.add_point_mark().set_position((10.0, 20.0, 0.0)).set_size((4.0, 4.0)).set_shape(shape);
+
+        // The outline of a custom shape is exported in place of its field.
+        let svg = c.export_svg(100, 50);
+        assert!(svg.contains("<path transform=\"translate(10 20) rotate(0) scale(2 2)\" d=\"M-1 -1L1 -1L-1 1Z\""));
+    }
+
+    #[test]
+    fn export_svg_image()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.register_image("logo", Image::new(2, 2)).unwrap();
+        c.add_image_mark().set_image("logo").set_position((10.0, 20.0)).set_size((8.0, 4.0));
+        c.add_image_mark().set_image("logo").set_size((8.0, 4.0)).set_color((1.0, 0.5, 0.0, 1.0));
+        c.add_image_mark().set_image("unknown").set_size((8.0, 4.0));
+
+        let svg = c.export_svg(100, 50);
+        assert_eq!(svg.matches("<image").count(), 2);
+        assert!(svg.contains("<image transform=\"translate(10 20) rotate(0)\" x=\"-4\" y=\"-2\" width=\"8\" height=\"4\" \
+                              preserveAspectRatio=\"none\" href=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(svg.contains("<filter id=\"image-1\"><feColorMatrix type=\"matrix\" values=\"1 0 0 0 0 0 0.5 0 0 0 0 0 0 0 0 0 0 0 1 0\"/></filter>"));
+        assert!(svg.contains("preserveAspectRatio=\"none\" filter=\"url(#image-1)\" href="));
+    }
+
+    #[test]
+    fn base64()
+    {
+        assert_eq!(super::base64(b""), "");
+        assert_eq!(super::base64(b"f"), "Zg==");
+        assert_eq!(super::base64(b"fo"), "Zm8=");
+        assert_eq!(super::base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn export_svg_line_style()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0));
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0))
+            .set_cap(LineCap::Round).set_join(LineJoin::Round).set_dashes(&[2.0, 1.0]).unwrap().set_dash_offset(0.5);
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_dashes(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+
+        let svg = c.export_svg(100, 50);
+        assert!(svg.contains("stroke-linecap=\"butt\" stroke-linejoin=\"miter\" stroke-miterlimit=\"4\"/>"));
+        assert!(svg.contains("stroke-linecap=\"round\" stroke-linejoin=\"round\" stroke-dasharray=\"2 1\" stroke-dashoffset=\"0.5\"/>"));
+        assert!(svg.contains("stroke-dasharray=\"1 2 3 4\" stroke-dashoffset=\"0\"/>"));
+    }
+
+    #[test]
+    fn export_svg_markers()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_end_marker(Marker::Circle);
+
+        let svg = c.export_svg(100, 50);
+        assert!(svg.contains("<path transform=\"translate(5 5) rotate(45) scale(2 2)\" d=\"M0.6 0A"));
+    }
+
+    #[test]
+    fn export_svg_colors()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((16.0, 0.0, 0.0)).set_colors(vec![Color::red(), Color::blue()]);
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_fill()
+         .set_color((1.0, 0.0, 0.0, 1.0)).set_gradient(Gradient::Radial((1.0, 2.0).into(), 4.0, Color::blue()));
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0))
+         .set_gradient(Gradient::Linear((0.0, 0.0).into(), (5.0, 0.0).into(), Color::green()));
+
+        let svg = c.export_svg(100, 50);
+        // The line is drawn in pieces of a single color.
+        assert_eq!(svg.matches("<polyline").count(), 17);
+        assert!(svg.contains("<polyline points=\"0,0 0.5,0\" fill=\"none\" stroke=\"#ff0000\""));
+        assert!(svg.contains("<polyline points=\"15.5,0 16,0\" fill=\"none\" stroke=\"#0000ff\""));
+        assert!(svg.contains("<radialGradient id=\"polygon-1-gradient\" gradientUnits=\"userSpaceOnUse\" cx=\"1\" cy=\"2\" r=\"4\">\
+                              <stop offset=\"0\" stop-color=\"#ff0000\" stop-opacity=\"1\"/><stop offset=\"1\" stop-color=\"#0000ff\" stop-opacity=\"1\"/>\
+                              </radialGradient>\n<polygon points=\"0,0 5,0 5,5\" fill=\"url(#polygon-1-gradient)\"/>"));
+        assert!(svg.contains("<linearGradient id=\"polygon-2-gradient\" gradientUnits=\"userSpaceOnUse\" x1=\"0\" y1=\"0\" x2=\"5\" y2=\"0\">"));
+        assert!(svg.contains("stroke=\"url(#polygon-2-gradient)\" stroke-width=\"30\""));
+    }
+
+    #[test]
+    fn export_svg_rings()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_polygon_mark()
+            .set_points(vec![(0.0, 0.0).into(), (4.0, 0.0).into(), (4.0, 4.0).into()])
+            .add_ring(vec![(1.0, 1.0).into(), (2.0, 1.0).into(), (2.0, 2.0).into()])
+            .add_ring(vec![(9.0, 9.0).into()])
+            .set_fill_rule(FillRule::EvenOdd);
+
+        let svg = c.export_svg(100, 50);
+        assert!(svg.contains("<clipPath id=\"polygon-1\"><path d=\"M0,0 L4,0 L4,4Z M1,1 L2,1 L2,2Z\" clip-rule=\"evenodd\"/></clipPath>"));
+    }
+
+    #[test]
+    fn export_svg_layers()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.set_clock(ManualClock::new());
+        c.add_layers(1);
+        c.add_point_mark().set_position((1.0, 0.0, 0.0)).set_size((2.0, 2.0)).set_shape(Shape::Rectangle);
+        c.set_current_layer(1);
+        c.add_point_mark().set_position((2.0, 0.0, 0.0)).set_size((2.0, 2.0)).set_shape(Shape::Rectangle);
+
+        // The marks of the layer 1 are behind, so they are written first.
+        let svg = c.export_svg(10, 10);
+        assert!(svg.find("translate(2 0)").unwrap() < svg.find("translate(1 0)").unwrap());
+    }
+
+    #[test]
+    fn export_svg_text()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.register_font("serif", "../contrast-renderer/crimson-b.ttf", 20).unwrap();
+        c.add_text_mark().set_font("serif").set_text("a < b").set_position((5.0, 15.0, 0.0)).set_color((0.0, 0.0, 1.0, 0.5));
+        c.add_text_mark().set_font("unknown").set_text("hidden");
+
+        let svg = c.export_svg(100, 50);
+        assert!(svg.contains("@font-face { font-family: \"serif\"; src: url(\"../contrast-renderer/crimson-b.ttf\"); }"));
+        assert!(svg.contains("<text x=\"5\" y=\"15\" font-family=\"serif\" font-size=\"20\" fill=\"#0000ff\" fill-opacity=\"0.5\">a &lt; b</text>"));
+        assert!(!svg.contains("hidden"));
+
+        // Each line of a text is written on its baseline.
+        c.add_text_mark().set_font("serif").set_text("one\ntwo").set_position((5.0, 30.0, 0.0)).set_line_spacing(2.0);
+        let svg = c.export_svg(100, 50);
+        assert!(svg.lines().any(|line| line.starts_with("<text x=\"5\" y=\"30\"") && line.ends_with(">one</text>")));
+        let two = svg.lines().find(|line| line.ends_with(">two</text>")).unwrap();
+        let y : f32 = two.split('"').nth(3).unwrap().parse().unwrap();
+        assert!(y >= 30.0 + 2.0 * 20.0);
+    }
+
+    #[test]
+    fn rounded_rectangle_path()
+    {
+        assert_eq!(super::rounded_rectangle_path(4.0, 2.0, 1.0), "M-3 -2L3 -2A1 1 0 0 1 4 -1L4 1A1 1 0 0 1 3 2L-3 2A1 1 0 0 1 -4 1L-4 -1A1 1 0 0 1 -3 -2Z");
+        // The radius is at most the half of the smallest side.
+        assert!(super::rounded_rectangle_path(4.0, 2.0, 5.0).starts_with("M-2 -2L2 -2A2 2 0 0 1 4 0"));
+    }
+
+    #[test]
+    fn squircle_path()
+    {
+        let path = super::squircle_path();
+        assert!(path.starts_with("M1 0L"));
+        assert_eq!(path.matches('L').count(), 63);
+    }
+}
@@ -0,0 +1,284 @@
+use std::cmp::Ordering;
+use properties::position::Position;
+
+/// This enum tells which points are inside a polygon whose outline
+/// crosses itself or is made of several contours.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum FillRule {
+    /// A point is inside when the outline winds around it, in any direction.
+    NonZero,
+    /// A point is inside when a ray going from it crosses the outline an odd number of times.
+    EvenOdd
+}
+
+impl Default for FillRule {
+    fn default() -> Self {
+        FillRule::NonZero
+    }
+}
+
+impl FillRule {
+    fn is_inside(self, winding : i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0
+        }
+    }
+}
+
+/// A point on a side of a polygon, at 'ratio' of the way from the point 'from' to the point 'to'.
+/// The points are numbered through every contour of the polygon, one contour after the other.
+/// Being defined by the sides rather than by a position, the triangles follow the polygon
+/// when its points are animated.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct EdgePoint {
+    pub from : usize,
+    pub to : usize,
+    pub ratio : f32
+}
+
+impl EdgePoint {
+    /// Returns the position of this point when the polygon is made of 'points'.
+    pub fn position(&self, points : &[Position]) -> Position {
+        let (a, b) = (points[self.from], points[self.to]);
+        Position { x : a.x + (b.x - a.x) * self.ratio,
+                   y : a.y + (b.y - a.y) * self.ratio,
+                   z : a.z + (b.z - a.z) * self.ratio }
+    }
+}
+
+/// A side of a polygon which is not horizontal.
+struct Edge {
+    from : usize,
+    to : usize,
+    a : [f32; 2],
+    b : [f32; 2]
+}
+
+impl Edge {
+    fn top(&self) -> f32 {
+        self.a[1].min(self.b[1])
+    }
+
+    fn bottom(&self) -> f32 {
+        self.a[1].max(self.b[1])
+    }
+
+    /// +1 when the side goes down, -1 when it goes up.
+    fn direction(&self) -> i32 {
+        if self.b[1] > self.a[1] { 1 } else { -1 }
+    }
+
+    fn ratio_at(&self, y : f32) -> f32 {
+        ((y - self.a[1]) / (self.b[1] - self.a[1])).clamp(0.0, 1.0)
+    }
+
+    fn x_at(&self, y : f32) -> f32 {
+        self.a[0] + (self.b[0] - self.a[0]) * self.ratio_at(y)
+    }
+
+    fn point_at(&self, y : f32) -> EdgePoint {
+        EdgePoint { from : self.from, to : self.to, ratio : self.ratio_at(y) }
+    }
+
+    /// Returns the ordinate where the two sides cross each other, if they do between their ends.
+    fn crossing(&self, other : &Edge) -> Option<f32> {
+        let cross = |u : [f32; 2], v : [f32; 2]| u[0] * v[1] - u[1] * v[0];
+        let (d1, d2) = ([self.b[0] - self.a[0], self.b[1] - self.a[1]], [other.b[0] - other.a[0], other.b[1] - other.a[1]]);
+        let denominator = cross(d1, d2);
+        if denominator == 0.0 {
+            return None;
+        }
+        let offset = [other.a[0] - self.a[0], other.a[1] - self.a[1]];
+        let (t, u) = (cross(offset, d2) / denominator, cross(offset, d1) / denominator);
+        if t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 { Some(self.a[1] + d1[1] * t) } else { None }
+    }
+}
+
+fn compare(a : &f32, b : &f32) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+/// Splits the inside of the polygon made of 'contours' into triangles, 'rule' telling where
+/// the inside is. The contours may be concave, cross themselves or each other.
+///
+/// The plane is cut into horizontal bands at the ordinates where a side starts, ends or crosses
+/// another one. Inside a band, no side crosses another, so the inside is a row of trapezoids,
+/// each one made of two triangles.
+pub fn triangulate(contours : &[&[Position]], rule : FillRule) -> Vec<[EdgePoint; 3]> {
+    let mut edges = Vec::new();
+    let mut start = 0;
+    for contour in contours {
+        let n = contour.len();
+        if n >= 3 {
+            for i in 0..n {
+                let (a, b) = (contour[i], contour[(i + 1) % n]);
+                if a.y != b.y && a.x.is_finite() && a.y.is_finite() && b.x.is_finite() && b.y.is_finite() {
+                    edges.push(Edge { from : start + i, to : start + (i + 1) % n, a : [a.x, a.y], b : [b.x, b.y] });
+                }
+            }
+        }
+        start += n;
+    }
+    edges.sort_by(|e1, e2| compare(&e1.top(), &e2.top()));
+
+    let mut ys : Vec<f32> = edges.iter().flat_map(|edge| vec![edge.a[1], edge.b[1]]).collect();
+    for (i, edge) in edges.iter().enumerate() {
+        // Only the sides starting before this one ends can cross it.
+        for other in edges[i + 1..].iter().take_while(|other| other.top() < edge.bottom()) {
+            ys.extend(edge.crossing(other));
+        }
+    }
+    ys.sort_by(compare);
+    ys.dedup();
+
+    let mut triangles = Vec::new();
+    let mut active : Vec<&Edge> = Vec::new();
+    let mut next = 0;
+    for band in ys.windows(2) {
+        let (top, bottom) = (band[0], band[1]);
+        active.retain(|edge| edge.bottom() > top);
+        while next < edges.len() && edges[next].top() <= top {
+            active.push(&edges[next]);
+            next += 1;
+        }
+
+        let middle = (top + bottom) / 2.0;
+        let mut crossing : Vec<(f32, &Edge)> = active.iter().map(|edge| (edge.x_at(middle), *edge)).collect();
+        crossing.sort_by(|(x1, _), (x2, _)| compare(x1, x2));
+
+        let mut winding = 0;
+        let mut left : Option<&Edge> = None;
+        for (_, edge) in crossing {
+            let was_inside = rule.is_inside(winding);
+            winding += edge.direction();
+            match (was_inside, rule.is_inside(winding)) {
+                (false, true) => left = Some(edge),
+                (true, false) => {
+                    if let Some(left) = left.take() {
+                        trapezoid(&mut triangles, left, edge, top, bottom);
+                    }
+                },
+                _ => ()
+            }
+        }
+    }
+    triangles
+}
+
+/// Adds the triangles of the trapezoid between the sides 'left' and 'right', from 'top' to 'bottom'.
+/// A triangle is left out when the trapezoid has no width on its side.
+fn trapezoid(triangles : &mut Vec<[EdgePoint; 3]>, left : &Edge, right : &Edge, top : f32, bottom : f32) {
+    let (top_left, top_right) = (left.point_at(top), right.point_at(top));
+    let (bottom_left, bottom_right) = (left.point_at(bottom), right.point_at(bottom));
+    if left.x_at(top) != right.x_at(top) {
+        triangles.push([top_left, top_right, bottom_right]);
+    }
+    if left.x_at(bottom) != right.x_at(bottom) {
+        triangles.push([top_left, bottom_right, bottom_left]);
+    }
+}
+
+/// Returns true if the ring 'index' of 'rings' is a hole, that is
+/// if it is inside an odd number of the other rings.
+pub fn is_hole(rings : &[&[Position]], index : usize) -> bool {
+    let point = match rings[index].first() {
+        Some(point) => point,
+        None => return false
+    };
+    let inside = rings.iter().enumerate().filter(|&(i, ring)| i != index && ring.len() >= 3 && contains(ring, point)).count();
+    inside % 2 == 1
+}
+
+/// Returns true if 'point' is inside 'ring', with the even-odd rule.
+fn contains(ring : &[Position], point : &Position) -> bool {
+    let mut inside = false;
+    for (i, a) in ring.iter().enumerate() {
+        let b = &ring[(i + 1) % ring.len()];
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(triangles : &[[EdgePoint; 3]], points : &[Position]) -> f32 {
+        triangles.iter().map(|triangle| {
+            let [a, b, c] = [triangle[0].position(points), triangle[1].position(points), triangle[2].position(points)];
+            ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() / 2.0
+        }).sum()
+    }
+
+    fn polygon(points : &[(f32, f32)]) -> Vec<Position> {
+        points.iter().map(|&(x, y)| Position { x, y, z : 0.0 }).collect()
+    }
+
+    #[test]
+    fn triangulate_concave()
+    {
+        // A U shape : the centroid is outside, in the notch.
+        let points = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 3.0), (2.0, 3.0), (2.0, 0.0), (3.0, 0.0), (3.0, 4.0), (0.0, 4.0)]);
+        let triangles = triangulate(&[&points], FillRule::NonZero);
+        assert!((area(&triangles, &points) - 9.0).abs() < 1e-5);
+
+        let square = polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+        assert_eq!(triangulate(&[&square], FillRule::EvenOdd).len(), 2);
+        assert!(triangulate(&[&square[..2]], FillRule::NonZero).is_empty());
+    }
+
+    #[test]
+    fn triangulate_self_intersecting()
+    {
+        // A bow tie : two triangles meeting at (1, 1).
+        let points = polygon(&[(0.0, 0.0), (2.0, 2.0), (2.0, 0.0), (0.0, 2.0)]);
+        let triangles = triangulate(&[&points], FillRule::NonZero);
+        assert!((area(&triangles, &points) - 2.0).abs() < 1e-5);
+        assert_eq!(triangles[0][0].position(&points), Position { x : 0.0, y : 0.0, z : 0.0 });
+
+        // A pentagram : its center is wound twice, so it is empty with the even-odd rule.
+        let star : Vec<(f32, f32)> = (0..5).map(|i| {
+            let angle = i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+            (angle.sin(), -angle.cos())
+        }).collect();
+        let points = polygon(&star);
+        let non_zero = area(&triangulate(&[&points], FillRule::NonZero), &points);
+        let even_odd = area(&triangulate(&[&points], FillRule::EvenOdd), &points);
+        assert!((non_zero - 1.1226).abs() < 1e-3);
+        assert!((non_zero - even_odd - 0.3469).abs() < 1e-3);
+    }
+
+    #[test]
+    fn triangulate_holes()
+    {
+        // A square with a square hole, and another square apart.
+        let outer = polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        let hole = polygon(&[(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]);
+        let apart = polygon(&[(5.0, 0.0), (6.0, 0.0), (6.0, 1.0), (5.0, 1.0)]);
+        let points = [outer.clone(), hole.clone(), apart.clone()].concat();
+        let rings : [&[Position]; 3] = [&outer, &hole, &apart];
+        assert!((area(&triangulate(&rings, FillRule::EvenOdd), &points) - 13.0).abs() < 1e-5);
+        // The hole goes the same way as the outer ring, so it is filled with the non-zero rule.
+        assert!((area(&triangulate(&rings, FillRule::NonZero), &points) - 17.0).abs() < 1e-5);
+
+        let reversed : Vec<Position> = hole.iter().rev().cloned().collect();
+        let points = [outer.clone(), reversed.clone(), apart.clone()].concat();
+        let rings : [&[Position]; 3] = [&outer, &reversed, &apart];
+        assert!((area(&triangulate(&rings, FillRule::NonZero), &points) - 13.0).abs() < 1e-5);
+
+        assert!(!is_hole(&rings, 0));
+        assert!(is_hole(&rings, 1));
+        assert!(!is_hole(&rings, 2));
+    }
+
+    #[test]
+    fn edge_point()
+    {
+        let points = polygon(&[(0.0, 0.0), (2.0, 4.0)]);
+        let point = EdgePoint { from : 0, to : 1, ratio : 0.25 };
+        assert_eq!(point.position(&points), Position { x : 0.5, y : 1.0, z : 0.0 });
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::marks::textmark::FaceCache;
+use crate::vector::Item;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Tables kept in the subsets, which are the ones needed by PDF and PostScript
+/// interpreters to draw the glyphs of a TrueType font.
+const SUBSET_TABLES : [&[u8; 4]; 9] = [b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"prep"];
+
+fn u16_at(data : &[u8], offset : usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]))
+}
+
+fn u32_at(data : &[u8], offset : usize) -> Option<u32> {
+    Some(u32::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?, *data.get(offset + 2)?, *data.get(offset + 3)?]))
+}
+
+/// Sum of the big endian words of 'data', as defined by the TrueType format.
+fn checksum(data : &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, word| {
+        let mut bytes = [0; 4];
+        bytes[..word.len()].copy_from_slice(word);
+        sum.wrapping_add(u32::from_be_bytes(bytes))
+    })
+}
+
+/// Font file embedded in an exported document, with the offsets where it can be split
+/// into the strings of a PostScript font : the start of each table and of each glyph.
+pub(crate) struct Subset {
+    pub(crate) data : Vec<u8>,
+    pub(crate) boundaries : Vec<usize>
+}
+
+/// TrueType font read from a font file, giving what is needed to embed
+/// the glyphs of the text marks in a PDF or a PostScript document.
+pub(crate) struct TrueType {
+    data : Vec<u8>,
+    tables : Vec<([u8; 4], usize, usize)>,
+    pub(crate) units_per_em : u16,
+    num_glyphs : u16,
+    long_loca : bool,
+    metrics : u16,
+    cmap : Option<usize>
+}
+
+impl TrueType {
+    /// Returns the font in 'data', or None if it is not a valid TrueType font.
+    /// Fonts with PostScript outlines (OpenType CFF) or Type 1 fonts are not supported.
+    pub(crate) fn parse(data : Vec<u8>) -> Option<TrueType> {
+        let version = u32_at(&data, 0)?;
+        if version != 0x0001_0000 && version != u32::from_be_bytes(*b"true") {
+            return None;
+        }
+
+        let mut tables = Vec::new();
+        for i in 0..u16_at(&data, 4)? as usize {
+            let record = 12 + 16 * i;
+            let tag = [*data.get(record)?, data[record + 1], data[record + 2], data[record + 3]];
+            let (offset, length) = (u32_at(&data, record + 8)? as usize, u32_at(&data, record + 12)? as usize);
+            if offset.checked_add(length)? > data.len() {
+                return None;
+            }
+            tables.push((tag, offset, length));
+        }
+
+        let mut font = TrueType { data, tables, units_per_em : 0, num_glyphs : 0, long_loca : false, metrics : 0, cmap : None };
+        let head = font.table(b"head")?;
+        let (units_per_em, long_loca) = (u16_at(head, 18)?, u16_at(head, 50)? == 1);
+        let num_glyphs = u16_at(font.table(b"maxp")?, 4)?;
+        let metrics = u16_at(font.table(b"hhea")?, 34)?;
+        font.table(b"glyf")?;
+        font.table(b"hmtx")?;
+        if units_per_em == 0 || metrics == 0 || font.table(b"loca")?.len() < (num_glyphs as usize + 1) * if long_loca { 4 } else { 2 } {
+            return None;
+        }
+        font.units_per_em = units_per_em;
+        font.num_glyphs = num_glyphs;
+        font.long_loca = long_loca;
+        font.metrics = metrics;
+        font.cmap = font.find_cmap();
+        Some(font)
+    }
+
+    fn table(&self, tag : &[u8; 4]) -> Option<&[u8]> {
+        self.tables.iter().find(|table| &table.0 == tag).map(|&(_, offset, length)| &self.data[offset..offset + length])
+    }
+
+    /// Returns the offset in the cmap table of the subtable mapping the unicode characters,
+    /// preferring the one covering every character.
+    fn find_cmap(&self) -> Option<usize> {
+        let cmap = self.table(b"cmap")?;
+        let mut found = None;
+        for i in 0..u16_at(cmap, 2)? as usize {
+            let (platform, encoding) = (u16_at(cmap, 4 + 8 * i)?, u16_at(cmap, 6 + 8 * i)?);
+            let offset = u32_at(cmap, 8 + 8 * i)? as usize;
+            let format = u16_at(cmap, offset)?;
+            let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+            if unicode && format == 12 {
+                return Some(offset);
+            }
+            if unicode && format == 4 {
+                found = Some(offset);
+            }
+        }
+        found
+    }
+
+    /// Returns the index of the glyph of 'c', or 0 (the missing glyph) if the font does not have it.
+    pub(crate) fn glyph_index(&self, c : char) -> u16 {
+        self.lookup(c as u32).filter(|&gid| gid < self.num_glyphs).unwrap_or(0)
+    }
+
+    fn lookup(&self, c : u32) -> Option<u16> {
+        let cmap = self.table(b"cmap")?;
+        let offset = self.cmap?;
+        if u16_at(cmap, offset)? == 12 {
+            for i in 0..u32_at(cmap, offset + 12)? as usize {
+                let group = offset + 16 + 12 * i;
+                let (start, end) = (u32_at(cmap, group)?, u32_at(cmap, group + 4)?);
+                if start <= c && c <= end {
+                    return Some((u32_at(cmap, group + 8)? + c - start) as u16);
+                }
+            }
+            return None;
+        }
+
+        if c > 0xffff {
+            return None;
+        }
+        let c = c as u16;
+        let segments = u16_at(cmap, offset + 6)? as usize / 2;
+        let (ends, starts) = (offset + 14, offset + 16 + 2 * segments);
+        let (deltas, ranges) = (starts + 2 * segments, starts + 4 * segments);
+        for i in 0..segments {
+            if c > u16_at(cmap, ends + 2 * i)? {
+                continue;
+            }
+            let start = u16_at(cmap, starts + 2 * i)?;
+            if c < start {
+                return None;
+            }
+            let (delta, range) = (u16_at(cmap, deltas + 2 * i)?, u16_at(cmap, ranges + 2 * i)? as usize);
+            if range == 0 {
+                return Some(c.wrapping_add(delta));
+            }
+            let gid = u16_at(cmap, ranges + 2 * i + range + 2 * (c - start) as usize)?;
+            return if gid == 0 { None } else { Some(gid.wrapping_add(delta)) };
+        }
+        None
+    }
+
+    /// Returns the advance width of the glyph 'gid', in units of the em square.
+    pub(crate) fn advance(&self, gid : u16) -> u16 {
+        let index = gid.min(self.metrics - 1) as usize;
+        self.table(b"hmtx").and_then(|hmtx| u16_at(hmtx, 4 * index)).unwrap_or(0)
+    }
+
+    /// Returns the bounding box of every glyph (x min, y min, x max, y max), in units of the em square.
+    pub(crate) fn bounding_box(&self) -> [i16; 4] {
+        let head = self.table(b"head").unwrap_or(&[]);
+        let value = |offset| u16_at(head, offset).unwrap_or(0) as i16;
+        [value(36), value(38), value(40), value(42)]
+    }
+
+    /// Returns the ascender and the descender of the font, in units of the em square.
+    pub(crate) fn ascent_descent(&self) -> (i16, i16) {
+        let hhea = self.table(b"hhea").unwrap_or(&[]);
+        (u16_at(hhea, 4).unwrap_or(0) as i16, u16_at(hhea, 6).unwrap_or(0) as i16)
+    }
+
+    /// Returns the bytes of the outline of the glyph 'gid'.
+    fn glyph(&self, gid : u16) -> &[u8] {
+        let (loca, glyf) = (self.table(b"loca").unwrap_or(&[]), self.table(b"glyf").unwrap_or(&[]));
+        let offset = |index : usize| if self.long_loca {
+            u32_at(loca, 4 * index).map(|offset| offset as usize)
+        }
+        else {
+            u16_at(loca, 2 * index).map(|offset| 2 * offset as usize)
+        };
+        match (offset(gid as usize), offset(gid as usize + 1)) {
+            (Some(start), Some(end)) if start <= end && end <= glyf.len() => &glyf[start..end],
+            _ => &[]
+        }
+    }
+
+    /// Returns the glyphs a composite glyph is made of.
+    fn components(glyph : &[u8]) -> Vec<u16> {
+        let mut components = Vec::new();
+        if glyph.len() < 10 || (u16_at(glyph, 0).unwrap_or(0) as i16) >= 0 {
+            return components;
+        }
+        let mut offset = 10;
+        while let (Some(flags), Some(gid)) = (u16_at(glyph, offset), u16_at(glyph, offset + 2)) {
+            components.push(gid);
+            offset += 4 + if flags & 0x0001 != 0 { 4 } else { 2 };
+            offset += if flags & 0x0008 != 0 { 2 } else if flags & 0x0040 != 0 { 4 } else if flags & 0x0080 != 0 { 8 } else { 0 };
+            if flags & 0x0020 == 0 {
+                break;
+            }
+        }
+        components
+    }
+
+    /// Returns a copy of the font keeping only the outlines of the glyphs 'gids', of the glyphs
+    /// they are made of and of the missing glyph. The other glyphs are left empty so the glyph
+    /// indices do not change, and the tables which are not needed to draw the glyphs are removed.
+    pub(crate) fn subset(&self, gids : &BTreeSet<u16>) -> Subset {
+        let mut kept : BTreeSet<u16> = BTreeSet::new();
+        let mut pending : Vec<u16> = gids.iter().cloned().chain(Some(0)).filter(|&gid| gid < self.num_glyphs).collect();
+        while let Some(gid) = pending.pop() {
+            if kept.insert(gid) {
+                pending.extend(TrueType::components(self.glyph(gid)).into_iter().filter(|&gid| gid < self.num_glyphs));
+            }
+        }
+
+        // The glyphs are padded to 4 bytes, and the loca table is always written in the long format.
+        let (mut glyf, mut loca, mut glyph_starts) = (Vec::new(), Vec::new(), Vec::new());
+        for gid in 0..self.num_glyphs {
+            loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+            if kept.contains(&gid) {
+                glyph_starts.push(glyf.len());
+                glyf.extend_from_slice(self.glyph(gid));
+                while glyf.len() % 4 != 0 { glyf.push(0); }
+            }
+        }
+        loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+
+        let mut tables : Vec<([u8; 4], Vec<u8>)> = Vec::new();
+        for tag in SUBSET_TABLES.iter() {
+            let table = match &**tag {
+                b"glyf" => glyf.clone(),
+                b"loca" => loca.clone(),
+                b"head" => {
+                    let mut head = self.table(b"head").unwrap_or(&[]).to_vec();
+                    head[8..12].copy_from_slice(&[0; 4]);
+                    head[50..52].copy_from_slice(&1u16.to_be_bytes());
+                    head
+                },
+                _ => match self.table(tag) {
+                    Some(table) => table.to_vec(),
+                    None => continue
+                }
+            };
+            tables.push((**tag, table));
+        }
+
+        // Offset table, then table records and tables sorted by tag.
+        let count = tables.len() as u16;
+        let selector = 15 - count.leading_zeros() as u16;
+        let mut data = Vec::new();
+        data.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        for value in &[count, 16 << selector, selector, count * 16 - (16 << selector)] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, table) in &tables {
+            data.extend_from_slice(tag);
+            for value in &[checksum(table), offset as u32, table.len() as u32] {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            offset += table.len().div_ceil(4) * 4;
+        }
+
+        let mut boundaries = Vec::new();
+        let mut head = 0;
+        for (tag, table) in &tables {
+            boundaries.push(data.len());
+            match tag {
+                b"glyf" => boundaries.extend(glyph_starts.iter().map(|start| data.len() + start)),
+                b"head" => head = data.len(),
+                _ => ()
+            }
+            data.extend_from_slice(table);
+            while data.len() % 4 != 0 { data.push(0); }
+        }
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&data));
+        data[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+        boundaries.dedup();
+
+        Subset { data, boundaries }
+    }
+}
+
+/// Font of the text marks embedded in an exported document, with the glyphs it must contain.
+pub(crate) struct EmbeddedFont<'a> {
+    pub(crate) face : &'a FaceCache,
+    pub(crate) font : TrueType,
+    /// Character of each glyph used by the texts, to be able to copy them from the document.
+    pub(crate) glyphs : BTreeMap<u16, char>
+}
+
+impl<'a> EmbeddedFont<'a> {
+    /// Reads the file of 'face'. Returns an error if it cannot be read or is not a TrueType font.
+    fn load(face : &'a FaceCache) -> Result<Self> {
+        let data = std::fs::read(&face.font).map_err(|err| Error::FontEmbed(face.font.clone(), err.to_string()))?;
+        let font = TrueType::parse(data).ok_or_else(|| Error::FontEmbed(face.font.clone(), "not a TrueType font".to_string()))?;
+        Ok(EmbeddedFont { face, font, glyphs : BTreeMap::new() })
+    }
+
+    /// Returns the glyph indices of the characters of 'text'.
+    pub(crate) fn glyph_indices(&self, text : &str) -> Vec<u16> {
+        text.chars().map(|c| self.font.glyph_index(c)).collect()
+    }
+
+    /// Returns the name of the subset of the font, made of a tag telling which subset it is
+    /// and of the name of the font in contrast.
+    pub(crate) fn name(&self, index : usize) -> String {
+        let tag : String = (0..6).map(|i| (b'A' + (index / 26usize.pow(i) % 26) as u8) as char).collect();
+        let name : String = self.face.name.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect();
+        format!("{}+{}", tag, if name.is_empty() { "Font" } else { &name })
+    }
+
+    pub(crate) fn subset(&self) -> Subset {
+        self.font.subset(&self.glyphs.keys().cloned().collect())
+    }
+}
+
+/// Loads the fonts used by the text items, each one once, with the glyphs of their texts.
+/// Returns an error if a font cannot be embedded.
+pub(crate) fn embedded_fonts<'a>(items : &[Item<'a>]) -> Result<Vec<EmbeddedFont<'a>>> {
+    let mut fonts : Vec<EmbeddedFont> = Vec::new();
+    for item in items {
+        if let Item::Text(face, _, text, _) = item {
+            let index = match fonts.iter().position(|font| font.face.name == face.name) {
+                Some(index) => index,
+                None => {
+                    fonts.push(EmbeddedFont::load(face)?);
+                    fonts.len() - 1
+                }
+            };
+            let font = &mut fonts[index];
+            for c in text.chars() {
+                let gid = font.font.glyph_index(c);
+                font.glyphs.entry(gid).or_insert(c);
+            }
+        }
+    }
+    Ok(fonts)
+}
+
+/// Returns the index of the font of 'face' in 'fonts'.
+pub(crate) fn font_index(fonts : &[EmbeddedFont], face : &FaceCache) -> usize {
+    fonts.iter().position(|font| font.face.name == face.name).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> TrueType {
+        TrueType::parse(std::fs::read("../contrast-renderer/crimson-b.ttf").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse()
+    {
+        let font = font();
+        assert_eq!(font.units_per_em, 1024);
+        assert!(font.glyph_index('A') != 0);
+        assert!(font.glyph_index('A') != font.glyph_index('B'));
+        assert_eq!(font.glyph_index('\u{10FFFF}'), 0);
+        assert!(font.advance(font.glyph_index('M')) > font.advance(font.glyph_index('i')));
+        assert!(TrueType::parse(b"not a font".to_vec()).is_none());
+    }
+
+    #[test]
+    fn subset()
+    {
+        let font = font();
+        let a = font.glyph_index('a');
+        let subset = font.subset(&[a].iter().cloned().collect());
+        assert!(subset.data.len() < font.data.len());
+
+        // The subset is a valid font with the same glyph indices.
+        let parsed = TrueType::parse(subset.data.clone()).unwrap();
+        assert_eq!(parsed.glyph(a), font.glyph(a));
+        assert_eq!(parsed.glyph(0), font.glyph(0));
+        assert!(parsed.glyph(font.glyph_index('b')).is_empty());
+        assert_eq!(parsed.advance(a), font.advance(a));
+        assert_eq!(checksum(&subset.data), 0xB1B0_AFBA);
+        assert!(subset.boundaries.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,566 @@
+use std::f32::consts::PI;
+use properties::color::Color;
+use properties::position::Position;
+use crate::markscontainer::Contrast;
+use crate::marks::mark::Mark;
+use crate::marks::pointmark::{PointMark, Shape};
+use crate::marks::shapeatlas::ShapeAtlas;
+use crate::marks::linemark::{LineCap, LineJoin};
+use crate::marks::polygonmark::Gradient;
+use crate::marks::textmark::{FaceCache, FontCache};
+use crate::marks::animationattribute::Interpolate;
+use crate::triangulation::FillRule;
+use crate::svg::{shape_path, rounded_rectangle_path};
+use crate::MarkMacro;
+
+/// Point of a path, in pixels from the top left corner.
+pub(crate) type Point = [f32; 2];
+
+/// Segment of a path, made only of what PDF and PostScript can draw natively.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Segment {
+    Move(Point),
+    Line(Point),
+    Cubic(Point, Point, Point),
+    Close
+}
+
+/// What must be drawn for a mark in a vector document, with the values of the
+/// mark displayed at the time of the export.
+pub(crate) enum Item<'a> {
+    /// A path filled with this rule.
+    Fill(Vec<Segment>, FillRule, Paint),
+    /// A path stroked with this width and this style.
+    Stroke(Vec<Segment>, f32, StrokeStyle<'a>, Color),
+    /// A closed path stroked only inside, with this width, the inside following this rule.
+    InnerStroke(Vec<Segment>, FillRule, f32, Paint),
+    /// A text written with a registered font, from the origin of its baseline.
+    Text(&'a FaceCache, Position, &'a str, Color)
+}
+
+/// How a path is painted : with a color, or with a gradient going from this color to the one of the gradient.
+/// A gradient is never empty : a gradient without length or without radius is replaced by its color.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Paint {
+    Color(Color),
+    Gradient(Color, Gradient)
+}
+
+impl Paint {
+    pub(crate) fn new(color : Color, gradient : Gradient) -> Self {
+        match gradient {
+            Gradient::None => Paint::Color(color),
+            Gradient::Linear(from, to, end) if from.x == to.x && from.y == to.y => Paint::Color(end),
+            Gradient::Radial(_, radius, end) if radius <= 0.0 => Paint::Color(end),
+            _ => Paint::Gradient(color, gradient)
+        }
+    }
+
+    /// Returns the color of the paint, or the first color of its gradient.
+    pub(crate) fn color(&self) -> Color {
+        match self {
+            Paint::Color(color) | Paint::Gradient(color, _) => *color
+        }
+    }
+}
+
+/// The shape of the ends and of the joins of a stroked path, and its dash pattern.
+pub(crate) struct StrokeStyle<'a> {
+    pub(crate) cap : LineCap,
+    pub(crate) join : LineJoin,
+    /// Lengths of the dashes and of the gaps, alternately, empty if the stroke is solid.
+    pub(crate) dashes : &'a [f32],
+    pub(crate) dash_offset : f32
+}
+
+impl StrokeStyle<'_> {
+    /// The style of the inner strokes of the polygons : the shaders do not limit the length of their miters.
+    pub(crate) fn polygon() -> Self {
+        StrokeStyle { cap : LineCap::Butt, join : LineJoin::Miter(1000.0), dashes : &[], dash_offset : 0.0 }
+    }
+
+    /// Returns the codes of the cap and of the join, and the miter limit, shared by PDF and PostScript.
+    /// The limit is the default one of both formats when the join is not a miter.
+    pub(crate) fn codes(&self) -> (u32, u32, f32) {
+        let cap = self.cap as u32;
+        match self.join {
+            LineJoin::Miter(limit) => (cap, 0, limit.max(1.0)),
+            LineJoin::Round => (cap, 1, 10.0),
+            LineJoin::Bevel => (cap, 2, 10.0)
+        }
+    }
+}
+
+/// Part of a line whose points have their own colors, stroked with a single color.
+pub(crate) struct Piece {
+    pub(crate) points : Vec<Position>,
+    pub(crate) color : Color,
+    /// Distance along the line from its first point to the first point of the piece.
+    pub(crate) distance : f32,
+    /// True if the piece ends the line, so it has the caps of the line.
+    pub(crate) end : bool
+}
+
+/// Largest difference of a component of the colors of two consecutive pieces.
+const COLOR_STEP : f32 = 1.0 / 16.0;
+
+/// Splits the line going through 'points', whose colors are 'colors', into pieces of a single color,
+/// as the vector formats have no gradient along a stroke. The sub-lines are cut so that
+/// the colors of two consecutive pieces are close, then each piece goes from the middle of
+/// a sub-line to the middle of the next one, so the joins of the line are kept.
+pub(crate) fn color_pieces(points : &[Position], colors : &[Color]) -> Vec<Piece> {
+    let mut samples = vec![(points[0], colors[0])];
+    for (p, c) in points.windows(2).zip(colors.windows(2)) {
+        let difference = (c[1].r - c[0].r).abs().max((c[1].g - c[0].g).abs()).max((c[1].b - c[0].b).abs()).max((c[1].a - c[0].a).abs());
+        let cuts = ((difference / COLOR_STEP).ceil() as usize).clamp(1, 16);
+        samples.extend((1..=cuts).map(|i| {
+            let progress = i as f32 / cuts as f32;
+            (p[0].interpolate(&p[1], progress), c[0].interpolate(&c[1], progress))
+        }));
+    }
+    let last = samples.len() - 1;
+    let middle = |i : usize| samples[i].0.interpolate(&samples[i + 1].0, 0.5);
+    let length = |a : &Position, b : &Position| ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+    let mut pieces = Vec::with_capacity(samples.len());
+    let mut distance = 0.0;
+    for (i, (point, color)) in samples.iter().enumerate() {
+        let start = if i == 0 { *point } else { middle(i - 1) };
+        let end = if i == last { *point } else { middle(i) };
+        let mut points = vec![start];
+        for p in [*point, end].iter() {
+            if points.last() != Some(p) { points.push(*p); }
+        }
+        pieces.push(Piece { points, color : *color, distance, end : i == 0 || i == last });
+        distance += length(&start, point) + length(point, &end);
+    }
+    pieces
+}
+
+/// Returns what must be drawn for each mark of contrast, in the order of
+/// Contrast::export_svg : from the deepest layer to the layer 0.
+/// The text marks are written with the faces of 'fonts', the fonts of contrast.
+pub(crate) fn items<'a>(contrast : &'a Contrast, fonts : &'a FontCache) -> Vec<Item<'a>> {
+    let now = contrast.get_time();
+    let mut layers : Vec<_> = contrast.layers.iter().collect();
+    layers.sort_by_key(|layer| std::cmp::Reverse(layer.depth));
+
+    let mut items = Vec::new();
+    for layer in layers {
+        for mark in layer.marks.iter().filter(|mark| mark.is_valid()) {
+            match mark {
+                Mark::Point(point) => point_items(&mut items, point, &contrast.shapes, now),
+                Mark::Line(line) => {
+                    let points = line.drawn_points_at(now);
+                    let thickness = line.thickness.value_at(now);
+                    let style = |cap, dash_offset| StrokeStyle { cap, join : line.join, dashes : &line.dashes, dash_offset };
+                    match line.drawn_colors_at(now) {
+                        _ if points.len() < 2 => (),
+                        // The vector formats have no gradient along a stroke, so the line is drawn in pieces.
+                        Some(colors) => for piece in color_pieces(&points, &colors).into_iter().filter(|piece| piece.points.len() >= 2) {
+                            let cap = if piece.end { line.cap } else { LineCap::Butt };
+                            items.push(Item::Stroke(polyline(&piece.points, false), thickness, style(cap, line.dash_offset + piece.distance), piece.color));
+                        },
+                        None => items.push(Item::Stroke(polyline(&points, false), thickness, style(line.cap, line.dash_offset), line.color.value_at(now)))
+                    }
+                    for marker in line.marker_points() {
+                        point_items(&mut items, &marker, &contrast.shapes, now);
+                    }
+                },
+                Mark::Polygon(polygon) => {
+                    let points = polygon.points.value_at(now);
+                    let path : Vec<Segment> = polygon.split(&points).into_iter().filter(|ring| ring.len() >= 3)
+                                                     .flat_map(|ring| polyline(ring, true)).collect();
+                    if !path.is_empty() {
+                        let color = Paint::new(polygon.color.value_at(now), polygon.gradient);
+                        items.push(if polygon.fill { Item::Fill(path, polygon.fill_rule, color) }
+                                   else { Item::InnerStroke(path, polygon.fill_rule, polygon.stroke_width.value_at(now), color) });
+                    }
+                },
+                Mark::Text(text) => {
+                    if let Some(face) = fonts.cached.get(text.get_font()) {
+                        let position = text.pos.value_at(now);
+                        for line in face.layout(text.get_text(), text.get_layout()) {
+                            let origin = Position { x : position.x + line.x as f32, y : position.y + line.y as f32, z : position.z };
+                            items.push(Item::Text(face, origin, line.text, text.color.value_at(now)));
+                        }
+                    }
+                },
+                Mark::Arc(arc) => {
+                    if let Some(shape) = arc.shape_at(now) {
+                        items.push(Item::Fill(parse_path(&shape.path(), |p| p), FillRule::NonZero, Paint::Color(arc.color.value_at(now))));
+                    }
+                },
+                // The images are only embedded in the SVG documents.
+                Mark::Image(_) => ()
+            }
+        }
+    }
+    items
+}
+
+/// Adds what must be drawn for a point mark : its filled shape, then its stroke inside the shape.
+fn point_items(items : &mut Vec<Item>, point : &PointMark, shapes : &ShapeAtlas, now : f32) {
+    let path = match point_path(point, shapes, now) {
+        Some(path) => path,
+        None => return
+    };
+    let stroke_width = point.stroke_width.value_at(now);
+    if point.fill {
+        items.push(Item::Fill(path.clone(), FillRule::NonZero, Paint::Color(point.color.value_at(now))));
+    }
+    if stroke_width > 0.0 {
+        items.push(Item::InnerStroke(path, FillRule::NonZero, stroke_width, Paint::Color(point.stroke_color.value_at(now))));
+    }
+}
+
+/// Returns the outline of a point mark : the path of its shape scaled, rotated and moved
+/// like the shaders do, or None if nothing is drawn.
+pub(crate) fn point_path(point : &PointMark, shapes : &ShapeAtlas, now : f32) -> Option<Vec<Segment>> {
+    let shape = point.shape.value_at(now);
+    let size = point.size.value_at(now);
+    if shape == Shape::None || size.width == 0.0 || size.height == 0.0 {
+        return None;
+    }
+    let center = point.center.value_at(now);
+    let (sin, cos) = point.rotation.value_at(now).sin_cos();
+    let (w, h) = (size.width / 2.0, size.height / 2.0);
+    let place = |x : f32, y : f32| [center.x + x * cos - y * sin, center.y + x * sin + y * cos];
+    let corner_radius = point.corner_radius.value_at(now);
+    let path = if shape == Shape::Rectangle && corner_radius > 0.0 {
+        parse_path(&rounded_rectangle_path(w, h, corner_radius), |p| place(p[0], p[1]))
+    } else {
+        parse_path(&shape_path(shape, shapes), |p| place(p[0] * w, p[1] * h))
+    };
+    if path.is_empty() { None } else { Some(path) }
+}
+
+fn polyline(points : &[Position], closed : bool) -> Vec<Segment> {
+    let mut path : Vec<Segment> = points.iter().map(|p| Segment::Line([p.x, p.y])).collect();
+    path[0] = Segment::Move([points[0].x, points[0].y]);
+    if closed {
+        path.push(Segment::Close);
+    }
+    path
+}
+
+/// Returns 'path' as SVG path data.
+pub(crate) fn path_data(path : &[Segment]) -> String {
+    path.iter().map(|segment| match segment {
+        Segment::Move(p) => format!("M{} {}", p[0], p[1]),
+        Segment::Line(p) => format!("L{} {}", p[0], p[1]),
+        Segment::Cubic(c1, c2, p) => format!("C{} {} {} {} {} {}", c1[0], c1[1], c2[0], c2[1], p[0], p[1]),
+        Segment::Close => String::from("Z")
+    }).collect()
+}
+
+/// Converts SVG path data into segments transformed by 'transform'. Every command of SVG is
+/// supported, with absolute or relative coordinates : the quadratic curves are raised to cubic
+/// ones, and the arcs are approximated by cubic Bézier curves before being transformed.
+pub(crate) fn parse_path<F : Fn(Point) -> Point>(data : &str, transform : F) -> Vec<Segment> {
+    let mut path = Vec::new();
+    let mut tokens = tokenize(data).into_iter().peekable();
+    let mut current = [0.0, 0.0];
+    let mut start = [0.0, 0.0];
+    // Kind and last control point of the previous curve, reflected by the smooth curves.
+    let mut control : Option<(char, Point)> = None;
+    let mut command = ' ';
+    while let Some(token) = tokens.peek().cloned() {
+        if let Token::Command(c) = token {
+            command = c;
+            tokens.next();
+            if c.eq_ignore_ascii_case(&'Z') {
+                path.push(Segment::Close);
+                current = start;
+                control = None;
+                continue;
+            }
+        }
+        let mut next = || match tokens.next() { Some(Token::Number(n)) => n, _ => 0.0 };
+        let origin = if command.is_ascii_lowercase() { current } else { [0.0, 0.0] };
+        let point = |x : f32, y : f32| [origin[0] + x, origin[1] + y];
+        let previous = control.take();
+        let reflected = |kind : char, current : Point| match previous {
+            Some((k, c)) if k == kind => [2.0 * current[0] - c[0], 2.0 * current[1] - c[1]],
+            _ => current
+        };
+        match command.to_ascii_uppercase() {
+            'M' => {
+                current = point(next(), next());
+                start = current;
+                path.push(Segment::Move(transform(current)));
+                // The next coordinates without command are lines.
+                command = if command == 'm' { 'l' } else { 'L' };
+            },
+            'L' => {
+                current = point(next(), next());
+                path.push(Segment::Line(transform(current)));
+            },
+            'H' => {
+                current = [origin[0] + next(), current[1]];
+                path.push(Segment::Line(transform(current)));
+            },
+            'V' => {
+                current = [current[0], origin[1] + next()];
+                path.push(Segment::Line(transform(current)));
+            },
+            'C' | 'S' => {
+                let c1 = if command.eq_ignore_ascii_case(&'C') { point(next(), next()) } else { reflected('C', current) };
+                let (c2, end) = (point(next(), next()), point(next(), next()));
+                path.push(Segment::Cubic(transform(c1), transform(c2), transform(end)));
+                control = Some(('C', c2));
+                current = end;
+            },
+            'Q' | 'T' => {
+                let c = if command.eq_ignore_ascii_case(&'Q') { point(next(), next()) } else { reflected('Q', current) };
+                let end = point(next(), next());
+                let c1 = [current[0] + (c[0] - current[0]) * 2.0 / 3.0, current[1] + (c[1] - current[1]) * 2.0 / 3.0];
+                let c2 = [end[0] + (c[0] - end[0]) * 2.0 / 3.0, end[1] + (c[1] - end[1]) * 2.0 / 3.0];
+                path.push(Segment::Cubic(transform(c1), transform(c2), transform(end)));
+                control = Some(('Q', c));
+                current = end;
+            },
+            'A' => {
+                let (rx, ry, rotation, large, sweep) = (next(), next(), next(), next() != 0.0, next() != 0.0);
+                let target = point(next(), next());
+                for (c1, c2, end) in elliptical_arc(current, target, [rx, ry], rotation.to_radians(), large, sweep) {
+                    path.push(Segment::Cubic(transform(c1), transform(c2), transform(end)));
+                }
+                current = target;
+            },
+            _ => { next(); }
+        }
+    }
+    path
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Token {
+    Command(char),
+    Number(f32)
+}
+
+fn tokenize(data : &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+    let push = |tokens : &mut Vec<Token>, number : &mut String| if !number.is_empty() {
+        tokens.push(Token::Number(number.parse().unwrap_or(0.0)));
+        number.clear();
+    };
+    for c in data.chars() {
+        let exponent = number.ends_with('e') || number.ends_with('E');
+        let starts = match c {
+            // A sign starts a number, unless it is the one of an exponent.
+            '-' | '+' => !exponent,
+            // A second point starts a number, as in "0.5.5".
+            '.' => number.contains('.') || number.contains('e') || number.contains('E'),
+            _ => false
+        };
+        if starts {
+            push(&mut tokens, &mut number);
+        }
+        if c.is_ascii_digit() || c == '.' || c == '-' || c == '+' || ((c == 'e' || c == 'E') && !number.is_empty()) {
+            number.push(c);
+            continue;
+        }
+        push(&mut tokens, &mut number);
+        if c.is_ascii_alphabetic() {
+            tokens.push(Token::Command(c));
+        }
+    }
+    push(&mut tokens, &mut number);
+    tokens
+}
+
+/// Returns the cubic Bézier curves (first control point, second control point, end) approximating
+/// the elliptical arc of SVG going from 'start' to 'end', whose radii are 'radii' and whose x axis
+/// is rotated by 'rotation' radians. The ellipse is turned into a circle, where the arc is computed.
+fn elliptical_arc(start : Point, end : Point, radii : [f32; 2], rotation : f32, large : bool, sweep : bool) -> Vec<(Point, Point, Point)> {
+    let (rx, ry) = (radii[0].abs(), radii[1].abs());
+    if rx == 0.0 || ry == 0.0 {
+        // An arc without radius is a straight line.
+        return vec![(start, end, end)];
+    }
+    let (sin, cos) = rotation.sin_cos();
+    let to_circle = |p : Point| [p[0] * cos + p[1] * sin, (p[1] * cos - p[0] * sin) * rx / ry];
+    let from_circle = |p : Point| {
+        let y = p[1] * ry / rx;
+        [p[0] * cos - y * sin, p[0] * sin + y * cos]
+    };
+    arc(to_circle(start), to_circle(end), rx, large, sweep).into_iter()
+        .map(|(c1, c2, p)| (from_circle(c1), from_circle(c2), from_circle(p))).collect()
+}
+
+/// Returns the cubic Bézier curves (first control point, second control point, end)
+/// approximating the circular arc of SVG going from 'start' to 'end'.
+fn arc(start : Point, end : Point, radius : f32, large : bool, sweep : bool) -> Vec<(Point, Point, Point)> {
+    // Center of the arc, following the implementation notes of the SVG specification.
+    let (hx, hy) = ((start[0] - end[0]) / 2.0, (start[1] - end[1]) / 2.0);
+    let radius = radius.abs().max((hx * hx + hy * hy).sqrt());
+    let r2 = radius * radius;
+    let mut coef = ((r2 - hx * hx - hy * hy) / (hx * hx + hy * hy)).max(0.0).sqrt();
+    if large == sweep {
+        coef = -coef;
+    }
+    let center = [coef * hy + (start[0] + end[0]) / 2.0, -coef * hx + (start[1] + end[1]) / 2.0];
+
+    let angle = |p : Point| (p[1] - center[1]).atan2(p[0] - center[0]);
+    let first = angle(start);
+    let mut delta = angle(end) - first;
+    if sweep && delta <= 0.0 { delta += 2.0 * PI; }
+    if !sweep && delta >= 0.0 { delta -= 2.0 * PI; }
+
+    // Each curve covers at most a quarter of circle.
+    let count = (delta.abs() / (PI / 2.0) - 1e-4).ceil().max(1.0) as usize;
+    let step = delta / count as f32;
+    let k = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+    (0..count).map(|i| {
+        let (a, b) = (first + step * i as f32, first + step * (i + 1) as f32);
+        let (p0, p3) = ([center[0] + radius * a.cos(), center[1] + radius * a.sin()], [center[0] + radius * b.cos(), center[1] + radius * b.sin()]);
+        ([p0[0] - k * a.sin(), p0[1] + k * a.cos()], [p3[0] + k * b.sin(), p3[1] - k * b.cos()], p3)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_path()
+    {
+        let path = super::parse_path("M-1 -1L1 -1ZM0 2", |p| [p[0] * 2.0, p[1] + 1.0]);
+        assert_eq!(path, vec![Segment::Move([-2.0, 0.0]), Segment::Line([2.0, 0.0]), Segment::Close, Segment::Move([0.0, 3.0])]);
+
+        // A half circle is made of two quarters, whose middle is on the circle.
+        let path = super::parse_path("M1 0A1 1 0 0 1 -1 0", |p| p);
+        assert_eq!(path.len(), 3);
+        match path[1] {
+            Segment::Cubic(_, _, end) => assert!(end[0].abs() < 1e-6 && (end[1] - 1.0).abs() < 1e-6),
+            _ => panic!("the arc should be converted into curves")
+        }
+
+        // Relative coordinates, implicit lines after a move, and the shortcuts of SVG.
+        let path = super::parse_path("m1 1 2 0h1v-1.5e0H0z", |p| p);
+        assert_eq!(path, vec![Segment::Move([1.0, 1.0]), Segment::Line([3.0, 1.0]), Segment::Line([4.0, 1.0]),
+                              Segment::Line([4.0, -0.5]), Segment::Line([0.0, -0.5]), Segment::Close]);
+        let path = super::parse_path("M0 0Q3 3 6 0T12 0", |p| p);
+        assert_eq!(path, vec![Segment::Move([0.0, 0.0]), Segment::Cubic([2.0, 2.0], [4.0, 2.0], [6.0, 0.0]),
+                              Segment::Cubic([8.0, -2.0], [10.0, -2.0], [12.0, 0.0])]);
+        let path = super::parse_path("M0 0c0 1 1 1 1 0s1-1 1 0", |p| p);
+        assert_eq!(path[2], Segment::Cubic([1.0, -1.0], [2.0, -1.0], [2.0, 0.0]));
+        assert_eq!(super::tokenize("1.5.5-2e-1"), vec![Token::Number(1.5), Token::Number(0.5), Token::Number(-0.2)]);
+
+        // An elliptical arc stays on its ellipse.
+        let path = super::parse_path("M2 0A2 1 0 0 1 -2 0", |p| p);
+        for segment in &path[1..] {
+            match segment {
+                Segment::Cubic(_, _, end) => assert!(((end[0] / 2.0).powi(2) + end[1].powi(2) - 1.0).abs() < 1e-5),
+                _ => panic!("the arc should be converted into curves")
+            }
+        }
+    }
+
+    #[test]
+    fn arc()
+    {
+        // The curves stay on the circle, centered on the origin or on the other side of the chord.
+        for &(large, sweep, center) in &[(false, true, [0.0, 0.0]), (true, false, [0.0, 0.0]), (false, false, [0.6, 0.6]), (true, true, [0.6, 0.6])] {
+            let distance = |p : Point| ((p[0] - center[0]).powi(2) + (p[1] - center[1]).powi(2)).sqrt();
+            let mut start = [0.6, 0.0];
+            for (c1, c2, end) in super::arc(start, [0.0, 0.6], 0.6, large, sweep) {
+                let middle = [(start[0] + end[0]) * 0.125 + (c1[0] + c2[0]) * 0.375, (start[1] + end[1]) * 0.125 + (c1[1] + c2[1]) * 0.375];
+                assert!((distance(middle) - 0.6).abs() < 1e-3);
+                assert!((distance(end) - 0.6).abs() < 1e-5);
+                start = end;
+            }
+        }
+        assert_eq!(super::arc([0.6, 0.0], [0.0, 0.6], 0.6, true, true).len(), 3);
+    }
+
+    #[test]
+    fn items()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_point_mark().set_shape(Shape::Rectangle).set_size((2.0, 4.0)).set_position((10.0, 10.0, 0.0));
+        c.add_line_mark().add_point((0.0, 0.0, 0.0)).add_point((1.0, 1.0, 0.0));
+        c.add_polygon_mark().add_point((0.0, 0.0, 0.0)).add_point((5.0, 0.0, 0.0)).add_point((5.0, 5.0, 0.0)).set_fill();
+        c.add_line_mark();
+
+        let fonts = c.fonts.borrow();
+        let items = super::items(&c, &fonts);
+        assert_eq!(items.len(), 3);
+        match &items[0] {
+            Item::Fill(path, _, _) => assert_eq!(path[..2], [Segment::Move([9.0, 8.0]), Segment::Line([11.0, 8.0])]),
+            _ => panic!("a point mark should be filled")
+        }
+        match &items[1] {
+            Item::Stroke(path, _, _, _) => assert_eq!(path.len(), 2),
+            _ => panic!("a line mark should be stroked")
+        }
+        match &items[2] {
+            Item::Fill(path, _, _) => assert_eq!(path.last(), Some(&Segment::Close)),
+            _ => panic!("a filled polygon mark should be filled")
+        }
+    }
+
+    #[test]
+    fn point_items()
+    {
+        let mut c = Contrast::new();
+        c.init();
+        c.add_point_mark().set_shape(Shape::Circle).set_size((2.0, 2.0)).set_stroke_width(1.0).set_stroke_color(Color::blue());
+        c.add_point_mark().set_shape(Shape::Circle).set_size((2.0, 2.0)).set_stroke_width(1.0).set_empty();
+        c.add_point_mark().set_shape(Shape::Circle).set_size((2.0, 2.0)).set_empty();
+
+        // The stroke is drawn over the fill, and a hollow point without stroke is not drawn.
+        let fonts = c.fonts.borrow();
+        let items = super::items(&c, &fonts);
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0], Item::Fill(..)));
+        match &items[1] {
+            Item::InnerStroke(_, _, width, paint) => assert_eq!((*width, *paint), (1.0, Paint::Color(Color::blue()))),
+            _ => panic!("the stroke of a point mark should be inside its shape")
+        }
+        assert!(matches!(items[2], Item::InnerStroke(..)));
+    }
+
+    #[test]
+    fn rounded_point_path()
+    {
+        let mut point = PointMark::new();
+        point.set_shape(Shape::Rectangle).set_size((20.0, 10.0)).set_position((50.0, 50.0)).set_corner_radius(2.0);
+        let path = point_path(&point, &ShapeAtlas::default(), 0.0).unwrap();
+        // Each corner is a quarter of circle, going from one side to the next one.
+        assert_eq!(path[..2], [Segment::Move([42.0, 45.0]), Segment::Line([58.0, 45.0])]);
+        assert_eq!(path.iter().filter(|segment| matches!(segment, Segment::Cubic(..))).count(), 4);
+        match path[2] {
+            Segment::Cubic(_, _, end) => assert_eq!(end, [60.0, 47.0]),
+            _ => panic!("the corner should be a curve")
+        }
+    }
+
+    #[test]
+    fn color_pieces()
+    {
+        let points = vec![Position { x : 0.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 0.0, z : 0.0 }, Position { x : 10.0, y : 10.0, z : 0.0 }];
+        let pieces = super::color_pieces(&points, &[Color::red(), Color::red(), Color::blue()]);
+        // The red sub-line is not cut, whereas the other one is cut into 16 pieces.
+        assert_eq!(pieces.len(), 18);
+        assert_eq!(pieces[0].points, vec![points[0], Position { x : 5.0, y : 0.0, z : 0.0 }]);
+        assert!(pieces[0].end && !pieces[1].end && pieces[17].end);
+        // The corner is inside a piece, with its join.
+        assert_eq!(pieces[1].points[1], points[1]);
+        assert_eq!(pieces[1].distance, 5.0);
+        assert_eq!(pieces[17].color, Color::blue());
+    }
+
+    #[test]
+    fn paint()
+    {
+        let linear = Gradient::Linear((0.0, 0.0).into(), (0.0, 0.0).into(), Color::blue());
+        assert_eq!(Paint::new(Color::red(), linear), Paint::Color(Color::blue()));
+        let radial = Gradient::Radial((0.0, 0.0).into(), 2.0, Color::blue());
+        assert_eq!(Paint::new(Color::red(), radial), Paint::Gradient(Color::red(), radial));
+        assert_eq!(Paint::new(Color::red(), radial).color(), Color::red());
+    }
+}